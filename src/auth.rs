@@ -0,0 +1,308 @@
+use std::io::{self, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+use openssl::error::ErrorStack;
+use openssl::pkey::Private;
+use openssl::rsa::{Padding, Rsa};
+use openssl::sha::Sha1;
+use openssl::ssl::{HandshakeError, SslConnector, SslMethod};
+use serde_json;
+use uuid::{self, Uuid};
+
+use crate::entity::player::PlayerProfile;
+
+#[derive(Fail, Debug)]
+pub enum Error {
+    #[fail(display = "io error: {}", _0)]
+    IOError(#[cause] io::Error),
+    #[fail(display = "openssl error: {}", _0)]
+    OpenSSLError(#[cause] ErrorStack),
+    #[fail(display = "tls handshake failed: {}", _0)]
+    HandshakeError(String),
+    #[fail(display = "invalid url: {}", _0)]
+    InvalidUrl(String),
+    #[fail(display = "invalid http response")]
+    InvalidResponse,
+    #[fail(display = "session server responded with status {}", _0)]
+    UnexpectedStatus(u16),
+    #[fail(display = "invalid profile json: {}", _0)]
+    JsonError(#[cause] serde_json::Error),
+}
+
+impl_from_for_newtype_enum!(Error::IOError, io::Error);
+impl_from_for_newtype_enum!(Error::OpenSSLError, ErrorStack);
+impl_from_for_newtype_enum!(Error::JsonError, serde_json::Error);
+
+/// Verifies that a player logging in is who they claim to be.
+pub trait SessionService: Send + Sync {
+    /// Returns the profile of the player if they told the session server that they are joining a server with given hash.
+    /// Returns None if they did not.
+    fn has_joined(&self, username: &str, server_hash: &str) -> Result<Option<PlayerProfile>, Error>;
+}
+
+/// Everything needed to verify players in online mode.
+pub struct Authenticator {
+    key: ServerKey,
+    session_service: Box<dyn SessionService>,
+}
+
+impl Authenticator {
+    /// Generates a new key pair.
+    pub fn new(session_service: Box<dyn SessionService>) -> Result<Authenticator, Error> {
+        Ok(Authenticator {
+            key: ServerKey::generate()?,
+            session_service,
+        })
+    }
+
+    pub fn get_key(&self) -> &ServerKey {
+        &self.key
+    }
+
+    pub fn has_joined(&self, username: &str, server_hash: &str) -> Result<Option<PlayerProfile>, Error> {
+        self.session_service.has_joined(username, server_hash)
+    }
+}
+
+/// Session service speaking the yggdrasil http api.
+pub struct HttpSessionService {
+    base_url: String,
+}
+
+impl HttpSessionService {
+    pub const MOJANG_URL: &'static str = "https://sessionserver.mojang.com";
+
+    pub fn new<S: Into<String>>(base_url: S) -> HttpSessionService {
+        HttpSessionService {
+            base_url: base_url.into(),
+        }
+    }
+}
+
+impl SessionService for HttpSessionService {
+    fn has_joined(&self, username: &str, server_hash: &str) -> Result<Option<PlayerProfile>, Error> {
+        let url = format!(
+            "{}/session/minecraft/hasJoined?username={}&serverId={}",
+            self.base_url.trim_end_matches('/'),
+            url_encode(username),
+            url_encode(server_hash)
+        );
+        let (status, body) = http_get(&url)?;
+        match status {
+            200 => Ok(Some(serde_json::from_str(&body)?)),
+            204 => Ok(None),
+            s => Err(Error::UnexpectedStatus(s)),
+        }
+    }
+}
+
+/// RSA key pair the server sends in the encryption request.
+pub struct ServerKey {
+    key: Rsa<Private>,
+    public_der: Vec<u8>,
+}
+
+impl ServerKey {
+    pub fn generate() -> Result<ServerKey, Error> {
+        let key = Rsa::generate(1024)?;
+        let public_der = key.public_key_to_der()?;
+        Ok(ServerKey { key, public_der })
+    }
+
+    /// public key in X.509 DER format.
+    pub fn public_der(&self) -> &[u8] {
+        &self.public_der
+    }
+
+    /// decrypts a PKCS#1 v1.5 padded message sent by the client.
+    pub fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>, Error> {
+        let mut buf = vec![0; self.key.size() as usize];
+        let len = self.key.private_decrypt(data, &mut buf, Padding::PKCS1)?;
+        buf.truncate(len);
+        Ok(buf)
+    }
+}
+
+/// Uuid of a player in offline mode, which only depends on the name.
+pub fn offline_uuid(name: &str) -> Uuid {
+    Uuid::new_v3(&uuid::NAMESPACE_URL, &format!("OfflinePlayer:{}", name))
+}
+
+/// The hash both the client and the server send to the session server.
+/// It is the sha1 digest of server id, shared secret and public key, as a signed hex number.
+pub fn server_hash(server_id: &str, shared_secret: &[u8], public_key: &[u8]) -> String {
+    let mut sha = Sha1::new();
+    sha.update(server_id.as_bytes());
+    sha.update(shared_secret);
+    sha.update(public_key);
+    let mut digest = sha.finish();
+
+    let negative = digest[0] & 0x80 != 0;
+    if negative {
+        // two's complement
+        let mut carry = true;
+        for b in digest.iter_mut().rev() {
+            *b = !*b;
+            if carry {
+                let (v, overflow) = b.overflowing_add(1);
+                *b = v;
+                carry = overflow;
+            }
+        }
+    }
+
+    let hex: String = digest.iter().map(|b| format!("{:02x}", b)).collect();
+    let hex = hex.trim_start_matches('0');
+    if negative {
+        format!("-{}", hex)
+    } else {
+        hex.to_string()
+    }
+}
+
+fn url_encode(s: &str) -> String {
+    let mut ret = String::with_capacity(s.len());
+    for b in s.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => ret.push(b as char),
+            b => ret.push_str(&format!("%{:02X}", b)),
+        }
+    }
+    ret
+}
+
+const HTTP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Minimal http GET supporting http and https urls. Returns the status code and the body.
+fn http_get(url: &str) -> Result<(u16, String), Error> {
+    let invalid = || Error::InvalidUrl(url.into());
+    let (https, rest) = if url.starts_with("https://") {
+        (true, &url["https://".len()..])
+    } else if url.starts_with("http://") {
+        (false, &url["http://".len()..])
+    } else {
+        return Err(invalid());
+    };
+    let (authority, path) = match rest.find('/') {
+        Some(i) => (&rest[..i], &rest[i..]),
+        None => (rest, "/"),
+    };
+    let (host, port) = match authority.rfind(':') {
+        Some(i) => (&authority[..i], authority[i + 1..].parse().map_err(|_| invalid())?),
+        None => (authority, if https { 443 } else { 80 }),
+    };
+
+    let addr = (host, port).to_socket_addrs()?.next().ok_or_else(invalid)?;
+    let stream = TcpStream::connect_timeout(&addr, HTTP_TIMEOUT)?;
+    stream.set_read_timeout(Some(HTTP_TIMEOUT))?;
+    stream.set_write_timeout(Some(HTTP_TIMEOUT))?;
+
+    let request = format!(
+        "GET {} HTTP/1.0\r\nHost: {}\r\nUser-Agent: Quartz\r\nConnection: close\r\n\r\n",
+        path, host
+    );
+    let mut response = Vec::new();
+    if https {
+        let connector = SslConnector::builder(SslMethod::tls())?.build();
+        let mut stream = connector.connect(host, stream).map_err(|e| match e {
+            HandshakeError::SetupFailure(e) => Error::OpenSSLError(e),
+            e => Error::HandshakeError(e.to_string()),
+        })?;
+        stream.write_all(request.as_bytes())?;
+        stream.read_to_end(&mut response)?;
+    } else {
+        let mut stream = stream;
+        stream.write_all(request.as_bytes())?;
+        stream.read_to_end(&mut response)?;
+    }
+
+    let response = String::from_utf8_lossy(&response);
+    let status = response
+        .split_whitespace()
+        .nth(1)
+        .and_then(|s| s.parse().ok())
+        .ok_or(Error::InvalidResponse)?;
+    let body = match response.find("\r\n\r\n") {
+        Some(i) => response[i + 4..].to_string(),
+        None => String::new(),
+    };
+    Ok((status, body))
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    use super::*;
+
+    #[test]
+    fn server_hash_vectors() {
+        // known digests of sha1("Notch"), sha1("jeb_") and sha1("simon").
+        assert_eq!(server_hash("Notch", &[], &[]), "4ed1f46bbe04bc756bcb17c0c7ce3e4632f06a48");
+        assert_eq!(server_hash("jeb_", &[], &[]), "-7c9d5b0044c130109a5d7b5fb5c317c02b4e28c1");
+        assert_eq!(server_hash("simon", &[], &[]), "88e16a1019277b15d58faf0541e11910eb756f6");
+    }
+
+    #[test]
+    fn key_decrypts_client_message() {
+        let key = ServerKey::generate().unwrap();
+        let public = Rsa::public_key_from_der(key.public_der()).unwrap();
+        let mut encrypted = vec![0; public.size() as usize];
+        let len = public.public_encrypt(b"secret", &mut encrypted, Padding::PKCS1).unwrap();
+        assert_eq!(key.decrypt(&encrypted[..len]).unwrap(), b"secret");
+    }
+
+    /// serves one response per connection and returns the request lines it got.
+    fn mock_session_server(responses: Vec<&'static str>) -> (String, thread::JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let handle = thread::spawn(move || {
+            let mut requests = Vec::new();
+            for response in responses {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                requests.push(line.trim_end().to_string());
+                loop {
+                    let mut header = String::new();
+                    reader.read_line(&mut header).unwrap();
+                    if header.trim_end().is_empty() {
+                        break;
+                    }
+                }
+                (&stream).write_all(response.as_bytes()).unwrap();
+            }
+            requests
+        });
+        (url, handle)
+    }
+
+    #[test]
+    fn has_joined_against_mock() {
+        let (url, server) = mock_session_server(vec![
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n\r\n\
+             {\"id\":\"069a79f444e94726a5befca90e38aaf5\",\"name\":\"Notch\",\"properties\":\
+             [{\"name\":\"textures\",\"value\":\"dGVzdA==\",\"signature\":\"c2ln\"}]}",
+            "HTTP/1.1 204 No Content\r\n\r\n",
+        ]);
+        let service = HttpSessionService::new(url);
+
+        let profile = service.has_joined("Notch", "-1a2b").unwrap().unwrap();
+        assert_eq!(profile.name, "Notch");
+        assert_eq!(profile.id.0.simple().to_string(), "069a79f444e94726a5befca90e38aaf5");
+        assert_eq!(profile.properties[0].value, "dGVzdA==");
+        assert_eq!(profile.properties[0].signature, Some("c2ln".into()));
+
+        assert!(service.has_joined("Notch", "abc").unwrap().is_none());
+
+        let requests = server.join().unwrap();
+        assert_eq!(
+            requests[0],
+            "GET /session/minecraft/hasJoined?username=Notch&serverId=-1a2b HTTP/1.0"
+        );
+    }
+}
@@ -14,13 +14,13 @@ use serde_json;
 use uuid::Uuid;
 
 use crate::block::{Block, BlockPos, BlockStateId, Facing};
-use crate::entity::player::PlayerProfile;
+use crate::entity::player::{PlayerProfile, PlayerProperty, UuidWrapper};
 use crate::entity::status_effect::StatusEffect;
 use crate::item::item_stack::ItemStack;
 use crate::nbt::{Compound, DeserializeError, List, Tag};
-use crate::text::chat::Chat;
+use crate::text::chat::{Chat, Component, StringComponent};
 
-const FURNACE_ID: &'static str = "Furnance";
+const FURNACE_ID: &'static str = "Furnace";
 const CHEST_ID: &'static str = "Chest";
 const ENDER_CHEST_ID: &'static str = "EnderChest";
 const JUKEBOX_ID: &'static str = "RecordPlayer";
@@ -42,9 +42,53 @@ const COMPARATOR_ID: &'static str = "Comparator";
 const FLOWER_POT_ID: &'static str = "FlowerPot";
 const BANNER_ID: &'static str = "Banner";
 
-pub trait BlockEntity: Debug {
+pub trait BlockEntity: Debug + Send {
     fn to_nbt(&self) -> Compound;
     fn id(&self) -> &'static str;
+    /// Loads the state written by `to_nbt`. Missing optional fields keep their current values.
+    fn load_nbt(&mut self, compound: &Compound) -> Result<(), DeserializeError>;
+    /// Slots in the order the container window shows them. Empty if the block entity holds no items.
+    fn get_slots(&mut self) -> Vec<&mut Option<ItemStack>> {
+        Vec::new()
+    }
+    /// the name given with an anvil, shown as the window title.
+    fn get_custom_name(&self) -> Option<&str> {
+        None
+    }
+}
+
+fn write_items(compound: &mut Compound, items: &[Option<ItemStack>]) {
+    let mut list = List(Vec::new());
+    for (i, item) in items.iter().enumerate() {
+        if let Some(ref item) = *item {
+            list.0.push(item.to_nbt_with_slot(i as u8).into());
+        }
+    }
+    compound.0.insert("Items".into(), list.into());
+}
+
+/// Reads "Items" into slots. Items with a slot out of range are dropped.
+fn read_items(compound: &Compound, items: &mut [Option<ItemStack>]) -> Result<(), DeserializeError> {
+    if !compound.contains_key("Items") {
+        return Ok(());
+    }
+    for tag in compound.get("Items")?.as_list()?.0.iter() {
+        let item = tag.as_compound()?;
+        let slot = item.get("Slot")?.as_byte()? as u8 as usize;
+        if slot < items.len() {
+            items[slot] = Some(ItemStack::from_nbt(item)?);
+        }
+    }
+    Ok(())
+}
+
+fn chat_from_json(s: &str) -> Chat {
+    serde_json::from_str(s).unwrap_or_else(|_| {
+        Chat::from(Component::from(StringComponent {
+            text: s.into(),
+            base: Default::default(),
+        }))
+    })
 }
 
 #[derive(Debug, Default)]
@@ -64,7 +108,7 @@ impl ContainerHeader {
         }
     }
 
-    fn from_nbt(compound: &mut Compound) -> Result<ContainerHeader, DeserializeError> {
+    fn from_nbt(compound: &Compound) -> Result<ContainerHeader, DeserializeError> {
         let custom_name = if compound.contains_key("CustomName") {
             Some(compound.get("CustomName")?.as_string()?.to_string())
         } else {
@@ -109,12 +153,35 @@ impl BlockEntity for Furnace {
         if let Some(ref result) = self.result {
             items.0.push(result.to_nbt_with_slot(2).into());
         }
+        compound.0.insert("Items".into(), items.into());
         compound
     }
 
     fn id(&self) -> &'static str {
         FURNACE_ID
     }
+
+    fn load_nbt(&mut self, compound: &Compound) -> Result<(), DeserializeError> {
+        self.header = ContainerHeader::from_nbt(compound)?;
+        self.burn_time = compound.get("BurnTime")?.as_short()?;
+        self.cook_time = compound.get("CookTime")?.as_short()?;
+        self.cook_time_total = compound.get("CookTimeTotal")?.as_short()?;
+        let mut items: [Option<ItemStack>; 3] = Default::default();
+        read_items(compound, &mut items)?;
+        let [smelting_item, fuel, result] = items;
+        self.smelting_item = smelting_item;
+        self.fuel = fuel;
+        self.result = result;
+        Ok(())
+    }
+
+    fn get_slots(&mut self) -> Vec<&mut Option<ItemStack>> {
+        vec![&mut self.smelting_item, &mut self.fuel, &mut self.result]
+    }
+
+    fn get_custom_name(&self) -> Option<&str> {
+        self.header.custom_name.as_deref()
+    }
 }
 
 #[derive(Debug, Default)]
@@ -127,19 +194,26 @@ impl BlockEntity for Chest {
     fn to_nbt(&self) -> Compound {
         let mut compound = Compound(HashMap::new());
         self.header.update_nbt(&mut compound);
-        let mut list = List(Vec::new());
-        for (i, item) in self.items.iter().enumerate() {
-            if let Some(ref item) = *item {
-                list.0.push(item.to_nbt_with_slot(i as u8).into());
-            }
-        }
-        compound.0.insert("Items".into(), list.into());
+        write_items(&mut compound, &self.items);
         compound
     }
 
     fn id(&self) -> &'static str {
         CHEST_ID
     }
+
+    fn load_nbt(&mut self, compound: &Compound) -> Result<(), DeserializeError> {
+        self.header = ContainerHeader::from_nbt(compound)?;
+        read_items(compound, &mut self.items)
+    }
+
+    fn get_slots(&mut self) -> Vec<&mut Option<ItemStack>> {
+        self.items.iter_mut().collect()
+    }
+
+    fn get_custom_name(&self) -> Option<&str> {
+        self.header.custom_name.as_deref()
+    }
 }
 
 #[derive(Debug, Default)]
@@ -153,6 +227,10 @@ impl BlockEntity for EnderChest {
     fn id(&self) -> &'static str {
         ENDER_CHEST_ID
     }
+
+    fn load_nbt(&mut self, _compound: &Compound) -> Result<(), DeserializeError> {
+        Ok(())
+    }
 }
 
 #[derive(Debug, Default)]
@@ -174,6 +252,15 @@ impl BlockEntity for Jukebox {
     fn id(&self) -> &'static str {
         JUKEBOX_ID
     }
+
+    fn load_nbt(&mut self, compound: &Compound) -> Result<(), DeserializeError> {
+        self.record = if compound.contains_key("RecordItem") {
+            Some(ItemStack::from_nbt(compound.get("RecordItem")?.as_compound()?)?)
+        } else {
+            None
+        };
+        Ok(())
+    }
 }
 
 #[derive(Debug, Default)]
@@ -185,19 +272,27 @@ pub struct Dispenser {
 impl BlockEntity for Dispenser {
     fn to_nbt(&self) -> Compound {
         let mut com = Compound(HashMap::new());
-        let mut items = List(Vec::new());
-        for (i, item) in self.items.iter().enumerate() {
-            if let Some(ref item) = *item {
-                items.0.push(item.to_nbt_with_slot(i as u8).into());
-            }
-        }
-        com.0.insert("Items".into(), items.into());
+        self.header.update_nbt(&mut com);
+        write_items(&mut com, &self.items);
         com
     }
 
     fn id(&self) -> &'static str {
         DISPENSER_ID
     }
+
+    fn load_nbt(&mut self, compound: &Compound) -> Result<(), DeserializeError> {
+        self.header = ContainerHeader::from_nbt(compound)?;
+        read_items(compound, &mut self.items)
+    }
+
+    fn get_slots(&mut self) -> Vec<&mut Option<ItemStack>> {
+        self.items.iter_mut().collect()
+    }
+
+    fn get_custom_name(&self) -> Option<&str> {
+        self.header.custom_name.as_deref()
+    }
 }
 
 #[derive(Debug, Default)]
@@ -211,6 +306,18 @@ impl BlockEntity for Dropper {
     fn id(&self) -> &'static str {
         DROPPER_ID
     }
+
+    fn load_nbt(&mut self, compound: &Compound) -> Result<(), DeserializeError> {
+        self.0.load_nbt(compound)
+    }
+
+    fn get_slots(&mut self) -> Vec<&mut Option<ItemStack>> {
+        self.0.get_slots()
+    }
+
+    fn get_custom_name(&self) -> Option<&str> {
+        self.0.get_custom_name()
+    }
 }
 
 #[derive(Debug, Default)]
@@ -246,6 +353,14 @@ impl BlockEntity for Sign {
     fn id(&self) -> &'static str {
         SIGN_ID
     }
+
+    fn load_nbt(&mut self, compound: &Compound) -> Result<(), DeserializeError> {
+        self.text1 = chat_from_json(compound.get("Text1")?.as_string()?);
+        self.text2 = chat_from_json(compound.get("Text2")?.as_string()?);
+        self.text3 = chat_from_json(compound.get("Text3")?.as_string()?);
+        self.text4 = chat_from_json(compound.get("Text4")?.as_string()?);
+        Ok(())
+    }
 }
 
 // not supported
@@ -269,6 +384,10 @@ impl BlockEntity for MobSpawner {
     fn id(&self) -> &'static str {
         MOB_SPAWNER_ID
     }
+
+    fn load_nbt(&mut self, _compound: &Compound) -> Result<(), DeserializeError> {
+        Ok(())
+    }
 }
 
 #[derive(Debug, Default)]
@@ -286,6 +405,11 @@ impl BlockEntity for Noteblock {
     fn id(&self) -> &'static str {
         NOTEBLOCK_ID
     }
+
+    fn load_nbt(&mut self, compound: &Compound) -> Result<(), DeserializeError> {
+        self.note = compound.get("note")?.as_byte()?;
+        Ok(())
+    }
 }
 
 #[derive(Debug, Default)]
@@ -315,6 +439,17 @@ impl BlockEntity for Piston {
     fn id(&self) -> &'static str {
         PISTON_ID
     }
+
+    fn load_nbt(&mut self, compound: &Compound) -> Result<(), DeserializeError> {
+        let block_id = compound.get("blockId")?.as_int()?;
+        let block = Block::from_u8(block_id as u8)
+            .ok_or_else(|| DeserializeError::UnknownId(block_id.to_string()))?;
+        self.block = BlockStateId::new(block, compound.get("blockData")?.as_int()? as u8);
+        self.facing = Facing::from_i8(compound.get("facing")?.as_int()? as i8).unwrap_or_default();
+        self.progress = compound.get("progress")?.as_float()?;
+        self.extending = compound.get("extending")?.as_byte()? != 0;
+        Ok(())
+    }
 }
 
 #[derive(Debug, Default)]
@@ -352,6 +487,27 @@ impl BlockEntity for BrewingStand {
     fn id(&self) -> &'static str {
         BREWING_STAND_ID
     }
+
+    fn load_nbt(&mut self, compound: &Compound) -> Result<(), DeserializeError> {
+        self.header = ContainerHeader::from_nbt(compound)?;
+        self.brew_time = compound.get("BrewTime")?.as_short()?;
+        let mut items: [Option<ItemStack>; 4] = Default::default();
+        read_items(compound, &mut items)?;
+        let [left, middle, right, ingredient] = items;
+        self.left = left;
+        self.middle = middle;
+        self.right = right;
+        self.ingredient = ingredient;
+        Ok(())
+    }
+
+    fn get_slots(&mut self) -> Vec<&mut Option<ItemStack>> {
+        vec![&mut self.left, &mut self.middle, &mut self.right, &mut self.ingredient]
+    }
+
+    fn get_custom_name(&self) -> Option<&str> {
+        self.header.custom_name.as_deref()
+    }
 }
 
 #[derive(Debug, Default)]
@@ -372,6 +528,11 @@ impl BlockEntity for EnchantmentTable {
     fn id(&self) -> &'static str {
         ENCHANTMENT_TABLE_ID
     }
+
+    fn load_nbt(&mut self, compound: &Compound) -> Result<(), DeserializeError> {
+        self.custom_name = ContainerHeader::from_nbt(compound)?.custom_name;
+        Ok(())
+    }
 }
 
 #[derive(Debug, Default)]
@@ -385,6 +546,10 @@ impl BlockEntity for EndPortal {
     fn id(&self) -> &'static str {
         END_PORTAL_ID
     }
+
+    fn load_nbt(&mut self, _compound: &Compound) -> Result<(), DeserializeError> {
+        Ok(())
+    }
 }
 
 #[derive(Debug, Default)]
@@ -403,6 +568,10 @@ impl BlockEntity for CommandBlock {
     fn id(&self) -> &'static str {
         COMMAND_BLOCK_ID
     }
+
+    fn load_nbt(&mut self, _compound: &Compound) -> Result<(), DeserializeError> {
+        Ok(())
+    }
 }
 
 #[derive(Debug)]
@@ -443,6 +612,22 @@ impl BlockEntity for Beacon {
     fn id(&self) -> &'static str {
         BEACON_ID
     }
+
+    fn load_nbt(&mut self, compound: &Compound) -> Result<(), DeserializeError> {
+        self.lock = ContainerHeader::from_nbt(compound)?.lock;
+        self.primary = if compound.contains_key("Primary") {
+            StatusEffect::from_i32(compound.get("Primary")?.as_int()?)
+        } else {
+            None
+        };
+        self.secondary = if compound.contains_key("Secondary") {
+            StatusEffect::from_i32(compound.get("Secondary")?.as_int()?)
+        } else {
+            None
+        };
+        self.levels = compound.get("Levels")?.as_int()?;
+        Ok(())
+    }
 }
 
 #[derive(Debug, Default)]
@@ -458,26 +643,29 @@ impl BlockEntity for Skull {
         com.0.insert("SkullType".into(), self.skull_type.into());
         com.0.insert("Rot".into(), self.rot.into());
         if let Some(ref profile) = self.profile {
-            let mut properties = Compound(HashMap::new());
-            properties
+            let mut owner = Compound(HashMap::new());
+            owner
                 .0
-                .insert("Id".into(), profile.id.0.simple().to_string().into());
-            properties
-                .0
-                .insert("Name".into(), profile.name.clone().into());
-            let mut textures = List(Vec::new());
+                .insert("Id".into(), profile.id.0.hyphenated().to_string().into());
+            owner.0.insert("Name".into(), profile.name.clone().into());
+            // properties are grouped by name, like the skin in "textures".
+            let mut properties = Compound(HashMap::new());
             for p in &profile.properties {
-                if p.name == "textures" {
-                    let mut entry = Compound(HashMap::new());
-                    entry.0.insert(
-                        "Value".into(),
-                        serde_json::to_string(&p.value).unwrap().into(),
-                    );
-                    break;
+                let mut entry = Compound(HashMap::new());
+                entry.0.insert("Value".into(), p.value.clone().into());
+                if let Some(ref signature) = p.signature {
+                    entry.0.insert("Signature".into(), signature.clone().into());
+                }
+                let list = properties
+                    .0
+                    .entry(p.name.clone())
+                    .or_insert_with(|| List(Vec::new()).into());
+                if let Tag::List(ref mut list) = *list {
+                    list.0.push(entry.into());
                 }
             }
-            properties.0.insert("textures".into(), textures.into());
-            com.0.insert("Properties".into(), properties.into());
+            owner.0.insert("Properties".into(), properties.into());
+            com.0.insert("Owner".into(), owner.into());
         }
         com
     }
@@ -485,6 +673,49 @@ impl BlockEntity for Skull {
     fn id(&self) -> &'static str {
         SKULL_ID
     }
+
+    fn load_nbt(&mut self, compound: &Compound) -> Result<(), DeserializeError> {
+        self.skull_type = compound.get("SkullType")?.as_byte()?;
+        self.rot = compound.get("Rot")?.as_byte()?;
+        self.profile = if compound.contains_key("Owner") {
+            Some(read_profile(compound.get("Owner")?.as_compound()?)?)
+        } else {
+            None
+        };
+        Ok(())
+    }
+}
+
+/// Reads the owner of a player head. Vanilla leaves out the id of heads which only have a name.
+fn read_profile(owner: &Compound) -> Result<PlayerProfile, DeserializeError> {
+    let id = if owner.contains_key("Id") {
+        Uuid::parse_str(owner.get("Id")?.as_string()?).unwrap_or_else(|_| Uuid::nil())
+    } else {
+        Uuid::nil()
+    };
+    let name = if owner.contains_key("Name") {
+        owner.get("Name")?.as_string()?.to_string()
+    } else {
+        String::new()
+    };
+    let mut properties = Vec::new();
+    if owner.contains_key("Properties") {
+        for (name, tag) in owner.get("Properties")?.as_compound()?.0.iter() {
+            for entry in tag.as_list()?.0.iter() {
+                let entry = entry.as_compound()?;
+                properties.push(PlayerProperty {
+                    name: name.clone(),
+                    value: entry.get("Value")?.as_string()?.to_string(),
+                    signature: if entry.contains_key("Signature") {
+                        Some(entry.get("Signature")?.as_string()?.to_string())
+                    } else {
+                        None
+                    },
+                });
+            }
+        }
+    }
+    Ok(PlayerProfile { id: UuidWrapper(id), name, properties })
 }
 
 #[derive(Debug, Default)]
@@ -498,6 +729,10 @@ impl BlockEntity for DaylightDetector {
     fn id(&self) -> &'static str {
         DAYLIGHT_DETECTOR_ID
     }
+
+    fn load_nbt(&mut self, _compound: &Compound) -> Result<(), DeserializeError> {
+        Ok(())
+    }
 }
 
 #[derive(Debug, Default)]
@@ -511,13 +746,7 @@ impl BlockEntity for Hopper {
     fn to_nbt(&self) -> Compound {
         let mut compound = Compound(HashMap::new());
         self.header.update_nbt(&mut compound);
-        let mut list = List(Vec::new());
-        for (i, item) in self.items.iter().enumerate() {
-            if let Some(ref item) = *item {
-                list.0.push(item.to_nbt_with_slot(i as u8).into());
-            }
-        }
-        compound.0.insert("Items".into(), list.into());
+        write_items(&mut compound, &self.items);
         compound
             .0
             .insert("TransferCooldown".into(), self.transfer_cooldown.into());
@@ -527,6 +756,20 @@ impl BlockEntity for Hopper {
     fn id(&self) -> &'static str {
         HOPPER_ID
     }
+
+    fn load_nbt(&mut self, compound: &Compound) -> Result<(), DeserializeError> {
+        self.header = ContainerHeader::from_nbt(compound)?;
+        self.transfer_cooldown = compound.get("TransferCooldown")?.as_int()?;
+        read_items(compound, &mut self.items)
+    }
+
+    fn get_slots(&mut self) -> Vec<&mut Option<ItemStack>> {
+        self.items.iter_mut().collect()
+    }
+
+    fn get_custom_name(&self) -> Option<&str> {
+        self.header.custom_name.as_deref()
+    }
 }
 
 #[derive(Debug, Default)]
@@ -545,6 +788,11 @@ impl BlockEntity for Comparator {
     fn id(&self) -> &'static str {
         COMPARATOR_ID
     }
+
+    fn load_nbt(&mut self, compound: &Compound) -> Result<(), DeserializeError> {
+        self.output_signal = compound.get("OutputSignal")?.as_int()?;
+        Ok(())
+    }
 }
 
 #[derive(Debug, Default)]
@@ -567,6 +815,16 @@ impl BlockEntity for FlowerPot {
     fn id(&self) -> &'static str {
         FLOWER_POT_ID
     }
+
+    fn load_nbt(&mut self, compound: &Compound) -> Result<(), DeserializeError> {
+        let block = match *compound.get("Item")? {
+            Tag::String(ref name) => Block::from_name(name.trim_start_matches("minecraft:")),
+            ref tag => Block::from_u8(tag.as_int()? as u8),
+        };
+        let data = compound.get("Data")?.as_int()?;
+        self.item = BlockStateId::new(block.unwrap_or_default(), data as u8);
+        Ok(())
+    }
 }
 
 #[derive(Debug, Default)]
@@ -594,4 +852,43 @@ impl BlockEntity for Banner {
     fn id(&self) -> &'static str {
         BANNER_ID
     }
+
+    fn load_nbt(&mut self, compound: &Compound) -> Result<(), DeserializeError> {
+        self.base = DyeColor::from_i32(compound.get("Base")?.as_int()?).unwrap_or_default();
+        self.patterns.clear();
+        if compound.contains_key("Patterns") {
+            for tag in compound.get("Patterns")?.as_list()?.0.iter() {
+                let pattern = tag.as_compound()?;
+                let color = DyeColor::from_i32(pattern.get("Color")?.as_int()?);
+                let name = pattern.get("Pattern")?.as_string()?;
+                if let (Some(color), Some(pattern)) = (color, BannerPattern::from_name(name)) {
+                    self.patterns.push((color, pattern));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn skull_keeps_owner() {
+        let mut skull = Skull::default();
+        skull.skull_type = 3;
+        skull.profile = Some(PlayerProfile {
+            id: UuidWrapper(Uuid::parse_str("069a79f4-44e9-4726-a5be-fca90e38aaf5").unwrap()),
+            name: "Notch".into(),
+            properties: vec![PlayerProperty { name: "textures".into(), value: "e30=".into(), signature: Some("sig".into()) }],
+        });
+        let compound = skull.to_nbt();
+        let mut loaded = Skull::default();
+        loaded.load_nbt(&compound).unwrap();
+        assert_eq!(loaded.to_nbt(), compound);
+        let profile = loaded.profile.unwrap();
+        assert_eq!(profile.name, "Notch");
+        assert_eq!(profile.properties[0].signature.as_ref().unwrap(), "sig");
+    }
 }
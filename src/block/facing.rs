@@ -28,4 +28,36 @@ impl Facing {
             _ => None,
         }
     }
+
+    pub fn opposite(&self) -> Facing {
+        match *self {
+            Facing::Down => Facing::Up,
+            Facing::Up => Facing::Down,
+            Facing::North => Facing::South,
+            Facing::South => Facing::North,
+            Facing::West => Facing::East,
+            Facing::East => Facing::West,
+        }
+    }
+
+    /// Horizontal direction an entity with given yaw looks in.
+    pub fn from_yaw(yaw: f64) -> Facing {
+        match (yaw / 90.0 + 0.5).floor() as i64 & 3 {
+            0 => Facing::South,
+            1 => Facing::West,
+            2 => Facing::North,
+            _ => Facing::East,
+        }
+    }
+
+    /// index of horizontal facings used in block metadata. south is 0, then clockwise. None for up and down.
+    pub fn get_horizontal_index(&self) -> Option<u8> {
+        match *self {
+            Facing::South => Some(0),
+            Facing::West => Some(1),
+            Facing::North => Some(2),
+            Facing::East => Some(3),
+            Facing::Down | Facing::Up => None,
+        }
+    }
 }
\ No newline at end of file
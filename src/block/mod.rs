@@ -13,6 +13,7 @@ use self::block_entity::BlockEntity;
 use crate::binary;
 use crate::entity::player::Player;
 use crate::item::item_id;
+use crate::item::tool::{Tool, ToolKind};
 use crate::proto;
 use crate::server::ServerContext;
 use crate::sound::{self, Sound};
@@ -1860,6 +1861,607 @@ impl Block {
         }
     }
 
+    /// How hard the block is to dig. Negative for blocks which can't be broken.
+    pub fn get_hardness(&self) -> f32 {
+        use self::Block::*;
+        match *self {
+            Air => 0.0,
+            Stone => 1.5,
+            Grass => 0.6,
+            Dirt => 0.5,
+            Cobblestone => 2.0,
+            Planks => 2.0,
+            Sapling => 0.0,
+            Bedrock => -1.0,
+            FlowingWater => 100.0,
+            Water => 100.0,
+            FlowingLava => 100.0,
+            Lava => 100.0,
+            Sand => 0.5,
+            Gravel => 0.6,
+            GoldOre => 3.0,
+            IronOre => 3.0,
+            CoalOre => 3.0,
+            Log => 2.0,
+            Leaves => 0.2,
+            Sponge => 0.6,
+            Glass => 0.3,
+            LapisOre => 3.0,
+            LapisBlock => 3.0,
+            Dispenser => 3.5,
+            Sandstone => 0.8,
+            Noteblock => 0.8,
+            Bed => 0.2,
+            GoldenRail => 0.7,
+            DetectorRail => 0.7,
+            StickyPiston => 0.5,
+            Web => 4.0,
+            Tallgrass => 0.0,
+            Deadbush => 0.0,
+            Piston => 0.5,
+            PistonHead => 0.5,
+            Wool => 0.8,
+            PistonExtension => -1.0,
+            YellowFlower => 0.0,
+            RedFlower => 0.0,
+            BrownMushroom => 0.0,
+            RedMushroom => 0.0,
+            GoldBlock => 3.0,
+            IronBlock => 5.0,
+            DoubleStoneSlab => 2.0,
+            StoneSlab => 2.0,
+            BrickBlock => 2.0,
+            Tnt => 0.0,
+            Bookshelf => 1.5,
+            MossyCobblestone => 2.0,
+            Obsidian => 50.0,
+            Torch => 0.0,
+            Fire => 0.0,
+            MobSpawner => 5.0,
+            OakStairs => 2.0,
+            Chest => 2.5,
+            RedstoneWire => 0.0,
+            DiamondOre => 3.0,
+            DiamondBlock => 5.0,
+            CraftingTable => 2.5,
+            Wheat => 0.0,
+            Farmland => 0.6,
+            Furnace => 3.5,
+            LitFurnace => 3.5,
+            StandingSign => 1.0,
+            WoodenDoor => 3.0,
+            Ladder => 0.4,
+            Rail => 0.7,
+            StoneStairs => 2.0,
+            WallSign => 1.0,
+            Lever => 0.5,
+            StonePressurePlate => 0.5,
+            IronDoor => 5.0,
+            WoodenPressurePlate => 0.5,
+            RedstoneOre => 3.0,
+            LitRedstoneOre => 3.0,
+            UnlitRedstoneTorch => 0.0,
+            RedstoneTorch => 0.0,
+            StoneButton => 0.5,
+            SnowLayer => 0.1,
+            Ice => 0.5,
+            Snow => 0.2,
+            Cactus => 0.4,
+            Clay => 0.6,
+            Reeds => 0.0,
+            Jukebox => 2.0,
+            Fence => 2.0,
+            Pumpkin => 1.0,
+            Netherrack => 0.4,
+            SoulSand => 0.5,
+            Glowstone => 0.3,
+            Portal => -1.0,
+            LitPumpkin => 1.0,
+            Cake => 0.5,
+            UnpoweredRepeater => 0.0,
+            PoweredRepeater => 0.0,
+            StainedGlass => 0.3,
+            Trapdoor => 3.0,
+            MonsterEgg => 0.75,
+            Stonebrick => 1.5,
+            BrownMushroomBlock => 0.2,
+            RedMushroomBlock => 0.2,
+            IronBars => 5.0,
+            GlassPane => 0.3,
+            MelonBlock => 1.0,
+            PumpkinStem => 0.0,
+            MelonStem => 0.0,
+            Vine => 0.2,
+            FenceGate => 2.0,
+            BrickStairs => 2.0,
+            StoneBrickStairs => 1.5,
+            Mycelium => 0.6,
+            Waterlily => 0.0,
+            NetherBrick => 2.0,
+            NetherBrickFence => 2.0,
+            NetherBrickStairs => 2.0,
+            NetherWart => 0.0,
+            EnchantingTable => 5.0,
+            BrewingStand => 0.5,
+            Cauldron => 2.0,
+            EndPortal => -1.0,
+            EndPortalFrame => -1.0,
+            EndStone => 3.0,
+            DragonEgg => 3.0,
+            RedstoneLamp => 0.3,
+            LitRedstoneLamp => 0.3,
+            DoubleWoodenSlab => 2.0,
+            WoodenSlab => 2.0,
+            Cocoa => 0.2,
+            SandstoneStairs => 0.8,
+            EmeraldOre => 3.0,
+            EnderChest => 22.5,
+            TripwireHook => 0.0,
+            Tripwire => 0.0,
+            EmeraldBlock => 5.0,
+            SpruceStairs => 2.0,
+            BirchStairs => 2.0,
+            JungleStairs => 2.0,
+            CommandBlock => -1.0,
+            Beacon => 3.0,
+            CobblestoneWall => 2.0,
+            FlowerPot => 0.0,
+            Carrots => 0.0,
+            Potatoes => 0.0,
+            WoodenButton => 0.5,
+            Skull => 1.0,
+            Anvil => 5.0,
+            TrappedChest => 2.5,
+            LightWeightedPressurePlate => 0.5,
+            HeavyWeightedPressurePlate => 0.5,
+            UnpoweredComparator => 0.0,
+            PoweredComparator => 0.0,
+            DaylightDetector => 0.2,
+            RedstoneBlock => 5.0,
+            QuartzOre => 3.0,
+            Hopper => 3.0,
+            QuartzBlock => 0.8,
+            QuartzStairs => 0.8,
+            ActivatorRail => 0.7,
+            Dropper => 3.5,
+            StainedHardenedClay => 1.25,
+            StainedGlassPane => 0.3,
+            Leaves2 => 0.2,
+            Log2 => 2.0,
+            AcaciaStairs => 2.0,
+            DarkOakStairs => 2.0,
+            Slime => 0.0,
+            Barrier => -1.0,
+            IronTrapdoor => 5.0,
+            Prismarine => 1.5,
+            SeaLantern => 0.3,
+            HayBlock => 0.5,
+            Carpet => 0.1,
+            HardenedClay => 1.25,
+            CoalBlock => 5.0,
+            PackedIce => 0.5,
+            DoublePlant => 0.0,
+            StandingBanner => 1.0,
+            WallBanner => 1.0,
+            DaylightDetectorInverted => 0.2,
+            RedSandstone => 0.8,
+            RedSandstoneStairs => 0.8,
+            DoubleStoneSlab2 => 2.0,
+            StoneSlab2 => 2.0,
+            SpruceFenceGate => 2.0,
+            BirchFenceGate => 2.0,
+            JungleFenceGate => 2.0,
+            DarkOakFenceGate => 2.0,
+            AcaciaFenceGate => 2.0,
+            SpruceFence => 2.0,
+            BirchFence => 2.0,
+            JungleFence => 2.0,
+            DarkOakFence => 2.0,
+            AcaciaFence => 2.0,
+            SpruceDoor => 3.0,
+            BirchDoor => 3.0,
+            JungleDoor => 3.0,
+            AcaciaDoor => 3.0,
+            DarkOakDoor => 3.0,
+        }
+    }
+
+    fn get_material(&self) -> Material {
+        use self::Block::*;
+        match *self {
+            Stone | Cobblestone | Bedrock | GoldOre | IronOre | CoalOre | LapisOre | LapisBlock | Dispenser | Sandstone
+            | DoubleStoneSlab | StoneSlab | BrickBlock | MossyCobblestone | Obsidian | MobSpawner | DiamondOre | Furnace
+            | LitFurnace | StoneStairs | StonePressurePlate | RedstoneOre | LitRedstoneOre | Netherrack | Stonebrick
+            | BrickStairs | StoneBrickStairs | NetherBrick | NetherBrickFence | NetherBrickStairs | EnchantingTable
+            | EndPortalFrame | EndStone | SandstoneStairs | EmeraldOre | EnderChest | CobblestoneWall | QuartzOre
+            | QuartzBlock | QuartzStairs | Dropper | StainedHardenedClay | HardenedClay | Prismarine | CoalBlock
+            | RedSandstone | RedSandstoneStairs | DoubleStoneSlab2 | StoneSlab2 => Material::Rock,
+            GoldBlock | IronBlock | IronDoor | IronBars | DiamondBlock | EmeraldBlock | BrewingStand | Cauldron
+            | RedstoneBlock | Hopper | IronTrapdoor | LightWeightedPressurePlate | HeavyWeightedPressurePlate => Material::Iron,
+            Anvil => Material::Anvil,
+            Planks | Log | Log2 | Bookshelf | Chest | TrappedChest | CraftingTable | OakStairs | SpruceStairs
+            | BirchStairs | JungleStairs | AcaciaStairs | DarkOakStairs | Fence | SpruceFence | BirchFence | JungleFence
+            | DarkOakFence | AcaciaFence | FenceGate | SpruceFenceGate | BirchFenceGate | JungleFenceGate
+            | DarkOakFenceGate | AcaciaFenceGate | WoodenDoor | SpruceDoor | BirchDoor | JungleDoor | AcaciaDoor
+            | DarkOakDoor | StandingSign | WallSign | Jukebox | Noteblock | Trapdoor | DoubleWoodenSlab | WoodenSlab
+            | WoodenPressurePlate | BrownMushroomBlock | RedMushroomBlock | StandingBanner | WallBanner
+            | DaylightDetector | DaylightDetectorInverted => Material::Wood,
+            Sapling | YellowFlower | RedFlower | BrownMushroom | RedMushroom | Wheat | Reeds | PumpkinStem | MelonStem
+            | Waterlily | NetherWart | Cocoa | Carrots | Potatoes => Material::Plants,
+            Tallgrass | Deadbush | Vine | DoublePlant => Material::Vine,
+            Leaves | Leaves2 => Material::Leaves,
+            Pumpkin | LitPumpkin | MelonBlock => Material::Gourd,
+            Web => Material::Web,
+            SnowLayer | Snow => Material::Snow,
+            Wool => Material::Cloth,
+            _ => Material::Other,
+        }
+    }
+
+    /// Lowest pickaxe level which gets drops from the block.
+    fn get_harvest_level(&self) -> u8 {
+        use self::Block::*;
+        match *self {
+            Obsidian => 3,
+            DiamondOre | DiamondBlock | EmeraldOre | EmeraldBlock | GoldOre | GoldBlock | RedstoneOre | LitRedstoneOre => 2,
+            IronOre | IronBlock | LapisOre | LapisBlock => 1,
+            _ => 0,
+        }
+    }
+
+    /// Whether the block drops anything when broken with the tool.
+    pub fn can_harvest(&self, tool: Option<Tool>) -> bool {
+        let kind = tool.map(|t| t.kind);
+        match self.get_material() {
+            Material::Rock | Material::Iron | Material::Anvil => {
+                kind == Some(ToolKind::Pickaxe) && tool.unwrap().level >= self.get_harvest_level()
+            }
+            Material::Web => kind == Some(ToolKind::Sword) || kind == Some(ToolKind::Shears),
+            Material::Snow => kind == Some(ToolKind::Shovel),
+            _ => true,
+        }
+    }
+
+    /// How much faster the tool digs the block than a hand.
+    pub fn get_dig_speed(&self, tool: Option<Tool>) -> f32 {
+        use self::Block::*;
+        let tool = match tool {
+            Some(tool) => tool,
+            None => return 1.0,
+        };
+        let material = self.get_material();
+        let effective = match tool.kind {
+            ToolKind::Pickaxe => match material {
+                Material::Rock | Material::Iron | Material::Anvil => true,
+                _ => match *self {
+                    Ice | PackedIce | Rail | GoldenRail | DetectorRail | ActivatorRail => true,
+                    _ => false,
+                },
+            },
+            ToolKind::Axe => match material {
+                Material::Wood | Material::Plants | Material::Vine | Material::Gourd => true,
+                _ => *self == Ladder || *self == WoodenButton,
+            },
+            ToolKind::Shovel => match *self {
+                Clay | Dirt | Farmland | Grass | Gravel | Mycelium | Sand | Snow | SnowLayer | SoulSand => true,
+                _ => false,
+            },
+            ToolKind::Sword => {
+                return match material {
+                    Material::Web => 15.0,
+                    Material::Plants | Material::Vine | Material::Leaves | Material::Gourd => 1.5,
+                    _ => 1.0,
+                };
+            }
+            ToolKind::Shears => {
+                return match material {
+                    Material::Web | Material::Leaves => 15.0,
+                    Material::Cloth => 5.0,
+                    _ => 1.0,
+                };
+            }
+        };
+        if effective { tool.efficiency } else { 1.0 }
+    }
+
+    /// Part of the block dug each tick. The block breaks once this adds up to 1.
+    pub fn get_dig_progress(&self, tool: Option<Tool>, on_ground: bool, in_water: bool) -> f32 {
+        let hardness = self.get_hardness();
+        if hardness < 0.0 {
+            return 0.0;
+        }
+        if hardness == 0.0 {
+            return 1.0;
+        }
+        let mut speed = self.get_dig_speed(tool);
+        // like vanilla, digging while jumping or falling is slow, and so is digging under water.
+        if !on_ground {
+            speed /= 5.0;
+        }
+        if in_water {
+            speed /= 5.0;
+        }
+        speed / hardness / if self.can_harvest(tool) { 30.0 } else { 100.0 }
+    }
+
+    /// Items the block drops when broken with the tool, as id, damage and count. random gives numbers from 0 to 1 for
+    /// drops which vary.
+    pub fn get_drops(&self, meta: u8, tool: Option<Tool>, random: &mut dyn FnMut() -> f32) -> Vec<(u16, i16, i8)> {
+        use self::Block::*;
+        fn between(random: &mut dyn FnMut() -> f32, min: i8, max: i8) -> i8 {
+            (min + (random() * (max - min + 1) as f32) as i8).min(max)
+        }
+
+        if !self.can_harvest(tool) {
+            return Vec::new();
+        }
+        let shears = tool.map_or(false, |t| t.kind == ToolKind::Shears);
+        let itself = (self.to_u8() as u16, 0, 1);
+        let variant = (self.to_u8() as u16, meta as i16, 1);
+        let drop = match *self {
+            Air | FlowingWater | Water | FlowingLava | Lava | Bedrock | Fire | Portal | EndPortal | EndPortalFrame
+            | PistonExtension | PistonHead | MobSpawner | Glass | GlassPane | StainedGlass | StainedGlassPane | Ice
+            | PackedIce | MonsterEgg | Barrier | CommandBlock | Cake => return Vec::new(),
+            Stone if meta == 0 => (item_id::COBBLESTONE, 0, 1),
+            Stone | Dirt | Planks | Sand | Sponge | Wool | YellowFlower | RedFlower | Stonebrick | Sandstone
+            | RedSandstone | Prismarine | CobblestoneWall | StainedHardenedClay | Carpet => variant,
+            Sapling | StoneSlab | WoodenSlab | StoneSlab2 => (self.to_u8() as u16, (meta & 7) as i16, 1),
+            Log | Log2 => (self.to_u8() as u16, (meta & 3) as i16, 1),
+            QuartzBlock => (item_id::QUARTZ_BLOCK, if meta >= 2 { 2 } else { meta as i16 }, 1),
+            Anvil => (item_id::ANVIL, (meta >> 2) as i16, 1),
+            DoubleStoneSlab => (item_id::STONE_SLAB, (meta & 7) as i16, 2),
+            DoubleWoodenSlab => (item_id::WOODEN_SLAB, (meta & 7) as i16, 2),
+            DoubleStoneSlab2 => (item_id::STONE_SLAB2, 0, 2),
+            Grass | Mycelium | Farmland => (item_id::DIRT, 0, 1),
+            CoalOre => (item_id::COAL, 0, 1),
+            DiamondOre => (item_id::DIAMOND, 0, 1),
+            EmeraldOre => (item_id::EMERALD, 0, 1),
+            QuartzOre => (item_id::QUARTZ, 0, 1),
+            LapisOre => (item_id::DYE, 4, between(random, 4, 8)),
+            RedstoneOre | LitRedstoneOre => (item_id::REDSTONE, 0, between(random, 4, 5)),
+            Glowstone => (item_id::GLOWSTONE_DUST, 0, between(random, 2, 4)),
+            SeaLantern => (item_id::PRISMARINE_CRYSTALS, 0, between(random, 2, 3)),
+            Gravel if random() < 0.1 => (item_id::FLINT, 0, 1),
+            Leaves | Leaves2 if shears => (self.to_u8() as u16, (meta & 3) as i16, 1),
+            Leaves | Leaves2 => {
+                let wood = if *self == Leaves { meta & 3 } else { (meta & 3) + 4 };
+                let mut drops = Vec::new();
+                if random() < 0.05 {
+                    drops.push((item_id::SAPLING, wood as i16, 1));
+                }
+                // oak and dark oak.
+                if (wood == 0 || wood == 5) && random() < 0.005 {
+                    drops.push((item_id::APPLE, 0, 1));
+                }
+                return drops;
+            }
+            Tallgrass | Vine if shears => variant,
+            Tallgrass if random() < 0.125 => (item_id::WHEAT_SEEDS, 0, 1),
+            Tallgrass | Vine => return Vec::new(),
+            Deadbush if shears => itself,
+            Deadbush => (item_id::STICK, 0, between(random, 0, 2)),
+            Web if shears => itself,
+            Web | Tripwire => (item_id::STRING, 0, 1),
+            DoublePlant if meta & 8 != 0 || meta == 2 || meta == 3 => return Vec::new(),
+            DoublePlant => variant,
+            Bookshelf => (item_id::BOOK, 0, 3),
+            Clay => (item_id::CLAY_BALL, 0, 4),
+            Snow => (item_id::SNOWBALL, 0, 4),
+            SnowLayer => (item_id::SNOWBALL, 0, (meta & 7) as i8 + 1),
+            MelonBlock => (item_id::MELON, 0, between(random, 3, 7)),
+            BrownMushroomBlock | RedMushroomBlock => {
+                let mushroom = if *self == BrownMushroomBlock { item_id::BROWN_MUSHROOM } else { item_id::RED_MUSHROOM };
+                (mushroom, 0, between(random, -7, 2).max(0))
+            }
+            Wheat if meta >= 7 => {
+                return vec![(item_id::WHEAT_ITEM, 0, 1), (item_id::WHEAT_SEEDS, 0, between(random, 1, 3))];
+            }
+            Wheat => (item_id::WHEAT_SEEDS, 0, 1),
+            Carrots => (item_id::CARROT, 0, if meta >= 7 { between(random, 1, 4) } else { 1 }),
+            Potatoes => (item_id::POTATO, 0, if meta >= 7 { between(random, 1, 4) } else { 1 }),
+            NetherWart => (item_id::NETHER_WART_ITEM, 0, if meta >= 3 { between(random, 2, 4) } else { 1 }),
+            Cocoa => (item_id::DYE, 3, if meta >> 2 >= 2 { 3 } else { 1 }),
+            PumpkinStem => (item_id::PUMPKIN_SEEDS, 0, 1),
+            MelonStem => (item_id::MELON_SEEDS, 0, 1),
+            Reeds => (item_id::REEDS_ITEM, 0, 1),
+            // beds and doors drop from one half only.
+            Bed | WoodenDoor | IronDoor | SpruceDoor | BirchDoor | JungleDoor | AcaciaDoor | DarkOakDoor if meta & 8 != 0 => {
+                return Vec::new();
+            }
+            Bed => (item_id::BED_ITEM, 0, 1),
+            WoodenDoor => (item_id::WOODEN_DOOR_ITEM, 0, 1),
+            IronDoor => (item_id::IRON_DOOR_ITEM, 0, 1),
+            SpruceDoor => (item_id::SPRUCE_DOOR_ITEM, 0, 1),
+            BirchDoor => (item_id::BIRCH_DOOR_ITEM, 0, 1),
+            JungleDoor => (item_id::JUNGLE_DOOR_ITEM, 0, 1),
+            AcaciaDoor => (item_id::ACACIA_DOOR_ITEM, 0, 1),
+            DarkOakDoor => (item_id::DARK_OAK_DOOR_ITEM, 0, 1),
+            StandingSign | WallSign => (item_id::SIGN, 0, 1),
+            RedstoneWire => (item_id::REDSTONE, 0, 1),
+            UnpoweredRepeater | PoweredRepeater => (item_id::REPEATER, 0, 1),
+            UnpoweredComparator | PoweredComparator => (item_id::COMPARATOR, 0, 1),
+            BrewingStand => (item_id::BREWING_STAND_ITEM, 0, 1),
+            Cauldron => (item_id::CAULDRON_ITEM, 0, 1),
+            FlowerPot => (item_id::FLOWER_POT_ITEM, 0, 1),
+            Skull => (item_id::SKULL_ITEM, 0, 1),
+            StandingBanner | WallBanner => (item_id::BANNER, 0, 1),
+            LitFurnace => (item_id::FURNACE, 0, 1),
+            LitRedstoneLamp => (item_id::REDSTONE_LAMP, 0, 1),
+            UnlitRedstoneTorch => (item_id::REDSTONE_TORCH, 0, 1),
+            DaylightDetectorInverted => (item_id::DAYLIGHT_DETECTOR, 0, 1),
+            _ => itself,
+        };
+        if drop.2 <= 0 {
+            return Vec::new();
+        }
+        vec![drop]
+    }
+
+    /// Block placed by the item. Most blocks are their own item, a few like doors and redstone are placed by others.
+    pub fn from_item_id(id: u16) -> Option<Block> {
+        use self::Block::*;
+        match id {
+            id if id < 256 => Block::from_u8(id as u8),
+            item_id::SIGN => Some(StandingSign),
+            item_id::WOODEN_DOOR_ITEM => Some(WoodenDoor),
+            item_id::IRON_DOOR_ITEM => Some(IronDoor),
+            item_id::SPRUCE_DOOR_ITEM => Some(SpruceDoor),
+            item_id::BIRCH_DOOR_ITEM => Some(BirchDoor),
+            item_id::JUNGLE_DOOR_ITEM => Some(JungleDoor),
+            item_id::ACACIA_DOOR_ITEM => Some(AcaciaDoor),
+            item_id::DARK_OAK_DOOR_ITEM => Some(DarkOakDoor),
+            item_id::REDSTONE => Some(RedstoneWire),
+            item_id::STRING => Some(Tripwire),
+            item_id::REEDS_ITEM => Some(Reeds),
+            item_id::CAKE_ITEM => Some(Cake),
+            item_id::REPEATER => Some(UnpoweredRepeater),
+            item_id::COMPARATOR => Some(UnpoweredComparator),
+            item_id::BREWING_STAND_ITEM => Some(BrewingStand),
+            item_id::CAULDRON_ITEM => Some(Cauldron),
+            item_id::FLOWER_POT_ITEM => Some(FlowerPot),
+            _ => None,
+        }
+    }
+
+    /// Whether placing a block into this one replaces it, like tall grass.
+    pub fn is_replaceable(&self, meta: u8) -> bool {
+        use self::Block::*;
+        match *self {
+            Air | FlowingWater | Water | FlowingLava | Lava | Tallgrass | Deadbush | Vine | Fire | DoublePlant => true,
+            // only a single layer.
+            SnowLayer => meta & 7 == 0,
+            _ => false,
+        }
+    }
+
+    /// Whether entities bump into the block. Blocks with collision are treated as full cubes.
+    pub fn has_collision(&self) -> bool {
+        use self::Block::*;
+        match *self {
+            Air | Sapling | FlowingWater | Water | FlowingLava | Lava | GoldenRail | DetectorRail | Rail | ActivatorRail
+            | Web | Tallgrass | Deadbush | YellowFlower | RedFlower | BrownMushroom | RedMushroom | Torch | Fire
+            | RedstoneWire | Wheat | StandingSign | WallSign | Lever | StonePressurePlate | WoodenPressurePlate
+            | LightWeightedPressurePlate | HeavyWeightedPressurePlate | UnlitRedstoneTorch | RedstoneTorch
+            | StoneButton | WoodenButton | SnowLayer | Reeds | Portal | PumpkinStem | MelonStem | Vine | NetherWart
+            | EndPortal | TripwireHook | Tripwire | Carrots | Potatoes | DoublePlant | StandingBanner | WallBanner => false,
+            _ => true,
+        }
+    }
+
+    pub fn is_door(&self) -> bool {
+        use self::Block::*;
+        match *self {
+            WoodenDoor | IronDoor | SpruceDoor | BirchDoor | JungleDoor | AcaciaDoor | DarkOakDoor => true,
+            _ => false,
+        }
+    }
+
+    /// Block a slab turns into when another slab of the same kind is put on it.
+    pub fn get_double_slab(&self) -> Option<Block> {
+        use self::Block::*;
+        match *self {
+            StoneSlab => Some(DoubleStoneSlab),
+            WoodenSlab => Some(DoubleWoodenSlab),
+            StoneSlab2 => Some(DoubleStoneSlab2),
+            _ => None,
+        }
+    }
+
+    /// Metadata of the block when placed by an item with given damage. Decides which way stairs, logs, torches and the
+    /// like face. None if the block can't be placed against that face, like torches on ceilings.
+    /// The top half of doors is left to the caller.
+    pub fn get_placement_meta(&self, damage: i16, placement: &Placement) -> Option<u8> {
+        use self::Block::*;
+        let damage = damage as u8 & 15;
+        let face = placement.face;
+        let horizontal = Facing::from_yaw(placement.yaw);
+        let horizontal_index = horizontal.get_horizontal_index().unwrap_or(0);
+        let top_half = face == Facing::Down || (face != Facing::Up && placement.cursor_y > 0.5);
+        // logs and pillars lie along the axis of the clicked face.
+        let axis = match face {
+            Facing::Down | Facing::Up => 0,
+            Facing::West | Facing::East => 1,
+            Facing::North | Facing::South => 2,
+        };
+        let meta = match *self {
+            OakStairs | StoneStairs | BrickStairs | StoneBrickStairs | NetherBrickStairs | SandstoneStairs
+            | SpruceStairs | BirchStairs | JungleStairs | QuartzStairs | AcaciaStairs | DarkOakStairs
+            | RedSandstoneStairs => {
+                let meta = match horizontal {
+                    Facing::East => 0,
+                    Facing::West => 1,
+                    Facing::South => 2,
+                    _ => 3,
+                };
+                if top_half { meta | 4 } else { meta }
+            }
+            StoneSlab | WoodenSlab | StoneSlab2 => if top_half { damage & 7 | 8 } else { damage & 7 },
+            Log | Log2 => damage & 3 | axis << 2,
+            HayBlock => axis << 2,
+            // only pillars have an axis.
+            QuartzBlock if damage >= 2 => 2 + axis,
+            Torch | RedstoneTorch | UnlitRedstoneTorch => match face {
+                Facing::East => 1,
+                Facing::West => 2,
+                Facing::South => 3,
+                Facing::North => 4,
+                Facing::Up => 5,
+                Facing::Down => return None,
+            },
+            StoneButton | WoodenButton => match face {
+                Facing::Down => 0,
+                Facing::East => 1,
+                Facing::West => 2,
+                Facing::South => 3,
+                Facing::North => 4,
+                Facing::Up => 5,
+            },
+            Lever => match face {
+                Facing::Down => if axis_of(horizontal) == 0 { 0 } else { 7 },
+                Facing::East => 1,
+                Facing::West => 2,
+                Facing::South => 3,
+                Facing::North => 4,
+                Facing::Up => if axis_of(horizontal) == 0 { 6 } else { 5 },
+            },
+            Ladder | WallSign | WallBanner => match face {
+                Facing::Down | Facing::Up => return None,
+                face => face.to_i8() as u8,
+            },
+            // clicking the side of a block makes a wall sign, the player handles that.
+            StandingSign | StandingBanner => (((placement.yaw + 180.0) * 16.0 / 360.0 + 0.5).floor() as i64 & 15) as u8,
+            Trapdoor | IronTrapdoor => {
+                let meta = match face {
+                    Facing::North => 0,
+                    Facing::South => 1,
+                    Facing::West => 2,
+                    Facing::East => 3,
+                    Facing::Down | Facing::Up => return None,
+                };
+                if top_half { meta | 8 } else { meta }
+            }
+            Chest | TrappedChest | EnderChest | Furnace | LitFurnace => horizontal.opposite().to_i8() as u8,
+            Pumpkin | LitPumpkin | UnpoweredRepeater | UnpoweredComparator => (horizontal_index + 2) & 3,
+            FenceGate | SpruceFenceGate | BirchFenceGate | JungleFenceGate | DarkOakFenceGate | AcaciaFenceGate => {
+                horizontal_index
+            }
+            // anvils stand sideways to the player.
+            Anvil => (damage >> 2) << 2 | (horizontal_index + 1) & 3,
+            Piston | StickyPiston | Dispenser | Dropper => placement.facing.opposite().to_i8() as u8,
+            // hoppers point into the clicked block, or down when placed on top of one.
+            Hopper => match face {
+                Facing::Up => 0,
+                face => face.opposite().to_i8() as u8,
+            },
+            // the lower half. the upper half has the hinge instead.
+            WoodenDoor | IronDoor | SpruceDoor | BirchDoor | JungleDoor | AcaciaDoor | DarkOakDoor => {
+                (horizontal_index + 1) & 3
+            }
+            _ => damage,
+        };
+        Some(meta)
+    }
+
     pub fn create_new_block_entity(&self) -> Option<Box<dyn BlockEntity>> {
         use self::Block::*;
         match *self {
@@ -1892,9 +2494,104 @@ impl Block {
     }
 }
 
+/// What a block is made of. Decides which tools dig it and whether it needs one to drop anything.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Material {
+    Rock,
+    Iron,
+    Anvil,
+    Wood,
+    Plants,
+    Vine,
+    Leaves,
+    Gourd,
+    Web,
+    Snow,
+    Cloth,
+    Other,
+}
+
+/// 0 for the x axis, 1 for z. Only used for horizontal facings.
+fn axis_of(facing: Facing) -> u8 {
+    match facing {
+        Facing::West | Facing::East => 0,
+        _ => 1,
+    }
+}
+
+/// How a player placed a block.
+#[derive(Debug, Clone, Copy)]
+pub struct Placement {
+    /// side of the clicked block.
+    pub face: Facing,
+    /// height on that side the player clicked at, from 0 to 1.
+    pub cursor_y: f32,
+    pub yaw: f64,
+    /// direction the player looks in. Up or down when they are close to the block and above or below it.
+    pub facing: Facing,
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct BlockSounds {
     pub breaks: Sound,
     pub step: Sound,
     pub place: Sound,
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn dig_timing() {
+        let iron_pickaxe = Tool::from_item_id(item_id::IRON_PICKAXE);
+        // ticks vanilla takes: stone by hand is 7.5 seconds, with an iron pickaxe 0.4.
+        assert_eq!((1.0 / Block::Stone.get_dig_progress(None, true, false)).ceil(), 150.0);
+        assert_eq!((1.0 / Block::Stone.get_dig_progress(iron_pickaxe, true, false)).ceil(), 8.0);
+        assert_eq!((1.0 / Block::Dirt.get_dig_progress(None, false, false)).ceil(), 75.0);
+        assert_eq!((1.0 / Block::Dirt.get_dig_progress(None, false, true)).ceil(), 375.0);
+        assert_eq!(Block::Tallgrass.get_dig_progress(None, true, false), 1.0);
+        assert_eq!(Block::Bedrock.get_dig_progress(iron_pickaxe, true, false), 0.0);
+        assert!(!Block::Obsidian.can_harvest(iron_pickaxe));
+        assert!(Block::Obsidian.can_harvest(Tool::from_item_id(item_id::DIAMOND_PICKAXE)));
+        assert!(Block::Web.can_harvest(Tool::from_item_id(item_id::SHEARS)));
+        assert_eq!(Block::Log.get_dig_speed(Tool::from_item_id(item_id::GOLDEN_AXE)), 12.0);
+        assert_eq!(Block::Log.get_dig_speed(iron_pickaxe), 1.0);
+    }
+
+    #[test]
+    fn drops() {
+        let pickaxe = Tool::from_item_id(item_id::STONE_PICKAXE);
+        let mut low = || 0.0;
+        let mut high = || 0.99;
+        assert_eq!(Block::Stone.get_drops(0, pickaxe, &mut low), vec![(item_id::COBBLESTONE, 0, 1)]);
+        assert!(Block::Stone.get_drops(0, None, &mut low).is_empty());
+        assert_eq!(Block::Wool.get_drops(14, None, &mut low), vec![(item_id::WOOL, 14, 1)]);
+        assert_eq!(Block::LapisOre.get_drops(0, pickaxe, &mut low), vec![(item_id::DYE, 4, 4)]);
+        assert_eq!(Block::LapisOre.get_drops(0, pickaxe, &mut high), vec![(item_id::DYE, 4, 8)]);
+        assert_eq!(Block::DoubleStoneSlab.get_drops(3, pickaxe, &mut low), vec![(item_id::STONE_SLAB, 3, 2)]);
+        assert_eq!(Block::Furnace.get_drops(5, pickaxe, &mut low), vec![(item_id::FURNACE, 0, 1)]);
+        assert!(Block::WoodenDoor.get_drops(8, None, &mut low).is_empty());
+        assert!(Block::Leaves.get_drops(0, None, &mut high).is_empty());
+    }
+
+    #[test]
+    fn placement_meta() {
+        let placement = |face, cursor_y, yaw| Placement { face, cursor_y, yaw, facing: Facing::from_yaw(yaw) };
+        let on_top = placement(Facing::Up, 1.0, 0.0);
+        // looking south, east or north.
+        assert_eq!(Block::OakStairs.get_placement_meta(0, &on_top), Some(2));
+        assert_eq!(Block::OakStairs.get_placement_meta(0, &placement(Facing::West, 0.75, 270.0)), Some(4));
+        assert_eq!(Block::Chest.get_placement_meta(0, &placement(Facing::Up, 1.0, 180.0)), Some(3));
+        assert_eq!(Block::StoneSlab.get_placement_meta(3, &placement(Facing::North, 0.75, 0.0)), Some(11));
+        assert_eq!(Block::StoneSlab.get_placement_meta(3, &on_top), Some(3));
+        assert_eq!(Block::Log.get_placement_meta(2, &placement(Facing::East, 0.5, 0.0)), Some(6));
+        assert_eq!(Block::Torch.get_placement_meta(0, &placement(Facing::North, 0.5, 0.0)), Some(4));
+        assert_eq!(Block::Torch.get_placement_meta(0, &placement(Facing::Down, 0.0, 0.0)), None);
+        assert_eq!(Block::WoodenDoor.get_placement_meta(0, &on_top), Some(1));
+        assert_eq!(Block::Wool.get_placement_meta(14, &on_top), Some(14));
+        assert_eq!(Block::from_item_id(item_id::IRON_DOOR_ITEM), Some(Block::IronDoor));
+        assert!(Block::SnowLayer.is_replaceable(0));
+        assert!(!Block::SnowLayer.is_replaceable(3));
+    }
+}
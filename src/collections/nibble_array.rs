@@ -21,6 +21,12 @@ impl NibbleArray {
         ret
     }
 
+    /// Creates an array from packed nibbles, low nibble first.
+    pub fn from_bytes(bytes: Vec<u8>) -> NibbleArray {
+        let len = bytes.len() * 2;
+        NibbleArray { backend: bytes, len }
+    }
+
     pub fn len(&self) -> usize {
         self.len
     }
@@ -0,0 +1,98 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use uuid::Uuid;
+
+use crate::entity::metadata::{EntityMetadata, MetadataEntry};
+use crate::item::item_stack::ItemStack;
+use crate::math::Vec3;
+use crate::proto::packets::{SPacket, SPlaySpawnObjectData};
+use crate::server::playerlist::PlayerList;
+use crate::server::TICKS_PER_SEC;
+use crate::world::chunk::ChunkPos;
+use crate::world::World;
+
+/// object type of items in SpawnObject.
+const OBJECT_TYPE: i8 = 2;
+/// metadata index of the item stack.
+const METADATA_ITEM: u8 = 10;
+/// items disappear after 5 minutes like vanilla.
+pub const DESPAWN_TICKS: u64 = 5 * 60 * TICKS_PER_SEC;
+
+/// An item lying in the world, like the drops of broken blocks.
+/// The server only keeps where it was dropped. Clients make it fall and bounce themselves.
+#[derive(Debug)]
+pub struct ItemEntity {
+    entity_id: i32,
+    stack: ItemStack,
+    pos: Vec3,
+    /// blocks per tick. only sent when the item spawns.
+    velocity: Vec3,
+    spawn_tick: u64,
+    /// players the item was spawned for.
+    viewers: HashSet<Uuid>,
+}
+
+impl ItemEntity {
+    pub fn new(entity_id: i32, stack: ItemStack, pos: Vec3, velocity: Vec3, spawn_tick: u64) -> ItemEntity {
+        ItemEntity { entity_id, stack, pos, velocity, spawn_tick, viewers: HashSet::new() }
+    }
+
+    pub fn get_entity_id(&self) -> i32 {
+        self.entity_id
+    }
+
+    pub fn is_expired(&self, tick: u64) -> bool {
+        tick - self.spawn_tick >= DESPAWN_TICKS
+    }
+
+    fn spawn_packets(&self) -> [Arc<SPacket>; 2] {
+        // velocity is in 1/8000 blocks per tick.
+        let velocity = |v: f64| (v * 8000.0).max(i16::min_value() as f64).min(i16::max_value() as f64) as i16;
+        let mut metadata = EntityMetadata::new();
+        metadata.insert(METADATA_ITEM, MetadataEntry::Slot(self.stack.to_slot_data()));
+        [
+            Arc::new(SPacket::PlaySpawnObject {
+                entity_id: self.entity_id,
+                object_type: OBJECT_TYPE,
+                x: self.pos.x,
+                y: self.pos.y,
+                z: self.pos.z,
+                pitch: 0.0,
+                yaw: 0.0,
+                data: Some(SPlaySpawnObjectData {
+                    data: 1,
+                    velocity_x: velocity(self.velocity.x),
+                    velocity_y: velocity(self.velocity.y),
+                    velocity_z: velocity(self.velocity.z),
+                }),
+            }),
+            Arc::new(SPacket::PlayEntityMetadata { entity_id: self.entity_id, metadata }),
+        ]
+    }
+
+    /// Spawns the item for players who have its chunk loaded, and removes it for players who don't anymore.
+    pub fn update_viewers(&mut self, world: &World, player_list: &PlayerList) {
+        let chunk_pos = ChunkPos::from(self.pos);
+        let viewers: HashSet<Uuid> = world.get_players_in_vicinity(chunk_pos).collect();
+        let spawn = self.spawn_packets();
+        for &p in viewers.difference(&self.viewers) {
+            for packet in &spawn {
+                player_list.send_packet_to_player(p, Arc::clone(packet));
+            }
+        }
+        let destroy = Arc::new(SPacket::PlayDestroyEntities { entity_ids: vec![self.entity_id] });
+        for &p in self.viewers.difference(&viewers) {
+            player_list.send_packet_to_player(p, Arc::clone(&destroy));
+        }
+        self.viewers = viewers;
+    }
+
+    /// Removes the item for everyone who sees it.
+    pub fn despawn(&mut self, player_list: &PlayerList) {
+        let destroy = Arc::new(SPacket::PlayDestroyEntities { entity_ids: vec![self.entity_id] });
+        for p in self.viewers.drain() {
+            player_list.send_packet_to_player(p, Arc::clone(&destroy));
+        }
+    }
+}
@@ -1,9 +1,9 @@
-use std::io::{self, Write};
-use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+use std::collections::BTreeMap;
 
 use crate::proto::data::SlotData;
 use crate::binary;
-use crate::proto::data;
+use crate::proto::{self, data};
 
 #[derive(Debug)]
 pub enum MetadataEntry {
@@ -41,17 +41,25 @@ impl MetadataEntry {
 }
 
 #[derive(Debug)]
-pub struct EntityMetadata(HashMap<u8, MetadataEntry>);
+pub struct EntityMetadata(BTreeMap<u8, MetadataEntry>);
 
 impl EntityMetadata {
     pub fn new() -> EntityMetadata {
-        EntityMetadata(HashMap::new())
+        EntityMetadata(BTreeMap::new())
     }
 
     pub fn insert(&mut self, key: u8, value: MetadataEntry) {
         self.0.insert(key, value);
     }
 
+    pub fn get(&self, key: u8) -> Option<&MetadataEntry> {
+        self.0.get(&key)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (u8, &MetadataEntry)> {
+        self.0.iter().map(|(&i, v)| (i, v))
+    }
+
     pub fn write_proto<W: Write>(&self, w: &mut W) -> io::Result<()> {
         for (&i, v) in self.0.iter() {
             let byte = (i as u8 & 0x1f) | (v.get_type() << 5);
@@ -78,4 +86,32 @@ impl EntityMetadata {
         binary::write_ubyte(w, 127)?;
         Ok(())
     }
-}
\ No newline at end of file
+    pub fn read_proto<R: BufRead>(r: &mut R) -> proto::Result<EntityMetadata> {
+        let mut metadata = EntityMetadata::new();
+        loop {
+            let byte = binary::read_ubyte(r)?;
+            if byte == 127 {
+                return Ok(metadata);
+            }
+            let value = match byte >> 5 {
+                0 => MetadataEntry::Byte(binary::read_byte(r)?),
+                1 => MetadataEntry::Short(binary::read_ishort(r)?),
+                2 => MetadataEntry::Int(binary::read_int(r)?),
+                3 => MetadataEntry::Float(binary::read_float(r)?),
+                4 => MetadataEntry::String(data::read_string(r)?),
+                5 => MetadataEntry::Slot(SlotData::read(r)?),
+                6 => MetadataEntry::Pos {
+                    x: binary::read_int(r)?,
+                    y: binary::read_int(r)?,
+                    z: binary::read_int(r)?,
+                },
+                _ => MetadataEntry::Orientation {
+                    pitch: binary::read_float(r)?,
+                    yaw: binary::read_float(r)?,
+                    roll: binary::read_float(r)?,
+                },
+            };
+            metadata.insert(byte & 0x1f, value);
+        }
+    }
+}
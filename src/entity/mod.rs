@@ -1,3 +1,4 @@
+pub mod item;
 pub mod player;
 pub mod metadata;
-pub mod status_effect;
\ No newline at end of file
+pub mod status_effect;
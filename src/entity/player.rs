@@ -1,7 +1,7 @@
 use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::net::SocketAddr;
-use std::sync::mpsc::{Receiver, Sender};
+use std::sync::mpsc::Receiver;
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
@@ -13,59 +13,141 @@ use serde_json;
 use uuid::Uuid;
 
 use crate::binary::double_to_fixed_point;
-use crate::block::{Block, BlockPos, BlockStateId, Facing};
+use crate::block::{Block, BlockPos, BlockStateId, Facing, Placement};
+use crate::entity::item::ItemEntity;
 use crate::entity::metadata::{EntityMetadata, MetadataEntry};
+use crate::item::container::{ContainerKind, ContainerWindow};
+use crate::item::inventory::{self, Inventory};
 use crate::item::item_stack::ItemStack;
-use crate::item::BlockItem;
+use crate::item::tool::Tool;
+use crate::item::window::{self, Click, Cursor};
+use crate::item::{BlockItem, GenericItem};
 use crate::math::Vec3;
+use crate::network::PacketSender;
+use crate::nbt::{Compound, DeserializeError, List, Nbt, Tag};
 use crate::proto::data::SlotData;
-use crate::proto::packets::{CPacket, SPacket, SPlayPlayerListItemData, SPlayPlayerListItemDataAction};
+use crate::proto::packets::{
+    CPacket, SPacket, SPlayPlayerListItemData, SPlayPlayerListItemDataAction, SPlayerListItemProperty,
+};
+use crate::server::permissions::{self, PermissionSet};
 use crate::server::playerlist::PlayerList;
 use crate::server::{Difficulty, Gamemode, ServerContext, ServerInfo, TICKS_PER_SEC};
-use crate::text::chat::Chat;
+use crate::text::chat::{Chat, Component, StringComponent, TranslationComponent};
 use crate::text::{self, ChatPos, Code};
 use crate::util;
 use crate::world::chunk::{Chunk, ChunkPos};
 use crate::world::{ChunkRectangle, Dimension, LevelType, World};
 
+/// squared distance from which players may dig or place blocks.
+const MAX_REACH_SQ: f64 = 36.0;
+/// vanilla lets clients finish digging a bit early, in case of lag.
+const MIN_DIG_PROGRESS: f32 = 0.7;
+const HALF_WIDTH: f64 = 0.3;
+const HEIGHT: f64 = 1.8;
+const EYE_HEIGHT: f64 = 1.62;
+/// squared distance from which players may use blocks like chests. Open containers close beyond it.
+const MAX_USE_DISTANCE_SQ: f64 = 64.0;
+/// window ids of containers go from 1 to this, 0 is the player inventory.
+const MAX_WINDOW_ID: u8 = 100;
+
+#[derive(Debug)]
+struct Digging {
+    location: BlockPos,
+    /// part of the block dug so far, added up each tick. 1 breaks it.
+    progress: f32,
+    /// crack stage other players see. -1 for none.
+    stage: i8,
+}
+
+/// Container the player looks into.
+#[derive(Debug)]
+struct OpenContainer {
+    window_id: u8,
+    kind: ContainerKind,
+    /// blocks holding the items, in window order. Large chests have two.
+    locations: Vec<BlockPos>,
+}
+
+fn slot_data(item: &Option<ItemStack>) -> SlotData {
+    item.as_ref().map_or(SlotData::Empty, |i| i.to_slot_data())
+}
+
+/// Chests of the same kind next to location, which a chest there joins.
+fn get_chest_neighbors(location: BlockPos, block: Block, world: &World) -> Vec<BlockPos> {
+    [Facing::North, Facing::South, Facing::West, Facing::East]
+        .iter()
+        .filter_map(|&f| location.offset(f))
+        .filter(|&l| world.get_block(l).map(|b| b.get_type()) == Some(block))
+        .collect()
+}
+
 #[derive(Debug)]
 pub struct Player {
     name: String,
     uuid: Uuid,
+    /// properties from the session server. (skin, cape) empty in offline mode.
+    properties: Vec<PlayerProperty>,
 
     connected: Arc<Mutex<bool>>,
     packet_recv_queue: Receiver<CPacket>,
-    packet_send_queue: Sender<Arc<SPacket>>,
+    packet_send_queue: PacketSender,
 
     ip: SocketAddr,
+    /// round trip time in ms, smoothed like vanilla.
     ping: i32,
+    /// id of the last keep-alive sent.
     last_keep_alive: i32,
     time_of_last_keep_alive: Instant,
+    /// whether the client hasn't answered the last keep-alive yet. no new one is sent until it does.
+    keep_alive_pending: bool,
     join_tick: u64,
 
     pos: Vec3,
     yaw: f64,
     pitch: f64,
     on_ground: bool,
+    sneaking: bool,
 
     dimension: Dimension,
     gamemode: Gamemode,
     entity_id: i32,
     players_in_vicinity: HashSet<Uuid>,
+    /// commands typed in chat. the server runs them after ticking players.
+    pending_commands: Vec<String>,
+    /// tab complete requests with the block the player looks at. answered after ticking players.
+    pending_tab_completes: Vec<(String, Option<BlockPos>)>,
+    /// block being dug in survival.
+    digging: Option<Digging>,
+    /// 0 for normal players, 4 for operators.
+    permission_level: u8,
+    /// from permissions.json. Nodes it doesn't decide fall back to the permission level.
+    permissions: PermissionSet,
+
+    inventory: Inventory,
+    cursor: Cursor,
+    /// click the client got wrong. Further clicks are ignored until the client confirms it was told.
+    unconfirmed_action: Option<i16>,
+    open_container: Option<OpenContainer>,
+    last_window_id: u8,
+    health: f32,
+    food_level: i32,
+    food_saturation: f32,
+    food_exhaustion: f32,
 }
 
 impl Player {
     pub fn new(
-        name: String,
-        uuid: Uuid,
-        packet_send_queue: Sender<Arc<SPacket>>,
+        profile: PlayerProfile,
+        packet_send_queue: PacketSender,
         packet_recv_queue: Receiver<CPacket>,
         ip: SocketAddr,
         connected: Arc<Mutex<bool>>,
+        gamemode: Gamemode,
     ) -> Player {
         Player {
-            name,
-            uuid,
+            name: profile.name,
+            uuid: profile.id.0,
+            properties: profile.properties,
 
             connected,
             packet_recv_queue,
@@ -75,18 +157,134 @@ impl Player {
             ping: 0,
             last_keep_alive: 0,
             time_of_last_keep_alive: Instant::now(),
+            keep_alive_pending: false,
             join_tick: 0,
 
             pos: Vec3::new(7.5, 82.0, 7.5),
             yaw: 0.0,
             pitch: 0.0,
             on_ground: true,
+            sneaking: false,
 
             dimension: Dimension::Overworld,
-            gamemode: Gamemode::Creative,
+            gamemode,
             entity_id: 0,
             players_in_vicinity: HashSet::new(),
+            pending_commands: Vec::new(),
+            pending_tab_completes: Vec::new(),
+            digging: None,
+            permission_level: 0,
+            permissions: PermissionSet::default(),
+
+            inventory: Self::starting_inventory(),
+            cursor: Cursor::default(),
+            unconfirmed_action: None,
+            open_container: None,
+            last_window_id: 0,
+            health: 20.0,
+            food_level: 20,
+            food_saturation: 5.0,
+            food_exhaustion: 0.0,
+        }
+    }
+
+    fn starting_inventory() -> Inventory {
+        let mut inventory = Inventory::new();
+        inventory.set_main(
+            0,
+            Some(ItemStack::new(
+                Box::new(BlockItem {
+                    id: BlockStateId::new(Block::Chest, 0),
+                    block_entity: Block::Chest.create_new_block_entity(),
+                    can_place_on: None,
+                }),
+                10,
+            )),
+        );
+        inventory
+    }
+
+    /// Writes the player in the format of vanilla playerdata/<uuid>.dat.
+    pub fn to_nbt(&self) -> Nbt {
+        let mut c = Compound(HashMap::new());
+        let pos = List(vec![self.pos.x.into(), self.pos.y.into(), self.pos.z.into()]);
+        c.0.insert("Pos".into(), pos.into());
+        let rotation = List(vec![(self.yaw as f32).into(), (self.pitch as f32).into()]);
+        c.0.insert("Rotation".into(), rotation.into());
+        c.0.insert("OnGround".into(), self.on_ground.into());
+        c.0.insert("Dimension".into(), (self.dimension as i32).into());
+        c.0.insert("playerGameType".into(), (self.gamemode as i32).into());
+        c.0.insert("Inventory".into(), self.inventory.to_nbt().into());
+        c.0.insert("SelectedItemSlot".into(), (self.inventory.get_held_slot() as i32).into());
+        // 1.8 reads HealF, older versions the short.
+        c.0.insert("HealF".into(), self.health.into());
+        c.0.insert("Health".into(), (self.health.ceil() as i16).into());
+        c.0.insert("foodLevel".into(), self.food_level.into());
+        c.0.insert("foodSaturationLevel".into(), self.food_saturation.into());
+        c.0.insert("foodExhaustionLevel".into(), self.food_exhaustion.into());
+        Nbt::Some("".into(), c)
+    }
+
+    /// Loads what was written by `to_nbt`. Missing fields keep their defaults.
+    pub fn load_nbt(&mut self, nbt: &Nbt) -> Result<(), DeserializeError> {
+        let c = match *nbt {
+            Nbt::Some(_, ref c) => c,
+            Nbt::Empty => return Ok(()),
+        };
+
+        if c.contains_key("Pos") {
+            let pos = c.get("Pos")?.as_list()?;
+            self.pos = Vec3::new(
+                pos.get(0)?.as_double()?,
+                pos.get(1)?.as_double()?,
+                pos.get(2)?.as_double()?,
+            );
+        }
+        if c.contains_key("Rotation") {
+            let rotation = c.get("Rotation")?.as_list()?;
+            self.yaw = rotation.get(0)?.as_float()? as f64;
+            self.pitch = rotation.get(1)?.as_float()? as f64;
+        }
+        if c.contains_key("OnGround") {
+            self.on_ground = c.get("OnGround")?.as_byte()? != 0;
+        }
+        if c.contains_key("Dimension") {
+            let dimension = c.get("Dimension")?.as_int()?;
+            self.dimension = Dimension::from_i32(dimension).unwrap_or(Dimension::Overworld);
+        }
+        if c.contains_key("playerGameType") {
+            let gamemode = c.get("playerGameType")?.as_int()?;
+            self.gamemode = Gamemode::from_u8(gamemode as u8).unwrap_or(Gamemode::Survival);
+        }
+        if c.contains_key("Inventory") {
+            self.inventory = Inventory::new();
+            self.inventory.load_nbt(c.get("Inventory")?.as_list()?)?;
         }
+        if c.contains_key("SelectedItemSlot") {
+            self.inventory.set_held_slot(c.get("SelectedItemSlot")?.as_int()? as u8);
+        }
+        if c.contains_key("HealF") {
+            self.health = c.get("HealF")?.as_float()?;
+        } else if c.contains_key("Health") {
+            self.health = match *c.get("Health")? {
+                Tag::Float(x) => x,
+                ref tag => tag.as_short()? as f32,
+            };
+        }
+        if c.contains_key("foodLevel") {
+            self.food_level = c.get("foodLevel")?.as_int()?;
+        }
+        if c.contains_key("foodSaturationLevel") {
+            self.food_saturation = c.get("foodSaturationLevel")?.as_float()?;
+        }
+        if c.contains_key("foodExhaustionLevel") {
+            self.food_exhaustion = c.get("foodExhaustionLevel")?.as_float()?;
+        }
+        Ok(())
+    }
+
+    pub fn get_ip(&self) -> SocketAddr {
+        self.ip
     }
 
     pub fn get_name(&self) -> &str {
@@ -97,6 +295,17 @@ impl Player {
         self.uuid
     }
 
+    pub fn get_list_item_properties(&self) -> Vec<SPlayerListItemProperty> {
+        self.properties
+            .iter()
+            .map(|p| SPlayerListItemProperty {
+                name: p.name.clone(),
+                value: p.value.clone(),
+                signature: p.signature.clone(),
+            })
+            .collect()
+    }
+
     pub fn get_ping(&self) -> i32 {
         self.ping
     }
@@ -113,6 +322,69 @@ impl Player {
         self.gamemode
     }
 
+    pub fn set_gamemode(&mut self, gamemode: Gamemode, ctx: &mut ServerContext) {
+        self.gamemode = gamemode;
+        self.send_packet(Arc::new(SPacket::PlayChangeGameState {
+            reason: 3,
+            value: gamemode as u8 as f32,
+        }));
+        self.send_packet(Arc::new(self.abilities_packet()));
+        let uuid = self.uuid;
+        self.send_packet_to_all_players(
+            ctx,
+            Arc::new(SPacket::PlayPlayerListItem {
+                players: vec![Arc::new(SPlayPlayerListItemData {
+                    uuid,
+                    action: SPlayPlayerListItemDataAction::UpdateGamemode {
+                        gamemode: gamemode as i32,
+                    },
+                })],
+            }),
+        );
+    }
+
+    fn abilities_packet(&self) -> SPacket {
+        let flags = match self.gamemode {
+            Gamemode::Creative => 0x08 | 0x04,
+            Gamemode::Spectator => 0x04 | 0x02,
+            Gamemode::Survival | Gamemode::Adventure => 0,
+        };
+        SPacket::PlayPlayerAbilities {
+            flags,
+            flying_speed: 0.05,
+            field_of_view_modifier: 2.0,
+        }
+    }
+
+    pub fn take_pending_commands(&mut self) -> Vec<String> {
+        ::std::mem::replace(&mut self.pending_commands, Vec::new())
+    }
+
+    pub fn take_pending_tab_completes(&mut self) -> Vec<(String, Option<BlockPos>)> {
+        ::std::mem::replace(&mut self.pending_tab_completes, Vec::new())
+    }
+
+    pub fn get_permission_level(&self) -> u8 {
+        self.permission_level
+    }
+
+    pub fn set_permission_level(&mut self, permission_level: u8) {
+        self.permission_level = permission_level;
+    }
+
+    pub fn get_permissions(&self) -> &PermissionSet {
+        &self.permissions
+    }
+
+    pub fn set_permissions(&mut self, permissions: PermissionSet) {
+        self.permissions = permissions;
+    }
+
+    /// Whether the player has the node, or the permission level it needs if no node decides.
+    pub fn has_permission(&self, node: &str) -> bool {
+        self.permissions.check(node).unwrap_or_else(|| self.permission_level >= permissions::default_level(node))
+    }
+
     pub fn get_connected(&self) -> bool {
         *self.connected.lock().unwrap()
     }
@@ -121,6 +393,15 @@ impl Player {
         *self.connected.lock().unwrap() = c;
     }
 
+    /// Disconnects the player, showing them the reason. The connection is closed once the reason is written and the
+    /// server removes the player on the next tick.
+    pub fn kick(&mut self, reason: Chat) {
+        info!("{} was kicked: {}", self.name, reason.to_plain());
+        self.send_packet(Arc::new(SPacket::PlayDisconnect { reason }));
+        self.packet_send_queue.close();
+        self.set_connected(false);
+    }
+
     pub fn get_entity_id(&self) -> i32 {
         self.entity_id
     }
@@ -134,10 +415,667 @@ impl Player {
         ChunkRectangle::centered(self.get_chunk_pos(), view_distance)
     }
 
+    /// Tool in the hand, if the held item is one.
+    fn get_held_tool(&self) -> Option<Tool> {
+        self.inventory.get_held_item().and_then(|i| Tool::from_item_id(i.get_item().get_id()))
+    }
+
+    /// Whether the eyes are in water, which slows digging down.
+    fn is_head_in_water(&self, world: &World) -> bool {
+        let y = (self.pos.y + EYE_HEIGHT).floor();
+        if y < 0.0 || y > 255.0 {
+            return false;
+        }
+        let eyes = BlockPos::new(self.pos.x.floor() as i32, y as u8, self.pos.z.floor() as i32);
+        match world.get_block(eyes).map(|b| b.get_type()) {
+            Some(Block::Water) | Some(Block::FlowingWater) => true,
+            _ => false,
+        }
+    }
+
+    /// Part of the block the player digs this tick.
+    fn get_dig_progress(&self, block: Block, world: &World) -> f32 {
+        block.get_dig_progress(self.get_held_tool(), self.on_ground, self.is_head_in_water(world))
+    }
+
+    /// Whether the block is close enough to dig or place against, measured like vanilla.
+    fn can_reach(&self, location: BlockPos) -> bool {
+        let dx = self.pos.x - (location.x as f64 + 0.5);
+        let dy = self.pos.y - (location.y as f64 + 0.5) + 1.5;
+        let dz = self.pos.z - (location.z as f64 + 0.5);
+        dx * dx + dy * dy + dz * dz <= MAX_REACH_SQ
+    }
+
+    fn start_digging(&mut self, location: BlockPos, ctx: &mut ServerContext) {
+        let block = match ctx.world.get_block(location) {
+            Some(x) => x,
+            None => return,
+        };
+        let may_dig = match self.gamemode {
+            Gamemode::Survival | Gamemode::Creative => true,
+            Gamemode::Adventure | Gamemode::Spectator => false,
+        };
+        // air can't be dug. Unbreakable blocks like bedrock can only be broken in creative.
+        let breakable = block.get_type() != Block::Air
+            && (self.gamemode == Gamemode::Creative || block.get_type().get_hardness() >= 0.0);
+        if !may_dig || !breakable || !self.has_permission(permissions::BREAK_BLOCKS) || !self.can_reach(location) {
+            self.resend_block(location, block);
+            return;
+        }
+        self.cancel_digging(ctx);
+        if self.gamemode == Gamemode::Creative {
+            self.break_block(location, block, false, ctx);
+        } else if self.get_dig_progress(block.get_type(), &ctx.world) >= 1.0 {
+            self.break_block(location, block, true, ctx);
+        } else {
+            self.digging = Some(Digging { location, progress: 0.0, stage: -1 });
+        }
+    }
+
+    fn cancel_digging(&mut self, ctx: &mut ServerContext) {
+        if let Some(digging) = self.digging.take() {
+            self.send_break_animation(digging.location, -1, ctx);
+        }
+    }
+
+    /// Breaks the block if the player dug it long enough. Clients which finish early get the block back.
+    fn finish_digging(&mut self, location: BlockPos, ctx: &mut ServerContext) {
+        let block = match ctx.world.get_block(location) {
+            Some(x) => x,
+            None => return,
+        };
+        let dug = match self.digging {
+            Some(ref digging) if digging.location == location => digging.progress,
+            _ => {
+                self.resend_block(location, block);
+                return;
+            }
+        };
+        self.cancel_digging(ctx);
+        // the client counts the tick it finishes on too.
+        let progress = dug + self.get_dig_progress(block.get_type(), &ctx.world);
+        if progress < MIN_DIG_PROGRESS || !self.can_reach(location) {
+            debug!("{} finished digging {:?} too early ({:.2} dug)", self.name, location, progress);
+            self.resend_block(location, block);
+            return;
+        }
+        self.break_block(location, block, true, ctx);
+    }
+
+    /// Digs on for a tick and shows how far the block is dug to other players.
+    fn update_digging(&mut self, ctx: &mut ServerContext) {
+        let (location, dug, stage) = match self.digging {
+            Some(ref digging) => (digging.location, digging.progress, digging.stage),
+            None => return,
+        };
+        let block = match ctx.world.get_block(location) {
+            Some(x) if x.get_type() != Block::Air => x,
+            // someone else broke it.
+            _ => {
+                self.cancel_digging(ctx);
+                return;
+            }
+        };
+        let progress = dug + self.get_dig_progress(block.get_type(), &ctx.world);
+        let new_stage = ((progress * 10.0) as i8).min(9);
+        if let Some(ref mut digging) = self.digging {
+            digging.progress = progress;
+            digging.stage = new_stage;
+        }
+        if new_stage != stage {
+            self.send_break_animation(location, new_stage, ctx);
+        }
+    }
+
+    /// stage is 0 to 9, or -1 to remove the cracks.
+    fn send_break_animation(&self, location: BlockPos, stage: i8, ctx: &mut ServerContext) {
+        let packet = Arc::new(SPacket::PlayBlockBreakAnimation { entity_id: self.entity_id, location, destroy_stage: stage });
+        self.send_packet_to_chunk_vicinity(ChunkPos::from(location), ctx, packet);
+    }
+
+    /// Sets the block to air and tells other players. The player already removed it on their side.
+    fn break_block(&mut self, location: BlockPos, block: BlockStateId, drop_items: bool, ctx: &mut ServerContext) {
+        // containers spill what they hold, even when broken in creative.
+        let mut contents = Vec::new();
+        if let Some(block_entity) = ctx.world.get_block_entity_mut(location) {
+            contents.extend(block_entity.get_slots().into_iter().filter_map(|s| s.take()));
+        }
+        ctx.world.set_block(location, BlockStateId::AIR);
+        for stack in contents {
+            ctx.world.drop_item(ctx.server_info.next_entity_id(), location, stack, ctx.server_info.tick);
+        }
+        let chunk_pos = ChunkPos::from(location);
+        let packet = Arc::new(SPacket::PlayBlockChange {
+            location,
+            block_id: BlockStateId::AIR.to_u16() as i32,
+        });
+        self.send_packet_to_chunk_vicinity(chunk_pos, ctx, packet);
+
+        let sounds = block.get_type().get_sounds();
+        if let Some(sounds) = sounds {
+            self.send_packet_to_chunk_vicinity(
+                chunk_pos,
+                ctx,
+                Arc::new(SPacket::PlaySoundEffect {
+                    name: sounds.breaks.name.into(),
+                    effect_pos_x: location.x as f64 + 0.5,
+                    effect_pos_y: location.y as f64 + 0.5,
+                    effect_pos_z: location.z as f64 + 0.5,
+                    volume: sounds.breaks.volume,
+                    pitch: sounds.breaks.pitch,
+                }),
+            );
+        }
+
+        if drop_items {
+            let drops = block.get_type().get_drops(block.get_meta(), self.get_held_tool(), &mut util::random_f32);
+            for (id, damage, count) in drops {
+                let stack = ItemStack::new(Box::new(GenericItem { id, damage, tag: None }), count);
+                ctx.world.drop_item(ctx.server_info.next_entity_id(), location, stack, ctx.server_info.tick);
+            }
+        }
+    }
+
+    /// Undoes a change the client made itself, like breaking a block it may not break.
+    fn resend_block(&mut self, location: BlockPos, block: BlockStateId) {
+        self.send_packet(Arc::new(SPacket::PlayBlockChange { location, block_id: block.to_u16() as i32 }));
+    }
+
+    /// Corrects the held item after the client used it up for something the server didn't allow.
+    fn resend_held_item(&mut self) {
+        let slot = Inventory::main_to_window_slot(self.inventory.get_held_slot() as usize) as i16;
+        let slot_data = self.inventory.get_held_item().map_or(SlotData::Empty, |i| i.to_slot_data());
+        self.send_packet(Arc::new(SPacket::PlaySetSlot { window_id: 0, slot, slot_data }));
+    }
+
+    /// Sends the whole inventory and the cursor, after the client got something wrong.
+    fn resend_inventory(&mut self) {
+        self.send_packet(Arc::new(SPacket::PlayWindowItems { window_id: 0, slots: self.inventory.to_window_slots() }));
+        self.send_cursor();
+    }
+
+    fn send_cursor(&mut self) {
+        let slot_data = self.cursor.item.as_ref().map_or(SlotData::Empty, |i| i.to_slot_data());
+        self.send_packet(Arc::new(SPacket::PlaySetSlot {
+            window_id: window::CURSOR as i8,
+            slot: window::CURSOR,
+            slot_data,
+        }));
+    }
+
+    fn click_window(
+        &mut self,
+        window_id: u8,
+        slot: i16,
+        button: i8,
+        action_num: i16,
+        mode: i8,
+        clicked_item: &SlotData,
+        ctx: &mut ServerContext,
+    ) {
+        if self.unconfirmed_action.is_some() || self.gamemode == Gamemode::Spectator {
+            return;
+        }
+        let creative = self.gamemode == Gamemode::Creative;
+        let click = if window_id == 0 {
+            window::click(&mut self.inventory, &mut self.cursor, slot, button, mode, creative)
+        } else if self.open_container.as_ref().map(|c| c.window_id) == Some(window_id) {
+            self.click_container(slot, button, mode, creative, ctx)
+        } else {
+            // a window the server closed already.
+            return;
+        };
+        let accepted = match click {
+            Some(click) => {
+                let matches = click.matches(clicked_item);
+                for item in click.dropped {
+                    self.drop_item(item, ctx);
+                }
+                matches
+            }
+            None => false,
+        };
+        self.send_packet(Arc::new(SPacket::PlayConfirmTransaction { window_id: window_id as i8, action_num, accepted }));
+        if !accepted {
+            debug!("{} clicked slot {} of window {} differently than the server", self.name, slot, window_id);
+            self.unconfirmed_action = Some(action_num);
+            if window_id == 0 {
+                self.resend_inventory();
+            } else {
+                self.send_container_items(ctx);
+            }
+        }
+    }
+
+    /// Opens the window of the container at location, if there is one. A chest next to another opens both.
+    fn open_container(&mut self, location: BlockPos, ctx: &mut ServerContext) -> bool {
+        let block = match ctx.world.get_block(location) {
+            Some(x) => x.get_type(),
+            None => return false,
+        };
+        let mut kind = match ContainerKind::from_block(block) {
+            Some(x) => x,
+            None => return false,
+        };
+        let mut locations = vec![location];
+        if kind == ContainerKind::Chest {
+            if let Some(&other) = get_chest_neighbors(location, block, ctx.world).first() {
+                locations.push(other);
+                // the chest further west or north comes first.
+                locations.sort_by_key(|l| (l.x, l.z));
+                kind = ContainerKind::LargeChest;
+            }
+        }
+        let mut custom_name = None;
+        for &l in &locations {
+            match ctx.world.get_block_entity_mut(l) {
+                Some(block_entity) => {
+                    if custom_name.is_none() {
+                        custom_name = block_entity.get_custom_name().map(String::from);
+                    }
+                }
+                None => return false,
+            }
+        }
+        if self.open_container.is_some() {
+            self.close_window(ctx);
+        }
+
+        self.last_window_id = self.last_window_id % MAX_WINDOW_ID + 1;
+        let window_id = self.last_window_id;
+        let window_title = match custom_name {
+            Some(text) => Component::from(StringComponent { text, base: Default::default() }),
+            None => Component::from(TranslationComponent {
+                translate: kind.get_title().into(),
+                base: Default::default(),
+                with: None,
+            }),
+        };
+        self.send_packet(Arc::new(SPacket::PlayOpenWindow {
+            window_id,
+            window_type: kind.get_window_type().into(),
+            window_title: Chat::from(window_title),
+            slot_num: kind.get_size() as u8,
+            entity_id: 0,
+        }));
+        self.open_container = Some(OpenContainer { window_id, kind, locations });
+        self.send_container_items(ctx);
+        true
+    }
+
+    /// Sends every slot of the open container window, and the cursor.
+    fn send_container_items(&mut self, ctx: &mut ServerContext) {
+        let (window_id, locations) = match self.open_container {
+            Some(ref c) => (c.window_id, c.locations.clone()),
+            None => return,
+        };
+        let mut slots = Vec::new();
+        for &l in &locations {
+            if let Some(block_entity) = ctx.world.get_block_entity_mut(l) {
+                slots.extend(block_entity.get_slots().into_iter().map(|s| slot_data(s)));
+            }
+        }
+        slots.extend(self.inventory.to_window_slots().into_iter().skip(inventory::WINDOW_MAIN.start));
+        self.send_packet(Arc::new(SPacket::PlayWindowItems { window_id, slots }));
+        self.send_cursor();
+    }
+
+    /// Clicks in the open container. The items are taken out of the block entities for the click and put back after,
+    /// showing what changed to everyone else looking into the same blocks.
+    fn click_container(
+        &mut self,
+        slot: i16,
+        button: i8,
+        mode: i8,
+        creative: bool,
+        ctx: &mut ServerContext,
+    ) -> Option<Click> {
+        let (kind, locations) = match self.open_container {
+            Some(ref c) if self.can_use_container(c, ctx) => (c.kind, c.locations.clone()),
+            _ => return None,
+        };
+        let mut items = Vec::with_capacity(kind.get_size());
+        for &l in &locations {
+            if let Some(block_entity) = ctx.world.get_block_entity_mut(l) {
+                items.extend(block_entity.get_slots().into_iter().map(|s| s.take()));
+            }
+        }
+        let before: Vec<SlotData> = items.iter().map(slot_data).collect();
+        let click = window::click(
+            &mut ContainerWindow::new(kind, &mut items, &mut self.inventory),
+            &mut self.cursor,
+            slot,
+            button,
+            mode,
+            creative,
+        );
+
+        let mut items = items.into_iter();
+        let mut before = before.into_iter();
+        let mut changes = Vec::new();
+        for &l in &locations {
+            if let Some(block_entity) = ctx.world.get_block_entity_mut(l) {
+                for (index, slot) in block_entity.get_slots().into_iter().enumerate() {
+                    *slot = items.next().flatten();
+                    let new = slot_data(slot);
+                    if before.next().as_ref() != Some(&new) {
+                        changes.push((l, index, new));
+                    }
+                }
+            }
+        }
+        if !changes.is_empty() {
+            for p in ctx.player_list.iter() {
+                // this player is borrowed already, and knows what changed.
+                if let Ok(mut p) = p.try_borrow_mut() {
+                    for &(location, index, ref slot_data) in &changes {
+                        p.update_container_slot(location, index, slot_data);
+                    }
+                }
+            }
+        }
+        click
+    }
+
+    /// Shows a change someone else made to a container slot, if the player looks into the same block.
+    fn update_container_slot(&mut self, location: BlockPos, index: usize, slot_data: &SlotData) {
+        let (window_id, slot) = match self.open_container {
+            Some(ref c) => match c.locations.iter().position(|&l| l == location) {
+                Some(i) => (c.window_id, i * c.kind.get_size() / c.locations.len() + index),
+                None => return,
+            },
+            None => return,
+        };
+        self.send_packet(Arc::new(SPacket::PlaySetSlot {
+            window_id: window_id as i8,
+            slot: slot as i16,
+            slot_data: slot_data.clone(),
+        }));
+    }
+
+    /// Whether the player is close enough to the block to use it.
+    fn can_use(&self, location: BlockPos) -> bool {
+        let dx = self.pos.x - (location.x as f64 + 0.5);
+        let dy = self.pos.y - (location.y as f64 + 0.5);
+        let dz = self.pos.z - (location.z as f64 + 0.5);
+        dx * dx + dy * dy + dz * dz <= MAX_USE_DISTANCE_SQ
+    }
+
+    /// Whether the blocks of the container are still there, and close enough.
+    fn can_use_container(&self, container: &OpenContainer, ctx: &ServerContext) -> bool {
+        container.locations.iter().all(|&l| {
+            let kind = ctx.world.get_block(l).and_then(|b| ContainerKind::from_block(b.get_type()));
+            let same_kind = kind == Some(container.kind)
+                || container.kind == ContainerKind::LargeChest && kind == Some(ContainerKind::Chest);
+            same_kind && self.can_use(l)
+        })
+    }
+
+    /// Closes the open container once its blocks are gone or the player walked away.
+    fn update_open_container(&mut self, ctx: &mut ServerContext) {
+        let window_id = match self.open_container {
+            Some(ref c) if !self.can_use_container(c, ctx) => c.window_id,
+            _ => return,
+        };
+        self.send_packet(Arc::new(SPacket::PlayCloseWindow { window_id }));
+        self.close_window(ctx);
+    }
+
+    /// Creative players take any item they like from the creative menu. Slot -1 throws it away.
+    fn set_creative_slot(&mut self, slot: i16, clicked_item: &SlotData, ctx: &mut ServerContext) {
+        if self.gamemode != Gamemode::Creative {
+            return;
+        }
+        let item = ItemStack::from_slot_data(clicked_item);
+        let valid = match item {
+            Some(ref item) => item.get_count() > 0 && item.get_count() <= 64 && item.get_item().get_damage_value() >= 0,
+            // unknown items.
+            None => *clicked_item == SlotData::Empty,
+        };
+        if !valid {
+            self.resend_inventory();
+            return;
+        }
+        if slot >= 1 && (slot as usize) < inventory::WINDOW_SIZE {
+            *window::Window::slot(&mut self.inventory, slot as usize) = item;
+        } else if slot == -1 {
+            if let Some(item) = item {
+                self.drop_item(item, ctx);
+            }
+        }
+    }
+
+    /// Closing a window drops what's on the cursor and in the crafting grid, like vanilla.
+    pub fn close_window(&mut self, ctx: &mut ServerContext) {
+        self.open_container = None;
+        let mut items = self.inventory.take_crafting_items();
+        items.extend(self.cursor.item.take());
+        for item in items {
+            self.drop_item(item, ctx);
+        }
+    }
+
+    fn drop_held_item(&mut self, whole_stack: bool, ctx: &mut ServerContext) {
+        if self.gamemode == Gamemode::Spectator {
+            return;
+        }
+        let count = match self.inventory.get_held_item() {
+            Some(item) if whole_stack => item.get_count(),
+            Some(_) => 1,
+            None => return,
+        };
+        let item = self.inventory.get_held_item().unwrap().copy_with_count(count);
+        self.inventory.remove_held_item(count);
+        self.drop_item(item, ctx);
+    }
+
+    /// Throws the item the way the player looks.
+    fn drop_item(&mut self, item: ItemStack, ctx: &mut ServerContext) {
+        let pos = Vec3::new(self.pos.x, self.pos.y + EYE_HEIGHT - 0.3, self.pos.z);
+        let (yaw, pitch) = (self.yaw.to_radians(), self.pitch.to_radians());
+        let velocity = Vec3::new(-yaw.sin() * pitch.cos() * 0.3, -pitch.sin() * 0.3 + 0.1, yaw.cos() * pitch.cos() * 0.3);
+        ctx.world.add_item(ItemEntity::new(ctx.server_info.next_entity_id(), item, pos, velocity, ctx.server_info.tick));
+    }
+
+    /// Whether the player's bounding box overlaps the block.
+    fn collides_with(&self, location: BlockPos) -> bool {
+        let (x, y, z) = (location.x as f64, location.y as f64, location.z as f64);
+        self.gamemode != Gamemode::Spectator
+            && self.pos.x + HALF_WIDTH > x
+            && self.pos.x - HALF_WIDTH < x + 1.0
+            && self.pos.y + HEIGHT > y
+            && self.pos.y < y + 1.0
+            && self.pos.z + HALF_WIDTH > z
+            && self.pos.z - HALF_WIDTH < z + 1.0
+    }
+
+    /// Direction the player looks in to place a block like a piston at location. Up or down when they stand next to it,
+    /// like vanilla.
+    fn get_placement_facing(&self, location: BlockPos) -> Facing {
+        if (self.pos.x - location.x as f64).abs() < 2.0 && (self.pos.z - location.z as f64).abs() < 2.0 {
+            let eye_y = self.pos.y + EYE_HEIGHT;
+            if eye_y - location.y as f64 > 2.0 {
+                return Facing::Down;
+            }
+            if location.y as f64 - eye_y > 0.0 {
+                return Facing::Up;
+            }
+        }
+        Facing::from_yaw(self.yaw)
+    }
+
+    /// Places the held block against the clicked face. The client already shows it, so anything the server doesn't
+    /// allow is undone on their side.
+    fn place_block(&mut self, against: BlockPos, face: i8, cursor_y: i8, ctx: &mut ServerContext) {
+        let face = match Facing::from_i8(face) {
+            Some(x) => x,
+            // using the held item without clicking a block.
+            None => return,
+        };
+        // right clicking a container opens it, unless the player sneaks to place something against it.
+        if !self.sneaking && self.can_use(against) && self.open_container(against, ctx) {
+            return;
+        }
+        let item = match self.gamemode {
+            Gamemode::Survival | Gamemode::Creative => {
+                self.inventory.get_held_item().map(|i| (i.get_item().get_id(), i.get_item().get_damage_value()))
+            }
+            Gamemode::Adventure | Gamemode::Spectator => None,
+        };
+        let blocks = match item {
+            Some((id, damage)) if self.has_permission(permissions::PLACE_BLOCKS) && self.can_reach(against) => {
+                self.get_placed_blocks(against, face, id, damage, cursor_y, ctx)
+            }
+            _ => None,
+        };
+        let blocks = match blocks {
+            Some(x) => x,
+            None => {
+                // where the client may have put the block.
+                let mut locations = vec![against];
+                locations.extend(against.offset(face));
+                for location in locations {
+                    if let Some(block) = ctx.world.get_block(location) {
+                        self.resend_block(location, block);
+                    }
+                }
+                self.resend_held_item();
+                return;
+            }
+        };
+
+        for &(location, block) in &blocks {
+            ctx.world.set_block(location, block);
+            // the client guesses the metadata, which may not match.
+            let packet = Arc::new(SPacket::PlayBlockChange { location, block_id: block.to_u16() as i32 });
+            self.send_packet_to_self_and_chunk_vicinity(ChunkPos::from(location), ctx, packet);
+        }
+        let (location, block) = blocks[0];
+        if let Some(sounds) = block.get_type().get_sounds() {
+            self.send_packet_to_self_and_chunk_vicinity(
+                ChunkPos::from(location),
+                ctx,
+                Arc::new(SPacket::PlaySoundEffect {
+                    name: sounds.place.name.into(),
+                    effect_pos_x: location.x as f64 + 0.5,
+                    effect_pos_y: location.y as f64 + 0.5,
+                    effect_pos_z: location.z as f64 + 0.5,
+                    volume: sounds.place.volume,
+                    pitch: sounds.place.pitch,
+                }),
+            );
+        }
+        if self.gamemode == Gamemode::Survival {
+            self.inventory.remove_held_item(1);
+        }
+    }
+
+    /// Blocks placing the item against the face would set, or None if it can't be placed there.
+    fn get_placed_blocks(
+        &self,
+        against: BlockPos,
+        face: Facing,
+        id: u16,
+        damage: i16,
+        cursor_y: i8,
+        ctx: &mut ServerContext,
+    ) -> Option<Vec<(BlockPos, BlockStateId)>> {
+        let block = Block::from_item_id(id)?;
+        let clicked = ctx.world.get_block(against)?;
+
+        let mut blocks = Vec::new();
+        // a slab put on top or below a slab of the same kind makes a double slab.
+        let clicked_top = clicked.get_meta() & 8 != 0;
+        match block.get_double_slab() {
+            Some(double)
+                if clicked.get_type() == block
+                    && clicked.get_meta() & 7 == damage as u8 & 7
+                    && (face == Facing::Up && !clicked_top || face == Facing::Down && clicked_top) =>
+            {
+                blocks.push((against, BlockStateId::new(double, damage as u8 & 7)));
+            }
+            _ => {
+                // clicking tall grass replaces it. air can't be clicked, a client which did sees something else there.
+                let replace_clicked = clicked.get_type().is_replaceable(clicked.get_meta());
+                let location = if replace_clicked && clicked.get_type() != Block::Air {
+                    against
+                } else {
+                    against.offset(face)?
+                };
+                let replaced = ctx.world.get_block(location)?;
+                if !replaced.get_type().is_replaceable(replaced.get_meta()) {
+                    return None;
+                }
+                // signs put on the side of a block hang on it.
+                let block = if block == Block::StandingSign && face != Facing::Up { Block::WallSign } else { block };
+                let placement = Placement {
+                    face,
+                    cursor_y: cursor_y as f32 / 16.0,
+                    yaw: self.yaw,
+                    facing: self.get_placement_facing(location),
+                };
+                let mut meta = block.get_placement_meta(damage, &placement)?;
+                let mut joined = None;
+                if block == Block::Chest || block == Block::TrappedChest {
+                    let neighbors = get_chest_neighbors(location, block, ctx.world);
+                    let other = neighbors.first().cloned();
+                    match other {
+                        None => {}
+                        Some(other) if neighbors.len() == 1 && get_chest_neighbors(other, block, ctx.world).is_empty() => {
+                            // both halves of a large chest face the same way, across the line they form.
+                            let along_x = other.x != location.x;
+                            let crosses = |meta: u8| if along_x { meta == 2 || meta == 3 } else { meta >= 4 };
+                            let other_meta = ctx.world.get_block(other)?.get_meta();
+                            if !crosses(meta) {
+                                meta = if crosses(other_meta) {
+                                    other_meta
+                                } else if along_x {
+                                    Facing::North.to_i8() as u8
+                                } else {
+                                    Facing::West.to_i8() as u8
+                                };
+                            }
+                            if other_meta != meta {
+                                joined = Some((other, BlockStateId::new(block, meta)));
+                            }
+                        }
+                        // chests can't get any larger.
+                        _ => return None,
+                    }
+                }
+                blocks.push((location, BlockStateId::new(block, meta)));
+                blocks.extend(joined);
+                if block.is_door() {
+                    let top = location.offset(Facing::Up)?;
+                    let replaced = ctx.world.get_block(top)?;
+                    if !replaced.get_type().is_replaceable(replaced.get_meta()) {
+                        return None;
+                    }
+                    // upper half with the hinge on the left.
+                    blocks.push((top, BlockStateId::new(block, 8)));
+                }
+            }
+        }
+
+        if blocks[0].1.get_type().has_collision() {
+            for &(location, _) in &blocks {
+                if self.collides_with(location) {
+                    return None;
+                }
+                for uuid in &self.players_in_vicinity {
+                    if let Some(player) = ctx.player_list.get_by_uuid(uuid) {
+                        if player.collides_with(location) {
+                            return None;
+                        }
+                    }
+                }
+            }
+        }
+        Some(blocks)
+    }
+
     pub fn send_packet(&mut self, p: Arc<SPacket>) {
-        // ignore because if other side is dropped,
-        // it's due to error and player will have to disconnect anyway
-        let _ = self.packet_send_queue.send(p);
+        self.packet_send_queue.send(p);
     }
 
     fn send_packet_to_all_players(&mut self, ctx: &ServerContext, packet: Arc<SPacket>) {
@@ -178,6 +1116,24 @@ impl Player {
         self.send_packet_to_chunk_vicinity(chunk_pos, ctx, packet);
     }
 
+    pub fn set_pos(&mut self, pos: Vec3) {
+        self.pos = pos;
+    }
+
+    /// Moves the player in the current world and tells the client.
+    pub fn teleport(&mut self, pos: Vec3, ctx: &mut ServerContext) {
+        let (yaw, pitch, on_ground) = (self.yaw, self.pitch, self.on_ground);
+        self.handle_motion_recv(pos.x, pos.y, pos.z, yaw, pitch, on_ground, true, false, ctx);
+        self.send_packet(Arc::new(SPacket::PlayPlayerPositionAndLook {
+            x: pos.x,
+            y: pos.y,
+            z: pos.z,
+            yaw: yaw as f32,
+            pitch: pitch as f32,
+            flags: 0,
+        }));
+    }
+
     pub fn get_pos(&self) -> Vec3 {
         self.pos
     }
@@ -197,15 +1153,23 @@ impl Player {
     pub fn tick(&mut self, ctx: &mut ServerContext) {
         self.handle_client_packets(ctx);
 
-        // every 2 secs.
-        if (ctx.server_info.tick - self.join_tick) % (TICKS_PER_SEC * 2) == 0 {
+        if self.keep_alive_pending {
+            if self.time_of_last_keep_alive.elapsed() >= ctx.server_info.keep_alive_timeout {
+                self.kick(Chat::from(text::parse_legacy("Timed out")));
+                return;
+            }
+        } else if (ctx.server_info.tick - self.join_tick) % (TICKS_PER_SEC * 2) == 0 {
+            // every 2 secs.
             self.send_packet(Arc::new(SPacket::PlayKeepAlive {
                 id: ctx.server_info.tick as i32,
             }));
             self.last_keep_alive = ctx.server_info.tick as i32;
             self.time_of_last_keep_alive = Instant::now();
+            self.keep_alive_pending = true;
         }
 
+        self.update_digging(ctx);
+        self.update_open_container(ctx);
         self.update_players_in_vicinity(ctx);
     }
 
@@ -258,22 +1222,19 @@ impl Player {
             gamemode: gamemode as u8,
             dimension: dimension as i8,
             difficulty: ctx.server_info.difficulty as u8,
-            max_players: 100,
-            level_type: ctx.server_info.level_type.as_str(),
+            max_players: ctx.server_info.max_players.min(255) as u8,
+            level_type: ctx.server_info.level_type.as_str().into(),
             reduced_debug_info: false,
         }));
         self.send_packet(Arc::new(SPacket::PlayPluginMessage {
-            channel: "MC|Brand",
+            channel: "MC|Brand".into(),
             data: "Quartz".as_bytes().to_vec(),
         }));
         self.send_packet(Arc::new(SPacket::PlayServerDifficulty {
             difficulty: ctx.server_info.difficulty as u8,
         }));
-        self.send_packet(Arc::new(SPacket::PlayPlayerAbilities {
-            flags: 0x08 | 0x04,
-            flying_speed: 0.05,
-            field_of_view_modifier: 2.0,
-        }));
+        let abilities = self.abilities_packet();
+        self.send_packet(Arc::new(abilities));
 
         self.send_packet(Arc::new(SPacket::PlayPlayerListHeaderAndFooter {
             header: Chat::from(text::parse_legacy_ex("&4&lQuartz server", '&')),
@@ -286,7 +1247,7 @@ impl Player {
                 name: self.name.clone(),
                 gamemode: self.gamemode as i32,
                 ping: self.ping,
-                properties: Vec::new(),
+                properties: self.get_list_item_properties(),
                 display_name: None,
             },
         });
@@ -316,7 +1277,7 @@ impl Player {
                     name: p.name.clone(),
                     gamemode: p.gamemode as i32,
                     ping: p.ping,
-                    properties: Vec::new(),
+                    properties: p.get_list_item_properties(),
                     display_name: None,
                 },
             }));
@@ -336,20 +1297,20 @@ impl Player {
             .insert_player(self.uuid);
 
         self.send_packet(Arc::new(SPacket::PlaySpawnPosition {
-            location: BlockPos::new(0, 80, 0),
+            location: ctx.world.get_spawn_pos(),
         }));
-        self.send_packet(Arc::new(SPacket::PlaySetSlot {
+        self.send_packet(Arc::new(ctx.server_info.time_update_packet()));
+        self.send_packet(Arc::new(SPacket::PlayWindowItems {
             window_id: 0,
-            slot: 36,
-            slot_data: ItemStack::new(
-                Box::new(BlockItem {
-                    id: BlockStateId::new(Block::Chest, 0),
-                    block_entity: Block::Chest.create_new_block_entity(),
-                    can_place_on: None,
-                }),
-                10,
-            )
-            .to_slot_data(),
+            slots: self.inventory.to_window_slots(),
+        }));
+        self.send_packet(Arc::new(SPacket::PlayHeldItemChange {
+            slot: self.inventory.get_held_slot() as i8,
+        }));
+        self.send_packet(Arc::new(SPacket::PlayUpdateHealth {
+            health: self.health,
+            food: self.food_level,
+            food_saturation: self.food_saturation,
         }));
         let (x, y, z, yaw, pitch) = (self.pos.x, self.pos.y, self.pos.z, self.yaw, self.pitch);
         self.send_packet(Arc::new(SPacket::PlayPlayerPositionAndLook {
@@ -568,12 +1529,13 @@ impl Player {
             match p {
                 Ok(p) => match p {
                     CPacket::PlayKeepAlive { id } => {
-                        if id == self.last_keep_alive {
+                        if !self.keep_alive_pending || id != self.last_keep_alive {
+                            warn!("{} answered keep-alive {} instead of {}", self.name, id, self.last_keep_alive);
+                        } else {
+                            self.keep_alive_pending = false;
                             let current = Instant::now();
-                            self.ping =
-                                util::duration_total_ms(current - self.time_of_last_keep_alive)
-                                    as i32
-                                    / 2;
+                            let ping = util::duration_total_ms(current - self.time_of_last_keep_alive) as i32;
+                            self.ping = (self.ping * 3 + ping) / 4;
                             self.time_of_last_keep_alive = current;
 
                             let (uuid, ping) = (self.uuid, self.ping);
@@ -590,11 +1552,20 @@ impl Player {
                             );
                         }
                     }
+                    CPacket::PlayChatMessage { ref message } if message.starts_with('/') => {
+                        self.pending_commands.push(message[1..].to_string());
+                    }
+                    CPacket::PlayTabComplete { text, pos } => {
+                        self.pending_tab_completes.push((text, pos));
+                    }
                     CPacket::PlayChatMessage { message } => {
-                        let message = Chat::from(text::parse_legacy_ex(
-                            &format!("{} > {}", self.name, message),
-                            '&',
-                        ));
+                        let message = format!("{} > {}", self.name, message);
+                        let message = if self.has_permission(permissions::CHAT_COLOR) {
+                            Chat::from(text::parse_legacy_ex(&message, '&'))
+                        } else {
+                            Chat::from(Component::String(StringComponent { text: message, ..Default::default() }))
+                        };
+                        info!("{}", message.to_ansi());
                         self.send_packet_to_all_players(
                             ctx,
                             Arc::new(SPacket::PlayChatMessage {
@@ -652,88 +1623,22 @@ impl Player {
                         location,
                         face,
                     } => match status {
-                        0 => {
-                            if self.gamemode == Gamemode::Creative {
-                                let prev_block = match ctx.world.get_block(location) {
-                                    Some(x) => x,
-                                    None => continue,
-                                };
-                                ctx.world.set_block(location, BlockStateId::AIR);
-                                let chunk_pos = ChunkPos::from(location);
-                                let packet = Arc::new(SPacket::PlayBlockChange {
-                                    location,
-                                    block_id: BlockStateId::AIR.to_u16() as i32,
-                                });
-                                self.send_packet_to_chunk_vicinity(chunk_pos, ctx, packet);
-
-                                let sounds = prev_block.get_type().get_sounds();
-                                if let Some(sounds) = sounds {
-                                    self.send_packet_to_chunk_vicinity(
-                                        chunk_pos,
-                                        ctx,
-                                        Arc::new(SPacket::PlaySoundEffect {
-                                            sound: sounds.breaks,
-                                            effect_pos_x: location.x as f64 + 0.5,
-                                            effect_pos_y: location.y as f64 + 0.5,
-                                            effect_pos_z: location.z as f64 + 0.5,
-                                        }),
-                                    );
-                                }
-                            }
-                        }
+                        0 => self.start_digging(location, ctx),
+                        1 => self.cancel_digging(ctx),
+                        2 => self.finish_digging(location, ctx),
+                        // q with and without ctrl, outside of windows.
+                        3 => self.drop_held_item(false, ctx),
+                        4 => self.drop_held_item(true, ctx),
                         _ => (),
                     },
-                    CPacket::PlayPlayerBlockPlacement {
-                        location,
-                        face,
-                        held_item,
-                        cursor_pos_x,
-                        cursor_pos_y,
-                        cursor_pos_z,
-                    } => {
-                        if self.gamemode == Gamemode::Creative {
-                            if let SlotData::Some {
-                                id, item_damage, ..
-                            } = held_item
-                            {
-                                let id = match Block::from_u8(id as u8) {
-                                    Some(x) => x,
-                                    None => continue,
-                                };
-                                let block = BlockStateId::new(id, item_damage as u8);
-                                let facing = match Facing::from_i8(face) {
-                                    Some(x) => x,
-                                    None => continue,
-                                };
-                                let location = match location.offset(facing) {
-                                    Some(x) => x,
-                                    None => continue,
-                                };
-
-                                ctx.world.set_block(location, block);
-
-                                let chunk_pos = ChunkPos::from(location);
-                                let packet = Arc::new(SPacket::PlayBlockChange {
-                                    location,
-                                    block_id: block.to_u16() as i32,
-                                });
-                                self.send_packet_to_chunk_vicinity(chunk_pos, ctx, packet);
-                                if let Some(sounds) = id.get_sounds() {
-                                    self.send_packet_to_self_and_chunk_vicinity(
-                                        chunk_pos,
-                                        ctx,
-                                        Arc::new(SPacket::PlaySoundEffect {
-                                            sound: sounds.place,
-                                            effect_pos_x: location.x as f64 + 0.5,
-                                            effect_pos_y: location.y as f64 + 0.5,
-                                            effect_pos_z: location.z as f64 + 0.5,
-                                        }),
-                                    );
-                                }
-                            }
+                    CPacket::PlayPlayerBlockPlacement { location, face, cursor_pos_y, .. } => {
+                        self.place_block(location, face, cursor_pos_y, ctx)
+                    }
+                    CPacket::PlayHeldItemChange { slot } => {
+                        if slot >= 0 && (slot as usize) < inventory::HOTBAR_SIZE {
+                            self.inventory.set_held_slot(slot as u8);
                         }
                     }
-                    /*CPacket::PlayHeldItemChange { slot } => {}*/
                     CPacket::PlayAnimation {} => {
                         self.send_packet_to_vicinity(
                             ctx,
@@ -749,6 +1654,7 @@ impl Player {
                         action_param,
                     } => match action_id {
                         0 => {
+                            self.sneaking = true;
                             for &p in &self.players_in_vicinity {
                                 ctx.player_list.send_packet_to_player(
                                     p,
@@ -764,6 +1670,7 @@ impl Player {
                             }
                         }
                         1 => {
+                            self.sneaking = false;
                             for &p in &self.players_in_vicinity {
                                 ctx.player_list.send_packet_to_player(
                                     p,
@@ -780,21 +1687,37 @@ impl Player {
                         }
                         _ => (),
                     },
+                    CPacket::PlayCloseWindow { window_id } => self.close_window(ctx),
+                    CPacket::PlayClickWindow {
+                        window_id,
+                        slot,
+                        button,
+                        action_num,
+                        mode,
+                        clicked_item,
+                    } => self.click_window(window_id, slot, button, action_num, mode, &clicked_item, ctx),
+                    CPacket::PlayConfirmTransaction {
+                        window_id,
+                        action_num,
+                        accepted,
+                    } => {
+                        if accepted && self.unconfirmed_action == Some(action_num) {
+                            self.unconfirmed_action = None;
+                        }
+                    }
+                    CPacket::PlayCreativeInventoryAction { slot, clicked_item } => {
+                        self.set_creative_slot(slot, &clicked_item, ctx)
+                    }
                     /*CPacket::PlaySteerVehicle { sideways, forward, flags } => {}
-                    CPacket::PlayCloseWindow { window_id } => {}
-                    CPacket::PlayClickWindow { window_id, slot, button, action_num, mode, clicked_item } => {}
-                    CPacket::PlayConfirmTransaction { window_id, action_num, accepted } => {}
-                    CPacket::PlayCreativeInventoryAction { slot, clicked_item } => {}
                     CPacket::PlayEnchantItem { window_id, enchantment } => {}
                     CPacket::PlayUpdateSign { location, line1, line2, line3, line4 } => {}
                     CPacket::PlayPlayerAbilities { flags, flying_speed, walking_speed } => {}
-                    CPacket::PlayTabComplete { text, pos } => {}
                     CPacket::PlayClientSettings { locale, view_distance, chat_mode, chat_colors, displayed_skin_parts } => {}
                     CPacket::PlayClientStatus { action_id } => {}
                     CPacket::PlayPluginMessage { channel, data } => {}
                     CPacket::PlaySpectate { target_player } => {}
                     CPacket::PlayResourcePackStatus { hash, result } => {}*/
-                    x @ _ => println!("{:?}", x),
+                    x @ _ => debug!("unhandled packet from {}: {:?}", self.name, x),
                 },
                 Err(_) => break,
             }
@@ -835,7 +1758,19 @@ pub struct PlayerProfile {
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct PlayerProperty {
     pub name: String,
-    pub value: PlayerPropertyValueWrapper,
+    /// base64 encoded value. kept as sent by the session server so the signature stays valid.
+    pub value: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub signature: Option<String>,
+}
+
+impl PlayerProperty {
+    /// decodes the value of a "textures" property.
+    pub fn decode_textures(&self) -> Option<PlayerPropertyValue> {
+        let decoded = base64::decode(&self.value).ok()?;
+        serde_json::from_slice(&decoded).ok()
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -848,35 +1783,6 @@ pub struct PlayerPropertyValue {
     pub textures: Textures,
 }
 
-#[derive(Debug, Clone)]
-pub struct PlayerPropertyValueWrapper(PlayerPropertyValue);
-
-impl Serialize for PlayerPropertyValueWrapper {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
-        serializer.serialize_str(&base64::encode(
-            &serde_json::to_string(&self.0).map_err(S::Error::custom)?,
-        ))
-    }
-}
-
-impl<'de> Deserialize<'de> for PlayerPropertyValueWrapper {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-    where
-        D: Deserializer<'de>,
-    {
-        let s = String::deserialize(deserializer)?;
-        let decoded = String::from_utf8(base64::decode(&s).map_err(D::Error::custom)?)
-            .map_err(D::Error::custom)?;
-
-        Ok(PlayerPropertyValueWrapper(
-            serde_json::from_str(&decoded).map_err(D::Error::custom)?,
-        ))
-    }
-}
-
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub struct Textures {
@@ -892,3 +1798,138 @@ pub struct Textures {
 pub struct SkinOrCape {
     pub url: String,
 }
+
+#[cfg(test)]
+mod test {
+    use crate::test_client::{
+        PlayBlockChange, PlayChunkData, PlayConfirmTransaction, PlayEntityRelativeMove, PlayMapChunkBulk,
+        PlayPlayerListItem, PlaySetSlot, PlaySpawnPlayer, PlaySpawnPosition, PlayWindowItems, TestServer,
+    };
+
+    use super::*;
+
+    #[test]
+    fn join_streams_chunks() {
+        let server = TestServer::start();
+        let mut client = server.connect("Alice");
+        // view distance 2 is 5x5 chunks, sent before the spawn position.
+        let mut chunks = 0;
+        while chunks < 25 {
+            chunks += client.wait_for::<PlayMapChunkBulk>().chunks.len();
+        }
+        assert_eq!(chunks, 25);
+
+        let spawn = client.wait_for::<PlaySpawnPosition>().location;
+        let pos = client.get_pos();
+        assert_eq!((pos.x.floor() as i32, pos.z.floor() as i32), (spawn.x, spawn.z));
+
+        // walking into the next chunk unloads the column left behind.
+        client.send_position(Vec3::new(pos.x + 16.0, pos.y, pos.z));
+        let unloaded = client.wait_for::<PlayChunkData>();
+        assert_eq!(unloaded.chunk_x, (spawn.x >> 4) - 2);
+        assert_eq!(unloaded.primary_bit_mask, 0);
+    }
+
+    #[test]
+    fn others_see_blocks_and_movement() {
+        let server = TestServer::start();
+        let mut alice = server.connect("Alice");
+        let mut bob = server.connect("Bob");
+        let alice_uuid = alice.get_uuid();
+        bob.wait_until(|p: &PlayPlayerListItem| {
+            p.players.iter().any(|p| {
+                p.uuid == alice_uuid
+                    && match p.action {
+                        SPlayPlayerListItemDataAction::AddPlayer { .. } => true,
+                        _ => false,
+                    }
+            })
+        });
+        let alice_id = alice.get_entity_id();
+        bob.wait_until(|p: &PlaySpawnPlayer| p.entity_id == alice_id);
+
+        let pos = alice.get_pos();
+        let location = BlockPos::new(pos.x.floor() as i32 + 1, pos.y.floor() as u8, pos.z.floor() as i32);
+        let stone = BlockStateId::new(Block::Stone, 0);
+        alice.place_block(location, stone);
+        let change = bob.wait_until(|p: &PlayBlockChange| p.location == location);
+        assert_eq!(change.block_id, i32::from(stone.to_u16()));
+
+        alice.send_position(Vec3::new(pos.x + 1.0, pos.y, pos.z));
+        let moved = bob.wait_until(|p: &PlayEntityRelativeMove| p.entity_id == alice_id);
+        assert_eq!(moved.delta_x, 32);
+    }
+
+    #[test]
+    fn breaks_bedrock_only_in_creative() {
+        let server = TestServer::start();
+        let mut alice = server.connect("Alice");
+        let mut bob = server.connect("Bob");
+        let pos = alice.get_pos();
+        let location = BlockPos::new(pos.x.floor() as i32 + 1, pos.y.floor() as u8, pos.z.floor() as i32);
+        let bedrock = BlockStateId::new(Block::Bedrock, 0);
+        alice.place_block(location, bedrock);
+        bob.wait_until(|p: &PlayBlockChange| p.location == location && p.block_id == i32::from(bedrock.to_u16()));
+        // players start in creative.
+        alice.send(CPacket::PlayPlayerDigging { status: 0, location, face: Facing::Up.to_i8() });
+        let change = bob.wait_until(|p: &PlayBlockChange| p.location == location);
+        assert_eq!(change.block_id, i32::from(BlockStateId::AIR.to_u16()));
+
+        let server = TestServer::with_properties(|p| p.gamemode = Gamemode::Survival);
+        let mut alice = server.connect("Alice");
+        // the floor of the world is bedrock.
+        let pos = alice.get_pos();
+        alice.send_position(Vec3::new(pos.x, 1.0, pos.z));
+        let location = BlockPos::new(pos.x.floor() as i32, 0, pos.z.floor() as i32);
+        alice.send(CPacket::PlayPlayerDigging { status: 0, location, face: Facing::Up.to_i8() });
+        alice.send(CPacket::PlayPlayerDigging { status: 2, location, face: Facing::Up.to_i8() });
+        let change = alice.wait_until(|p: &PlayBlockChange| p.location == location);
+        assert_eq!(change.block_id, i32::from(bedrock.to_u16()));
+    }
+
+    #[test]
+    fn validates_placed_blocks() {
+        let server = TestServer::start();
+        let mut alice = server.connect("Alice");
+        let pos = alice.get_pos();
+        let feet = BlockPos::new(pos.x.floor() as i32, pos.y.floor() as u8, pos.z.floor() as i32);
+        alice.place_block(feet, BlockStateId::new(Block::Stone, 0));
+        let change = alice.wait_until(|p: &PlayBlockChange| p.location == feet);
+        assert_eq!(change.block_id, i32::from(BlockStateId::AIR.to_u16()));
+
+        // players start looking south, so the stairs face south too.
+        let location = BlockPos::new(feet.x + 1, feet.y, feet.z);
+        alice.place_block(location, BlockStateId::new(Block::OakStairs, 0));
+        let change = alice.wait_until(|p: &PlayBlockChange| p.location == location);
+        assert_eq!(change.block_id, i32::from(BlockStateId::new(Block::OakStairs, 2).to_u16()));
+    }
+
+    #[test]
+    fn resyncs_wrong_clicks() {
+        let server = TestServer::start();
+        let mut alice = server.connect("Alice");
+        let stone = SlotData::Some { id: 1, item_count: 5, item_damage: 0, tag: Nbt::Empty };
+        alice.set_held_item(stone.clone());
+        let click = |action_num, clicked_item| CPacket::PlayClickWindow {
+            window_id: 0,
+            slot: 36,
+            button: 0,
+            action_num,
+            mode: 0,
+            clicked_item,
+        };
+        // the click picks the stone up, but the client thought the slot was empty.
+        alice.send(click(1, SlotData::Empty));
+        let confirm = alice.wait_until(|p: &PlayConfirmTransaction| p.action_num == 1);
+        assert!(!confirm.accepted);
+        alice.wait_until(|p: &PlayWindowItems| p.slots[36] == SlotData::Empty);
+        alice.wait_until(|p: &PlaySetSlot| p.window_id == -1 && p.slot_data == stone);
+
+        // clicks are ignored until the client confirms it got the inventory again.
+        alice.send(click(2, stone.clone()));
+        alice.send(CPacket::PlayConfirmTransaction { window_id: 0, action_num: 1, accepted: true });
+        alice.send(click(3, SlotData::Empty));
+        let confirm = alice.wait_until(|p: &PlayConfirmTransaction| p.accepted);
+        assert_eq!(confirm.action_num, 3);
+    }
+}
@@ -0,0 +1,319 @@
+//! Windows of blocks holding items, like chests and furnaces. Their slots come first, the inventory of the player
+//! looking into them follows.
+
+use std::ops::Range;
+
+use crate::block::Block;
+use crate::item::inventory::{self, Inventory, MAIN_SIZE};
+use crate::item::item_id;
+use crate::item::item_stack::ItemStack;
+use crate::item::window::Window;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContainerKind {
+    Chest,
+    /// two chests next to each other, sharing one window.
+    LargeChest,
+    Furnace,
+    Dispenser,
+    Dropper,
+    Hopper,
+    BrewingStand,
+}
+
+impl ContainerKind {
+    /// Window a block opens when right clicked. A chest opens a small one, it is up to the caller to find its partner.
+    pub fn from_block(block: Block) -> Option<ContainerKind> {
+        use self::ContainerKind::*;
+        Some(match block {
+            Block::Chest | Block::TrappedChest => Chest,
+            Block::Furnace | Block::LitFurnace => Furnace,
+            Block::Dispenser => Dispenser,
+            Block::Dropper => Dropper,
+            Block::Hopper => Hopper,
+            Block::BrewingStand => BrewingStand,
+            _ => return None,
+        })
+    }
+
+    /// window type of OpenWindow.
+    pub fn get_window_type(&self) -> &'static str {
+        use self::ContainerKind::*;
+        match *self {
+            Chest | LargeChest => "minecraft:chest",
+            Furnace => "minecraft:furnace",
+            Dispenser => "minecraft:dispenser",
+            Dropper => "minecraft:dropper",
+            Hopper => "minecraft:hopper",
+            BrewingStand => "minecraft:brewing_stand",
+        }
+    }
+
+    /// translation key of the title, used unless the container has a custom name.
+    pub fn get_title(&self) -> &'static str {
+        use self::ContainerKind::*;
+        match *self {
+            Chest => "container.chest",
+            LargeChest => "container.chestDouble",
+            Furnace => "container.furnace",
+            Dispenser => "container.dispenser",
+            Dropper => "container.dropper",
+            Hopper => "container.hopper",
+            BrewingStand => "container.brewing",
+        }
+    }
+
+    /// slots of the container, without the player inventory.
+    pub fn get_size(&self) -> usize {
+        use self::ContainerKind::*;
+        match *self {
+            Chest => 27,
+            LargeChest => 54,
+            Furnace => 3,
+            Dispenser | Dropper => 9,
+            Hopper => 5,
+            BrewingStand => 4,
+        }
+    }
+}
+
+const FURNACE_INPUT: usize = 0;
+const FURNACE_FUEL: usize = 1;
+const FURNACE_RESULT: usize = 2;
+const BREWING_BOTTLES: Range<usize> = 0..3;
+const BREWING_INGREDIENT: usize = 3;
+
+/// Whether a furnace has a recipe for the item. Shift clicking puts these into the input slot.
+fn is_smeltable(item: &ItemStack) -> bool {
+    use crate::item::item_id::*;
+    let damage = item.get_item().get_damage_value();
+    match item.get_item().get_id() {
+        IRON_ORE | GOLD_ORE | DIAMOND_ORE | EMERALD_ORE | LAPIS_ORE | REDSTONE_ORE | COAL_ORE | QUARTZ_ORE => true,
+        SAND | COBBLESTONE | NETHERRACK | CLAY | CLAY_BALL | CACTUS | LOG | LOG2 | POTATO => true,
+        PORKCHOP | BEEF | CHICKEN | RABBIT | MUTTON => true,
+        // cod and salmon.
+        FISH => damage == 0 || damage == 1,
+        STONEBRICK => damage == 0,
+        // wet sponge.
+        SPONGE => damage == 1,
+        _ => false,
+    }
+}
+
+/// Whether the item burns in a furnace.
+fn is_fuel(item: &ItemStack) -> bool {
+    use crate::item::item_id::*;
+    match item.get_item().get_id() {
+        PLANKS | LOG | LOG2 | WOODEN_SLAB | OAK_STAIRS | SPRUCE_STAIRS | BIRCH_STAIRS | JUNGLE_STAIRS
+        | ACACIA_STAIRS | DARK_OAK_STAIRS | FENCE | SPRUCE_FENCE..=ACACIA_FENCE | FENCE_GATE
+        | SPRUCE_FENCE_GATE..=ACACIA_FENCE_GATE | CHEST | TRAPPED_CHEST | CRAFTING_TABLE | BOOKSHELF | JUKEBOX
+        | NOTEBLOCK | TRAPDOOR | WOODEN_PRESSURE_PLATE | DAYLIGHT_DETECTOR | COAL_BLOCK => true,
+        WOODEN_SWORD | WOODEN_SHOVEL | WOODEN_PICKAXE | WOODEN_AXE | WOODEN_HOE => true,
+        STICK | COAL | LAVA_BUCKET | SAPLING | BLAZE_ROD => true,
+        _ => false,
+    }
+}
+
+fn is_bottle(item: &ItemStack) -> bool {
+    let id = item.get_item().get_id();
+    id == item_id::POTION || id == item_id::GLASS_BOTTLE
+}
+
+/// Whether a brewing stand takes the item as ingredient.
+fn is_brewing_ingredient(item: &ItemStack) -> bool {
+    use crate::item::item_id::*;
+    match item.get_item().get_id() {
+        NETHER_WART_ITEM | SUGAR | GHAST_TEAR | SPIDER_EYE | FERMENTED_SPIDER_EYE | BLAZE_POWDER | MAGMA_CREAM
+        | SPECKLED_MELON | GOLDEN_CARROT | RABBIT_FOOT | REDSTONE | GLOWSTONE_DUST | GUNPOWDER => true,
+        // pufferfish.
+        FISH => item.get_item().get_damage_value() == 3,
+        _ => false,
+    }
+}
+
+/// The window of an open container.
+pub struct ContainerWindow<'a> {
+    kind: ContainerKind,
+    slots: &'a mut [Option<ItemStack>],
+    inventory: &'a mut Inventory,
+}
+
+impl<'a> ContainerWindow<'a> {
+    pub fn new(kind: ContainerKind, slots: &'a mut [Option<ItemStack>], inventory: &'a mut Inventory) -> Self {
+        ContainerWindow { kind, slots, inventory }
+    }
+}
+
+impl<'a> Window for ContainerWindow<'a> {
+    fn len(&self) -> usize {
+        self.slots.len() + MAIN_SIZE
+    }
+
+    fn slot(&mut self, index: usize) -> &mut Option<ItemStack> {
+        let size = self.slots.len();
+        if index < size {
+            &mut self.slots[index]
+        } else {
+            self.inventory.slot(index - size + inventory::WINDOW_MAIN.start)
+        }
+    }
+
+    fn get_limit(&self, index: usize, item: &ItemStack) -> i8 {
+        let max = item.get_max_stack_size();
+        match (self.kind, index) {
+            (ContainerKind::Furnace, FURNACE_RESULT) => 0,
+            (ContainerKind::Furnace, FURNACE_FUEL) if !is_fuel(item) && item.get_item().get_id() != item_id::BUCKET => 0,
+            (ContainerKind::BrewingStand, i) if BREWING_BOTTLES.contains(&i) => {
+                if is_bottle(item) { 1 } else { 0 }
+            }
+            (ContainerKind::BrewingStand, BREWING_INGREDIENT) if !is_brewing_ingredient(item) => 0,
+            _ => max,
+        }
+    }
+
+    fn is_output(&self, index: usize) -> bool {
+        self.kind == ContainerKind::Furnace && index == FURNACE_RESULT
+    }
+
+    fn get_shift_targets(&self, index: usize, item: &ItemStack) -> Vec<(Range<usize>, bool)> {
+        let size = self.slots.len();
+        let inventory = size..size + MAIN_SIZE;
+        // main inventory without the hotbar, and the hotbar.
+        let main = size..size + MAIN_SIZE - 9;
+        let hotbar = main.end..inventory.end;
+        if index < size {
+            // outputs and chests fill the hotbar first.
+            let reverse = self.kind != ContainerKind::Furnace || index == FURNACE_RESULT;
+            return vec![(inventory, reverse)];
+        }
+        let to_other_part = if main.contains(&index) { hotbar } else { main };
+        match self.kind {
+            ContainerKind::Furnace if is_smeltable(item) => vec![(FURNACE_INPUT..FURNACE_INPUT + 1, false)],
+            ContainerKind::Furnace if is_fuel(item) => vec![(FURNACE_FUEL..FURNACE_FUEL + 1, false)],
+            ContainerKind::BrewingStand if self.slots[BREWING_INGREDIENT].is_none() && is_brewing_ingredient(item) => {
+                vec![(BREWING_INGREDIENT..BREWING_INGREDIENT + 1, false)]
+            }
+            ContainerKind::BrewingStand if is_bottle(item) => vec![(BREWING_BOTTLES, false)],
+            ContainerKind::Furnace | ContainerKind::BrewingStand => vec![(to_other_part, false)],
+            _ => vec![(0..size, false)],
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::block::{Block, BlockPos, BlockStateId};
+    use crate::item::window::{click, Cursor};
+    use crate::item::GenericItem;
+    use crate::nbt::Nbt;
+    use crate::proto::data::SlotData;
+    use crate::proto::packets::CPacket;
+    use crate::test_client::{PlayBlockChange, PlayConfirmTransaction, PlayOpenWindow, PlaySetSlot, PlayWindowItems, TestServer};
+
+    fn stack(id: u16, count: i8) -> Option<ItemStack> {
+        Some(ItemStack::new(Box::new(GenericItem { id, damage: 0, tag: None }), count))
+    }
+
+    fn count(slot: &Option<ItemStack>) -> i8 {
+        slot.as_ref().map_or(0, |i| i.get_count())
+    }
+
+    #[test]
+    fn shift_clicks() {
+        let mut inventory = Inventory::new();
+        let mut cursor = Cursor::default();
+        let mut chest: Vec<Option<ItemStack>> = (0..27).map(|_| None).collect();
+        chest[0] = stack(item_id::STONE, 64);
+        inventory.set_main(0, stack(item_id::STONE, 10));
+        {
+            // the chest goes to the end of the hotbar, onto the stack there first.
+            let mut window = ContainerWindow::new(ContainerKind::Chest, &mut chest, &mut inventory);
+            click(&mut window, &mut cursor, 0, 0, 1, false).unwrap();
+        }
+        assert_eq!(count(&chest[0]), 0);
+        assert_eq!(inventory.get_main(0).map_or(0, |i| i.get_count()), 64);
+        assert_eq!(inventory.get_main(8).map_or(0, |i| i.get_count()), 10);
+        {
+            let mut window = ContainerWindow::new(ContainerKind::Chest, &mut chest, &mut inventory);
+            click(&mut window, &mut cursor, 27 + 27, 0, 1, false).unwrap();
+        }
+        assert_eq!(count(&chest[0]), 64);
+
+        let mut furnace: Vec<Option<ItemStack>> = (0..3).map(|_| None).collect();
+        inventory.set_main(1, stack(item_id::IRON_ORE, 5));
+        inventory.set_main(2, stack(item_id::COAL, 5));
+        inventory.set_main(3, stack(item_id::DIRT, 5));
+        let mut window = ContainerWindow::new(ContainerKind::Furnace, &mut furnace, &mut inventory);
+        for slot in 3 + 27 + 1..3 + 27 + 4 {
+            click(&mut window, &mut cursor, slot as i16, 0, 1, false).unwrap();
+        }
+        // dirt doesn't burn, it moves out of the hotbar instead.
+        assert_eq!(count(window.slot(0)), 5);
+        assert_eq!(count(window.slot(1)), 5);
+        assert_eq!(count(window.slot(3)), 5);
+        // nothing can be put into the result, and only fuel next to it.
+        cursor.item = stack(item_id::DIRT, 1);
+        click(&mut window, &mut cursor, 2, 0, 0, false).unwrap();
+        click(&mut window, &mut cursor, 1, 0, 0, false).unwrap();
+        assert_eq!(count(&cursor.item), 1);
+    }
+
+    #[test]
+    fn brewing_stand_slots() {
+        let mut inventory = Inventory::new();
+        let mut cursor = Cursor::default();
+        let mut stand: Vec<Option<ItemStack>> = (0..4).map(|_| None).collect();
+        inventory.set_main(0, stack(item_id::GLASS_BOTTLE, 5));
+        let mut window = ContainerWindow::new(ContainerKind::BrewingStand, &mut stand, &mut inventory);
+        click(&mut window, &mut cursor, 4 + 27, 0, 1, false).unwrap();
+        assert_eq!((0..4).map(|i| count(window.slot(i))).collect::<Vec<_>>(), vec![1, 1, 1, 0]);
+        assert_eq!(count(window.slot(4 + 27)), 2);
+        cursor.item = stack(item_id::SUGAR, 3);
+        click(&mut window, &mut cursor, 3, 0, 0, false).unwrap();
+        assert_eq!(count(window.slot(3)), 3);
+    }
+    #[test]
+    fn shares_large_chests() {
+        let server = TestServer::start();
+        let mut alice = server.connect("Alice");
+        let mut bob = server.connect("Bob");
+        let pos = alice.get_pos();
+        let first = BlockPos::new(pos.x.floor() as i32 + 1, pos.y.floor() as u8, pos.z.floor() as i32);
+        let second = BlockPos::new(first.x, first.y, first.z + 1);
+        let chest = BlockStateId::new(Block::Chest, 0);
+        alice.place_block(first, chest);
+        alice.place_block(second, chest);
+        bob.wait_until(|p: &PlayBlockChange| p.location == second);
+
+        bob.use_block(second);
+        let bob_window = bob.wait_for::<PlayOpenWindow>();
+        assert_eq!(bob_window.window_type, "minecraft:chest");
+        assert_eq!(bob_window.slot_num, 54);
+        let stone = SlotData::Some { id: 1, item_count: 5, item_damage: 0, tag: Nbt::Empty };
+        alice.set_held_item(stone.clone());
+        alice.use_block(first);
+        let alice_window = alice.wait_for::<PlayOpenWindow>().window_id;
+        // the first hotbar slot follows the chests and the rest of the inventory.
+        let click = |slot, action_num, clicked_item| CPacket::PlayClickWindow {
+            window_id: alice_window,
+            slot,
+            button: 0,
+            action_num,
+            mode: 0,
+            clicked_item,
+        };
+        alice.send(click(54 + 27, 1, stone.clone()));
+        alice.send(click(30, 2, SlotData::Empty));
+        let confirm = alice.wait_until(|p: &PlayConfirmTransaction| p.action_num == 2);
+        assert!(confirm.accepted);
+        let set_slot = bob.wait_until(|p: &PlaySetSlot| p.window_id == bob_window.window_id as i8 && p.slot == 30);
+        assert_eq!(set_slot.slot_data, stone);
+
+        // the stone stays in the chest after closing it.
+        alice.send(CPacket::PlayCloseWindow { window_id: alice_window });
+        alice.use_block(second);
+        let items = alice.wait_until(|p: &PlayWindowItems| p.window_id != 0);
+        assert_eq!(items.slots[30], stone);
+    }
+}
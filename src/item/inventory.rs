@@ -1,25 +1,268 @@
-use item::Item;
+use std::ops::Range;
 
+use crate::item::item_id;
+use crate::item::item_stack::ItemStack;
+use crate::item::window::Window;
+use crate::nbt::{DeserializeError, List};
+use crate::proto::data::SlotData;
+
+pub const MAIN_SIZE: usize = 36;
+pub const HOTBAR_SIZE: usize = 9;
+/// slot count of the player inventory window. (crafting result, crafting, armor, main)
+pub const WINDOW_SIZE: usize = 45;
+
+/// first slot of armor in saved inventories. feet is 100, head is 103.
+const NBT_ARMOR_START: u8 = 100;
+
+const WINDOW_CRAFTING_RESULT: usize = 0;
+const WINDOW_CRAFTING: Range<usize> = 1..5;
+const WINDOW_ARMOR: Range<usize> = 5..9;
+pub const WINDOW_MAIN: Range<usize> = 9..36;
+const WINDOW_HOTBAR: Range<usize> = 36..45;
+
+/// Armor slot the item is worn in. 0 is the head, 3 the feet.
+pub fn get_armor_slot(id: u16) -> Option<usize> {
+    match id {
+        item_id::LEATHER_HELMET..=item_id::GOLDEN_BOOTS => Some((id - item_id::LEATHER_HELMET) as usize % 4),
+        item_id::PUMPKIN | item_id::SKULL_ITEM => Some(0),
+        _ => None,
+    }
+}
+
+/// Inventory of a player.
+/// Main slots are indexed like vanilla saves them: 0-8 is the hotbar, 9-35 the rest.
+#[derive(Debug)]
 pub struct Inventory {
-    head: Option<Item>,
-    chest: Option<Item>,
-    legs: Option<Item>,
-    feet: Option<Item>,
-    crafting: [Option<Item>; 4],
-    crafting_result: Option<Item>,
-    main: [Option<Item>; 36],
+    /// head, chest, legs, feet
+    armor: [Option<ItemStack>; 4],
+    crafting: [Option<ItemStack>; 4],
+    crafting_result: Option<ItemStack>,
+    main: [Option<ItemStack>; MAIN_SIZE],
+    /// selected hotbar slot. 0-8
+    held_slot: u8,
 }
 
-impl Inventory {
-    pub fn new() -> Inventory {
+impl Default for Inventory {
+    fn default() -> Self {
         Inventory {
-            head: None,
-            chest: None,
-            legs: None,
-            feet: None,
-            crafting: [None; 4],
+            armor: Default::default(),
+            crafting: Default::default(),
             crafting_result: None,
-            main: [None; 36],
+            main: ::std::array::from_fn(|_| None),
+            held_slot: 0,
+        }
+    }
+}
+
+impl Inventory {
+    pub fn new() -> Inventory {
+        Default::default()
+    }
+
+    pub fn get_held_slot(&self) -> u8 {
+        self.held_slot
+    }
+
+    pub fn set_held_slot(&mut self, slot: u8) {
+        self.held_slot = slot.min(HOTBAR_SIZE as u8 - 1);
+    }
+
+    pub fn get_main(&self, index: usize) -> Option<&ItemStack> {
+        self.main[index].as_ref()
+    }
+
+    pub fn set_main(&mut self, index: usize, item: Option<ItemStack>) {
+        self.main[index] = item;
+    }
+
+    pub fn get_held_item(&self) -> Option<&ItemStack> {
+        self.get_main(self.held_slot as usize)
+    }
+
+    /// Puts the stack where vanilla would when picking it up: onto stacks of the same item first, then into the first
+    /// free slot. Returns what didn't fit.
+    pub fn add_item(&mut self, mut item: ItemStack) -> Option<ItemStack> {
+        let max = item.get_max_stack_size();
+        for slot in self.main.iter_mut() {
+            if let Some(ref mut stack) = *slot {
+                if stack.is_similar(&item) && stack.get_count() < max {
+                    let count = (max - stack.get_count()).min(item.get_count());
+                    stack.set_count(stack.get_count() + count);
+                    item.set_count(item.get_count() - count);
+                    if item.get_count() <= 0 {
+                        return None;
+                    }
+                }
+            }
+        }
+        match self.main.iter_mut().find(|s| s.is_none()) {
+            Some(slot) => {
+                *slot = Some(item);
+                None
+            }
+            None => Some(item),
+        }
+    }
+
+    /// Takes count items from the held stack, emptying the slot once none are left.
+    pub fn remove_held_item(&mut self, count: i8) {
+        let slot = &mut self.main[self.held_slot as usize];
+        let left = slot.as_ref().map_or(0, |i| i.get_count() - count);
+        match *slot {
+            Some(ref mut item) if left > 0 => item.set_count(left),
+            _ => *slot = None,
+        }
+    }
+
+    /// slot of given main index in the player inventory window.
+    pub fn main_to_window_slot(index: usize) -> usize {
+        if index < HOTBAR_SIZE {
+            index + 36
+        } else {
+            index
+        }
+    }
+
+    /// slots as sent in WindowItems of window 0.
+    pub fn to_window_slots(&self) -> Vec<SlotData> {
+        fn slot(item: &Option<ItemStack>) -> SlotData {
+            item.as_ref().map_or(SlotData::Empty, |i| i.to_slot_data())
+        }
+
+        let mut slots = Vec::with_capacity(WINDOW_SIZE);
+        slots.push(slot(&self.crafting_result));
+        slots.extend(self.crafting.iter().map(slot));
+        slots.extend(self.armor.iter().map(slot));
+        slots.extend(self.main[HOTBAR_SIZE..].iter().map(slot));
+        slots.extend(self.main[..HOTBAR_SIZE].iter().map(slot));
+        slots
+    }
+
+    /// Takes the items out of the crafting grid.
+    pub fn take_crafting_items(&mut self) -> Vec<ItemStack> {
+        self.crafting.iter_mut().filter_map(|s| s.take()).collect()
+    }
+
+    /// Writes the "Inventory" list of player data. Crafting slots are not saved, same as vanilla.
+    pub fn to_nbt(&self) -> List {
+        let mut list = List(Vec::new());
+        for (i, item) in self.main.iter().enumerate() {
+            if let Some(ref item) = *item {
+                list.0.push(item.to_nbt_with_slot(i as u8).into());
+            }
+        }
+        // armor is saved feet first.
+        for (i, item) in self.armor.iter().rev().enumerate() {
+            if let Some(ref item) = *item {
+                list.0.push(item.to_nbt_with_slot(NBT_ARMOR_START + i as u8).into());
+            }
+        }
+        list
+    }
+
+    pub fn load_nbt(&mut self, list: &List) -> Result<(), DeserializeError> {
+        for tag in list.0.iter() {
+            let compound = tag.as_compound()?;
+            let slot = compound.get("Slot")?.as_byte()? as u8;
+            let item = Some(ItemStack::from_nbt(compound)?);
+            match slot {
+                s if (s as usize) < MAIN_SIZE => self.main[s as usize] = item,
+                s if s >= NBT_ARMOR_START && s < NBT_ARMOR_START + 4 => {
+                    self.armor[3 - (s - NBT_ARMOR_START) as usize] = item
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The player inventory window, numbered like `to_window_slots`.
+impl Window for Inventory {
+    fn len(&self) -> usize {
+        WINDOW_SIZE
+    }
+
+    fn slot(&mut self, index: usize) -> &mut Option<ItemStack> {
+        match index {
+            WINDOW_CRAFTING_RESULT => &mut self.crafting_result,
+            i if i < WINDOW_ARMOR.start => &mut self.crafting[i - WINDOW_CRAFTING.start],
+            i if i < WINDOW_MAIN.start => &mut self.armor[i - WINDOW_ARMOR.start],
+            i if i < WINDOW_HOTBAR.start => &mut self.main[i],
+            i => &mut self.main[i - WINDOW_HOTBAR.start],
         }
     }
-}
\ No newline at end of file
+
+    fn get_limit(&self, index: usize, item: &ItemStack) -> i8 {
+        match index {
+            // there are no recipes, so nothing is ever crafted.
+            WINDOW_CRAFTING_RESULT => 0,
+            i if WINDOW_ARMOR.contains(&i) => {
+                if get_armor_slot(item.get_item().get_id()) == Some(i - WINDOW_ARMOR.start) { 1 } else { 0 }
+            }
+            _ => item.get_max_stack_size(),
+        }
+    }
+
+    fn is_output(&self, index: usize) -> bool {
+        index == WINDOW_CRAFTING_RESULT
+    }
+
+    fn get_shift_targets(&self, index: usize, item: &ItemStack) -> Vec<(Range<usize>, bool)> {
+        let armor = get_armor_slot(item.get_item().get_id())
+            .map(|a| WINDOW_ARMOR.start + a)
+            .filter(|&a| self.armor[a - WINDOW_ARMOR.start].is_none());
+        match index {
+            i if i < WINDOW_MAIN.start => vec![(WINDOW_MAIN.start..WINDOW_HOTBAR.end, i == WINDOW_CRAFTING_RESULT)],
+            i => match armor {
+                Some(a) => vec![(a..a + 1, false)],
+                None if i < WINDOW_HOTBAR.start => vec![(WINDOW_HOTBAR, false)],
+                None => vec![(WINDOW_MAIN, false)],
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::item::GenericItem;
+
+    fn stack(id: u16, count: i8) -> Option<ItemStack> {
+        Some(ItemStack::new(Box::new(GenericItem { id, damage: 0, tag: None }), count))
+    }
+
+    #[test]
+    fn nbt_slots() {
+        let mut inventory = Inventory::new();
+        inventory.set_main(3, stack(1, 64));
+        inventory.set_main(20, stack(280, 5));
+        inventory.armor[0] = stack(310, 1);
+        inventory.armor[3] = stack(313, 1);
+
+        let list = inventory.to_nbt();
+        let mut slots: Vec<i8> = list
+            .0
+            .iter()
+            .map(|t| t.as_compound().unwrap().get("Slot").unwrap().as_byte().unwrap())
+            .collect();
+        slots.sort();
+        assert_eq!(slots, vec![3, 20, 100, 103]);
+
+        let mut loaded = Inventory::new();
+        loaded.load_nbt(&list).unwrap();
+        let ids: Vec<Option<i16>> = loaded
+            .to_window_slots()
+            .iter()
+            .map(|s| match *s {
+                SlotData::Some { id, .. } => Some(id),
+                SlotData::Empty => None,
+            })
+            .collect();
+        assert_eq!(ids[5], Some(310));
+        assert_eq!(ids[8], Some(313));
+        assert_eq!(ids[20], Some(280));
+        assert_eq!(ids[39], Some(1));
+        assert_eq!(ids.iter().filter(|x| x.is_some()).count(), 4);
+    }
+}
@@ -771,4 +771,785 @@ pub const RECORD_STAL_NAME: &str = "record_stal";
 pub const RECORD_STRAD_NAME: &str = "record_strad";
 pub const RECORD_WARD_NAME: &str = "record_ward";
 pub const RECORD_11_NAME: &str = "record_11";
-pub const RECORD_WAIT_NAME: &str = "record_wait";
\ No newline at end of file
+pub const RECORD_WAIT_NAME: &str = "record_wait";
+
+/// Most items stack up to 64. Tools, armor and the like don't stack.
+pub fn get_max_stack_size(id: u16) -> i8 {
+    match id {
+        IRON_SHOVEL..=FLINT_AND_STEEL | BOW | IRON_SWORD..=DIAMOND_AXE | MUSHROOM_STEW | GOLDEN_SWORD..=GOLDEN_AXE
+        | WOODEN_HOE..=GOLDEN_HOE | LEATHER_HELMET..=GOLDEN_BOOTS | WATER_BUCKET..=SADDLE | BOAT | MILK_BUCKET
+        | CHEST_MINECART | FURNACE_MINECART | FISHING_ROD | CAKE_ITEM | BED_ITEM | SHEARS | POTION | WRITABLE_BOOK
+        | WRITTEN_BOOK | CARROT_ON_A_STICK | ENCHANTED_BOOK | TNT_MINECART | HOPPER_MINECART | RABBIT_STEW
+        | IRON_HORSE_ARMOR..=DIAMOND_HORSE_ARMOR | COMMAND_BLOCK_MINECART | RECORD_13..=RECORD_WAIT => 1,
+        SIGN | BUCKET | SNOWBALL | EGG | ENDER_PEARL | ARMOR_STAND | BANNER => 16,
+        _ => 64,
+    }
+}
+
+/// Returns the registry name of given item id.
+pub fn to_name(id: u16) -> Option<&'static str> {
+    match id {
+        AIR => Some(AIR_NAME),
+        STONE => Some(STONE_NAME),
+        GRASS => Some(GRASS_NAME),
+        DIRT => Some(DIRT_NAME),
+        COBBLESTONE => Some(COBBLESTONE_NAME),
+        PLANKS => Some(PLANKS_NAME),
+        SAPLING => Some(SAPLING_NAME),
+        BEDROCK => Some(BEDROCK_NAME),
+        FLOWING_WATER => Some(FLOWING_WATER_NAME),
+        WATER => Some(WATER_NAME),
+        FLOWING_LAVA => Some(FLOWING_LAVA_NAME),
+        LAVA => Some(LAVA_NAME),
+        SAND => Some(SAND_NAME),
+        GRAVEL => Some(GRAVEL_NAME),
+        GOLD_ORE => Some(GOLD_ORE_NAME),
+        IRON_ORE => Some(IRON_ORE_NAME),
+        COAL_ORE => Some(COAL_ORE_NAME),
+        LOG => Some(LOG_NAME),
+        LEAVES => Some(LEAVES_NAME),
+        SPONGE => Some(SPONGE_NAME),
+        GLASS => Some(GLASS_NAME),
+        LAPIS_ORE => Some(LAPIS_ORE_NAME),
+        LAPIS_BLOCK => Some(LAPIS_BLOCK_NAME),
+        DISPENSER => Some(DISPENSER_NAME),
+        SANDSTONE => Some(SANDSTONE_NAME),
+        NOTEBLOCK => Some(NOTEBLOCK_NAME),
+        BED => Some(BED_NAME),
+        GOLDEN_RAIL => Some(GOLDEN_RAIL_NAME),
+        DETECTOR_RAIL => Some(DETECTOR_RAIL_NAME),
+        STICKY_PISTON => Some(STICKY_PISTON_NAME),
+        WEB => Some(WEB_NAME),
+        TALLGRASS => Some(TALLGRASS_NAME),
+        DEADBUSH => Some(DEADBUSH_NAME),
+        PISTON => Some(PISTON_NAME),
+        PISTON_HEAD => Some(PISTON_HEAD_NAME),
+        WOOL => Some(WOOL_NAME),
+        PISTON_EXTENSION => Some(PISTON_EXTENSION_NAME),
+        YELLOW_FLOWER => Some(YELLOW_FLOWER_NAME),
+        RED_FLOWER => Some(RED_FLOWER_NAME),
+        BROWN_MUSHROOM => Some(BROWN_MUSHROOM_NAME),
+        RED_MUSHROOM => Some(RED_MUSHROOM_NAME),
+        GOLD_BLOCK => Some(GOLD_BLOCK_NAME),
+        IRON_BLOCK => Some(IRON_BLOCK_NAME),
+        DOUBLE_STONE_SLAB => Some(DOUBLE_STONE_SLAB_NAME),
+        STONE_SLAB => Some(STONE_SLAB_NAME),
+        BRICK_BLOCK => Some(BRICK_BLOCK_NAME),
+        TNT => Some(TNT_NAME),
+        BOOKSHELF => Some(BOOKSHELF_NAME),
+        MOSSY_COBBLESTONE => Some(MOSSY_COBBLESTONE_NAME),
+        OBSIDIAN => Some(OBSIDIAN_NAME),
+        TORCH => Some(TORCH_NAME),
+        FIRE => Some(FIRE_NAME),
+        MOB_SPAWNER => Some(MOB_SPAWNER_NAME),
+        OAK_STAIRS => Some(OAK_STAIRS_NAME),
+        CHEST => Some(CHEST_NAME),
+        REDSTONE_WIRE => Some(REDSTONE_WIRE_NAME),
+        DIAMOND_ORE => Some(DIAMOND_ORE_NAME),
+        DIAMOND_BLOCK => Some(DIAMOND_BLOCK_NAME),
+        CRAFTING_TABLE => Some(CRAFTING_TABLE_NAME),
+        WHEAT => Some(WHEAT_NAME),
+        FARMLAND => Some(FARMLAND_NAME),
+        FURNACE => Some(FURNACE_NAME),
+        LIT_FURNACE => Some(LIT_FURNACE_NAME),
+        STANDING_SIGN => Some(STANDING_SIGN_NAME),
+        WOODEN_DOOR => Some(WOODEN_DOOR_NAME),
+        LADDER => Some(LADDER_NAME),
+        RAIL => Some(RAIL_NAME),
+        STONE_STAIRS => Some(STONE_STAIRS_NAME),
+        WALL_SIGN => Some(WALL_SIGN_NAME),
+        LEVER => Some(LEVER_NAME),
+        STONE_PRESSURE_PLATE => Some(STONE_PRESSURE_PLATE_NAME),
+        IRON_DOOR => Some(IRON_DOOR_NAME),
+        WOODEN_PRESSURE_PLATE => Some(WOODEN_PRESSURE_PLATE_NAME),
+        REDSTONE_ORE => Some(REDSTONE_ORE_NAME),
+        LIT_REDSTONE_ORE => Some(LIT_REDSTONE_ORE_NAME),
+        UNLIT_REDSTONE_TORCH => Some(UNLIT_REDSTONE_TORCH_NAME),
+        REDSTONE_TORCH => Some(REDSTONE_TORCH_NAME),
+        STONE_BUTTON => Some(STONE_BUTTON_NAME),
+        SNOW_LAYER => Some(SNOW_LAYER_NAME),
+        ICE => Some(ICE_NAME),
+        SNOW => Some(SNOW_NAME),
+        CACTUS => Some(CACTUS_NAME),
+        CLAY => Some(CLAY_NAME),
+        REEDS => Some(REEDS_NAME),
+        JUKEBOX => Some(JUKEBOX_NAME),
+        FENCE => Some(FENCE_NAME),
+        PUMPKIN => Some(PUMPKIN_NAME),
+        NETHERRACK => Some(NETHERRACK_NAME),
+        SOUL_SAND => Some(SOUL_SAND_NAME),
+        GLOWSTONE => Some(GLOWSTONE_NAME),
+        PORTAL => Some(PORTAL_NAME),
+        LIT_PUMPKIN => Some(LIT_PUMPKIN_NAME),
+        CAKE => Some(CAKE_NAME),
+        UNPOWERED_REPEATER => Some(UNPOWERED_REPEATER_NAME),
+        POWERED_REPEATER => Some(POWERED_REPEATER_NAME),
+        STAINED_GLASS => Some(STAINED_GLASS_NAME),
+        TRAPDOOR => Some(TRAPDOOR_NAME),
+        MONSTER_EGG => Some(MONSTER_EGG_NAME),
+        STONEBRICK => Some(STONEBRICK_NAME),
+        BROWN_MUSHROOM_BLOCK => Some(BROWN_MUSHROOM_BLOCK_NAME),
+        RED_MUSHROOM_BLOCK => Some(RED_MUSHROOM_BLOCK_NAME),
+        IRON_BARS => Some(IRON_BARS_NAME),
+        GLASS_PANE => Some(GLASS_PANE_NAME),
+        MELON_BLOCK => Some(MELON_BLOCK_NAME),
+        PUMPKIN_STEM => Some(PUMPKIN_STEM_NAME),
+        MELON_STEM => Some(MELON_STEM_NAME),
+        VINE => Some(VINE_NAME),
+        FENCE_GATE => Some(FENCE_GATE_NAME),
+        BRICK_STAIRS => Some(BRICK_STAIRS_NAME),
+        STONE_BRICK_STAIRS => Some(STONE_BRICK_STAIRS_NAME),
+        MYCELIUM => Some(MYCELIUM_NAME),
+        WATERLILY => Some(WATERLILY_NAME),
+        NETHER_BRICK => Some(NETHER_BRICK_NAME),
+        NETHER_BRICK_FENCE => Some(NETHER_BRICK_FENCE_NAME),
+        NETHER_BRICK_STAIRS => Some(NETHER_BRICK_STAIRS_NAME),
+        NETHER_WART => Some(NETHER_WART_NAME),
+        ENCHANTING_TABLE => Some(ENCHANTING_TABLE_NAME),
+        BREWING_STAND => Some(BREWING_STAND_NAME),
+        CAULDRON => Some(CAULDRON_NAME),
+        END_PORTAL => Some(END_PORTAL_NAME),
+        END_PORTAL_FRAME => Some(END_PORTAL_FRAME_NAME),
+        END_STONE => Some(END_STONE_NAME),
+        DRAGON_EGG => Some(DRAGON_EGG_NAME),
+        REDSTONE_LAMP => Some(REDSTONE_LAMP_NAME),
+        LIT_REDSTONE_LAMP => Some(LIT_REDSTONE_LAMP_NAME),
+        DOUBLE_WOODEN_SLAB => Some(DOUBLE_WOODEN_SLAB_NAME),
+        WOODEN_SLAB => Some(WOODEN_SLAB_NAME),
+        COCOA => Some(COCOA_NAME),
+        SANDSTONE_STAIRS => Some(SANDSTONE_STAIRS_NAME),
+        EMERALD_ORE => Some(EMERALD_ORE_NAME),
+        ENDER_CHEST => Some(ENDER_CHEST_NAME),
+        TRIPWIRE_HOOK => Some(TRIPWIRE_HOOK_NAME),
+        TRIPWIRE => Some(TRIPWIRE_NAME),
+        EMERALD_BLOCK => Some(EMERALD_BLOCK_NAME),
+        SPRUCE_STAIRS => Some(SPRUCE_STAIRS_NAME),
+        BIRCH_STAIRS => Some(BIRCH_STAIRS_NAME),
+        JUNGLE_STAIRS => Some(JUNGLE_STAIRS_NAME),
+        COMMAND_BLOCK => Some(COMMAND_BLOCK_NAME),
+        BEACON => Some(BEACON_NAME),
+        COBBLESTONE_WALL => Some(COBBLESTONE_WALL_NAME),
+        FLOWER_POT => Some(FLOWER_POT_NAME),
+        CARROTS => Some(CARROTS_NAME),
+        POTATOES => Some(POTATOES_NAME),
+        WOODEN_BUTTON => Some(WOODEN_BUTTON_NAME),
+        SKULL => Some(SKULL_NAME),
+        ANVIL => Some(ANVIL_NAME),
+        TRAPPED_CHEST => Some(TRAPPED_CHEST_NAME),
+        LIGHT_WEIGHTED_PRESSURE_PLATE => Some(LIGHT_WEIGHTED_PRESSURE_PLATE_NAME),
+        HEAVY_WEIGHTED_PRESSURE_PLATE => Some(HEAVY_WEIGHTED_PRESSURE_PLATE_NAME),
+        UNPOWERED_COMPARATOR => Some(UNPOWERED_COMPARATOR_NAME),
+        POWERED_COMPARATOR => Some(POWERED_COMPARATOR_NAME),
+        DAYLIGHT_DETECTOR => Some(DAYLIGHT_DETECTOR_NAME),
+        REDSTONE_BLOCK => Some(REDSTONE_BLOCK_NAME),
+        QUARTZ_ORE => Some(QUARTZ_ORE_NAME),
+        HOPPER => Some(HOPPER_NAME),
+        QUARTZ_BLOCK => Some(QUARTZ_BLOCK_NAME),
+        QUARTZ_STAIRS => Some(QUARTZ_STAIRS_NAME),
+        ACTIVATOR_RAIL => Some(ACTIVATOR_RAIL_NAME),
+        DROPPER => Some(DROPPER_NAME),
+        STAINED_HARDENED_CLAY => Some(STAINED_HARDENED_CLAY_NAME),
+        STAINED_GLASS_PANE => Some(STAINED_GLASS_PANE_NAME),
+        LEAVES2 => Some(LEAVES2_NAME),
+        LOG2 => Some(LOG2_NAME),
+        ACACIA_STAIRS => Some(ACACIA_STAIRS_NAME),
+        DARK_OAK_STAIRS => Some(DARK_OAK_STAIRS_NAME),
+        SLIME => Some(SLIME_NAME),
+        BARRIER => Some(BARRIER_NAME),
+        IRON_TRAPDOOR => Some(IRON_TRAPDOOR_NAME),
+        PRISMARINE => Some(PRISMARINE_NAME),
+        SEA_LANTERN => Some(SEA_LANTERN_NAME),
+        HAY_BLOCK => Some(HAY_BLOCK_NAME),
+        CARPET => Some(CARPET_NAME),
+        HARDENED_CLAY => Some(HARDENED_CLAY_NAME),
+        COAL_BLOCK => Some(COAL_BLOCK_NAME),
+        PACKED_ICE => Some(PACKED_ICE_NAME),
+        DOUBLE_PLANT => Some(DOUBLE_PLANT_NAME),
+        STANDING_BANNER => Some(STANDING_BANNER_NAME),
+        WALL_BANNER => Some(WALL_BANNER_NAME),
+        DAYLIGHT_DETECTOR_INVERTED => Some(DAYLIGHT_DETECTOR_INVERTED_NAME),
+        RED_SANDSTONE => Some(RED_SANDSTONE_NAME),
+        RED_SANDSTONE_STAIRS => Some(RED_SANDSTONE_STAIRS_NAME),
+        DOUBLE_STONE_SLAB2 => Some(DOUBLE_STONE_SLAB2_NAME),
+        STONE_SLAB2 => Some(STONE_SLAB2_NAME),
+        SPRUCE_FENCE_GATE => Some(SPRUCE_FENCE_GATE_NAME),
+        BIRCH_FENCE_GATE => Some(BIRCH_FENCE_GATE_NAME),
+        JUNGLE_FENCE_GATE => Some(JUNGLE_FENCE_GATE_NAME),
+        DARK_OAK_FENCE_GATE => Some(DARK_OAK_FENCE_GATE_NAME),
+        ACACIA_FENCE_GATE => Some(ACACIA_FENCE_GATE_NAME),
+        SPRUCE_FENCE => Some(SPRUCE_FENCE_NAME),
+        BIRCH_FENCE => Some(BIRCH_FENCE_NAME),
+        JUNGLE_FENCE => Some(JUNGLE_FENCE_NAME),
+        DARK_OAK_FENCE => Some(DARK_OAK_FENCE_NAME),
+        ACACIA_FENCE => Some(ACACIA_FENCE_NAME),
+        SPRUCE_DOOR => Some(SPRUCE_DOOR_NAME),
+        BIRCH_DOOR => Some(BIRCH_DOOR_NAME),
+        JUNGLE_DOOR => Some(JUNGLE_DOOR_NAME),
+        ACACIA_DOOR => Some(ACACIA_DOOR_NAME),
+        DARK_OAK_DOOR => Some(DARK_OAK_DOOR_NAME),
+        IRON_SHOVEL => Some(IRON_SHOVEL_NAME),
+        IRON_PICKAXE => Some(IRON_PICKAXE_NAME),
+        IRON_AXE => Some(IRON_AXE_NAME),
+        FLINT_AND_STEEL => Some(FLINT_AND_STEEL_NAME),
+        APPLE => Some(APPLE_NAME),
+        BOW => Some(BOW_NAME),
+        ARROW => Some(ARROW_NAME),
+        COAL => Some(COAL_NAME),
+        DIAMOND => Some(DIAMOND_NAME),
+        IRON_INGOT => Some(IRON_INGOT_NAME),
+        GOLD_INGOT => Some(GOLD_INGOT_NAME),
+        IRON_SWORD => Some(IRON_SWORD_NAME),
+        WOODEN_SWORD => Some(WOODEN_SWORD_NAME),
+        WOODEN_SHOVEL => Some(WOODEN_SHOVEL_NAME),
+        WOODEN_PICKAXE => Some(WOODEN_PICKAXE_NAME),
+        WOODEN_AXE => Some(WOODEN_AXE_NAME),
+        STONE_SWORD => Some(STONE_SWORD_NAME),
+        STONE_SHOVEL => Some(STONE_SHOVEL_NAME),
+        STONE_PICKAXE => Some(STONE_PICKAXE_NAME),
+        STONE_AXE => Some(STONE_AXE_NAME),
+        DIAMOND_SWORD => Some(DIAMOND_SWORD_NAME),
+        DIAMOND_SHOVEL => Some(DIAMOND_SHOVEL_NAME),
+        DIAMOND_PICKAXE => Some(DIAMOND_PICKAXE_NAME),
+        DIAMOND_AXE => Some(DIAMOND_AXE_NAME),
+        STICK => Some(STICK_NAME),
+        BOWL => Some(BOWL_NAME),
+        MUSHROOM_STEW => Some(MUSHROOM_STEW_NAME),
+        GOLDEN_SWORD => Some(GOLDEN_SWORD_NAME),
+        GOLDEN_SHOVEL => Some(GOLDEN_SHOVEL_NAME),
+        GOLDEN_PICKAXE => Some(GOLDEN_PICKAXE_NAME),
+        GOLDEN_AXE => Some(GOLDEN_AXE_NAME),
+        STRING => Some(STRING_NAME),
+        FEATHER => Some(FEATHER_NAME),
+        GUNPOWDER => Some(GUNPOWDER_NAME),
+        WOODEN_HOE => Some(WOODEN_HOE_NAME),
+        STONE_HOE => Some(STONE_HOE_NAME),
+        IRON_HOE => Some(IRON_HOE_NAME),
+        DIAMOND_HOE => Some(DIAMOND_HOE_NAME),
+        GOLDEN_HOE => Some(GOLDEN_HOE_NAME),
+        WHEAT_SEEDS => Some(WHEAT_SEEDS_NAME),
+        WHEAT_ITEM => Some(WHEAT_ITEM_NAME),
+        BREAD => Some(BREAD_NAME),
+        LEATHER_HELMET => Some(LEATHER_HELMET_NAME),
+        LEATHER_CHESTPLATE => Some(LEATHER_CHESTPLATE_NAME),
+        LEATHER_LEGGINGS => Some(LEATHER_LEGGINGS_NAME),
+        LEATHER_BOOTS => Some(LEATHER_BOOTS_NAME),
+        CHAINMAIL_HELMET => Some(CHAINMAIL_HELMET_NAME),
+        CHAINMAIL_CHESTPLATE => Some(CHAINMAIL_CHESTPLATE_NAME),
+        CHAINMAIL_LEGGINGS => Some(CHAINMAIL_LEGGINGS_NAME),
+        CHAINMAIL_BOOTS => Some(CHAINMAIL_BOOTS_NAME),
+        IRON_HELMET => Some(IRON_HELMET_NAME),
+        IRON_CHESTPLATE => Some(IRON_CHESTPLATE_NAME),
+        IRON_LEGGINGS => Some(IRON_LEGGINGS_NAME),
+        IRON_BOOTS => Some(IRON_BOOTS_NAME),
+        DIAMOND_HELMET => Some(DIAMOND_HELMET_NAME),
+        DIAMOND_CHESTPLATE => Some(DIAMOND_CHESTPLATE_NAME),
+        DIAMOND_LEGGINGS => Some(DIAMOND_LEGGINGS_NAME),
+        DIAMOND_BOOTS => Some(DIAMOND_BOOTS_NAME),
+        GOLDEN_HELMET => Some(GOLDEN_HELMET_NAME),
+        GOLDEN_CHESTPLATE => Some(GOLDEN_CHESTPLATE_NAME),
+        GOLDEN_LEGGINGS => Some(GOLDEN_LEGGINGS_NAME),
+        GOLDEN_BOOTS => Some(GOLDEN_BOOTS_NAME),
+        FLINT => Some(FLINT_NAME),
+        PORKCHOP => Some(PORKCHOP_NAME),
+        COOKED_PORKCHOP => Some(COOKED_PORKCHOP_NAME),
+        PAINTING => Some(PAINTING_NAME),
+        GOLDEN_APPLE => Some(GOLDEN_APPLE_NAME),
+        SIGN => Some(SIGN_NAME),
+        WOODEN_DOOR_ITEM => Some(WOODEN_DOOR_ITEM_NAME),
+        BUCKET => Some(BUCKET_NAME),
+        WATER_BUCKET => Some(WATER_BUCKET_NAME),
+        LAVA_BUCKET => Some(LAVA_BUCKET_NAME),
+        MINECART => Some(MINECART_NAME),
+        SADDLE => Some(SADDLE_NAME),
+        IRON_DOOR_ITEM => Some(IRON_DOOR__ITEM_NAME),
+        REDSTONE => Some(REDSTONE_NAME),
+        SNOWBALL => Some(SNOWBALL_NAME),
+        BOAT => Some(BOAT_NAME),
+        LEATHER => Some(LEATHER_NAME),
+        MILK_BUCKET => Some(MILK_BUCKET_NAME),
+        BRICK => Some(BRICK_NAME),
+        CLAY_BALL => Some(CLAY_BALL_NAME),
+        REEDS_ITEM => Some(REEDS_ITEM_NAME),
+        PAPER => Some(PAPER_NAME),
+        BOOK => Some(BOOK_NAME),
+        SLIME_BALL => Some(SLIME_BALL_NAME),
+        CHEST_MINECART => Some(CHEST_MINECART_NAME),
+        FURNACE_MINECART => Some(FURNACE_MINECART_NAME),
+        EGG => Some(EGG_NAME),
+        COMPASS => Some(COMPASS_NAME),
+        FISHING_ROD => Some(FISHING_ROD_NAME),
+        CLOCK => Some(CLOCK_NAME),
+        GLOWSTONE_DUST => Some(GLOWSTONE_DUST_NAME),
+        FISH => Some(FISH_NAME),
+        COOKED_FISH => Some(COOKED_FISH_NAME),
+        DYE => Some(DYE_NAME),
+        BONE => Some(BONE_NAME),
+        SUGAR => Some(SUGAR_NAME),
+        CAKE_ITEM => Some(CAKE_ITEM_NAME),
+        BED_ITEM => Some(BED_ITEM_NAME),
+        REPEATER => Some(REPEATER_NAME),
+        COOKIE => Some(COOKIE_NAME),
+        FILLED_MAP => Some(FILLED_MAP_NAME),
+        SHEARS => Some(SHEARS_NAME),
+        MELON => Some(MELON_NAME),
+        PUMPKIN_SEEDS => Some(PUMPKIN_SEEDS_NAME),
+        MELON_SEEDS => Some(MELON_SEEDS_NAME),
+        BEEF => Some(BEEF_NAME),
+        COOKED_BEEF => Some(COOKED_BEEF_NAME),
+        CHICKEN => Some(CHICKEN_NAME),
+        COOKED_CHICKEN => Some(COOKED_CHICKEN_NAME),
+        ROTTEN_FLESH => Some(ROTTEN_FLESH_NAME),
+        ENDER_PEARL => Some(ENDER_PEARL_NAME),
+        BLAZE_ROD => Some(BLAZE_ROD_NAME),
+        GHAST_TEAR => Some(GHAST_TEAR_NAME),
+        GOLD_NUGGET => Some(GOLD_NUGGET_NAME),
+        NETHER_WART_ITEM => Some(NETHER_WART_ITEM_NAME),
+        POTION => Some(POTION_NAME),
+        GLASS_BOTTLE => Some(GLASS_BOTTLE_NAME),
+        SPIDER_EYE => Some(SPIDER_EYE_NAME),
+        FERMENTED_SPIDER_EYE => Some(FERMENTED_SPIDER_EYE_NAME),
+        BLAZE_POWDER => Some(BLAZE_POWDER_NAME),
+        MAGMA_CREAM => Some(MAGMA_CREAM_NAME),
+        BREWING_STAND_ITEM => Some(BREWING_STAND_ITEM_NAME),
+        CAULDRON_ITEM => Some(CAULDRON_ITEM_NAME),
+        ENDER_EYE => Some(ENDER_EYE_NAME),
+        SPECKLED_MELON => Some(SPECKLED_MELON_NAME),
+        SPAWN_EGG => Some(SPAWN_EGG_NAME),
+        EXPERIENCE_BOTTLE => Some(EXPERIENCE_BOTTLE_NAME),
+        FIRE_CHARGE => Some(FIRE_CHARGE_NAME),
+        WRITABLE_BOOK => Some(WRITABLE_BOOK_NAME),
+        WRITTEN_BOOK => Some(WRITTEN_BOOK_NAME),
+        EMERALD => Some(EMERALD_NAME),
+        ITEM_FRAME => Some(ITEM_FRAME_NAME),
+        FLOWER_POT_ITEM => Some(FLOWER_POT_ITEM_NAME),
+        CARROT => Some(CARROT_NAME),
+        POTATO => Some(POTATO_NAME),
+        BAKED_POTATO => Some(BAKED_POTATO_NAME),
+        POISONOUS_POTATO => Some(POISONOUS_POTATO_NAME),
+        MAP => Some(MAP_NAME),
+        GOLDEN_CARROT => Some(GOLDEN_CARROT_NAME),
+        SKULL_ITEM => Some(SKULL_ITEM_NAME),
+        CARROT_ON_A_STICK => Some(CARROT_ON_A_STICK_NAME),
+        NETHER_STAR => Some(NETHER_STAR_NAME),
+        PUMPKIN_PIE => Some(PUMPKIN_PIE_NAME),
+        FIREWORKS => Some(FIREWORKS_NAME),
+        FIREWORK_CHARGE => Some(FIREWORK_CHARGE_NAME),
+        ENCHANTED_BOOK => Some(ENCHANTED_BOOK_NAME),
+        COMPARATOR => Some(COMPARATOR_NAME),
+        NETHERBRICK => Some(NETHERBRICK_NAME),
+        QUARTZ => Some(QUARTZ_NAME),
+        TNT_MINECART => Some(TNT_MINECART_NAME),
+        HOPPER_MINECART => Some(HOPPER_MINECART_NAME),
+        PRISMARINE_SHARD => Some(PRISMARINE_SHARD_NAME),
+        PRISMARINE_CRYSTALS => Some(PRISMARINE_CRYSTALS_NAME),
+        RABBIT => Some(RABBIT_NAME),
+        COOKED_RABBIT => Some(COOKED_RABBIT_NAME),
+        RABBIT_STEW => Some(RABBIT_STEW_NAME),
+        RABBIT_FOOT => Some(RABBIT_FOOT_NAME),
+        RABBIT_HIDE => Some(RABBIT_HIDE_NAME),
+        ARMOR_STAND => Some(ARMOR_STAND_NAME),
+        IRON_HORSE_ARMOR => Some(IRON_HORSE_ARMOR_NAME),
+        GOLDEN_HORSE_ARMOR => Some(GOLDEN_HORSE_ARMOR_NAME),
+        DIAMOND_HORSE_ARMOR => Some(DIAMOND_HORSE_ARMOR_NAME),
+        LEAD => Some(LEAD_NAME),
+        NAME_TAG => Some(NAME_TAG_NAME),
+        COMMAND_BLOCK_MINECART => Some(COMMAND_BLOCK_MINECART_NAME),
+        MUTTON => Some(MUTTON_NAME),
+        COOKED_MUTTON => Some(COOKED_MUTTON_NAME),
+        BANNER => Some(BANNER_NAME),
+        SPRUCE_DOOR_ITEM => Some(SPRUCE_DOOR_ITEM_NAME),
+        BIRCH_DOOR_ITEM => Some(BIRCH_DOOR_ITEM_NAME),
+        JUNGLE_DOOR_ITEM => Some(JUNGLE_DOOR_ITEM_NAME),
+        ACACIA_DOOR_ITEM => Some(ACACIA_DOOR_ITEM_NAME),
+        DARK_OAK_DOOR_ITEM => Some(DARK_OAK_DOOR_ITEM_NAME),
+        RECORD_13 => Some(RECORD_13_NAME),
+        RECORD_CAT => Some(RECORD_CAT_NAME),
+        RECORD_BLOCKS => Some(RECORD_BLOCKS_NAME),
+        RECORD_CHIRP => Some(RECORD_CHIRP_NAME),
+        RECORD_FAR => Some(RECORD_FAR_NAME),
+        RECORD_MALL => Some(RECORD_MALL_NAME),
+        RECORD_MELLOHI => Some(RECORD_MELLOHI_NAME),
+        RECORD_STAL => Some(RECORD_STAL_NAME),
+        RECORD_STRAD => Some(RECORD_STRAD_NAME),
+        RECORD_WARD => Some(RECORD_WARD_NAME),
+        RECORD_11 => Some(RECORD_11_NAME),
+        RECORD_WAIT => Some(RECORD_WAIT_NAME),
+        _ => None,
+    }
+}
+
+/// Returns the item id of given registry name. Names shared by a block and its item (eg: cake) resolve to the item.
+pub fn from_name(name: &str) -> Option<u16> {
+    match name {
+        AIR_NAME => Some(AIR),
+        STONE_NAME => Some(STONE),
+        GRASS_NAME => Some(GRASS),
+        DIRT_NAME => Some(DIRT),
+        COBBLESTONE_NAME => Some(COBBLESTONE),
+        PLANKS_NAME => Some(PLANKS),
+        SAPLING_NAME => Some(SAPLING),
+        BEDROCK_NAME => Some(BEDROCK),
+        FLOWING_WATER_NAME => Some(FLOWING_WATER),
+        WATER_NAME => Some(WATER),
+        FLOWING_LAVA_NAME => Some(FLOWING_LAVA),
+        LAVA_NAME => Some(LAVA),
+        SAND_NAME => Some(SAND),
+        GRAVEL_NAME => Some(GRAVEL),
+        GOLD_ORE_NAME => Some(GOLD_ORE),
+        IRON_ORE_NAME => Some(IRON_ORE),
+        COAL_ORE_NAME => Some(COAL_ORE),
+        LOG_NAME => Some(LOG),
+        LEAVES_NAME => Some(LEAVES),
+        SPONGE_NAME => Some(SPONGE),
+        GLASS_NAME => Some(GLASS),
+        LAPIS_ORE_NAME => Some(LAPIS_ORE),
+        LAPIS_BLOCK_NAME => Some(LAPIS_BLOCK),
+        DISPENSER_NAME => Some(DISPENSER),
+        SANDSTONE_NAME => Some(SANDSTONE),
+        NOTEBLOCK_NAME => Some(NOTEBLOCK),
+        GOLDEN_RAIL_NAME => Some(GOLDEN_RAIL),
+        DETECTOR_RAIL_NAME => Some(DETECTOR_RAIL),
+        STICKY_PISTON_NAME => Some(STICKY_PISTON),
+        WEB_NAME => Some(WEB),
+        TALLGRASS_NAME => Some(TALLGRASS),
+        DEADBUSH_NAME => Some(DEADBUSH),
+        PISTON_NAME => Some(PISTON),
+        PISTON_HEAD_NAME => Some(PISTON_HEAD),
+        WOOL_NAME => Some(WOOL),
+        PISTON_EXTENSION_NAME => Some(PISTON_EXTENSION),
+        YELLOW_FLOWER_NAME => Some(YELLOW_FLOWER),
+        RED_FLOWER_NAME => Some(RED_FLOWER),
+        BROWN_MUSHROOM_NAME => Some(BROWN_MUSHROOM),
+        RED_MUSHROOM_NAME => Some(RED_MUSHROOM),
+        GOLD_BLOCK_NAME => Some(GOLD_BLOCK),
+        IRON_BLOCK_NAME => Some(IRON_BLOCK),
+        DOUBLE_STONE_SLAB_NAME => Some(DOUBLE_STONE_SLAB),
+        STONE_SLAB_NAME => Some(STONE_SLAB),
+        BRICK_BLOCK_NAME => Some(BRICK_BLOCK),
+        TNT_NAME => Some(TNT),
+        BOOKSHELF_NAME => Some(BOOKSHELF),
+        MOSSY_COBBLESTONE_NAME => Some(MOSSY_COBBLESTONE),
+        OBSIDIAN_NAME => Some(OBSIDIAN),
+        TORCH_NAME => Some(TORCH),
+        FIRE_NAME => Some(FIRE),
+        MOB_SPAWNER_NAME => Some(MOB_SPAWNER),
+        OAK_STAIRS_NAME => Some(OAK_STAIRS),
+        CHEST_NAME => Some(CHEST),
+        REDSTONE_WIRE_NAME => Some(REDSTONE_WIRE),
+        DIAMOND_ORE_NAME => Some(DIAMOND_ORE),
+        DIAMOND_BLOCK_NAME => Some(DIAMOND_BLOCK),
+        CRAFTING_TABLE_NAME => Some(CRAFTING_TABLE),
+        FARMLAND_NAME => Some(FARMLAND),
+        FURNACE_NAME => Some(FURNACE),
+        LIT_FURNACE_NAME => Some(LIT_FURNACE),
+        STANDING_SIGN_NAME => Some(STANDING_SIGN),
+        LADDER_NAME => Some(LADDER),
+        RAIL_NAME => Some(RAIL),
+        STONE_STAIRS_NAME => Some(STONE_STAIRS),
+        WALL_SIGN_NAME => Some(WALL_SIGN),
+        LEVER_NAME => Some(LEVER),
+        STONE_PRESSURE_PLATE_NAME => Some(STONE_PRESSURE_PLATE),
+        WOODEN_PRESSURE_PLATE_NAME => Some(WOODEN_PRESSURE_PLATE),
+        REDSTONE_ORE_NAME => Some(REDSTONE_ORE),
+        LIT_REDSTONE_ORE_NAME => Some(LIT_REDSTONE_ORE),
+        UNLIT_REDSTONE_TORCH_NAME => Some(UNLIT_REDSTONE_TORCH),
+        REDSTONE_TORCH_NAME => Some(REDSTONE_TORCH),
+        STONE_BUTTON_NAME => Some(STONE_BUTTON),
+        SNOW_LAYER_NAME => Some(SNOW_LAYER),
+        ICE_NAME => Some(ICE),
+        SNOW_NAME => Some(SNOW),
+        CACTUS_NAME => Some(CACTUS),
+        CLAY_NAME => Some(CLAY),
+        JUKEBOX_NAME => Some(JUKEBOX),
+        FENCE_NAME => Some(FENCE),
+        PUMPKIN_NAME => Some(PUMPKIN),
+        NETHERRACK_NAME => Some(NETHERRACK),
+        SOUL_SAND_NAME => Some(SOUL_SAND),
+        GLOWSTONE_NAME => Some(GLOWSTONE),
+        PORTAL_NAME => Some(PORTAL),
+        LIT_PUMPKIN_NAME => Some(LIT_PUMPKIN),
+        UNPOWERED_REPEATER_NAME => Some(UNPOWERED_REPEATER),
+        POWERED_REPEATER_NAME => Some(POWERED_REPEATER),
+        STAINED_GLASS_NAME => Some(STAINED_GLASS),
+        TRAPDOOR_NAME => Some(TRAPDOOR),
+        MONSTER_EGG_NAME => Some(MONSTER_EGG),
+        STONEBRICK_NAME => Some(STONEBRICK),
+        BROWN_MUSHROOM_BLOCK_NAME => Some(BROWN_MUSHROOM_BLOCK),
+        RED_MUSHROOM_BLOCK_NAME => Some(RED_MUSHROOM_BLOCK),
+        IRON_BARS_NAME => Some(IRON_BARS),
+        GLASS_PANE_NAME => Some(GLASS_PANE),
+        MELON_BLOCK_NAME => Some(MELON_BLOCK),
+        PUMPKIN_STEM_NAME => Some(PUMPKIN_STEM),
+        MELON_STEM_NAME => Some(MELON_STEM),
+        VINE_NAME => Some(VINE),
+        FENCE_GATE_NAME => Some(FENCE_GATE),
+        BRICK_STAIRS_NAME => Some(BRICK_STAIRS),
+        STONE_BRICK_STAIRS_NAME => Some(STONE_BRICK_STAIRS),
+        MYCELIUM_NAME => Some(MYCELIUM),
+        WATERLILY_NAME => Some(WATERLILY),
+        NETHER_BRICK_NAME => Some(NETHER_BRICK),
+        NETHER_BRICK_FENCE_NAME => Some(NETHER_BRICK_FENCE),
+        NETHER_BRICK_STAIRS_NAME => Some(NETHER_BRICK_STAIRS),
+        ENCHANTING_TABLE_NAME => Some(ENCHANTING_TABLE),
+        END_PORTAL_NAME => Some(END_PORTAL),
+        END_PORTAL_FRAME_NAME => Some(END_PORTAL_FRAME),
+        END_STONE_NAME => Some(END_STONE),
+        DRAGON_EGG_NAME => Some(DRAGON_EGG),
+        REDSTONE_LAMP_NAME => Some(REDSTONE_LAMP),
+        LIT_REDSTONE_LAMP_NAME => Some(LIT_REDSTONE_LAMP),
+        DOUBLE_WOODEN_SLAB_NAME => Some(DOUBLE_WOODEN_SLAB),
+        WOODEN_SLAB_NAME => Some(WOODEN_SLAB),
+        COCOA_NAME => Some(COCOA),
+        SANDSTONE_STAIRS_NAME => Some(SANDSTONE_STAIRS),
+        EMERALD_ORE_NAME => Some(EMERALD_ORE),
+        ENDER_CHEST_NAME => Some(ENDER_CHEST),
+        TRIPWIRE_HOOK_NAME => Some(TRIPWIRE_HOOK),
+        TRIPWIRE_NAME => Some(TRIPWIRE),
+        EMERALD_BLOCK_NAME => Some(EMERALD_BLOCK),
+        SPRUCE_STAIRS_NAME => Some(SPRUCE_STAIRS),
+        BIRCH_STAIRS_NAME => Some(BIRCH_STAIRS),
+        JUNGLE_STAIRS_NAME => Some(JUNGLE_STAIRS),
+        COMMAND_BLOCK_NAME => Some(COMMAND_BLOCK),
+        BEACON_NAME => Some(BEACON),
+        COBBLESTONE_WALL_NAME => Some(COBBLESTONE_WALL),
+        CARROTS_NAME => Some(CARROTS),
+        POTATOES_NAME => Some(POTATOES),
+        WOODEN_BUTTON_NAME => Some(WOODEN_BUTTON),
+        ANVIL_NAME => Some(ANVIL),
+        TRAPPED_CHEST_NAME => Some(TRAPPED_CHEST),
+        LIGHT_WEIGHTED_PRESSURE_PLATE_NAME => Some(LIGHT_WEIGHTED_PRESSURE_PLATE),
+        HEAVY_WEIGHTED_PRESSURE_PLATE_NAME => Some(HEAVY_WEIGHTED_PRESSURE_PLATE),
+        UNPOWERED_COMPARATOR_NAME => Some(UNPOWERED_COMPARATOR),
+        POWERED_COMPARATOR_NAME => Some(POWERED_COMPARATOR),
+        DAYLIGHT_DETECTOR_NAME => Some(DAYLIGHT_DETECTOR),
+        REDSTONE_BLOCK_NAME => Some(REDSTONE_BLOCK),
+        QUARTZ_ORE_NAME => Some(QUARTZ_ORE),
+        HOPPER_NAME => Some(HOPPER),
+        QUARTZ_BLOCK_NAME => Some(QUARTZ_BLOCK),
+        QUARTZ_STAIRS_NAME => Some(QUARTZ_STAIRS),
+        ACTIVATOR_RAIL_NAME => Some(ACTIVATOR_RAIL),
+        DROPPER_NAME => Some(DROPPER),
+        STAINED_HARDENED_CLAY_NAME => Some(STAINED_HARDENED_CLAY),
+        STAINED_GLASS_PANE_NAME => Some(STAINED_GLASS_PANE),
+        LEAVES2_NAME => Some(LEAVES2),
+        LOG2_NAME => Some(LOG2),
+        ACACIA_STAIRS_NAME => Some(ACACIA_STAIRS),
+        DARK_OAK_STAIRS_NAME => Some(DARK_OAK_STAIRS),
+        SLIME_NAME => Some(SLIME),
+        BARRIER_NAME => Some(BARRIER),
+        IRON_TRAPDOOR_NAME => Some(IRON_TRAPDOOR),
+        PRISMARINE_NAME => Some(PRISMARINE),
+        SEA_LANTERN_NAME => Some(SEA_LANTERN),
+        HAY_BLOCK_NAME => Some(HAY_BLOCK),
+        CARPET_NAME => Some(CARPET),
+        HARDENED_CLAY_NAME => Some(HARDENED_CLAY),
+        COAL_BLOCK_NAME => Some(COAL_BLOCK),
+        PACKED_ICE_NAME => Some(PACKED_ICE),
+        DOUBLE_PLANT_NAME => Some(DOUBLE_PLANT),
+        STANDING_BANNER_NAME => Some(STANDING_BANNER),
+        WALL_BANNER_NAME => Some(WALL_BANNER),
+        DAYLIGHT_DETECTOR_INVERTED_NAME => Some(DAYLIGHT_DETECTOR_INVERTED),
+        RED_SANDSTONE_NAME => Some(RED_SANDSTONE),
+        RED_SANDSTONE_STAIRS_NAME => Some(RED_SANDSTONE_STAIRS),
+        DOUBLE_STONE_SLAB2_NAME => Some(DOUBLE_STONE_SLAB2),
+        STONE_SLAB2_NAME => Some(STONE_SLAB2),
+        SPRUCE_FENCE_GATE_NAME => Some(SPRUCE_FENCE_GATE),
+        BIRCH_FENCE_GATE_NAME => Some(BIRCH_FENCE_GATE),
+        JUNGLE_FENCE_GATE_NAME => Some(JUNGLE_FENCE_GATE),
+        DARK_OAK_FENCE_GATE_NAME => Some(DARK_OAK_FENCE_GATE),
+        ACACIA_FENCE_GATE_NAME => Some(ACACIA_FENCE_GATE),
+        SPRUCE_FENCE_NAME => Some(SPRUCE_FENCE),
+        BIRCH_FENCE_NAME => Some(BIRCH_FENCE),
+        JUNGLE_FENCE_NAME => Some(JUNGLE_FENCE),
+        DARK_OAK_FENCE_NAME => Some(DARK_OAK_FENCE),
+        ACACIA_FENCE_NAME => Some(ACACIA_FENCE),
+        IRON_SHOVEL_NAME => Some(IRON_SHOVEL),
+        IRON_PICKAXE_NAME => Some(IRON_PICKAXE),
+        IRON_AXE_NAME => Some(IRON_AXE),
+        FLINT_AND_STEEL_NAME => Some(FLINT_AND_STEEL),
+        APPLE_NAME => Some(APPLE),
+        BOW_NAME => Some(BOW),
+        ARROW_NAME => Some(ARROW),
+        COAL_NAME => Some(COAL),
+        DIAMOND_NAME => Some(DIAMOND),
+        IRON_INGOT_NAME => Some(IRON_INGOT),
+        GOLD_INGOT_NAME => Some(GOLD_INGOT),
+        IRON_SWORD_NAME => Some(IRON_SWORD),
+        WOODEN_SWORD_NAME => Some(WOODEN_SWORD),
+        WOODEN_SHOVEL_NAME => Some(WOODEN_SHOVEL),
+        WOODEN_PICKAXE_NAME => Some(WOODEN_PICKAXE),
+        WOODEN_AXE_NAME => Some(WOODEN_AXE),
+        STONE_SWORD_NAME => Some(STONE_SWORD),
+        STONE_SHOVEL_NAME => Some(STONE_SHOVEL),
+        STONE_PICKAXE_NAME => Some(STONE_PICKAXE),
+        STONE_AXE_NAME => Some(STONE_AXE),
+        DIAMOND_SWORD_NAME => Some(DIAMOND_SWORD),
+        DIAMOND_SHOVEL_NAME => Some(DIAMOND_SHOVEL),
+        DIAMOND_PICKAXE_NAME => Some(DIAMOND_PICKAXE),
+        DIAMOND_AXE_NAME => Some(DIAMOND_AXE),
+        STICK_NAME => Some(STICK),
+        BOWL_NAME => Some(BOWL),
+        MUSHROOM_STEW_NAME => Some(MUSHROOM_STEW),
+        GOLDEN_SWORD_NAME => Some(GOLDEN_SWORD),
+        GOLDEN_SHOVEL_NAME => Some(GOLDEN_SHOVEL),
+        GOLDEN_PICKAXE_NAME => Some(GOLDEN_PICKAXE),
+        GOLDEN_AXE_NAME => Some(GOLDEN_AXE),
+        STRING_NAME => Some(STRING),
+        FEATHER_NAME => Some(FEATHER),
+        GUNPOWDER_NAME => Some(GUNPOWDER),
+        WOODEN_HOE_NAME => Some(WOODEN_HOE),
+        STONE_HOE_NAME => Some(STONE_HOE),
+        IRON_HOE_NAME => Some(IRON_HOE),
+        DIAMOND_HOE_NAME => Some(DIAMOND_HOE),
+        GOLDEN_HOE_NAME => Some(GOLDEN_HOE),
+        WHEAT_SEEDS_NAME => Some(WHEAT_SEEDS),
+        WHEAT_ITEM_NAME => Some(WHEAT_ITEM),
+        BREAD_NAME => Some(BREAD),
+        LEATHER_HELMET_NAME => Some(LEATHER_HELMET),
+        LEATHER_CHESTPLATE_NAME => Some(LEATHER_CHESTPLATE),
+        LEATHER_LEGGINGS_NAME => Some(LEATHER_LEGGINGS),
+        LEATHER_BOOTS_NAME => Some(LEATHER_BOOTS),
+        CHAINMAIL_HELMET_NAME => Some(CHAINMAIL_HELMET),
+        CHAINMAIL_CHESTPLATE_NAME => Some(CHAINMAIL_CHESTPLATE),
+        CHAINMAIL_LEGGINGS_NAME => Some(CHAINMAIL_LEGGINGS),
+        CHAINMAIL_BOOTS_NAME => Some(CHAINMAIL_BOOTS),
+        IRON_HELMET_NAME => Some(IRON_HELMET),
+        IRON_CHESTPLATE_NAME => Some(IRON_CHESTPLATE),
+        IRON_LEGGINGS_NAME => Some(IRON_LEGGINGS),
+        IRON_BOOTS_NAME => Some(IRON_BOOTS),
+        DIAMOND_HELMET_NAME => Some(DIAMOND_HELMET),
+        DIAMOND_CHESTPLATE_NAME => Some(DIAMOND_CHESTPLATE),
+        DIAMOND_LEGGINGS_NAME => Some(DIAMOND_LEGGINGS),
+        DIAMOND_BOOTS_NAME => Some(DIAMOND_BOOTS),
+        GOLDEN_HELMET_NAME => Some(GOLDEN_HELMET),
+        GOLDEN_CHESTPLATE_NAME => Some(GOLDEN_CHESTPLATE),
+        GOLDEN_LEGGINGS_NAME => Some(GOLDEN_LEGGINGS),
+        GOLDEN_BOOTS_NAME => Some(GOLDEN_BOOTS),
+        FLINT_NAME => Some(FLINT),
+        PORKCHOP_NAME => Some(PORKCHOP),
+        COOKED_PORKCHOP_NAME => Some(COOKED_PORKCHOP),
+        PAINTING_NAME => Some(PAINTING),
+        GOLDEN_APPLE_NAME => Some(GOLDEN_APPLE),
+        SIGN_NAME => Some(SIGN),
+        WOODEN_DOOR_ITEM_NAME => Some(WOODEN_DOOR_ITEM),
+        BUCKET_NAME => Some(BUCKET),
+        WATER_BUCKET_NAME => Some(WATER_BUCKET),
+        LAVA_BUCKET_NAME => Some(LAVA_BUCKET),
+        MINECART_NAME => Some(MINECART),
+        SADDLE_NAME => Some(SADDLE),
+        IRON_DOOR__ITEM_NAME => Some(IRON_DOOR_ITEM),
+        REDSTONE_NAME => Some(REDSTONE),
+        SNOWBALL_NAME => Some(SNOWBALL),
+        BOAT_NAME => Some(BOAT),
+        LEATHER_NAME => Some(LEATHER),
+        MILK_BUCKET_NAME => Some(MILK_BUCKET),
+        BRICK_NAME => Some(BRICK),
+        CLAY_BALL_NAME => Some(CLAY_BALL),
+        REEDS_ITEM_NAME => Some(REEDS_ITEM),
+        PAPER_NAME => Some(PAPER),
+        BOOK_NAME => Some(BOOK),
+        SLIME_BALL_NAME => Some(SLIME_BALL),
+        CHEST_MINECART_NAME => Some(CHEST_MINECART),
+        FURNACE_MINECART_NAME => Some(FURNACE_MINECART),
+        EGG_NAME => Some(EGG),
+        COMPASS_NAME => Some(COMPASS),
+        FISHING_ROD_NAME => Some(FISHING_ROD),
+        CLOCK_NAME => Some(CLOCK),
+        GLOWSTONE_DUST_NAME => Some(GLOWSTONE_DUST),
+        FISH_NAME => Some(FISH),
+        COOKED_FISH_NAME => Some(COOKED_FISH),
+        DYE_NAME => Some(DYE),
+        BONE_NAME => Some(BONE),
+        SUGAR_NAME => Some(SUGAR),
+        CAKE_ITEM_NAME => Some(CAKE_ITEM),
+        BED_ITEM_NAME => Some(BED_ITEM),
+        REPEATER_NAME => Some(REPEATER),
+        COOKIE_NAME => Some(COOKIE),
+        FILLED_MAP_NAME => Some(FILLED_MAP),
+        SHEARS_NAME => Some(SHEARS),
+        MELON_NAME => Some(MELON),
+        PUMPKIN_SEEDS_NAME => Some(PUMPKIN_SEEDS),
+        MELON_SEEDS_NAME => Some(MELON_SEEDS),
+        BEEF_NAME => Some(BEEF),
+        COOKED_BEEF_NAME => Some(COOKED_BEEF),
+        CHICKEN_NAME => Some(CHICKEN),
+        COOKED_CHICKEN_NAME => Some(COOKED_CHICKEN),
+        ROTTEN_FLESH_NAME => Some(ROTTEN_FLESH),
+        ENDER_PEARL_NAME => Some(ENDER_PEARL),
+        BLAZE_ROD_NAME => Some(BLAZE_ROD),
+        GHAST_TEAR_NAME => Some(GHAST_TEAR),
+        GOLD_NUGGET_NAME => Some(GOLD_NUGGET),
+        NETHER_WART_ITEM_NAME => Some(NETHER_WART_ITEM),
+        POTION_NAME => Some(POTION),
+        GLASS_BOTTLE_NAME => Some(GLASS_BOTTLE),
+        SPIDER_EYE_NAME => Some(SPIDER_EYE),
+        FERMENTED_SPIDER_EYE_NAME => Some(FERMENTED_SPIDER_EYE),
+        BLAZE_POWDER_NAME => Some(BLAZE_POWDER),
+        MAGMA_CREAM_NAME => Some(MAGMA_CREAM),
+        BREWING_STAND_ITEM_NAME => Some(BREWING_STAND_ITEM),
+        CAULDRON_ITEM_NAME => Some(CAULDRON_ITEM),
+        ENDER_EYE_NAME => Some(ENDER_EYE),
+        SPECKLED_MELON_NAME => Some(SPECKLED_MELON),
+        SPAWN_EGG_NAME => Some(SPAWN_EGG),
+        EXPERIENCE_BOTTLE_NAME => Some(EXPERIENCE_BOTTLE),
+        FIRE_CHARGE_NAME => Some(FIRE_CHARGE),
+        WRITABLE_BOOK_NAME => Some(WRITABLE_BOOK),
+        WRITTEN_BOOK_NAME => Some(WRITTEN_BOOK),
+        EMERALD_NAME => Some(EMERALD),
+        ITEM_FRAME_NAME => Some(ITEM_FRAME),
+        FLOWER_POT_ITEM_NAME => Some(FLOWER_POT_ITEM),
+        CARROT_NAME => Some(CARROT),
+        POTATO_NAME => Some(POTATO),
+        BAKED_POTATO_NAME => Some(BAKED_POTATO),
+        POISONOUS_POTATO_NAME => Some(POISONOUS_POTATO),
+        MAP_NAME => Some(MAP),
+        GOLDEN_CARROT_NAME => Some(GOLDEN_CARROT),
+        SKULL_ITEM_NAME => Some(SKULL_ITEM),
+        CARROT_ON_A_STICK_NAME => Some(CARROT_ON_A_STICK),
+        NETHER_STAR_NAME => Some(NETHER_STAR),
+        PUMPKIN_PIE_NAME => Some(PUMPKIN_PIE),
+        FIREWORKS_NAME => Some(FIREWORKS),
+        FIREWORK_CHARGE_NAME => Some(FIREWORK_CHARGE),
+        ENCHANTED_BOOK_NAME => Some(ENCHANTED_BOOK),
+        COMPARATOR_NAME => Some(COMPARATOR),
+        NETHERBRICK_NAME => Some(NETHERBRICK),
+        QUARTZ_NAME => Some(QUARTZ),
+        TNT_MINECART_NAME => Some(TNT_MINECART),
+        HOPPER_MINECART_NAME => Some(HOPPER_MINECART),
+        PRISMARINE_SHARD_NAME => Some(PRISMARINE_SHARD),
+        PRISMARINE_CRYSTALS_NAME => Some(PRISMARINE_CRYSTALS),
+        RABBIT_NAME => Some(RABBIT),
+        COOKED_RABBIT_NAME => Some(COOKED_RABBIT),
+        RABBIT_STEW_NAME => Some(RABBIT_STEW),
+        RABBIT_FOOT_NAME => Some(RABBIT_FOOT),
+        RABBIT_HIDE_NAME => Some(RABBIT_HIDE),
+        ARMOR_STAND_NAME => Some(ARMOR_STAND),
+        IRON_HORSE_ARMOR_NAME => Some(IRON_HORSE_ARMOR),
+        GOLDEN_HORSE_ARMOR_NAME => Some(GOLDEN_HORSE_ARMOR),
+        DIAMOND_HORSE_ARMOR_NAME => Some(DIAMOND_HORSE_ARMOR),
+        LEAD_NAME => Some(LEAD),
+        NAME_TAG_NAME => Some(NAME_TAG),
+        COMMAND_BLOCK_MINECART_NAME => Some(COMMAND_BLOCK_MINECART),
+        MUTTON_NAME => Some(MUTTON),
+        COOKED_MUTTON_NAME => Some(COOKED_MUTTON),
+        BANNER_NAME => Some(BANNER),
+        SPRUCE_DOOR_ITEM_NAME => Some(SPRUCE_DOOR_ITEM),
+        BIRCH_DOOR_ITEM_NAME => Some(BIRCH_DOOR_ITEM),
+        JUNGLE_DOOR_ITEM_NAME => Some(JUNGLE_DOOR_ITEM),
+        ACACIA_DOOR_ITEM_NAME => Some(ACACIA_DOOR_ITEM),
+        DARK_OAK_DOOR_ITEM_NAME => Some(DARK_OAK_DOOR_ITEM),
+        RECORD_13_NAME => Some(RECORD_13),
+        RECORD_CAT_NAME => Some(RECORD_CAT),
+        RECORD_BLOCKS_NAME => Some(RECORD_BLOCKS),
+        RECORD_CHIRP_NAME => Some(RECORD_CHIRP),
+        RECORD_FAR_NAME => Some(RECORD_FAR),
+        RECORD_MALL_NAME => Some(RECORD_MALL),
+        RECORD_MELLOHI_NAME => Some(RECORD_MELLOHI),
+        RECORD_STAL_NAME => Some(RECORD_STAL),
+        RECORD_STRAD_NAME => Some(RECORD_STRAD),
+        RECORD_WARD_NAME => Some(RECORD_WARD),
+        RECORD_11_NAME => Some(RECORD_11),
+        RECORD_WAIT_NAME => Some(RECORD_WAIT),
+        _ => None,
+    }
+}
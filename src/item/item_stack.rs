@@ -1,7 +1,7 @@
 use std::collections::HashMap;
 
-use crate::item::Item;
-use crate::nbt::{Compound, Nbt};
+use crate::item::{item_id, GenericItem, Item};
+use crate::nbt::{Compound, DeserializeError, Nbt, Tag};
 use crate::proto::data::SlotData;
 
 #[derive(Debug)]
@@ -15,6 +15,67 @@ impl ItemStack {
         ItemStack { item, count }
     }
 
+    pub fn get_item(&self) -> &dyn Item {
+        &*self.item
+    }
+
+    pub fn get_count(&self) -> i8 {
+        self.count
+    }
+
+    pub fn set_count(&mut self, count: i8) {
+        self.count = count;
+    }
+
+    pub fn get_max_stack_size(&self) -> i8 {
+        item_id::get_max_stack_size(self.item.get_id())
+    }
+
+    fn get_tag(&self) -> Option<Compound> {
+        let mut tag = Compound(HashMap::new());
+        self.item.update_tag(&mut tag);
+        if tag.0.is_empty() {
+            None
+        } else {
+            Some(tag)
+        }
+    }
+
+    /// Whether both stacks are the same item and could be stacked together.
+    pub fn is_similar(&self, other: &ItemStack) -> bool {
+        self.item.get_id() == other.item.get_id()
+            && self.item.get_damage_value() == other.item.get_damage_value()
+            && self.get_tag() == other.get_tag()
+    }
+
+    /// A copy of the stack with a different count.
+    pub fn copy_with_count(&self, count: i8) -> ItemStack {
+        let item = GenericItem { id: self.item.get_id(), damage: self.item.get_damage_value(), tag: self.get_tag() };
+        ItemStack::new(Box::new(item), count)
+    }
+
+    /// Takes count items off the stack, which may leave it with none.
+    pub fn split(&mut self, count: i8) -> ItemStack {
+        let count = count.min(self.count);
+        self.count -= count;
+        self.copy_with_count(count)
+    }
+
+    /// Stack sent by the client. None for empty slots and items which don't exist.
+    pub fn from_slot_data(data: &SlotData) -> Option<ItemStack> {
+        match *data {
+            SlotData::Some { id, item_count, item_damage, ref tag } => {
+                item_id::to_name(id as u16)?;
+                let tag = match *tag {
+                    Nbt::Some(_, ref tag) => Some(tag.clone()),
+                    Nbt::Empty => None,
+                };
+                Some(ItemStack::new(Box::new(GenericItem { id: id as u16, damage: item_damage, tag }), item_count))
+            }
+            SlotData::Empty => None,
+        }
+    }
+
     fn to_nbt_impl(&self, slot: Option<u8>) -> Compound {
         let mut compound = Compound(HashMap::new());
         compound.0.insert("Count".into(), self.count.into());
@@ -60,4 +121,28 @@ impl ItemStack {
     pub fn to_nbt(&self) -> Compound {
         self.to_nbt_impl(None)
     }
+
+    /// Reads an item stack in the format written by `to_nbt`. "id" can be a name (with or without namespace) or a numeric id.
+    pub fn from_nbt(compound: &Compound) -> Result<ItemStack, DeserializeError> {
+        let id = match *compound.get("id")? {
+            Tag::String(ref name) => {
+                let name = name.trim_start_matches("minecraft:");
+                item_id::from_name(name).ok_or_else(|| DeserializeError::UnknownId(name.into()))?
+            }
+            ref tag => tag.as_short()? as u16,
+        };
+        let count = compound.get("Count")?.as_byte()?;
+        let damage = if compound.contains_key("Damage") {
+            compound.get("Damage")?.as_short()?
+        } else {
+            0
+        };
+        let tag = if compound.contains_key("tag") {
+            Some(compound.get("tag")?.as_compound()?.clone())
+        } else {
+            None
+        };
+
+        Ok(ItemStack::new(Box::new(GenericItem { id, damage, tag }), count))
+    }
 }
@@ -1,6 +1,9 @@
+pub mod container;
 pub mod item_id;
 pub mod item_stack;
-//pub mod inventory;
+pub mod inventory;
+pub mod tool;
+pub mod window;
 
 use std::any::Any;
 use std::fmt::Debug;
@@ -9,7 +12,7 @@ use crate::block::block_entity::BlockEntity;
 use crate::block::{Block, BlockStateId};
 use crate::nbt;
 
-pub trait Item: Debug {
+pub trait Item: Debug + Send {
     fn get_id(&self) -> u16;
     fn get_name(&self) -> &'static str;
     fn get_damage_value(&self) -> i16 {
@@ -51,6 +54,34 @@ impl Item for BlockItem {
     }
 }
 
+/// An item only known by its id, damage and tag. Items read from disk are represented with this so nothing is lost.
+#[derive(Debug, Clone)]
+pub struct GenericItem {
+    pub id: u16,
+    pub damage: i16,
+    pub tag: Option<nbt::Compound>,
+}
+
+impl Item for GenericItem {
+    fn get_id(&self) -> u16 {
+        self.id
+    }
+    fn get_name(&self) -> &'static str {
+        item_id::to_name(self.id).unwrap_or(item_id::AIR_NAME)
+    }
+    fn get_damage_value(&self) -> i16 {
+        self.damage
+    }
+
+    fn update_tag(&self, tag: &mut nbt::Compound) {
+        if let Some(ref t) = self.tag {
+            for (k, v) in t.0.iter() {
+                tag.0.insert(k.clone(), v.clone());
+            }
+        }
+    }
+}
+
 /*
 #[derive(Debug)]
 pub enum Item {
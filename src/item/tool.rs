@@ -0,0 +1,58 @@
+use crate::item::item_id;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToolKind {
+    Pickaxe,
+    Shovel,
+    Axe,
+    Sword,
+    Shears,
+}
+
+/// An item which digs some blocks faster, or is needed to get drops from them.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tool {
+    pub kind: ToolKind,
+    /// 0 for wood and gold, 1 stone, 2 iron, 3 diamond.
+    pub level: u8,
+    /// dig speed on blocks the tool is made for.
+    pub efficiency: f32,
+}
+
+impl Tool {
+    const WOOD: (u8, f32) = (0, 2.0);
+    const STONE: (u8, f32) = (1, 4.0);
+    const IRON: (u8, f32) = (2, 6.0);
+    const DIAMOND: (u8, f32) = (3, 8.0);
+    const GOLD: (u8, f32) = (0, 12.0);
+
+    pub fn from_item_id(id: u16) -> Option<Tool> {
+        use self::ToolKind::*;
+        let (kind, (level, efficiency)) = match id {
+            item_id::WOODEN_PICKAXE => (Pickaxe, Self::WOOD),
+            item_id::STONE_PICKAXE => (Pickaxe, Self::STONE),
+            item_id::IRON_PICKAXE => (Pickaxe, Self::IRON),
+            item_id::DIAMOND_PICKAXE => (Pickaxe, Self::DIAMOND),
+            item_id::GOLDEN_PICKAXE => (Pickaxe, Self::GOLD),
+            item_id::WOODEN_SHOVEL => (Shovel, Self::WOOD),
+            item_id::STONE_SHOVEL => (Shovel, Self::STONE),
+            item_id::IRON_SHOVEL => (Shovel, Self::IRON),
+            item_id::DIAMOND_SHOVEL => (Shovel, Self::DIAMOND),
+            item_id::GOLDEN_SHOVEL => (Shovel, Self::GOLD),
+            item_id::WOODEN_AXE => (Axe, Self::WOOD),
+            item_id::STONE_AXE => (Axe, Self::STONE),
+            item_id::IRON_AXE => (Axe, Self::IRON),
+            item_id::DIAMOND_AXE => (Axe, Self::DIAMOND),
+            item_id::GOLDEN_AXE => (Axe, Self::GOLD),
+            // swords and shears have their own speeds for the few blocks they are good at.
+            item_id::WOODEN_SWORD => (Sword, Self::WOOD),
+            item_id::STONE_SWORD => (Sword, Self::STONE),
+            item_id::IRON_SWORD => (Sword, Self::IRON),
+            item_id::DIAMOND_SWORD => (Sword, Self::DIAMOND),
+            item_id::GOLDEN_SWORD => (Sword, Self::GOLD),
+            item_id::SHEARS => (Shears, (0, 1.0)),
+            _ => return None,
+        };
+        Some(Tool { kind, level, efficiency })
+    }
+}
@@ -0,0 +1,400 @@
+//! Clicks in inventory windows. The client predicts what a click does, so this works the same way vanilla does
+//! and the server can tell whether both agree.
+
+use std::ops::Range;
+
+use crate::item::item_stack::ItemStack;
+use crate::proto::data::SlotData;
+
+/// slot of clicks outside the window.
+pub const OUTSIDE: i16 = -999;
+/// window id and slot of the item on the cursor in SetSlot.
+pub const CURSOR: i16 = -1;
+
+/// Slots of an open window, numbered like the client does.
+pub trait Window {
+    fn len(&self) -> usize;
+
+    fn slot(&mut self, index: usize) -> &mut Option<ItemStack>;
+
+    /// Most items of the stack the slot holds, 0 if it can't be put there.
+    fn get_limit(&self, index: usize, item: &ItemStack) -> i8 {
+        item.get_max_stack_size()
+    }
+
+    /// Whether items can only be taken out of the slot, like crafting results.
+    fn is_output(&self, index: usize) -> bool {
+        false
+    }
+
+    /// Slots shift clicking moves the item in the slot to, tried in order. The bool fills the range from its end.
+    fn get_shift_targets(&self, index: usize, item: &ItemStack) -> Vec<(Range<usize>, bool)>;
+
+    /// Slot of the hotbar slot (0-8) in this window. The hotbar comes last in every window.
+    fn get_hotbar_slot(&self, hotbar: usize) -> usize {
+        self.len() - 9 + hotbar
+    }
+}
+
+/// Item the player moves around with the mouse, and the slots they drag it over.
+#[derive(Debug, Default)]
+pub struct Cursor {
+    pub item: Option<ItemStack>,
+    /// 0 splits the item evenly, 1 puts one in each slot, 2 fills every slot (creative). None when not dragging.
+    drag_mode: Option<i8>,
+    drag_slots: Vec<usize>,
+}
+
+impl Cursor {
+    fn reset_drag(&mut self) {
+        self.drag_mode = None;
+        self.drag_slots.clear();
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Click {
+    /// what vanilla returns for the click. The client sends what it got, if they differ the window is out of sync.
+    pub clicked: Option<ItemStack>,
+    /// items thrown out of the window.
+    pub dropped: Vec<ItemStack>,
+}
+
+impl Click {
+    /// Whether the client got the same result.
+    pub fn matches(&self, clicked_item: &SlotData) -> bool {
+        self.clicked.as_ref().map_or(SlotData::Empty, |i| i.to_slot_data()) == *clicked_item
+    }
+}
+
+/// Takes count items out of the slot, emptying it once none are left.
+fn take(slot: &mut Option<ItemStack>, count: i8) -> Option<ItemStack> {
+    let (taken, empty) = match *slot {
+        Some(ref mut item) if count > 0 => {
+            let taken = item.split(count);
+            (Some(taken), item.get_count() <= 0)
+        }
+        _ => (None, false),
+    };
+    if empty {
+        *slot = None;
+    }
+    taken
+}
+
+fn count(slot: &Option<ItemStack>) -> i8 {
+    slot.as_ref().map_or(0, |i| i.get_count())
+}
+
+/// Whether the item could be put into the slot. With stack_size, all of it has to fit.
+fn can_add(slot: &Option<ItemStack>, item: &ItemStack, stack_size: bool) -> bool {
+    match *slot {
+        None => true,
+        Some(ref other) => {
+            other.is_similar(item)
+                && other.get_count() + if stack_size { item.get_count() } else { 0 } <= item.get_max_stack_size()
+        }
+    }
+}
+
+/// Handles a ClickWindow packet. None if the click makes no sense, like a slot outside the window.
+pub fn click(window: &mut dyn Window, cursor: &mut Cursor, slot: i16, button: i8, mode: i8, creative: bool) -> Option<Click> {
+    let index = match slot {
+        OUTSIDE => None,
+        s if s >= 0 && (s as usize) < window.len() => Some(s as usize),
+        _ => return None,
+    };
+    let mut click = Click::default();
+    if mode == 5 {
+        drag(window, cursor, index, button, creative);
+        return Some(click);
+    }
+    if cursor.drag_mode.is_some() {
+        // anything else cancels dragging, and does nothing else.
+        cursor.reset_drag();
+        return Some(click);
+    }
+    match (mode, button, index) {
+        (0, 0, None) | (1, 0, None) => click.dropped.extend(cursor.item.take()),
+        (0, 1, None) | (1, 1, None) => click.dropped.extend(take(&mut cursor.item, 1)),
+        (0, 0, Some(i)) | (0, 1, Some(i)) => {
+            click.clicked = window.slot(i).as_ref().map(|i| i.copy_with_count(i.get_count()));
+            click_slot(window, cursor, i, button == 1);
+        }
+        (1, 0, Some(i)) | (1, 1, Some(i)) => click.clicked = shift_click(window, i),
+        (2, 0..=8, Some(i)) => {
+            let hotbar = window.get_hotbar_slot(button as usize);
+            let slot_item = window.slot(i).take();
+            let hotbar_item = window.slot(hotbar).take();
+            let fits = hotbar_item.as_ref().map_or(true, |h| window.get_limit(i, h) >= h.get_count());
+            let (slot_item, hotbar_item) = if fits { (hotbar_item, slot_item) } else { (slot_item, hotbar_item) };
+            *window.slot(i) = slot_item;
+            *window.slot(hotbar) = hotbar_item;
+        }
+        (3, 2, Some(i)) if creative && cursor.item.is_none() => {
+            cursor.item = window.slot(i).as_ref().map(|i| i.copy_with_count(i.get_max_stack_size()));
+        }
+        (4, 0, Some(i)) if cursor.item.is_none() => click.dropped.extend(take(window.slot(i), 1)),
+        (4, 1, Some(i)) if cursor.item.is_none() => click.dropped.extend(window.slot(i).take()),
+        (6, 0, Some(i)) if window.slot(i).is_none() => collect(window, cursor),
+        _ => {}
+    }
+    Some(click)
+}
+
+/// A left click puts the whole cursor down or picks the whole slot up, a right click one item or half the slot.
+fn click_slot(window: &mut dyn Window, cursor: &mut Cursor, i: usize, right: bool) {
+    let held = match cursor.item.take() {
+        Some(held) => held,
+        None => {
+            let count = count(window.slot(i));
+            cursor.item = take(window.slot(i), if right { (count + 1) / 2 } else { count });
+            return;
+        }
+    };
+    let limit = window.get_limit(i, &held);
+    let wanted = if right { 1 } else { held.get_count() };
+    let mut held = Some(held);
+    let slot = window.slot(i);
+    if limit > 0 {
+        match *slot {
+            None => *slot = take(&mut held, wanted.min(limit)),
+            Some(ref mut item) if item.is_similar(held.as_ref().unwrap()) => {
+                let moved = wanted.min(limit - item.get_count());
+                if moved > 0 {
+                    take(&mut held, moved);
+                    item.set_count(item.get_count() + moved);
+                }
+            }
+            Some(_) if count(&held) <= limit => ::std::mem::swap(slot, &mut held),
+            Some(_) => {}
+        }
+    } else if let Some(ref item) = *slot {
+        // output slots and the like can only be taken from, as long as everything fits on the cursor.
+        let held_item = held.as_mut().unwrap();
+        if item.is_similar(held_item) && item.get_count() + held_item.get_count() <= held_item.get_max_stack_size() {
+            held_item.set_count(held_item.get_count() + item.get_count());
+            *slot = None;
+        }
+    }
+    cursor.item = held;
+}
+
+/// Moves the item to the other part of the window. Returns what the slot had if anything moved.
+fn shift_click(window: &mut dyn Window, i: usize) -> Option<ItemStack> {
+    let mut item = window.slot(i).take()?;
+    let before = item.copy_with_count(item.get_count());
+    for (range, reverse) in window.get_shift_targets(i, &item) {
+        merge(window, &mut item, range, reverse);
+        if item.get_count() <= 0 {
+            break;
+        }
+    }
+    let moved = item.get_count() != before.get_count();
+    if item.get_count() > 0 {
+        *window.slot(i) = Some(item);
+    }
+    if moved {
+        Some(before)
+    } else {
+        None
+    }
+}
+
+/// Puts as much of the item into the slots as fits, onto stacks of the same item first.
+fn merge(window: &mut dyn Window, item: &mut ItemStack, range: Range<usize>, reverse: bool) {
+    let slots: Vec<usize> = if reverse { range.rev().collect() } else { range.collect() };
+    for &j in &slots {
+        let limit = window.get_limit(j, item);
+        if let Some(ref mut other) = *window.slot(j) {
+            if other.is_similar(item) && other.get_count() < limit {
+                let moved = (limit - other.get_count()).min(item.get_count());
+                other.set_count(other.get_count() + moved);
+                item.set_count(item.get_count() - moved);
+                if item.get_count() <= 0 {
+                    return;
+                }
+            }
+        }
+    }
+    for &j in &slots {
+        let limit = window.get_limit(j, item);
+        if limit > 0 && window.slot(j).is_none() {
+            *window.slot(j) = Some(item.split(limit));
+            if item.get_count() <= 0 {
+                return;
+            }
+        }
+    }
+}
+
+/// Double click, which picks up items like the one on the cursor until it is full. Stacks which aren't full go first.
+fn collect(window: &mut dyn Window, cursor: &mut Cursor) {
+    let held = match cursor.item {
+        Some(ref mut held) => held,
+        None => return,
+    };
+    let max = held.get_max_stack_size();
+    for full in &[false, true] {
+        for j in 0..window.len() {
+            if held.get_count() >= max {
+                return;
+            }
+            if window.is_output(j) {
+                continue;
+            }
+            let taken = match *window.slot(j) {
+                Some(ref mut item) if item.is_similar(held) && (*full || item.get_count() < max) => {
+                    (max - held.get_count()).min(item.get_count())
+                }
+                _ => continue,
+            };
+            take(window.slot(j), taken);
+            held.set_count(held.get_count() + taken);
+        }
+    }
+}
+
+/// Dragging starts outside the window, adds slots one at a time and ends outside the window again, spreading the
+/// cursor over the slots.
+fn drag(window: &mut dyn Window, cursor: &mut Cursor, index: Option<usize>, button: i8, creative: bool) {
+    let stage = button & 3;
+    let mode = (button >> 2) & 3;
+    if cursor.item.is_none() {
+        cursor.reset_drag();
+        return;
+    }
+    match (cursor.drag_mode, stage, index) {
+        (None, 0, None) if mode == 0 || mode == 1 || mode == 2 && creative => {
+            cursor.drag_mode = Some(mode);
+            cursor.drag_slots.clear();
+        }
+        (Some(_), 1, Some(i)) => {
+            let held = cursor.item.as_ref().unwrap();
+            if can_add(window.slot(i), held, true)
+                && window.get_limit(i, held) > 0
+                && held.get_count() as usize > cursor.drag_slots.len()
+                && !cursor.drag_slots.contains(&i)
+            {
+                cursor.drag_slots.push(i);
+            }
+        }
+        // adding the slot outside the window does nothing.
+        (Some(_), 1, None) => {}
+        (Some(mode), 2, None) => {
+            spread(window, cursor, mode);
+            cursor.reset_drag();
+        }
+        _ => cursor.reset_drag(),
+    }
+}
+
+fn spread(window: &mut dyn Window, cursor: &mut Cursor, mode: i8) {
+    let held = match cursor.item.take() {
+        Some(held) => held,
+        None => return,
+    };
+    let slots = cursor.drag_slots.len();
+    let mut left = held.get_count() as i32;
+    for &i in &cursor.drag_slots {
+        if !can_add(window.slot(i), &held, true) || window.get_limit(i, &held) <= 0 || (held.get_count() as usize) < slots {
+            continue;
+        }
+        let before = count(window.slot(i)) as i32;
+        let added = match mode {
+            0 => held.get_count() as i32 / slots as i32,
+            1 => 1,
+            _ => held.get_max_stack_size() as i32,
+        };
+        let new_count = (before + added).min(held.get_max_stack_size() as i32).min(window.get_limit(i, &held) as i32);
+        left -= new_count - before;
+        *window.slot(i) = Some(held.copy_with_count(new_count as i8));
+    }
+    if left > 0 {
+        cursor.item = Some(held.copy_with_count(left as i8));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::item::inventory::Inventory;
+    use crate::item::{item_id, GenericItem};
+
+    fn stack(id: u16, count: i8) -> Option<ItemStack> {
+        Some(ItemStack::new(Box::new(GenericItem { id, damage: 0, tag: None }), count))
+    }
+
+    fn counts(inventory: &mut Inventory, slots: &[usize]) -> Vec<i8> {
+        slots.iter().map(|&s| count(inventory.slot(s))).collect()
+    }
+
+    #[test]
+    fn clicks() {
+        let mut inventory = Inventory::new();
+        let mut cursor = Cursor::default();
+        *inventory.slot(36) = stack(item_id::STONE, 10);
+        // right clicks pick up half and put down one.
+        let click_result = click(&mut inventory, &mut cursor, 36, 1, 0, false).unwrap();
+        assert_eq!(count(&click_result.clicked), 10);
+        assert_eq!(count(&cursor.item), 5);
+        click(&mut inventory, &mut cursor, 37, 1, 0, false).unwrap();
+        assert_eq!(counts(&mut inventory, &[36, 37]), vec![5, 1]);
+        click(&mut inventory, &mut cursor, 36, 0, 0, false).unwrap();
+        assert_eq!(counts(&mut inventory, &[36, 37]), vec![9, 1]);
+        assert!(cursor.item.is_none());
+
+        // armor only goes into its own slot.
+        *inventory.slot(9) = stack(item_id::IRON_HELMET, 1);
+        click(&mut inventory, &mut cursor, 9, 0, 0, false).unwrap();
+        click(&mut inventory, &mut cursor, 6, 0, 0, false).unwrap();
+        assert!(cursor.item.is_some());
+        click(&mut inventory, &mut cursor, 5, 0, 0, false).unwrap();
+        assert_eq!(counts(&mut inventory, &[5, 6, 9]), vec![1, 0, 0]);
+
+        // shift clicking takes armor off, and moves items between the hotbar and the rest.
+        assert!(click(&mut inventory, &mut cursor, 5, 0, 1, false).unwrap().clicked.is_some());
+        click(&mut inventory, &mut cursor, 36, 0, 1, false).unwrap();
+        assert_eq!(counts(&mut inventory, &[5, 9, 10, 36]), vec![0, 1, 9, 0]);
+        click(&mut inventory, &mut cursor, 9, 0, 1, false).unwrap();
+        assert_eq!(counts(&mut inventory, &[5, 9]), vec![1, 0]);
+
+        // number keys swap with the hotbar.
+        click(&mut inventory, &mut cursor, 10, 2, 2, false).unwrap();
+        assert_eq!(counts(&mut inventory, &[10, 38]), vec![0, 9]);
+        let dropped = click(&mut inventory, &mut cursor, 38, 0, 4, false).unwrap().dropped;
+        assert_eq!(dropped.iter().map(|i| i.get_count()).collect::<Vec<_>>(), vec![1]);
+        assert_eq!(count(inventory.slot(38)), 8);
+        // the crafting result can't be filled.
+        click(&mut inventory, &mut cursor, 38, 0, 0, false).unwrap();
+        click(&mut inventory, &mut cursor, 0, 0, 0, false).unwrap();
+        assert_eq!(count(&cursor.item), 8);
+        assert!(click(&mut inventory, &mut cursor, 45, 0, 0, false).is_none());
+    }
+
+    #[test]
+    fn drag_and_collect() {
+        let mut inventory = Inventory::new();
+        let mut cursor = Cursor::default();
+        cursor.item = stack(item_id::STONE, 10);
+        for &(slot, button) in &[(OUTSIDE, 0), (9, 1), (10, 1), (11, 1), (OUTSIDE, 2)] {
+            click(&mut inventory, &mut cursor, slot, button, 5, false).unwrap();
+        }
+        assert_eq!(counts(&mut inventory, &[9, 10, 11]), vec![3, 3, 3]);
+        assert_eq!(count(&cursor.item), 1);
+
+        // adding slots without starting to drag does nothing.
+        click(&mut inventory, &mut cursor, 12, 5, 5, false).unwrap();
+        click(&mut inventory, &mut cursor, OUTSIDE, 6, 5, false).unwrap();
+        assert_eq!(count(inventory.slot(12)), 0);
+        // middle click dragging is for creative players.
+        click(&mut inventory, &mut cursor, OUTSIDE, 8, 5, false).unwrap();
+        click(&mut inventory, &mut cursor, 12, 9, 5, false).unwrap();
+        click(&mut inventory, &mut cursor, OUTSIDE, 10, 5, false).unwrap();
+        assert_eq!(count(inventory.slot(12)), 0);
+
+        click(&mut inventory, &mut cursor, 20, 0, 6, false).unwrap();
+        assert_eq!(count(&cursor.item), 10);
+        assert_eq!(counts(&mut inventory, &[9, 10, 11]), vec![0, 0, 0]);
+    }
+}
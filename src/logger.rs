@@ -0,0 +1,250 @@
+use std::borrow::Cow;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Mutex, RwLock};
+use std::thread;
+
+use chrono::{DateTime, Local, NaiveDate};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use log::{LevelFilter, Log, Metadata, Record};
+
+const CRATE_PREFIX: &str = "quartz_rs::";
+
+/// Which levels are logged, per module. Written like `info,network=debug,world=warn`.
+/// Module paths can leave out the crate name. The longest matching module wins.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogFilter {
+    default: LevelFilter,
+    modules: Vec<(String, LevelFilter)>,
+}
+
+impl LogFilter {
+    pub const fn new(default: LevelFilter) -> LogFilter {
+        LogFilter { default, modules: Vec::new() }
+    }
+
+    pub fn level_for(&self, target: &str) -> LevelFilter {
+        let target = if target.starts_with(CRATE_PREFIX) { &target[CRATE_PREFIX.len()..] } else { target };
+        self.modules
+            .iter()
+            .filter(|&&(ref module, _)| {
+                target.starts_with(module.as_str()) && (target.len() == module.len() || target[module.len()..].starts_with("::"))
+            })
+            .max_by_key(|&&(ref module, _)| module.len())
+            .map_or(self.default, |&(_, level)| level)
+    }
+
+    fn max_level(&self) -> LevelFilter {
+        self.modules.iter().map(|&(_, level)| level).fold(self.default, ::std::cmp::max)
+    }
+}
+
+impl FromStr for LogFilter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<LogFilter, String> {
+        let mut filter = LogFilter::new(LevelFilter::Info);
+        for part in s.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let (module, level) = match part.find('=') {
+                Some(i) => (Some(part[..i].trim()), part[i + 1..].trim()),
+                None => (None, part),
+            };
+            let level = level.parse().map_err(|_| format!("unknown log level {}", level))?;
+            match module {
+                Some(module) => {
+                    let module = if module.starts_with(CRATE_PREFIX) { &module[CRATE_PREFIX.len()..] } else { module };
+                    filter.modules.push((module.into(), level));
+                }
+                None => filter.default = level,
+            }
+        }
+        Ok(filter)
+    }
+}
+
+impl fmt::Display for LogFilter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.default.to_string().to_lowercase())?;
+        for &(ref module, level) in &self.modules {
+            write!(f, ",{}={}", module, level.to_string().to_lowercase())?;
+        }
+        Ok(())
+    }
+}
+
+/// Removes ANSI escape codes, like the colors of `Chat::to_ansi`. Log files get the plain text.
+fn strip_ansi(s: &str) -> Cow<str> {
+    if !s.contains('\x1b') {
+        return Cow::Borrowed(s);
+    }
+    let mut plain = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            plain.push(c);
+            continue;
+        }
+        // CSI sequences end with a byte from @ to ~.
+        if chars.next() == Some('[') {
+            for c in &mut chars {
+                if ('@'..='~').contains(&c) {
+                    break;
+                }
+            }
+        }
+    }
+    Cow::Owned(plain)
+}
+
+/// logs/latest.log. Moved to a gzipped archive named after its date when the server starts and at midnight.
+struct LogFile {
+    dir: PathBuf,
+    file: File,
+    date: NaiveDate,
+}
+
+impl LogFile {
+    fn open(dir: &Path) -> io::Result<LogFile> {
+        fs::create_dir_all(dir)?;
+        let latest = dir.join("latest.log");
+        if latest.exists() {
+            let modified: DateTime<Local> = fs::metadata(&latest)?.modified()?.into();
+            archive(dir, modified.date_naive())?;
+        }
+        Ok(LogFile {
+            dir: dir.into(),
+            file: OpenOptions::new().create(true).append(true).open(latest)?,
+            date: Local::now().date_naive(),
+        })
+    }
+
+    fn write_line(&mut self, line: &str, date: NaiveDate) -> io::Result<()> {
+        if date != self.date {
+            *self = LogFile::open(&self.dir)?;
+        }
+        writeln!(self.file, "{}", line)
+    }
+}
+
+/// Compresses latest.log to `<date>-<n>.log.gz` with the first unused n, like vanilla.
+fn archive(dir: &Path, date: NaiveDate) -> io::Result<()> {
+    let path = (1..)
+        .map(|n| dir.join(format!("{}-{}.log.gz", date.format("%Y-%m-%d"), n)))
+        .find(|p| !p.exists())
+        .unwrap();
+    let latest = dir.join("latest.log");
+    let mut encoder = GzEncoder::new(File::create(path)?, Compression::default());
+    io::copy(&mut File::open(&latest)?, &mut encoder)?;
+    encoder.finish()?;
+    fs::remove_file(latest)
+}
+
+struct Logger {
+    filter: RwLock<LogFilter>,
+    file: Mutex<Option<LogFile>>,
+}
+
+static LOGGER: Logger = Logger {
+    filter: RwLock::new(LogFilter::new(LevelFilter::Info)),
+    file: Mutex::new(None),
+};
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.filter.read().unwrap().level_for(metadata.target())
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let now = Local::now();
+        let line = format!(
+            "[{}] [{}/{}]: {}",
+            now.format("%H:%M:%S"),
+            thread::current().name().unwrap_or("unnamed"),
+            record.level(),
+            record.args()
+        );
+        println!("{}", line);
+        if let Some(ref mut file) = *self.file.lock().unwrap() {
+            // nowhere to report this. the line was printed at least.
+            let _ = file.write_line(&strip_ansi(&line), now.date_naive());
+        }
+    }
+
+    fn flush(&self) {
+        if let Some(ref mut file) = *self.file.lock().unwrap() {
+            let _ = file.file.flush();
+        }
+    }
+}
+
+/// Starts logging to the terminal and to latest.log in dir. Messages are filtered with `info` until set_filter is called.
+/// If the log file can't be opened, the error is returned and only the terminal is logged to.
+pub fn init<P: AsRef<Path>>(dir: P) -> io::Result<()> {
+    log::set_logger(&LOGGER).map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))?;
+    log::set_max_level(LOGGER.filter.read().unwrap().max_level());
+    *LOGGER.file.lock().unwrap() = Some(LogFile::open(dir.as_ref())?);
+    Ok(())
+}
+
+pub fn set_filter(filter: LogFilter) {
+    log::set_max_level(filter.max_level());
+    *LOGGER.filter.write().unwrap() = filter;
+}
+
+#[cfg(test)]
+mod test {
+    use std::env;
+    use std::io::Read;
+
+    use flate2::read::GzDecoder;
+
+    use super::*;
+    use crate::text::chat::Chat;
+
+    #[test]
+    fn filter_levels() {
+        let filter: LogFilter = "warn, network=debug, quartz_rs::world::region=trace".parse().unwrap();
+        assert_eq!(filter.level_for("quartz_rs::server"), LevelFilter::Warn);
+        assert_eq!(filter.level_for("quartz_rs::network"), LevelFilter::Debug);
+        assert_eq!(filter.level_for("quartz_rs::networking"), LevelFilter::Warn);
+        assert_eq!(filter.level_for("quartz_rs::world"), LevelFilter::Warn);
+        assert_eq!(filter.level_for("quartz_rs::world::region"), LevelFilter::Trace);
+        assert_eq!(filter.max_level(), LevelFilter::Trace);
+        assert_eq!(filter.to_string(), "warn,network=debug,world::region=trace");
+        assert!("network=loud".parse::<LogFilter>().is_err());
+    }
+
+    #[test]
+    fn strips_colors_for_files() {
+        let chat = Chat::from(crate::text::parse_legacy_ex("&chi &lthere&r!", '&'));
+        assert_eq!(strip_ansi(&chat.to_ansi()), chat.to_plain());
+        assert_eq!(strip_ansi("no colors"), "no colors");
+    }
+
+    #[test]
+    fn archives_latest_log() {
+        let dir = env::temp_dir().join(format!("quartz-logs-{}", ::std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("latest.log"), "old line\n").unwrap();
+        let date = Local::now().date_naive();
+        let name = |n| format!("{}-{}.log.gz", date.format("%Y-%m-%d"), n);
+        File::create(dir.join(name(1))).unwrap();
+
+        let mut log = LogFile::open(&dir).unwrap();
+        log.write_line("new line", date).unwrap();
+
+        let mut archived = String::new();
+        GzDecoder::new(File::open(dir.join(name(2))).unwrap()).read_to_string(&mut archived).unwrap();
+        assert_eq!(archived, "old line\n");
+        assert_eq!(fs::read_to_string(dir.join("latest.log")).unwrap(), "new line\n");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
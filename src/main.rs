@@ -12,33 +12,93 @@ extern crate uuid;
 #[macro_use]
 extern crate failure_derive;
 extern crate noise;
+extern crate openssl;
+extern crate mio;
+extern crate ctrlc;
+#[macro_use]
+extern crate log;
+extern crate chrono;
 
 #[macro_use]
 mod util;
+mod auth;
 mod binary;
 mod block;
 mod collections;
 mod entity;
 mod item;
+mod logger;
 mod math;
 mod nbt;
 mod network;
 mod proto;
+mod replay;
 mod server;
 mod sound;
+#[cfg(test)]
+mod test_client;
 mod text;
 mod world;
 
-use std::net::{Ipv4Addr, SocketAddr};
+use std::env;
+use std::process;
+
+use crate::auth::HttpSessionService;
+use crate::server::properties::ServerProperties;
 
 fn main() {
-    let mut s = match server::Server::new(SocketAddr::new(Ipv4Addr::new(0, 0, 0, 0).into(), 25565))
-    {
+    let args: Vec<String> = env::args().collect();
+    if args.len() >= 2 && args[1] == "replay" {
+        if args.len() != 3 {
+            eprintln!("usage: {} replay <capture file>", args[0]);
+            process::exit(2);
+        }
+        replay(&args[2]);
+    }
+
+    if let Err(e) = logger::init("logs") {
+        error!("could not open log file: {}", e);
+    }
+
+    let properties = match ServerProperties::load("server.properties") {
+        Ok(p) => p,
+        Err(e) => panic!("error loading server.properties: {}", e),
+    };
+    logger::set_filter(properties.log_level.clone());
+
+    let session_service = Box::new(HttpSessionService::new(HttpSessionService::MOJANG_URL));
+    let mut s = match server::Server::new(properties, session_service) {
         Ok(s) => s,
         Err(e) => panic!("error creating server: {}", e),
     };
 
+    let running = s.get_running();
+    let result = ctrlc::set_handler(move || {
+        *running.lock().unwrap() = false;
+    });
+    if let Err(e) = result {
+        warn!("could not set signal handler: {}", e);
+    }
+
     if let Err(e) = s.start() {
         panic!("error starting server: {}", e);
     }
+    log::logger().flush();
+}
+
+/// Replays a packet capture against a new server and exits with 1 if the server sent something else.
+fn replay(path: &str) {
+    // a replay shouldn't touch the config of the server, so the file is only read.
+    let properties = match ServerProperties::read("server.properties") {
+        Ok(p) => p,
+        Err(e) => panic!("error loading server.properties: {}", e),
+    };
+    match replay::replay(path, properties) {
+        Ok(0) => process::exit(0),
+        Ok(_) => process::exit(1),
+        Err(e) => {
+            eprintln!("error replaying {}: {}", path, e);
+            process::exit(2);
+        }
+    }
 }
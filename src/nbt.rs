@@ -1,8 +1,14 @@
 use std::collections::HashMap;
 use std::error::Error as StdError;
-use std::io::{self, Read, Write};
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
 use std::string::FromUtf8Error;
 
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+
 use crate::binary;
 
 #[derive(Debug, Fail)]
@@ -34,6 +40,10 @@ pub enum DeserializeError {
     TypeMismatch(&'static str, &'static str),
     #[fail(display = "index out of bounds: the len is {} but the index is {}", _0, _0)]
     OutOfBounds(usize, usize),
+    #[fail(display = "field \"{}\" has invalid length {}", _0, _1)]
+    InvalidLength(&'static str, usize),
+    #[fail(display = "unknown id: {}", _0)]
+    UnknownId(String),
 }
 
 fn write_string<W: Write>(w: &mut W, s: &str) -> io::Result<()> {
@@ -51,7 +61,7 @@ fn read_string<R: Read>(r: &mut R) -> Result<String, Error> {
 
 const MAX_NEXT_LEVEL: u32 = 512;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Compound(pub HashMap<String, Tag>);
 
 impl Compound {
@@ -95,7 +105,7 @@ impl Compound {
 }
 
 /// This is the root tag. Similar to compound except it has only 1 tag which is a compound and no end byte.
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Nbt {
     Empty,
     Some(String, Compound),
@@ -113,6 +123,28 @@ impl Nbt {
         }
     }
 
+    /// Reads a gzip compressed file such as level.dat.
+    pub fn read_gzip_file<P: AsRef<Path>>(path: P) -> Result<Nbt, Error> {
+        Nbt::read(&mut GzDecoder::new(BufReader::new(File::open(path)?)))
+    }
+
+    /// Writes a gzip compressed file. It is written to a temporary file first and renamed over, so a crash
+    /// can't leave a half written file behind.
+    pub fn write_gzip_file<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        let path = path.as_ref();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let tmp_path = path.with_extension("tmp");
+        {
+            let mut w = GzEncoder::new(BufWriter::new(File::create(&tmp_path)?), Compression::default());
+            self.write(&mut w)?;
+            w.finish()?.flush()?;
+        }
+        fs::rename(tmp_path, path)?;
+        Ok(())
+    }
+
     pub fn read<R: Read>(r: &mut R) -> Result<Nbt, Error> {
         let id = binary::read_ubyte(r)?;
         if id == 0 {
@@ -127,7 +159,7 @@ impl Nbt {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct List(pub Vec<Tag>);
 
 impl List {
@@ -173,7 +205,7 @@ impl List {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct ByteArray(pub Vec<u8>);
 
 impl ByteArray {
@@ -198,7 +230,7 @@ impl ByteArray {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct IntArray(pub Vec<i32>);
 
 impl IntArray {
@@ -229,7 +261,7 @@ impl IntArray {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Tag {
     Byte(i8),
     Short(i16),
@@ -1,26 +1,52 @@
-use std::io::{self, Read, Write};
-use std::net::{SocketAddr, TcpListener, TcpStream};
-use std::sync::mpsc::{self, Receiver, Sender};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Read, Write};
+use std::mem;
+use std::net::{IpAddr, Shutdown, SocketAddr};
+use std::path::Path;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
 use std::sync::{Arc, Mutex};
-use std::{
-    collections::HashSet,
-    thread::{self, JoinHandle},
-};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use chrono::Local;
 
 use failure::Fail;
+use mio::net::{TcpListener, TcpStream};
+use mio::{Events, Interest, Poll, Token, Waker};
+use openssl::rand::rand_bytes;
 use uuid::{self, Uuid};
 
-use crate::entity::player::Player;
+use crate::auth::{self, Authenticator};
+use crate::binary;
+use crate::entity::player::{Player, PlayerProfile, PlayerProperty, UuidWrapper};
+use crate::proto::capture::{CaptureHeader, CaptureWriter, Direction};
 use crate::proto::packets::SStatusResponsePlayer;
 use crate::proto::packets::*;
+use crate::proto::translate::Translator;
+use crate::proto::version::ProtocolVersion;
 use crate::proto::{self, Reader, State, Writer};
+use crate::server::properties::ServerProperties;
+use crate::server::userlist::UserLists;
 use crate::text;
-use crate::text::chat::{Chat, Component, StringComponent};
-
+use crate::text::chat::Chat;
 #[derive(Fail, Debug)]
 pub enum Error {
     #[fail(display = "io error: {}", _0)]
     IOError(#[cause] io::Error),
+    #[fail(display = "protocol error: {}", _0)]
+    ProtoError(#[cause] proto::Error),
+    #[fail(display = "auth error: {}", _0)]
+    AuthError(#[cause] auth::Error),
+    #[fail(display = "verify token sent by client did not match")]
+    VerifyTokenMismatch,
+    #[fail(display = "shared secret has invalid length {}", _0)]
+    InvalidSharedSecret(usize),
+    #[fail(display = "invalid BungeeCord forwarding data")]
+    InvalidForwarding,
 }
 
 impl From<io::Error> for Error {
@@ -29,290 +55,981 @@ impl From<io::Error> for Error {
     }
 }
 
+impl From<proto::Error> for Error {
+    fn from(x: proto::Error) -> Self {
+        Error::ProtoError(x)
+    }
+}
+
+impl From<auth::Error> for Error {
+    fn from(x: auth::Error) -> Self {
+        Error::AuthError(x)
+    }
+}
+
+const LISTENER: Token = Token(0);
+const WAKER: Token = Token(1);
+/// How long the event loop waits for clients to receive what's left when stopping.
+const STOP_FLUSH_TIMEOUT: Duration = Duration::from_secs(2);
+/// How often connections are checked for timeouts.
+const TIMEOUT_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+/// Most players listed in the server list, like vanilla.
+const STATUS_SAMPLE_SIZE: usize = 12;
+/// Protocol number answered to legacy pings. No client before 1.7 has it, so they show the version name as outdated.
+const LEGACY_PING_PROTOCOL: i32 = 127;
+
+/// Wakes the event loop when other threads have something for it.
+#[derive(Debug)]
+struct Notifier {
+    waker: Waker,
+    /// connections which have packets queued by the server.
+    ready: Mutex<Vec<Token>>,
+    /// results of session server lookups.
+    sessions: Mutex<Vec<(Token, Result<Option<PlayerProfile>, auth::Error>)>>,
+    stopping: AtomicBool,
+}
+
+impl Notifier {
+    fn notify(&self, token: Token) {
+        let mut ready = self.ready.lock().unwrap();
+        ready.push(token);
+        // if there were tokens already, the event loop has been woken up and hasn't taken them yet.
+        if ready.len() == 1 {
+            let _ = self.waker.wake();
+        }
+    }
+
+    fn session_done(&self, token: Token, result: Result<Option<PlayerProfile>, auth::Error>) {
+        self.sessions.lock().unwrap().push((token, result));
+        let _ = self.waker.wake();
+    }
+}
+
+/// Queue of packets to send to a client. The network thread is woken up when packets are queued.
+pub struct PacketSender {
+    sender: Sender<Arc<SPacket>>,
+    token: Token,
+    /// set when the network thread was told about queued packets, cleared when it starts writing them.
+    queued: Arc<AtomicBool>,
+    /// set when the connection should be closed once everything queued is written.
+    close: Arc<AtomicBool>,
+    notifier: Arc<Notifier>,
+}
+
+impl PacketSender {
+    pub fn send(&self, packet: Arc<SPacket>) {
+        // ignore because if other side is dropped,
+        // it's due to error and player will have to disconnect anyway
+        if self.sender.send(packet).is_ok() && !self.queued.swap(true, Ordering::SeqCst) {
+            self.notifier.notify(self.token);
+        }
+    }
+
+    /// Closes the connection after the packets sent so far. Packets sent after this may not be written.
+    pub fn close(&self) {
+        self.close.store(true, Ordering::SeqCst);
+        self.notifier.notify(self.token);
+    }
+}
+
+impl Drop for PacketSender {
+    /// Lets the network thread know that the player is gone so it closes the connection.
+    fn drop(&mut self) {
+        self.notifier.notify(self.token);
+    }
+}
+
+impl fmt::Debug for PacketSender {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PacketSender").field("token", &self.token).finish()
+    }
+}
+
 pub struct NetworkServer {
+    properties: Arc<ServerProperties>,
+    /// None in offline mode.
+    authenticator: Option<Arc<Authenticator>>,
     favicon: Arc<Option<String>>,
-    running: Arc<Mutex<bool>>,
-    addr: SocketAddr,
     incoming_players: Sender<Player>,
     player_list: Arc<Mutex<HashSet<SStatusResponsePlayer>>>,
+    user_lists: Arc<Mutex<UserLists>>,
+    /// set while the network thread runs.
+    notifier: Option<Arc<Notifier>>,
+    thread: Option<JoinHandle<()>>,
 }
 
 impl NetworkServer {
     pub fn new(
-        addr: SocketAddr,
+        properties: Arc<ServerProperties>,
+        authenticator: Option<Authenticator>,
         favicon: Option<String>,
-        running: Arc<Mutex<bool>>,
         incoming_players: Sender<Player>,
+        user_lists: Arc<Mutex<UserLists>>,
     ) -> NetworkServer {
         NetworkServer {
-            addr,
+            properties,
+            authenticator: authenticator.map(Arc::new),
             favicon: Arc::new(favicon),
-            running,
             incoming_players,
             player_list: Arc::new(Mutex::new(HashSet::new())),
+            user_lists,
+            notifier: None,
+            thread: None,
         }
     }
 
-    pub fn start(&mut self) -> Result<(), Error> {
-        let listener = TcpListener::bind(self.addr)?;
-
-        let favicon = Arc::clone(&self.favicon);
-        let running = Arc::clone(&self.running);
-        let incoming_players = self.incoming_players.clone();
-        let player_list = Arc::clone(&self.player_list);
-
-        thread::spawn(move || {
-            while *running.lock().unwrap() {
-                let favicon = Arc::clone(&favicon);
-                let incoming_players = incoming_players.clone();
-                let player_list = Arc::clone(&player_list);
-
-                let (stream, addr) = listener.accept().unwrap();
-                println!("{} has connected", addr);
-                // TODO: async io
-                thread::spawn(move || {
-                    if let Err(e) = NetworkServer::handle_client(
-                        &stream,
-                        addr,
-                        favicon,
-                        incoming_players,
-                        player_list,
-                    ) {
-                        println!("{} has been disconnected for error: {:?}", addr, e);
-                    }
-                });
-            }
+    /// Starts the network thread. It owns every connection and runs until `stop` is called.
+    /// Returns the address listened on, which has the actual port if the properties ask for port 0.
+    pub fn start(&mut self) -> Result<SocketAddr, Error> {
+        info!("Starting server on {}", self.properties.get_bind_addr());
+        let mut listener = TcpListener::bind(self.properties.get_bind_addr())?;
+        let local_addr = listener.local_addr()?;
+        let poll = Poll::new()?;
+        poll.registry().register(&mut listener, LISTENER, Interest::READABLE)?;
+        let notifier = Arc::new(Notifier {
+            waker: Waker::new(poll.registry(), WAKER)?,
+            ready: Mutex::new(Vec::new()),
+            sessions: Mutex::new(Vec::new()),
+            stopping: AtomicBool::new(false),
         });
+        self.notifier = Some(Arc::clone(&notifier));
 
-        Ok(())
+        let ctx = Context {
+            properties: Arc::clone(&self.properties),
+            authenticator: self.authenticator.clone(),
+            favicon: Arc::clone(&self.favicon),
+            incoming_players: self.incoming_players.clone(),
+            player_list: Arc::clone(&self.player_list),
+            user_lists: Arc::clone(&self.user_lists),
+            ip_limits: Arc::new(Mutex::new(IpLimits::default())),
+            notifier,
+        };
+        self.thread = Some(thread::Builder::new().name("Network thread".into()).spawn(move || {
+            EventLoop {
+                poll,
+                listener,
+                connections: HashMap::new(),
+                next_token: WAKER.0 + 1,
+                next_timeout_check: Instant::now() + TIMEOUT_CHECK_INTERVAL,
+                ctx,
+            }
+            .run()
+        })?);
+
+        Ok(local_addr)
     }
 
-    fn handle_client(
-        stream: &TcpStream,
-        addr: SocketAddr,
-        favicon: Arc<Option<String>>,
-        incoming_players: Sender<Player>,
-        player_list: Arc<Mutex<HashSet<SStatusResponsePlayer>>>,
-    ) -> Result<(), proto::Error> {
-        let mut reader = Reader::new(stream.try_clone().unwrap());
-        let writer = Writer::new(stream.try_clone().unwrap());
-
-        let packet = reader.read_packet()?;
-
-        match packet {
-            CPacket::Handshake { next_state, .. } => {
-                if next_state == 1 {
-                    NetworkServer::handle_status(reader, writer, favicon, player_list)
-                } else {
-                    NetworkServer::handle_login(reader, writer, addr, player_list, incoming_players)
+    /// Writes what's left to clients, closes every connection and waits for the network thread to finish.
+    pub fn stop(&mut self) {
+        if let Some(notifier) = self.notifier.take() {
+            notifier.stopping.store(true, Ordering::SeqCst);
+            let _ = notifier.waker.wake();
+        }
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// What connections need from the network server.
+struct Context {
+    properties: Arc<ServerProperties>,
+    authenticator: Option<Arc<Authenticator>>,
+    favicon: Arc<Option<String>>,
+    incoming_players: Sender<Player>,
+    player_list: Arc<Mutex<HashSet<SStatusResponsePlayer>>>,
+    user_lists: Arc<Mutex<UserLists>>,
+    ip_limits: Arc<Mutex<IpLimits>>,
+    notifier: Arc<Notifier>,
+}
+
+/// Connections and recent logins of each ip, for the limits of the properties.
+#[derive(Default)]
+struct IpLimits {
+    connections: HashMap<IpAddr, u32>,
+    /// when the logins within the throttle interval started, oldest first.
+    logins: HashMap<IpAddr, VecDeque<Instant>>,
+}
+
+impl IpLimits {
+    fn forget_logins_before(&mut self, time: Instant) {
+        for logins in self.logins.values_mut() {
+            while logins.front().map_or(false, |&t| t <= time) {
+                logins.pop_front();
+            }
+        }
+        self.logins.retain(|_, logins| !logins.is_empty());
+    }
+}
+
+struct EventLoop {
+    poll: Poll,
+    listener: TcpListener,
+    connections: HashMap<Token, Connection>,
+    /// tokens aren't reused so late session server results can't reach the wrong connection.
+    next_token: usize,
+    next_timeout_check: Instant,
+    ctx: Context,
+}
+
+impl EventLoop {
+    fn run(&mut self) {
+        let mut events = Events::with_capacity(1024);
+        while !self.ctx.notifier.stopping.load(Ordering::SeqCst) {
+            if let Err(e) = self.poll.poll(&mut events, Some(TIMEOUT_CHECK_INTERVAL)) {
+                if e.kind() == io::ErrorKind::Interrupted {
+                    continue;
                 }
+                error!("error polling sockets: {}", e);
+                break;
             }
-            _ => unreachable!(), // reader is in handshake state so only handshake can be read.
-        }
-    }
-
-    fn handle_status<R: Read + Send + 'static, W: Write + Send + 'static>(
-        mut reader: Reader<R>,
-        mut writer: Writer<W>,
-        favicon: Arc<Option<String>>,
-        player_list: Arc<Mutex<HashSet<SStatusResponsePlayer>>>,
-    ) -> Result<(), proto::Error> {
-        reader.set_state(State::Status);
-
-        let mut packet = reader.read_packet()?;
-        match packet {
-            CPacket::StatusRequest {} => {}
-            _ => {
-                return Err(proto::Error::UnexpectedPacket {
-                    expected: stringify!(CPacket::StatusRequest),
-                    got: format!("{:?}", packet),
-                });
+
+            for event in events.iter() {
+                match event.token() {
+                    LISTENER => self.accept(),
+                    WAKER => {}
+                    token if event.is_readable() => self.update(token, Connection::handle_readable),
+                    // only writable. update flushes.
+                    token => self.update(token, |_, _| Ok(())),
+                }
+            }
+            self.handle_notifications();
+            if Instant::now() >= self.next_timeout_check {
+                self.check_timeouts();
             }
         }
 
-        let online = player_list.lock().unwrap().len() as i32;
-        let response = SPacket::StatusResponse {
-            data: SStatusResponseData {
-                version: SStatusResponseVersion {
-                    name: proto::VERSION_STRING,
-                    protocol: proto::VERSION,
-                },
-                players: SStatusResponsePlayers {
-                    max: 1000,
-                    online,
-                    sample: Some(player_list),
-                },
-                description: Chat::from(Component::from(StringComponent {
-                    text: "Quartz minecraft server".into(),
-                    base: Default::default(),
-                })),
-                favicon,
+        // players were dropped by the server. their last packets (disconnect messages) still have to be written.
+        self.handle_notifications();
+        let deadline = Instant::now() + STOP_FLUSH_TIMEOUT;
+        while self.connections.values().any(Connection::is_closing) {
+            let now = Instant::now();
+            if now >= deadline {
+                break;
+            }
+            if let Err(e) = self.poll.poll(&mut events, Some(deadline - now)) {
+                if e.kind() == io::ErrorKind::Interrupted {
+                    continue;
+                }
+                break;
+            }
+            for event in events.iter() {
+                if event.token() != LISTENER && event.token() != WAKER {
+                    self.update(event.token(), |_, _| Ok(()));
+                }
+            }
+        }
+        // dropping connections closes them and marks players as disconnected.
+        self.connections.clear();
+    }
+
+    fn accept(&mut self) {
+        loop {
+            match self.listener.accept() {
+                Ok((mut stream, addr)) => {
+                    let token = Token(self.next_token);
+                    self.next_token += 1;
+                    // sockets are edge triggered so there is no need to change interests later.
+                    let interest = Interest::READABLE | Interest::WRITABLE;
+                    if let Err(e) = self.poll.registry().register(&mut stream, token, interest) {
+                        warn!("error registering {}: {}", addr, e);
+                        continue;
+                    }
+                    info!("{} has connected", addr);
+                    let conn = Connection::new(stream, addr, token, self.ctx.properties.proxy_protocol);
+                    self.connections.insert(token, conn);
+                }
+                Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => {
+                    warn!("error accepting connection: {}", e);
+                    break;
+                }
+            }
+        }
+    }
+
+    fn handle_notifications(&mut self) {
+        let ready = mem::replace(&mut *self.ctx.notifier.ready.lock().unwrap(), Vec::new());
+        for token in ready {
+            self.update(token, Connection::write_queued);
+        }
+
+        let sessions = mem::replace(&mut *self.ctx.notifier.sessions.lock().unwrap(), Vec::new());
+        for (token, result) in sessions {
+            self.update(token, move |conn, ctx| conn.handle_session_result(result, ctx));
+        }
+    }
+
+    fn check_timeouts(&mut self) {
+        let now = Instant::now();
+        self.next_timeout_check = now + TIMEOUT_CHECK_INTERVAL;
+        let tokens: Vec<Token> = self.connections.keys().cloned().collect();
+        for token in tokens {
+            self.update(token, |conn, ctx| conn.check_timeout(now, ctx));
+        }
+        let interval = Duration::from_secs(self.ctx.properties.connection_throttle_interval);
+        if let Some(time) = now.checked_sub(interval) {
+            self.ctx.ip_limits.lock().unwrap().forget_logins_before(time);
+        }
+    }
+
+    /// Runs f on the connection and writes what it queued. The connection is closed if it failed or is done.
+    fn update<F>(&mut self, token: Token, f: F)
+    where
+        F: FnOnce(&mut Connection, &Context) -> Result<(), Error>,
+    {
+        let done = match self.connections.get_mut(&token) {
+            Some(conn) => match f(conn, &self.ctx).and_then(|_| Ok(conn.writer.flush()?)) {
+                Ok(()) => conn.is_done(),
+                Err(Error::IOError(ref e)) if e.kind() == io::ErrorKind::UnexpectedEof => {
+                    info!("{} has disconnected", conn.addr);
+                    true
+                }
+                Err(e) => {
+                    info!("{} has been disconnected for error: {}", conn.addr, e);
+                    true
+                }
             },
+            None => return,
         };
+        if done {
+            self.connections.remove(&token);
+        }
+    }
+}
 
-        writer.write_packet(&response)?;
+/// Lets the reader and writer of a connection share its socket.
+struct Stream(Rc<TcpStream>);
 
-        packet = reader.read_packet()?;
-        let payload = match packet {
-            CPacket::StatusPing { payload } => payload,
-            _ => {
-                return Err(proto::Error::UnexpectedPacket {
-                    expected: stringify!(CPacket::StatusPing),
-                    got: format!("{:?}", packet),
-                });
-            }
-        };
+impl Read for Stream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        (&*self.0).read(buf)
+    }
+}
 
-        writer.write_packet(&SPacket::StatusPong { payload })?;
-        Ok(())
+impl Write for Stream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        (&*self.0).write(buf)
     }
 
-    fn handle_login<R: Read + Send + 'static, W: Write + Send + 'static>(
-        mut reader: Reader<R>,
-        mut writer: Writer<W>,
-        addr: SocketAddr,
-        player_list: Arc<Mutex<HashSet<SStatusResponsePlayer>>>,
-        incoming_players: Sender<Player>,
-    ) -> Result<(), proto::Error> {
-        reader.set_state(State::Login);
-        let packet = reader.read_packet()?;
-        match packet {
-            CPacket::LoginLoginStart { name } => {
-                let uuid = Uuid::new_v3(&uuid::NAMESPACE_URL, &format!("OfflinePlayer:{}", name));
-
-                let response_player = SStatusResponsePlayer {
-                    name: name.clone(),
-                    id: uuid.hyphenated().to_string(),
-                };
-
-                // check if player is already logged in
-                if player_list.lock().unwrap().contains(&response_player) {
-                    writer.write_packet(&SPacket::LoginDisconnect {
-                        reason: Chat::from(text::parse_legacy("You are already logged in")),
-                    })?;
+    fn flush(&mut self) -> io::Result<()> {
+        (&*self.0).flush()
+    }
+}
+
+enum ConnectionState {
+    /// waiting for the PROXY protocol header.
+    Proxy,
+    Handshake,
+    Status,
+    Login,
+    /// encryption request was sent, waiting for the response.
+    Encryption { name: String, verify_token: [u8; 4] },
+    /// waiting for the session server.
+    Authenticating { name: String },
+    Play(PlayState),
+    /// connection is closed once everything queued is written.
+    Closing,
+}
+
+struct PlayState {
+    sender: Sender<CPacket>,
+    receiver: Receiver<Arc<SPacket>>,
+    queued: Arc<AtomicBool>,
+    close: Arc<AtomicBool>,
+    connected: Arc<Mutex<bool>>,
+    /// recording of the packets if the capture-dir property is set.
+    capture: Option<CaptureWriter<BufWriter<File>>>,
+    _guard: PlayerGuard, // remove player from player_list when the connection is gone
+}
+
+struct Connection {
+    token: Token,
+    addr: SocketAddr,
+    stream: Rc<TcpStream>,
+    reader: Reader<Stream>,
+    writer: Writer<Stream>,
+    state: ConnectionState,
+    /// protocol version of the client's handshake.
+    protocol_version: i32,
+    /// set for supported versions other than 1.8.
+    translator: Option<Translator>,
+    /// uuid and properties BungeeCord sent in the handshake.
+    forwarded: Option<(Uuid, Vec<PlayerProperty>)>,
+    connected_at: Instant,
+    /// set once the connection counts towards the connections of its ip.
+    _ip_guard: Option<IpGuard>,
+}
+
+impl Connection {
+    fn new(stream: TcpStream, addr: SocketAddr, token: Token, proxy_protocol: bool) -> Connection {
+        let stream = Rc::new(stream);
+        Connection {
+            token,
+            addr,
+            reader: Reader::new(Stream(Rc::clone(&stream))),
+            writer: Writer::new(Stream(Rc::clone(&stream))),
+            stream,
+            state: if proxy_protocol { ConnectionState::Proxy } else { ConnectionState::Handshake },
+            protocol_version: proto::VERSION,
+            translator: None,
+            forwarded: None,
+            connected_at: Instant::now(),
+            _ip_guard: None,
+        }
+    }
+
+    fn is_closing(&self) -> bool {
+        match self.state {
+            ConnectionState::Closing => true,
+            _ => false,
+        }
+    }
+
+    fn is_done(&self) -> bool {
+        match self.state {
+            ConnectionState::Closing => !self.writer.has_pending(),
+            _ => false,
+        }
+    }
+
+    /// Reads everything available and handles packets as they are decoded.
+    fn handle_readable(&mut self, ctx: &Context) -> Result<(), Error> {
+        loop {
+            let result = self.reader.read_available();
+            if let ConnectionState::Proxy = self.state {
+                match self.reader.take_proxy_header()? {
+                    Some(source) => {
+                        if let Some(source) = source {
+                            debug!("{} is connecting through {}", source, self.addr);
+                            self.addr = source;
+                        }
+                        self.state = ConnectionState::Handshake;
+                    }
+                    None => return Ok(result.map(|_| ())?),
+                }
+            }
+            if let Some(since_1_4) = self.reader.take_legacy_ping() {
+                self.writer.queue_unframed(&legacy_status_response(ctx, since_1_4))?;
+                self.state = ConnectionState::Closing;
+                return Ok(());
+            }
+            while let Some(packet) = self.next_packet()? {
+                if let ConnectionState::Closing = self.state {
                     return Ok(());
                 }
-
-                const THRESHOLD: u32 = 256;
-                writer.write_packet(&SPacket::LoginSetCompression {
-                    threshold: THRESHOLD as i32,
-                })?;
-                reader.set_compression(THRESHOLD);
-                writer.set_compression(THRESHOLD);
-
-                writer.write_packet(&SPacket::LoginLoginSuccess {
-                    username: name.clone(),
-                    uuid: uuid.hyphenated().to_string(),
-                })?;
-
-                let (server_sender, server_receiver) = mpsc::channel();
-                let (client_sender, client_receiver) = mpsc::channel();
-
-                let connected = Arc::new(Mutex::new(true));
-                let mut player = Player::new(
-                    name,
-                    uuid,
-                    server_sender,
-                    client_receiver,
-                    addr,
-                    Arc::clone(&connected),
-                );
-
-                player_list.lock().unwrap().insert(response_player.clone());
-
-                incoming_players.send(player).unwrap();
-
-                NetworkServer::handle_play(
-                    reader,
-                    writer,
-                    connected,
-                    client_sender,
-                    server_receiver,
-                    response_player,
-                    player_list,
-                )
+                self.handle_packet(packet, ctx)?;
             }
-            _ => {
-                return Err(proto::Error::UnexpectedPacket {
-                    expected: stringify!(CPacket::LoginLoginStart),
-                    got: format!("{:?}", packet),
-                });
+            match result {
+                Ok(true) => {}
+                Ok(false) => return Ok(()),
+                Err(_) if self.is_done() => return Ok(()),
+                Err(e) => return Err(e.into()),
             }
         }
     }
 
-    // Repeatedly reads from client and sends to client.
-    // When the server disconnects a player deliberately, connected_s will be set to false and other end of the channels will be dropped.
-    // send_thread will return immediately then, and receiving thread will eventually return (when read_packet returns error or timeout)
-    // TODO: make this return immediately as well
-    // If an error occurs from this side from one thread, that thread will return,
-    // connected_s will be set to false and the server will follow the same procedure as above.
-    fn handle_play<R: Read + Send + 'static, W: Write + Send + 'static>(
-        mut reader: Reader<R>,
-        mut writer: Writer<W>,
-        connected: Arc<Mutex<bool>>,
-        sender: Sender<CPacket>,
-        receiver: Receiver<Arc<SPacket>>,
-        player: SStatusResponsePlayer,
-        player_list: Arc<Mutex<HashSet<SStatusResponsePlayer>>>,
-    ) -> Result<(), proto::Error> {
-        reader.set_state(State::Play);
-        let _player_guard = PlayerGuard {
-            player_list,
-            player,
-        }; // remove player from player_list when returning from this function
-
-        let connected_s = Arc::clone(&connected);
-        // loop for packet sending.
-        let send_thread: JoinHandle<Result<(), proto::Error>> = thread::spawn(move || {
-            while *connected_s.lock().unwrap() {
-                let packet = receiver.recv();
-                match packet {
-                    Ok(p) => {
-                        if let Err(e) = writer.write_packet(&p) {
-                            *connected_s.lock().unwrap() = false;
-                            /*                            // normal disconnect
-                            if e.kind() == io::ErrorKind::ConnectionAborted {
-                                break;
-                            }*/
-
-                            return Err(From::from(e));
+    fn handle_packet(&mut self, packet: CPacket, ctx: &Context) -> Result<(), Error> {
+        match self.state {
+            ConnectionState::Handshake => match packet {
+                CPacket::Handshake { protocol_version, server_address, next_state, .. } => {
+                    self.protocol_version = protocol_version;
+                    self.translator = ProtocolVersion::from_i32(protocol_version)
+                        .filter(|&v| v != ProtocolVersion::NATIVE)
+                        .map(Translator::new);
+                    if next_state == 1 {
+                        self.reader.set_state(State::Status);
+                        self.state = ConnectionState::Status;
+                    } else {
+                        self.reader.set_state(State::Login);
+                        self.state = ConnectionState::Login;
+                        if ctx.properties.bungeecord {
+                            self.read_forwarding(&server_address)?;
                         }
                     }
-                    Err(_) => break, // send side is dropped when player is deallocated. That's gonna happen after player sets connected to false.
+                    // the address is only known for sure now, proxies may have changed it.
+                    if !self.is_closing() {
+                        self.check_ip_limits(next_state != 1, ctx)?;
+                    }
+                }
+                _ => unreachable!(), // reader is in handshake state so only handshake can be read.
+            },
+            ConnectionState::Status => match packet {
+                CPacket::StatusRequest {} => {
+                    let version = ProtocolVersion::from_i32(self.protocol_version);
+                    self.queue_packet(&status_response(ctx, version))?
                 }
+                CPacket::StatusPing { payload } => {
+                    self.queue_packet(&SPacket::StatusPong { payload })?;
+                    self.state = ConnectionState::Closing;
+                }
+                _ => return Err(unexpected(stringify!(CPacket::StatusRequest), packet)),
+            },
+            ConnectionState::Login => match packet {
+                CPacket::LoginLoginStart { .. } if ProtocolVersion::from_i32(self.protocol_version).is_none() => {
+                    let reason = if self.protocol_version > ProtocolVersion::NEWEST.to_i32() {
+                        format!("Outdated server! I'm still on {}", ProtocolVersion::NEWEST.name())
+                    } else {
+                        format!("Outdated client! Please use {}", ProtocolVersion::OLDEST.name())
+                    };
+                    self.disconnect_login(&reason)?;
+                }
+                // BungeeCord authenticated the player already.
+                CPacket::LoginLoginStart { name } if self.forwarded.is_some() => {
+                    let (uuid, properties) = self.forwarded.take().unwrap();
+                    self.finish_login(PlayerProfile { id: UuidWrapper(uuid), name, properties }, ctx)?;
+                }
+                CPacket::LoginLoginStart { name } => match ctx.authenticator {
+                    Some(ref authenticator) => {
+                        let mut verify_token = [0; 4];
+                        rand_bytes(&mut verify_token).map_err(auth::Error::from)?;
+                        self.queue_packet(&SPacket::LoginEncryptionRequest {
+                            server_id: String::new(),
+                            public_key: authenticator.get_key().public_der().to_vec(),
+                            verify_token: verify_token.to_vec(),
+                        })?;
+                        self.state = ConnectionState::Encryption { name, verify_token };
+                    }
+                    None => {
+                        let profile = PlayerProfile {
+                            id: UuidWrapper(auth::offline_uuid(&name)),
+                            name,
+                            properties: Vec::new(),
+                        };
+                        self.finish_login(profile, ctx)?;
+                    }
+                },
+                _ => return Err(unexpected(stringify!(CPacket::LoginLoginStart), packet)),
+            },
+            ConnectionState::Encryption { ref name, verify_token } => match packet {
+                CPacket::LoginEncryptionResponse {
+                    shared_secret,
+                    verify_token: client_verify_token,
+                } => {
+                    let name = name.clone();
+                    self.start_authentication(name, &verify_token, &shared_secret, &client_verify_token, ctx)?;
+                }
+                _ => return Err(unexpected(stringify!(CPacket::LoginEncryptionResponse), packet)),
+            },
+            // client waits for login success.
+            ConnectionState::Authenticating { .. } => return Err(unexpected("nothing", packet)),
+            ConnectionState::Play(ref mut play) => {
+                play.record(Direction::Serverbound, &packet);
+                if play.sender.send(packet).is_err() {
+                    // player is gone.
+                    self.state = ConnectionState::Closing;
+                }
+            }
+            ConnectionState::Closing => {}
+            ConnectionState::Proxy => unreachable!(), // packets aren't read before the header.
+        }
+        Ok(())
+    }
+
+    /// Counts the connection towards its ip. Refuses it if the ip has too many connections or logged in too often
+    /// lately.
+    fn check_ip_limits(&mut self, login: bool, ctx: &Context) -> Result<(), Error> {
+        let ip = self.addr.ip();
+        let properties = &ctx.properties;
+        let mut limits = ctx.ip_limits.lock().unwrap();
+        let connections = limits.connections.entry(ip).or_insert(0);
+        if properties.max_connections_per_ip != 0 && *connections >= properties.max_connections_per_ip {
+            info!("{} has too many connections", self.addr);
+            return self.refuse(login, "Too many connections from your IP address");
+        }
+        *connections += 1;
+        self._ip_guard = Some(IpGuard { ip_limits: Arc::clone(&ctx.ip_limits), ip });
+
+        if login && properties.connection_throttle_limit != 0 {
+            let now = Instant::now();
+            let interval = Duration::from_secs(properties.connection_throttle_interval);
+            let logins = limits.logins.entry(ip).or_insert_with(VecDeque::new);
+            while logins.front().map_or(false, |&t| now - t >= interval) {
+                logins.pop_front();
+            }
+            if logins.len() >= properties.connection_throttle_limit as usize {
+                info!("{} has been throttled", self.addr);
+                return self.refuse(login, "Connection throttled! Please wait before reconnecting.");
             }
+            logins.push_back(now);
+        }
+        Ok(())
+    }
 
+    /// Closes the connection, telling the client why if it is logging in. Status requests have no way to.
+    fn refuse(&mut self, login: bool, reason: &str) -> Result<(), Error> {
+        if login {
+            self.disconnect_login(reason)
+        } else {
+            self.state = ConnectionState::Closing;
             Ok(())
-        });
+        }
+    }
 
-        let mut err = Ok(());
-        // loop for packet receiving.
-        while *connected.lock().unwrap() {
-            let packet = reader.read_packet();
-            match packet {
-                Ok(p) => {
-                    if let Err(_) = sender.send(p) {
-                        break;
+    /// Disconnects clients which take too long to log in. Players who stop answering keep-alives are kicked by the
+    /// server, which knows which keep-alive it waits for.
+    fn check_timeout(&mut self, now: Instant, ctx: &Context) -> Result<(), Error> {
+        match self.state {
+            ConnectionState::Play(_) | ConnectionState::Closing => {}
+            _ if now - self.connected_at >= Duration::from_secs(ctx.properties.login_timeout) => {
+                info!("{} took too long to log in", self.addr);
+                match self.state {
+                    ConnectionState::Login | ConnectionState::Encryption { .. } | ConnectionState::Authenticating { .. } => {
+                        self.disconnect_login("Took too long to log in")?
                     }
+                    _ => self.state = ConnectionState::Closing,
                 }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// BungeeCord puts `<host>\0<player ip>\0<uuid>\0<properties>` in the handshake's address. Players who connect
+    /// without it are kicked because they would be in offline mode.
+    fn read_forwarding(&mut self, server_address: &str) -> Result<(), Error> {
+        let fields: Vec<&str> = server_address.split('\0').collect();
+        if fields.len() < 3 {
+            return self.disconnect_login("If you wish to use IP forwarding, please enable it in your BungeeCord config as well!");
+        }
+        let ip: IpAddr = fields[1].parse().map_err(|_| Error::InvalidForwarding)?;
+        let uuid = Uuid::parse_str(fields[2]).map_err(|_| Error::InvalidForwarding)?;
+        let properties = match fields.get(3) {
+            Some(json) => serde_json::from_str(json).map_err(|_| Error::InvalidForwarding)?,
+            None => Vec::new(),
+        };
+        self.addr = SocketAddr::new(ip, self.addr.port());
+        self.forwarded = Some((uuid, properties));
+        Ok(())
+    }
+
+    /// Checks the encryption response, enables encryption and asks the session server on another thread whether the
+    /// player is who they claim to be.
+    fn start_authentication(
+        &mut self,
+        name: String,
+        verify_token: &[u8],
+        shared_secret: &[u8],
+        client_verify_token: &[u8],
+        ctx: &Context,
+    ) -> Result<(), Error> {
+        // only in encryption state in online mode.
+        let authenticator = Arc::clone(ctx.authenticator.as_ref().unwrap());
+
+        let key = authenticator.get_key();
+        if key.decrypt(client_verify_token)? != verify_token {
+            return Err(Error::VerifyTokenMismatch);
+        }
+        let shared_secret = key.decrypt(shared_secret)?;
+        if shared_secret.len() != 16 {
+            return Err(Error::InvalidSharedSecret(shared_secret.len()));
+        }
+        self.reader.enable_encryption(&shared_secret)?;
+        self.writer.enable_encryption(&shared_secret)?;
+
+        let hash = auth::server_hash("", &shared_secret, key.public_der());
+        let notifier = Arc::clone(&ctx.notifier);
+        let token = self.token;
+        let player_name = name.clone();
+        let builder = thread::Builder::new().name(format!("User Authenticator {}", token.0));
+        builder.spawn(move || {
+            let result = authenticator.has_joined(&player_name, &hash);
+            notifier.session_done(token, result);
+        })?;
+        self.state = ConnectionState::Authenticating { name };
+        Ok(())
+    }
+
+    fn handle_session_result(
+        &mut self,
+        result: Result<Option<PlayerProfile>, auth::Error>,
+        ctx: &Context,
+    ) -> Result<(), Error> {
+        let name = match self.state {
+            ConnectionState::Authenticating { ref name } => name.clone(),
+            _ => return Ok(()),
+        };
+        let reason = match result {
+            Ok(Some(profile)) => return self.finish_login(profile, ctx),
+            Ok(None) => "Failed to verify username!",
+            Err(e) => {
+                warn!("could not verify {}: {}", name, e);
+                "Authentication servers are down. Please try again later, sorry!"
+            }
+        };
+        self.disconnect_login(reason)
+    }
+
+    fn disconnect_login(&mut self, reason: &str) -> Result<(), Error> {
+        self.queue_packet(&SPacket::LoginDisconnect {
+            reason: Chat::from(text::parse_legacy(reason)),
+        })?;
+        self.state = ConnectionState::Closing;
+        Ok(())
+    }
+
+    /// Sends login success and hands the player over to the server.
+    fn finish_login(&mut self, profile: PlayerProfile, ctx: &Context) -> Result<(), Error> {
+        let (name, uuid) = (profile.name.clone(), profile.id.0);
+
+        let response_player = SStatusResponsePlayer {
+            name: name.clone(),
+            id: uuid.hyphenated().to_string(),
+        };
+
+        // check if player is already logged in
+        if ctx.player_list.lock().unwrap().contains(&response_player) {
+            return self.disconnect_login("You are already logged in");
+        }
+        let refusal = ctx.user_lists.lock().unwrap().check_login(uuid, self.addr.ip());
+        if let Some(reason) = refusal {
+            info!("{} ({}) may not join: {}", name, self.addr, reason.replace('\n', " "));
+            return self.disconnect_login(&reason);
+        }
+
+        let supports_compression = self.translator.as_ref().map_or(true, |t| t.get_version().supports_compression());
+        if let (Some(threshold), true) = (ctx.properties.get_compression_threshold(), supports_compression) {
+            self.queue_packet(&SPacket::LoginSetCompression {
+                threshold: threshold as i32,
+            })?;
+            self.reader.set_compression(threshold);
+            self.writer.set_compression(threshold);
+        }
+
+        self.queue_packet(&SPacket::LoginLoginSuccess {
+            username: name,
+            uuid: uuid.hyphenated().to_string(),
+        })?;
+        self.reader.set_state(State::Play);
+
+        let (server_sender, server_receiver) = mpsc::channel();
+        let (client_sender, client_receiver) = mpsc::channel();
+        let queued = Arc::new(AtomicBool::new(false));
+        let close = Arc::new(AtomicBool::new(false));
+        let connected = Arc::new(Mutex::new(true));
+
+        let packet_sender = PacketSender {
+            sender: server_sender,
+            token: self.token,
+            queued: Arc::clone(&queued),
+            close: Arc::clone(&close),
+            notifier: Arc::clone(&ctx.notifier),
+        };
+        let capture = match ctx.properties.capture_dir {
+            Some(ref dir) => match start_capture(Path::new(dir), &profile) {
+                Ok(capture) => Some(capture),
                 Err(e) => {
-                    *connected.lock().unwrap() = false;
-                    // normal disconnect
-                    /*if let Some(ioerr) = e.root_cause().downcast_ref::<io::Error>() {
-                        if ioerr.kind() == io::ErrorKind::UnexpectedEof {
-                            break;
+                    warn!("could not record packets of {}: {}", profile.name, e);
+                    None
+                }
+            },
+            None => None,
+        };
+        let player = Player::new(
+            profile,
+            packet_sender,
+            client_receiver,
+            self.addr,
+            Arc::clone(&connected),
+            ctx.properties.gamemode,
+        );
+
+        ctx.player_list.lock().unwrap().insert(response_player.clone());
+        self.state = ConnectionState::Play(PlayState {
+            sender: client_sender,
+            receiver: server_receiver,
+            queued,
+            close,
+            connected,
+            capture,
+            _guard: PlayerGuard {
+                player_list: Arc::clone(&ctx.player_list),
+                player: response_player,
+            },
+        });
+
+        if ctx.incoming_players.send(player).is_err() {
+            // server is shutting down.
+            self.state = ConnectionState::Closing;
+        }
+        Ok(())
+    }
+
+    /// Queues packets the server sent. Closes the connection if the player was dropped by the server.
+    fn write_queued(&mut self, _ctx: &Context) -> Result<(), Error> {
+        let gone = match self.state {
+            ConnectionState::Play(ref mut play) => {
+                play.queued.store(false, Ordering::SeqCst);
+                // checked first so everything sent before closing is written.
+                let close = play.close.load(Ordering::SeqCst);
+                loop {
+                    match play.receiver.try_recv() {
+                        Ok(packet) => {
+                            play.record(Direction::Clientbound, &*packet);
+                            queue_packet(&mut self.writer, &mut self.translator, &*packet)?;
                         }
-                    }*/
-                    err = Err(e);
-                    break;
+                        Err(TryRecvError::Empty) => break close,
+                        Err(TryRecvError::Disconnected) => break true,
+                    }
                 }
             }
+            _ => false,
+        };
+        if gone {
+            self.state = ConnectionState::Closing;
         }
-        err.and(send_thread.join().unwrap())
+        Ok(())
+    }
+
+    /// Next packet the client sent, as a 1.8 packet. Packets 1.8 has nothing like are skipped.
+    fn next_packet(&mut self) -> Result<Option<CPacket>, Error> {
+        let translator = match self.translator {
+            Some(ref mut translator) => translator,
+            None => return Ok(self.reader.next_packet()?),
+        };
+        while let Some(data) = self.reader.next_raw()? {
+            if let Some(packet) = translator.read_serverbound(&data, self.reader.get_state())? {
+                return Ok(Some(packet));
+            }
+        }
+        Ok(None)
+    }
+
+    fn queue_packet(&mut self, packet: &SPacket) -> Result<(), Error> {
+        queue_packet(&mut self.writer, &mut self.translator, packet)
+    }
+}
+
+/// Queues the packet in the client's version.
+fn queue_packet(writer: &mut Writer<Stream>, translator: &mut Option<Translator>, packet: &SPacket) -> Result<(), Error> {
+    match *translator {
+        Some(ref mut translator) => {
+            let mut packets = Vec::new();
+            translator.write_clientbound(packet, &mut packets)?;
+            for data in packets {
+                writer.queue_raw(&data)?;
+            }
+        }
+        None => writer.queue_packet(packet)?,
+    }
+    Ok(())
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        if let ConnectionState::Play(ref play) = self.state {
+            *play.connected.lock().unwrap() = false;
+        }
+        let _ = self.stream.shutdown(Shutdown::Both);
+    }
+}
+
+impl PlayState {
+    /// Adds the packet to the capture. Recording stops if the file can't be written.
+    fn record<P: proto::Packet>(&mut self, direction: Direction, packet: &P) {
+        let result = match self.capture {
+            Some(ref mut capture) => capture.record(direction, packet),
+            None => return,
+        };
+        if let Err(e) = result {
+            warn!("stopped recording packets: {}", e);
+            self.capture = None;
+        }
+    }
+}
+
+/// Creates `<name>-<date>.qcap` in dir.
+fn start_capture(dir: &Path, profile: &PlayerProfile) -> io::Result<CaptureWriter<BufWriter<File>>> {
+    fs::create_dir_all(dir)?;
+    let path = dir.join(format!("{}-{}.qcap", profile.name, Local::now().format("%Y-%m-%d_%H-%M-%S")));
+    info!("Recording packets of {} to {}", profile.name, path.display());
+    let start_time = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_millis() as i64);
+    let header = CaptureHeader {
+        protocol_version: proto::VERSION,
+        name: profile.name.clone(),
+        uuid: profile.id.0.hyphenated().to_string(),
+        start_time,
+    };
+    CaptureWriter::new(BufWriter::new(File::create(path)?), &header)
+}
+
+fn unexpected(expected: &'static str, got: CPacket) -> Error {
+    proto::Error::UnexpectedPacket {
+        expected,
+        got: format!("{:?}", got),
+    }
+    .into()
+}
+
+/// Reports the client's own version, so any supported client shows the server as compatible. Clients of other
+/// versions see the outdated version name.
+fn status_response(ctx: &Context, version: Option<ProtocolVersion>) -> SPacket {
+    let players = ctx.player_list.lock().unwrap();
+    let version = match version {
+        Some(version) => SStatusResponseVersion { name: version.name().into(), protocol: version.to_i32() },
+        None => SStatusResponseVersion { name: outdated_version_name(ctx).into(), protocol: proto::VERSION },
+    };
+    SPacket::StatusResponse {
+        data: SStatusResponseData {
+            version,
+            players: SStatusResponsePlayers {
+                max: ctx.properties.max_players,
+                online: players.len() as i32,
+                sample: if ctx.properties.hide_online_players { None } else { Some(status_sample(&players)) },
+            },
+            description: Chat::from(text::parse_legacy(&ctx.properties.motd)),
+            favicon: Arc::clone(&ctx.favicon),
+        },
+    }
+}
+
+/// At most STATUS_SAMPLE_SIZE players picked at random, so big servers don't list everyone.
+fn status_sample(players: &HashSet<SStatusResponsePlayer>) -> Vec<SStatusResponsePlayer> {
+    let mut players: Vec<_> = players.iter().cloned().collect();
+    let count = players.len().min(STATUS_SAMPLE_SIZE);
+    // shuffles the first count players.
+    for i in 0..count {
+        let mut bytes = [0; 4];
+        // only fails without entropy. the sample just isn't shuffled then.
+        let _ = rand_bytes(&mut bytes);
+        let j = i + u32::from_le_bytes(bytes) as usize % (players.len() - i);
+        players.swap(i, j);
+    }
+    players.truncate(count);
+    players
+}
+
+fn outdated_version_name(ctx: &Context) -> String {
+    match ctx.properties.outdated_version_name {
+        Some(ref name) => name.clone(),
+        None => format!("{}-{}", ProtocolVersion::OLDEST.name(), ProtocolVersion::NEWEST.name()),
     }
 }
 
+/// Answer to the ping of clients before 1.7: a kick packet with the status in its reason, as UTF-16.
+fn legacy_status_response(ctx: &Context, since_1_4: bool) -> Vec<u8> {
+    let online = ctx.player_list.lock().unwrap().len();
+    let max = ctx.properties.max_players;
+    // only one line is shown.
+    let motd = ctx.properties.motd.lines().next().unwrap_or("");
+    let reason = if since_1_4 {
+        format!("\u{a7}1\0{}\0{}\0{}\0{}\0{}", LEGACY_PING_PROTOCOL, outdated_version_name(ctx), motd, online, max)
+    } else {
+        // fields are separated by \u{a7} so the motd can't have formatting codes.
+        let motd = Chat::from(text::parse_legacy(motd)).to_plain();
+        format!("{}\u{a7}{}\u{a7}{}", motd, online, max)
+    };
+    let reason: Vec<u16> = reason.encode_utf16().collect();
+    let mut data = vec![0xff];
+    // writing to a vec can't fail.
+    binary::write_ushort(&mut data, reason.len() as u16).unwrap();
+    for c in reason {
+        binary::write_ushort(&mut data, c).unwrap();
+    }
+    data
+}
+
 struct PlayerGuard {
     player_list: Arc<Mutex<HashSet<SStatusResponsePlayer>>>,
     player: SStatusResponsePlayer,
@@ -323,3 +1040,117 @@ impl Drop for PlayerGuard {
         self.player_list.lock().unwrap().remove(&self.player);
     }
 }
+
+struct IpGuard {
+    ip_limits: Arc<Mutex<IpLimits>>,
+    ip: IpAddr,
+}
+
+impl Drop for IpGuard {
+    fn drop(&mut self) {
+        let mut limits = self.ip_limits.lock().unwrap();
+        if let Some(connections) = limits.connections.get_mut(&self.ip) {
+            *connections -= 1;
+            if *connections == 0 {
+                limits.connections.remove(&self.ip);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::net::TcpStream;
+
+    use crate::test_client::{PlayDisconnect, PlayPlayerListItem, TestClient, TestServer};
+
+    use super::*;
+
+    #[test]
+    fn takes_player_from_proxies() {
+        let server = TestServer::with_properties(|p| {
+            p.proxy_protocol = true;
+            p.bungeecord = true;
+        });
+        let uuid = Uuid::parse_str("069a79f4-44e9-4726-a5be-fca90e38aaf5").unwrap();
+        let header = format!("PROXY TCP4 192.0.2.1 127.0.0.1 51234 {}\r\n", server.get_addr().port());
+        let properties = r#"[{"name":"textures","value":"e30=","signature":"c2ln"}]"#;
+        let address = format!("localhost\0192.0.2.1\0{}\0{}", uuid.simple(), properties);
+        let mut client = TestClient::connect_through_proxy(server.get_addr(), "Notch", header.as_bytes(), address);
+        assert_eq!(client.get_uuid(), uuid);
+
+        let list = client.wait_for::<PlayPlayerListItem>();
+        let properties = list.players.iter().find_map(|p| match p.action {
+            SPlayPlayerListItemDataAction::AddPlayer { ref properties, .. } if p.uuid == uuid => {
+                Some(properties.iter().map(|p| (p.name.clone(), p.value.clone(), p.signature.clone())).collect::<Vec<_>>())
+            }
+            _ => None,
+        });
+        assert_eq!(properties.unwrap(), [("textures".to_string(), "e30=".to_string(), Some("c2ln".to_string()))]);
+    }
+
+    #[test]
+    fn limits_connections_per_ip() {
+        let server = TestServer::with_properties(|p| {
+            p.connection_throttle_limit = 2;
+            p.max_connections_per_ip = 1;
+        });
+        let alice = server.connect("Alice");
+        let reason = TestClient::try_connect(server.get_addr(), "Bob").err().unwrap();
+        assert_eq!(reason.to_plain(), "Too many connections from your IP address");
+
+        drop(alice);
+        let reason = loop {
+            // the server may not have noticed that alice is gone yet.
+            match TestClient::try_connect(server.get_addr(), "Bob") {
+                Ok(bob) => drop(bob),
+                Err(ref reason) if reason.to_plain().starts_with("Too many") => thread::sleep(Duration::from_millis(10)),
+                Err(reason) => break reason,
+            }
+        };
+        assert_eq!(reason.to_plain(), "Connection throttled! Please wait before reconnecting.");
+    }
+
+    #[test]
+    fn closes_idle_connections() {
+        let server = TestServer::with_properties(|p| p.login_timeout = 1);
+        let mut stream = TcpStream::connect(server.get_addr()).unwrap();
+        stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        let start = Instant::now();
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).unwrap();
+        assert!(response.is_empty());
+        assert!(start.elapsed() >= Duration::from_secs(1));
+    }
+
+    #[test]
+    fn times_out_silent_players() {
+        let server = TestServer::with_properties(|p| p.keep_alive_timeout = 1);
+        let mut client = server.connect("Alice");
+        // keep-alives are only answered while reading.
+        thread::sleep(Duration::from_millis(3500));
+        let reason = client.wait_for::<PlayDisconnect>().reason;
+        assert_eq!(reason.to_plain(), "Timed out");
+    }
+
+    #[test]
+    fn answers_legacy_ping() {
+        let server = TestServer::with_properties(|p| p.motd = "A \u{a7}aQuartz\u{a7}r server".into());
+        let mut stream = TcpStream::connect(server.get_addr()).unwrap();
+        stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        stream.write_all(&[0xfe, 0x01]).unwrap();
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).unwrap();
+
+        assert_eq!(response[0], 0xff);
+        let len = (usize::from(response[1]) << 8) | usize::from(response[2]);
+        let units: Vec<u16> = response[3..].chunks(2).map(|c| (u16::from(c[0]) << 8) | u16::from(c[1])).collect();
+        assert_eq!(units.len(), len);
+        let reason = String::from_utf16(&units).unwrap();
+        let fields: Vec<&str> = reason.split('\0').collect();
+        assert_eq!(fields[0], "\u{a7}1");
+        assert_eq!(fields[1], "127");
+        assert_eq!(fields[3], "A \u{a7}aQuartz\u{a7}r server");
+        assert_eq!(&fields[4..], ["0", "20"]);
+    }
+}
@@ -0,0 +1,206 @@
+//! Recordings of the packets of a connection, for finding out what happened when a client misbehaves.
+//!
+//! A capture starts with `QCAP`, the format version, the protocol version, the player's name and uuid and when the
+//! recording started in milliseconds since the epoch. Every packet follows as its direction, the milliseconds since the
+//! packet before as a varint and the uncompressed packet (id and fields) as a byte array.
+
+use std::io::{self, BufRead, Write};
+use std::time::{Duration, Instant};
+
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+
+use crate::binary;
+use crate::proto::data::{read_byte_array, read_string, write_byte_array, write_string};
+use crate::proto::{Error, Packet, Result, State};
+
+const MAGIC: &[u8; 4] = b"QCAP";
+const FORMAT_VERSION: u8 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// sent by the client.
+    Serverbound,
+    /// sent by the server.
+    Clientbound,
+}
+
+impl Direction {
+    fn to_u8(self) -> u8 {
+        match self {
+            Direction::Serverbound => 0,
+            Direction::Clientbound => 1,
+        }
+    }
+
+    fn from_u8(b: u8) -> Option<Direction> {
+        match b {
+            0 => Some(Direction::Serverbound),
+            1 => Some(Direction::Clientbound),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CaptureHeader {
+    pub protocol_version: i32,
+    pub name: String,
+    pub uuid: String,
+    /// milliseconds since the epoch.
+    pub start_time: i64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Record {
+    /// since the capture started.
+    pub time: Duration,
+    pub direction: Direction,
+    /// packet id followed by its fields.
+    pub data: Vec<u8>,
+}
+
+impl Record {
+    pub fn decode<P: Packet>(&self, state: State) -> Result<P> {
+        let mut slice = &self.data[..];
+        let id = binary::read_varint(&mut slice)?;
+        let packet = P::read(&mut slice, state, id)?;
+        if !slice.is_empty() {
+            return Err(Error::PacketTooLarge(slice.len()));
+        }
+        Ok(packet)
+    }
+}
+
+/// Encodes a packet the way it is stored in a capture.
+pub fn encode<P: Packet>(packet: &P) -> io::Result<Vec<u8>> {
+    let mut data = Vec::new();
+    binary::write_varint(&mut data, packet.id())?;
+    packet.write(&mut data)?;
+    Ok(data)
+}
+
+pub struct CaptureWriter<W: Write> {
+    w: W,
+    start: Instant,
+    /// milliseconds since start of the last record.
+    last: u64,
+}
+
+impl<W: Write> CaptureWriter<W> {
+    pub fn new(mut w: W, header: &CaptureHeader) -> io::Result<CaptureWriter<W>> {
+        w.write_all(MAGIC)?;
+        w.write_u8(FORMAT_VERSION)?;
+        binary::write_varint(&mut w, header.protocol_version)?;
+        write_string(&mut w, &header.name)?;
+        write_string(&mut w, &header.uuid)?;
+        w.write_i64::<BigEndian>(header.start_time)?;
+        Ok(CaptureWriter { w, start: Instant::now(), last: 0 })
+    }
+
+    pub fn record<P: Packet>(&mut self, direction: Direction, packet: &P) -> io::Result<()> {
+        let now = self.start.elapsed().as_millis() as u64;
+        self.write_record(now, direction, &encode(packet)?)
+    }
+
+    fn write_record(&mut self, time: u64, direction: Direction, data: &[u8]) -> io::Result<()> {
+        let delta = time.saturating_sub(self.last).min(i32::max_value() as u64);
+        self.last = time;
+        self.w.write_u8(direction.to_u8())?;
+        binary::write_varint(&mut self.w, delta as i32)?;
+        write_byte_array(&mut self.w, data)
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.w.flush()
+    }
+}
+
+pub struct CaptureReader<R: BufRead> {
+    r: R,
+    header: CaptureHeader,
+    /// milliseconds since start of the last record.
+    time: u64,
+}
+
+impl<R: BufRead> CaptureReader<R> {
+    pub fn new(mut r: R) -> Result<CaptureReader<R>> {
+        let mut magic = [0; 4];
+        r.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(Error::NotACapture);
+        }
+        let version = r.read_u8()?;
+        if version != FORMAT_VERSION {
+            return Err(Error::UnsupportedCaptureVersion(version));
+        }
+        let header = CaptureHeader {
+            protocol_version: binary::read_varint(&mut r)?,
+            name: read_string(&mut r)?,
+            uuid: read_string(&mut r)?,
+            start_time: r.read_i64::<BigEndian>()?,
+        };
+        Ok(CaptureReader { r, header, time: 0 })
+    }
+
+    pub fn get_header(&self) -> &CaptureHeader {
+        &self.header
+    }
+
+    /// Returns None at the end of the capture. A record cut off by a crash is also treated as the end.
+    pub fn next_record(&mut self) -> Result<Option<Record>> {
+        if self.r.fill_buf()?.is_empty() {
+            return Ok(None);
+        }
+        let record = (|| -> Result<Record> {
+            let direction = self.r.read_u8()?;
+            let direction = Direction::from_u8(direction).ok_or(Error::InvalidCaptureDirection(direction))?;
+            self.time += binary::read_varint(&mut self.r)? as u64;
+            let data = read_byte_array(&mut self.r)?;
+            Ok(Record { time: Duration::from_millis(self.time), direction, data })
+        })();
+        match record {
+            Ok(record) => Ok(Some(record)),
+            Err(Error::IOError(ref e)) | Err(Error::VarintError(binary::VarintError::IOErr(ref e)))
+                if e.kind() == io::ErrorKind::UnexpectedEof =>
+            {
+                Ok(None)
+            }
+            Err(e) => Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::proto::packets::{CPacket, SPacket};
+
+    use super::*;
+
+    #[test]
+    fn capture_round_trip() {
+        let header = CaptureHeader { protocol_version: 47, name: "Alice".into(), uuid: "abc".into(), start_time: 1234 };
+        let mut writer = CaptureWriter::new(Vec::new(), &header).unwrap();
+        writer.write_record(5, Direction::Clientbound, &encode(&SPacket::PlayKeepAlive { id: 7 }).unwrap()).unwrap();
+        writer.write_record(305, Direction::Serverbound, &encode(&CPacket::PlayKeepAlive { id: 7 }).unwrap()).unwrap();
+        let mut bytes = writer.w;
+        bytes.extend_from_slice(&[1, 3]); // cut off
+
+        let mut reader = CaptureReader::new(&bytes[..]).unwrap();
+        assert_eq!(reader.get_header(), &header);
+        let record = reader.next_record().unwrap().unwrap();
+        assert_eq!((record.time, record.direction), (Duration::from_millis(5), Direction::Clientbound));
+        match record.decode(State::Play).unwrap() {
+            SPacket::PlayKeepAlive { id: 7 } => {}
+            p => panic!("wrong packet {:?}", p),
+        }
+        let record = reader.next_record().unwrap().unwrap();
+        assert_eq!((record.time, record.direction), (Duration::from_millis(305), Direction::Serverbound));
+        match record.decode(State::Play).unwrap() {
+            CPacket::PlayKeepAlive { id: 7 } => {}
+            p => panic!("wrong packet {:?}", p),
+        }
+        assert!(reader.next_record().unwrap().is_none());
+
+        assert!(CaptureReader::new(&b"PCAP\x01"[..]).is_err());
+    }
+}
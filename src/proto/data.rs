@@ -14,11 +14,27 @@ pub fn write_string<W: Write>(w: &mut W, s: &str) -> io::Result<()> {
 }
 
 pub fn read_string<R: BufRead>(r: &mut R) -> Result<String> {
+    let buf = read_byte_array(r)?;
+    let string = String::from_utf8(buf)?;
+    Ok(string)
+}
+
+/// byte array prefixed with its length as a varint.
+pub fn write_byte_array<W: Write>(w: &mut W, data: &[u8]) -> io::Result<()> {
+    binary::write_varint(w, data.len() as i32)?;
+    w.write_all(data)
+}
+
+pub fn read_byte_array<R: BufRead>(r: &mut R) -> Result<Vec<u8>> {
     let len = binary::read_varint(r)?;
+    if len < 0 {
+        return Err(crate::proto::Error::NegativePacketLen(len));
+    } else if len > crate::proto::MAX_PACKET_LEN {
+        return Err(crate::proto::Error::PacketSizeExceededMaxAllowed(len));
+    }
     let mut buf = vec![0u8; len as usize];
     r.read_exact(&mut buf)?;
-    let string = String::from_utf8(buf)?;
-    Ok(string)
+    Ok(buf)
 }
 
 pub fn write_uuid<W: Write>(w: &mut W, u: &uuid::Uuid) -> io::Result<()> {
@@ -36,7 +52,8 @@ pub fn read_uuid<R: Read>(r: &mut R) -> io::Result<uuid::Uuid> {
 }
 
 pub fn write_angle<W: Write>(w: &mut W, a: f64) -> io::Result<()> {
-    binary::write_byte(w, ((f64::from(a.round() as i32 % 360) / 360.0) * 256.0).floor() as i8)
+    // wraps around like vanilla, so 180 degrees is -128.
+    binary::write_byte(w, (a * 256.0 / 360.0).floor() as i32 as i8)
 }
 
 pub fn read_angle<R: Read>(r: &mut R) -> io::Result<f64> {
@@ -44,7 +61,7 @@ pub fn read_angle<R: Read>(r: &mut R) -> io::Result<f64> {
     Ok(f64::from(b) * (360.0 / 256.0))
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum SlotData {
     Empty,
     Some {
@@ -162,6 +179,15 @@ impl GroundUpContinuous {
     pub fn len(&self) -> usize {
         self.sections.len() + (16 * 16)
     }
+
+    pub fn read<R: Read>(r: &mut R, section_count: usize, sky_light: bool) -> io::Result<GroundUpContinuous> {
+        let sections = GroundUpNonContinuous::read(r, section_count, sky_light)?;
+        let mut biome_array = Box::new([[0; 16]; 16]);
+        for z in biome_array.iter_mut() {
+            r.read_exact(z)?;
+        }
+        Ok(GroundUpContinuous { sections, biome_array })
+    }
 }
 
 #[derive(Debug)]
@@ -188,4 +214,29 @@ impl GroundUpNonContinuous {
     pub fn len(&self) -> usize {
         self.sections.iter().fold(0, |acc, x| acc + x.len())
     }
+
+    /// Reads the arrays grouped the same way write does. section_count is the number of bits set in the bit mask.
+    pub fn read<R: Read>(r: &mut R, section_count: usize, sky_light: bool) -> io::Result<GroundUpNonContinuous> {
+        let mut sections = Vec::new();
+        for _ in 0..section_count {
+            let mut blocks = [0; CHUNK_SECTION_BLOCK_COUNT * 2];
+            r.read_exact(&mut blocks)?;
+            sections.push(ChunkSection {
+                blocks,
+                block_light: [0; CHUNK_SECTION_BLOCK_COUNT / 2],
+                sky_light: None,
+            });
+        }
+        for sec in &mut sections {
+            r.read_exact(&mut sec.block_light)?;
+        }
+        if sky_light {
+            for sec in &mut sections {
+                let mut arr = [0; CHUNK_SECTION_BLOCK_COUNT / 2];
+                r.read_exact(&mut arr)?;
+                sec.sky_light = Some(arr);
+            }
+        }
+        Ok(GroundUpNonContinuous { sections })
+    }
 }
\ No newline at end of file
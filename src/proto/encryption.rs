@@ -0,0 +1,105 @@
+use std::io::{self, Read, Write};
+
+use openssl::error::ErrorStack;
+use openssl::symm::{Cipher, Crypter, Mode};
+
+fn to_io_error(e: ErrorStack) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, e)
+}
+
+/// AES/CFB8 with the shared secret used as both key and iv, like the client does.
+fn new_crypter(mode: Mode, shared_secret: &[u8]) -> Result<Crypter, ErrorStack> {
+    Crypter::new(Cipher::aes_128_cfb8(), mode, shared_secret, Some(shared_secret))
+}
+
+/// Reader which decrypts everything read after encryption is enabled.
+pub struct CipherReader<R: Read> {
+    inner: R,
+    crypter: Option<Crypter>,
+    buf: Vec<u8>,
+}
+
+impl<R: Read> CipherReader<R> {
+    pub fn new(inner: R) -> CipherReader<R> {
+        CipherReader {
+            inner,
+            crypter: None,
+            buf: Vec::new(),
+        }
+    }
+
+    pub fn enable_encryption(&mut self, shared_secret: &[u8]) -> Result<(), ErrorStack> {
+        self.crypter = Some(new_crypter(Mode::Decrypt, shared_secret)?);
+        Ok(())
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+}
+
+impl<R: Read> Read for CipherReader<R> {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        let crypter = match self.crypter {
+            Some(ref mut c) => c,
+            None => return self.inner.read(out),
+        };
+
+        self.buf.resize(out.len(), 0);
+        let n = self.inner.read(&mut self.buf)?;
+        // cfb8 is a stream mode. output has the same length as input, but openssl wants one block of room.
+        let mut decrypted = vec![0; n + 1];
+        let len = crypter.update(&self.buf[..n], &mut decrypted).map_err(to_io_error)?;
+        out[..len].copy_from_slice(&decrypted[..len]);
+        Ok(len)
+    }
+}
+
+/// Writer which encrypts everything written after encryption is enabled.
+pub struct CipherWriter<W: Write> {
+    inner: W,
+    crypter: Option<Crypter>,
+    buf: Vec<u8>,
+}
+
+impl<W: Write> CipherWriter<W> {
+    pub fn new(inner: W) -> CipherWriter<W> {
+        CipherWriter {
+            inner,
+            crypter: None,
+            buf: Vec::new(),
+        }
+    }
+
+    pub fn enable_encryption(&mut self, shared_secret: &[u8]) -> Result<(), ErrorStack> {
+        self.crypter = Some(new_crypter(Mode::Encrypt, shared_secret)?);
+        Ok(())
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+}
+
+impl<W: Write> Write for CipherWriter<W> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        let crypter = match self.crypter {
+            Some(ref mut c) => c,
+            None => return self.inner.write(data),
+        };
+
+        // everything has to be written. encrypted bytes can't be taken back.
+        self.buf.resize(data.len() + 1, 0);
+        let len = crypter.update(data, &mut self.buf).map_err(to_io_error)?;
+        self.inner.write_all(&self.buf[..len])?;
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
@@ -1,3 +1,6 @@
+use openssl::error::ErrorStack;
+use serde_json;
+
 use crate::binary;
 use crate::nbt;
 use std::error::Error as StdError;
@@ -34,6 +37,20 @@ pub enum Error {
     ChatError(#[cause] chat::Error),
     #[fail(display = "varint error: {}", _0)]
     VarintError(#[cause] binary::VarintError),
+    #[fail(display = "encryption error: {}", _0)]
+    EncryptionError(#[cause] ErrorStack),
+    #[fail(display = "invalid json: {}", _0)]
+    JsonError(#[cause] serde_json::Error),
+    #[fail(display = "chunk data of {} bytes does not fit bit mask {:#06x}", size, primary_bit_mask)]
+    InvalidChunkDataSize {size: i32, primary_bit_mask: u16},
+    #[fail(display = "not a packet capture")]
+    NotACapture,
+    #[fail(display = "unsupported capture format version {}", _0)]
+    UnsupportedCaptureVersion(u8),
+    #[fail(display = "invalid packet direction {} in capture", _0)]
+    InvalidCaptureDirection(u8),
+    #[fail(display = "invalid PROXY protocol header: {}", _0)]
+    InvalidProxyHeader(&'static str),
 }
 
 impl From<io::Error> for Error {
@@ -52,6 +69,14 @@ impl From<chat::Error> for Error {
     fn from(x: chat::Error) -> Self { Error::ChatError(x) }
 }
 
+impl From<ErrorStack> for Error {
+    fn from(x: ErrorStack) -> Self { Error::EncryptionError(x) }
+}
+
 impl From<binary::VarintError> for Error {
     fn from(x: binary::VarintError) -> Self { Error::VarintError(x) }
-}
\ No newline at end of file
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(x: serde_json::Error) -> Self { Error::JsonError(x) }
+}
@@ -1,21 +1,26 @@
 mod error;
 pub mod packets;
 pub mod data;
+pub mod encryption;
+pub mod capture;
+pub mod version;
+pub mod translate;
+pub mod proxy;
 
 pub use self::error::*;
 
-use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
-use std::net::TcpStream;
+use std::io::{self, Read, Write};
+use std::net::SocketAddr;
 
 use flate2::write::ZlibEncoder;
 use flate2::read::ZlibDecoder;
 use flate2::Compression;
 
 use crate::binary;
+use self::encryption::{CipherReader, CipherWriter};
 use self::packets::{SPacket, CPacket};
 
 pub const VERSION: i32 = 47;
-pub const VERSION_STRING: &str = "1.8";
 pub const MAX_PACKET_LEN: i32 = 2_097_152;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -26,15 +31,53 @@ pub enum State {
     Login
 }
 
+/// Both directions of packets. The server reads CPackets and writes SPackets, clients do the opposite.
+pub trait Packet: Sized {
+    fn id(&self) -> i32;
+    fn write<W: Write>(&self, w: &mut W) -> io::Result<()>;
+    fn read(r: &mut &[u8], state: State, id: i32) -> Result<Self>;
+}
+
+impl Packet for SPacket {
+    fn id(&self) -> i32 { SPacket::id(self) }
+    fn write<W: Write>(&self, w: &mut W) -> io::Result<()> { SPacket::write(self, w) }
+    fn read(r: &mut &[u8], state: State, id: i32) -> Result<Self> { SPacket::read(r, state, id) }
+}
+
+impl Packet for CPacket {
+    fn id(&self) -> i32 { CPacket::id(self) }
+    fn write<W: Write>(&self, w: &mut W) -> io::Result<()> { CPacket::write(self, w) }
+    fn read(r: &mut &[u8], state: State, id: i32) -> Result<Self> { CPacket::read(r, state, id) }
+}
+
+/// Bytes read from the socket at once.
+const READ_CHUNK: usize = 4096;
+/// `Reader::read_available` stops after buffering this many bytes so one client can't make us buffer forever.
+const MAX_BUFFERED: usize = MAX_PACKET_LEN as usize + 4 * READ_CHUNK;
+
+/// Writes packets into a buffer which is written to `w` as far as `w` allows. This way `w` can be a non blocking socket.
+/// Packets are encrypted as they are buffered so enabling encryption only affects packets written after.
 pub struct Writer<W: Write> {
-    w: BufWriter<W>,
+    w: W,
+    /// encoded packets which weren't written to w yet.
+    out: CipherWriter<Vec<u8>>,
     buf: Vec<u8>,
     compression_threshold: Option<u32>,
 }
 
 impl<W: Write> Writer<W> {
     pub fn new(w: W) -> Writer<W> {
-        Writer { w: BufWriter::new(w), buf: Vec::new(), compression_threshold: None }
+        Writer { w, out: CipherWriter::new(Vec::new()), buf: Vec::new(), compression_threshold: None }
+    }
+
+    /// Encrypts everything written from now on with AES/CFB8.
+    pub fn enable_encryption(&mut self, shared_secret: &[u8]) -> Result<()> {
+        self.out.enable_encryption(shared_secret)?;
+        Ok(())
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.w
     }
 
     pub fn set_compression(&mut self, compression_threshold: u32) {
@@ -45,10 +88,37 @@ impl<W: Write> Writer<W> {
         self.compression_threshold = None;
     }
 
-    pub fn write_packet(&mut self, packet: &SPacket) -> io::Result<()> {
+    /// Whether there are buffered bytes which couldn't be written yet.
+    pub fn has_pending(&self) -> bool {
+        !self.out.get_ref().is_empty()
+    }
+
+    /// Buffers the packet and writes as much as possible.
+    pub fn write_packet<P: Packet>(&mut self, packet: &P) -> io::Result<()> {
+        self.queue_packet(packet)?;
+        self.flush()
+    }
+
+    /// Only buffers the packet. Call `flush` to write it.
+    pub fn queue_packet<P: Packet>(&mut self, packet: &P) -> io::Result<()> {
         binary::write_varint(&mut self.buf, packet.id())?;
         packet.write(&mut self.buf)?;
+        self.queue_buf()
+    }
+
+    /// Buffers a packet which is already encoded as its id followed by its fields.
+    pub fn queue_raw(&mut self, data: &[u8]) -> io::Result<()> {
+        self.buf.extend_from_slice(data);
+        self.queue_buf()
+    }
 
+    /// Buffers bytes as they are, without a packet frame. For answering pings of clients before 1.7.
+    pub fn queue_unframed(&mut self, data: &[u8]) -> io::Result<()> {
+        self.out.write_all(data)
+    }
+
+    /// Frames the packet in buf and moves it to out.
+    fn queue_buf(&mut self) -> io::Result<()> {
         let uncompressed_len = self.buf.len();
 
         if let Some(threshold) = self.compression_threshold {
@@ -59,35 +129,69 @@ impl<W: Write> Writer<W> {
 
                 self.buf.clear();
                 binary::write_varint(&mut self.buf, uncompressed_len as i32)?;
-                binary::write_varint(&mut self.w, (self.buf.len() + compressed_data.len()) as i32)?;
-                self.w.write_all(&self.buf[..])?;
-                self.w.write_all(&compressed_data[..])?;
+                binary::write_varint(&mut self.out, (self.buf.len() + compressed_data.len()) as i32)?;
+                self.out.write_all(&self.buf[..])?;
+                self.out.write_all(&compressed_data[..])?;
             } else {
-                binary::write_varint(&mut self.w, (self.buf.len() + 1) as i32)?;
-                binary::write_varint(&mut self.w, 0)?;
-                self.w.write_all(&self.buf[..])?;
+                binary::write_varint(&mut self.out, (self.buf.len() + 1) as i32)?;
+                binary::write_varint(&mut self.out, 0)?;
+                self.out.write_all(&self.buf[..])?;
             }
         } else {
-            binary::write_varint(&mut self.w, self.buf.len() as i32)?;
-            self.w.write_all(&self.buf[..])?;
+            binary::write_varint(&mut self.out, self.buf.len() as i32)?;
+            self.out.write_all(&self.buf[..])?;
         }
 
-        self.w.flush()?;
         self.buf.clear();
         Ok(())
     }
+
+    /// Writes buffered bytes until everything is written or `w` would block.
+    pub fn flush(&mut self) -> io::Result<()> {
+        let out = self.out.get_mut();
+        let mut written = 0;
+        let result = loop {
+            if written == out.len() {
+                break self.w.flush();
+            }
+            match self.w.write(&out[written..]) {
+                Ok(0) => break Err(io::ErrorKind::WriteZero.into()),
+                Ok(n) => written += n,
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => break Ok(()),
+                Err(e) => break Err(e),
+            }
+        };
+        out.drain(..written);
+        result
+    }
 }
 
+/// Decodes packets from bytes read from `r`. `read_packet` blocks, `read_available` and `next_packet` can be used
+/// with non blocking sources to decode packets as their bytes arrive.
 pub struct Reader<R: Read> {
-    r: BufReader<R>,
-    buf: Vec<u8>,
+    r: CipherReader<R>,
+    /// bytes read which weren't decoded yet. starting at pos.
+    pending: Vec<u8>,
+    pos: usize,
     state: State,
     compression_threshold: Option<u32>,
 }
 
 impl<R: Read> Reader<R> {
     pub fn new(r: R) -> Reader<R> {
-        Reader { r: BufReader::new(r), buf: Vec::new(), state: State::Handshake, compression_threshold: None }
+        Reader { r: CipherReader::new(r), pending: Vec::new(), pos: 0, state: State::Handshake, compression_threshold: None }
+    }
+
+    /// Decrypts everything read from now on with AES/CFB8.
+    /// Bytes already buffered were sent before encryption so they are left as they are.
+    pub fn enable_encryption(&mut self, shared_secret: &[u8]) -> Result<()> {
+        self.r.enable_encryption(shared_secret)?;
+        Ok(())
+    }
+
+    pub fn get_ref(&self) -> &R {
+        self.r.get_ref()
     }
 
     pub fn set_compression(&mut self, compression_threshold: u32) {
@@ -102,52 +206,240 @@ impl<R: Read> Reader<R> {
         self.state = s;
     }
 
-    pub fn read_packet(&mut self) -> Result<CPacket> {
-        let len = binary::read_varint(&mut self.r)?;
+    pub fn get_state(&self) -> State {
+        self.state
+    }
+
+    /// Blocks until a whole packet is read.
+    pub fn read_packet<P: Packet>(&mut self) -> Result<P> {
+        loop {
+            if let Some(packet) = self.next_packet()? {
+                return Ok(packet);
+            }
+            if self.fill()? == 0 {
+                return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+            }
+        }
+    }
+
+    /// Reads until `r` would block. End of stream is an `UnexpectedEof` error.
+    /// Returns true if it stopped because too much is buffered. Decode packets with `next_packet` and call this again then.
+    pub fn read_available(&mut self) -> io::Result<bool> {
+        loop {
+            if self.pending.len() - self.pos >= MAX_BUFFERED {
+                return Ok(true);
+            }
+            match self.fill() {
+                Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+                Ok(_) => {}
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => return Ok(false),
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Clients before 1.7 ping with 0xfe instead of a handshake. If the connection starts with one, everything read is
+    /// dropped and returns whether the client is 1.4 or newer, which send 0xfe 0x01.
+    pub fn take_legacy_ping(&mut self) -> Option<bool> {
+        if self.state != State::Handshake || self.pending.get(self.pos) != Some(&0xfe) {
+            return None;
+        }
+        let since_1_4 = self.pending.get(self.pos + 1) == Some(&0x01);
+        self.pos = self.pending.len();
+        Some(since_1_4)
+    }
+
+    /// Takes the PROXY protocol header the connection starts with. Returns None if not all of it was read yet, or the
+    /// address the proxy got the connection from.
+    pub fn take_proxy_header(&mut self) -> Result<Option<Option<SocketAddr>>> {
+        match proxy::parse(&self.pending[self.pos..])? {
+            Some((source, len)) => {
+                self.pos += len;
+                Ok(Some(source))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Decodes the next packet if all of its bytes were read.
+    pub fn next_packet<P: Packet>(&mut self) -> Result<Option<P>> {
+        let frame = match self.next_frame()? {
+            Some(frame) => frame,
+            None => return Ok(None),
+        };
+        let mut decompressed = Vec::new();
+        let mut slice = decompress(&self.pending[frame.0..frame.1], self.compression_threshold, &mut decompressed)?;
+        let id = binary::read_varint(&mut slice)?;
+        let packet = P::read(&mut slice, self.state, id)?;
+        if !slice.is_empty() {
+            return Err(Error::PacketTooLarge(slice.len()))
+        }
+        Ok(Some(packet))
+    }
+
+    /// Like `next_packet` but leaves the id and fields undecoded, for packets of other versions.
+    pub fn next_raw(&mut self) -> Result<Option<Vec<u8>>> {
+        let frame = match self.next_frame()? {
+            Some(frame) => frame,
+            None => return Ok(None),
+        };
+        let mut decompressed = Vec::new();
+        let data = decompress(&self.pending[frame.0..frame.1], self.compression_threshold, &mut decompressed)?;
+        Ok(Some(data.to_vec()))
+    }
+
+    /// Start and end in pending of the next frame without its length prefix, if all of it was read.
+    fn next_frame(&mut self) -> Result<Option<(usize, usize)>> {
+        let mut slice = &self.pending[self.pos..];
+        let len = match binary::read_varint(&mut slice) {
+            Ok(len) => len,
+            Err(binary::VarintError::IOErr(ref e)) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
         if len <= 0 {
             return Err(Error::NegativePacketLen(len));
         } else if len > MAX_PACKET_LEN {
             return Err(Error::PacketSizeExceededMaxAllowed(len))
         }
+        if slice.len() < len as usize {
+            return Ok(None);
+        }
 
-        self.buf.resize(len as usize, 0);
-        self.r.read_exact(&mut self.buf[..])?;
+        let header_len = self.pending.len() - self.pos - slice.len();
+        let start = self.pos + header_len;
+        self.pos = start + len as usize;
+        Ok(Some((start, self.pos)))
+    }
 
-        let mut decompressed_data = Vec::<u8>::new();
+    /// Reads once from `r` into the pending buffer.
+    fn fill(&mut self) -> io::Result<usize> {
+        if self.pos > 0 {
+            self.pending.drain(..self.pos);
+            self.pos = 0;
+        }
+        let len = self.pending.len();
+        self.pending.resize(len + READ_CHUNK, 0);
+        let result = self.r.read(&mut self.pending[len..]);
+        self.pending.truncate(len + *result.as_ref().unwrap_or(&0));
+        result
+    }
+}
 
-        let mut slice = &self.buf[..];
+/// Returns the id and fields of a packet frame without its length prefix. They are decompressed into decompressed_data if needed.
+fn decompress<'a>(frame: &'a [u8], compression_threshold: Option<u32>, decompressed_data: &'a mut Vec<u8>) -> Result<&'a [u8]> {
+    let mut slice = frame;
 
-        if let Some(threshold) = self.compression_threshold {
-            let uncompressed_len = binary::read_varint(&mut slice)?;
-            if uncompressed_len < 0 {
-                return Err(Error::NegativeUncompressedLen(uncompressed_len));
-            } else if uncompressed_len == 0 {
-                // uncompressed. go down
-            } else {
-                if (uncompressed_len as u32) < threshold {
-                    return Err(Error::CompressedBeforeThreshold);
-                }
-                if uncompressed_len > MAX_PACKET_LEN {
-                    return Err(Error::PacketSizeExceededMaxAllowed(uncompressed_len));
-                }
-                {
-                    decompressed_data.resize(uncompressed_len as usize, 0);
-                    let mut decoder = ZlibDecoder::new(&mut slice);
-                    decoder.read_exact(&mut decompressed_data[..])?;
-                }
-                if !slice.is_empty() {
-                    return Err(Error::PacketTooLarge(slice.len()))
+    if let Some(threshold) = compression_threshold {
+        let uncompressed_len = binary::read_varint(&mut slice)?;
+        if uncompressed_len < 0 {
+            return Err(Error::NegativeUncompressedLen(uncompressed_len));
+        } else if uncompressed_len == 0 {
+            // uncompressed. go down
+        } else {
+            if (uncompressed_len as u32) < threshold {
+                return Err(Error::CompressedBeforeThreshold);
+            }
+            if uncompressed_len > MAX_PACKET_LEN {
+                return Err(Error::PacketSizeExceededMaxAllowed(uncompressed_len));
+            }
+            {
+                decompressed_data.resize(uncompressed_len as usize, 0);
+                let mut decoder = ZlibDecoder::new(&mut slice);
+                decoder.read_exact(&mut decompressed_data[..])?;
+            }
+            if !slice.is_empty() {
+                return Err(Error::PacketTooLarge(slice.len()))
+            }
+            return Ok(&decompressed_data[..]);
+        }
+    }
+    Ok(slice)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// gives out the bytes in the given chunks, blocking between them.
+    struct Chunked {
+        chunks: Vec<Vec<u8>>,
+        block: bool,
+    }
+
+    impl Read for Chunked {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.block = !self.block;
+            if self.block {
+                return Err(io::ErrorKind::WouldBlock.into());
+            }
+            if self.chunks.is_empty() {
+                return Ok(0);
+            }
+            let chunk = self.chunks.remove(0);
+            buf[..chunk.len()].copy_from_slice(&chunk);
+            Ok(chunk.len())
+        }
+    }
+
+    #[test]
+    fn reader_decodes_incrementally() {
+        // handshake followed by a status request, split in awkward places.
+        let mut bytes = vec![15, 0, 47, 9];
+        bytes.extend_from_slice(b"localhost");
+        bytes.extend_from_slice(&[0x63, 0xdd, 1, 1, 0]);
+        let chunks = vec![bytes[..1].to_vec(), bytes[1..6].to_vec(), bytes[6..17].to_vec(), bytes[17..].to_vec()];
+        let mut reader = Reader::new(Chunked { chunks, block: false });
+
+        let mut packets = Vec::new();
+        loop {
+            match reader.read_available() {
+                Ok(_) => {}
+                Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+                Err(e) => panic!("{}", e),
+            }
+            while let Some(p) = reader.next_packet::<CPacket>().unwrap() {
+                if let CPacket::Handshake { .. } = p {
+                    reader.set_state(State::Status);
                 }
-                slice = &decompressed_data[..];
+                packets.push(format!("{:?}", p));
             }
         }
+        assert_eq!(packets.len(), 2);
+        assert!(packets[0].starts_with("Handshake"));
+        assert!(packets[1].starts_with("StatusRequest"));
+    }
 
-        let id = binary::read_varint(&mut slice)?;
-        let packet = CPacket::read(&mut slice, self.state, id)?;
-        if !slice.is_empty() {
-            return Err(Error::PacketTooLarge(slice.len()))
-        } 
+    /// accepts at most 3 bytes per write, then blocks once.
+    struct Slow {
+        written: Vec<u8>,
+        block: bool,
+    }
+
+    impl Write for Slow {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.block = !self.block;
+            if self.block {
+                return Err(io::ErrorKind::WouldBlock.into());
+            }
+            let n = buf.len().min(3);
+            self.written.extend_from_slice(&buf[..n]);
+            Ok(n)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
 
-        Ok(packet)
+    #[test]
+    fn writer_keeps_what_would_block() {
+        let mut writer = Writer::new(Slow { written: Vec::new(), block: false });
+        writer.write_packet(&SPacket::StatusPong { payload: 5 }).unwrap();
+        assert!(writer.has_pending());
+        while writer.has_pending() {
+            writer.flush().unwrap();
+        }
+        assert_eq!(writer.get_ref().written, vec![9, 1, 0, 0, 0, 0, 0, 0, 0, 5]);
     }
-}
\ No newline at end of file
+}
@@ -1,7 +1,7 @@
+use std::borrow::Cow;
 use std::io;
 use std::io::Read;
-use std::sync::{Arc, Mutex};
-use std::collections::HashSet;
+use std::sync::Arc;
 
 use serde_json;
 use uuid::Uuid;
@@ -13,7 +13,6 @@ use crate::entity::metadata::EntityMetadata;
 use crate::text;
 use crate::text::chat::Chat;
 use crate::block::BlockPos;
-use crate::sound::Sound;
 
 #[derive(Debug)]
 pub enum CPacket {
@@ -150,6 +149,10 @@ pub enum CPacket {
     LoginLoginStart {
         name: String,
     },
+    LoginEncryptionResponse {
+        shared_secret: Vec<u8>,
+        verify_token: Vec<u8>,
+    },
 }
 
 impl CPacket {
@@ -317,11 +320,293 @@ impl CPacket {
             State::Login => {
                 match id {
                     0 => Ok(CPacket::LoginLoginStart {name: data::read_string(r)?}),
+                    1 => Ok(CPacket::LoginEncryptionResponse {
+                        shared_secret: data::read_byte_array(r)?,
+                        verify_token: data::read_byte_array(r)?,
+                    }),
                     _ => Err(Error::InvalidPacketId(id))
                 }
             }
         }
     }
+
+    pub fn id(&self) -> i32 {
+        match *self {
+            CPacket::Handshake { .. } => 0,
+            CPacket::PlayKeepAlive { .. } => 0,
+            CPacket::PlayChatMessage { .. } => 1,
+            CPacket::PlayUseEntity { .. } => 2,
+            CPacket::PlayPlayer { .. } => 3,
+            CPacket::PlayPlayerPosition { .. } => 4,
+            CPacket::PlayPlayerLook { .. } => 5,
+            CPacket::PlayPlayerPositionAndLook { .. } => 6,
+            CPacket::PlayPlayerDigging { .. } => 7,
+            CPacket::PlayPlayerBlockPlacement { .. } => 8,
+            CPacket::PlayHeldItemChange { .. } => 9,
+            CPacket::PlayAnimation { .. } => 10,
+            CPacket::PlayEntityAction { .. } => 11,
+            CPacket::PlaySteerVehicle { .. } => 12,
+            CPacket::PlayCloseWindow { .. } => 13,
+            CPacket::PlayClickWindow { .. } => 14,
+            CPacket::PlayConfirmTransaction { .. } => 15,
+            CPacket::PlayCreativeInventoryAction { .. } => 16,
+            CPacket::PlayEnchantItem { .. } => 17,
+            CPacket::PlayUpdateSign { .. } => 18,
+            CPacket::PlayPlayerAbilities { .. } => 19,
+            CPacket::PlayTabComplete { .. } => 20,
+            CPacket::PlayClientSettings { .. } => 21,
+            CPacket::PlayClientStatus { .. } => 22,
+            CPacket::PlayPluginMessage { .. } => 23,
+            CPacket::PlaySpectate { .. } => 24,
+            CPacket::PlayResourcePackStatus { .. } => 25,
+            CPacket::StatusRequest { .. } => 0,
+            CPacket::StatusPing { .. } => 1,
+            CPacket::LoginLoginStart { .. } => 0,
+            CPacket::LoginEncryptionResponse { .. } => 1,
+        }
+    }
+
+    pub fn write<W: io::Write>(&self, w: &mut W) -> io::Result<()> {
+        match *self {
+            CPacket::Handshake {
+                protocol_version,
+                ref server_address,
+                server_port,
+                next_state,
+            } => {
+                write_varint(w, protocol_version)?;
+                data::write_string(w, server_address)?;
+                write_ushort(w, server_port)?;
+                write_varint(w, next_state)?;
+            }
+            CPacket::PlayKeepAlive { id } => {
+                write_varint(w, id)?;
+            }
+            CPacket::PlayChatMessage { ref message } => {
+                data::write_string(w, message)?;
+            }
+            CPacket::PlayUseEntity { target, ref data } => {
+                write_varint(w, target)?;
+                match *data {
+                    CPlayUseEntityData::Interact => write_varint(w, 0)?,
+                    CPlayUseEntityData::Attack => write_varint(w, 1)?,
+                    CPlayUseEntityData::InteractAt {
+                        target_x,
+                        target_y,
+                        target_z,
+                    } => {
+                        write_varint(w, 2)?;
+                        write_float(w, target_x)?;
+                        write_float(w, target_y)?;
+                        write_float(w, target_z)?;
+                    }
+                    CPlayUseEntityData::Unknown => {
+                        return Err(io::Error::new(io::ErrorKind::InvalidInput, "unknown use entity type"))
+                    }
+                }
+            }
+            CPacket::PlayPlayer { on_ground } => {
+                write_bool(w, on_ground)?;
+            }
+            CPacket::PlayPlayerPosition {
+                x,
+                feet_y,
+                z,
+                on_ground,
+            } => {
+                write_double(w, x)?;
+                write_double(w, feet_y)?;
+                write_double(w, z)?;
+                write_bool(w, on_ground)?;
+            }
+            CPacket::PlayPlayerLook {
+                yaw,
+                pitch,
+                on_ground,
+            } => {
+                write_float(w, yaw)?;
+                write_float(w, pitch)?;
+                write_bool(w, on_ground)?;
+            }
+            CPacket::PlayPlayerPositionAndLook {
+                x,
+                feet_y,
+                z,
+                yaw,
+                pitch,
+                on_ground,
+            } => {
+                write_double(w, x)?;
+                write_double(w, feet_y)?;
+                write_double(w, z)?;
+                write_float(w, yaw)?;
+                write_float(w, pitch)?;
+                write_bool(w, on_ground)?;
+            }
+            CPacket::PlayPlayerDigging {
+                status,
+                location,
+                face,
+            } => {
+                write_byte(w, status)?;
+                location.write_proto(w)?;
+                write_byte(w, face)?;
+            }
+            CPacket::PlayPlayerBlockPlacement {
+                location,
+                face,
+                ref held_item,
+                cursor_pos_x,
+                cursor_pos_y,
+                cursor_pos_z,
+            } => {
+                location.write_proto(w)?;
+                write_byte(w, face)?;
+                held_item.write(w)?;
+                write_byte(w, cursor_pos_x)?;
+                write_byte(w, cursor_pos_y)?;
+                write_byte(w, cursor_pos_z)?;
+            }
+            CPacket::PlayHeldItemChange { slot } => {
+                write_ishort(w, slot)?;
+            }
+            CPacket::PlayAnimation {} => (),
+            CPacket::PlayEntityAction {
+                entity_id,
+                action_id,
+                action_param,
+            } => {
+                write_varint(w, entity_id)?;
+                write_varint(w, action_id)?;
+                write_varint(w, action_param)?;
+            }
+            CPacket::PlaySteerVehicle {
+                sideways,
+                forward,
+                flags,
+            } => {
+                write_float(w, sideways)?;
+                write_float(w, forward)?;
+                write_ubyte(w, flags)?;
+            }
+            CPacket::PlayCloseWindow { window_id } => {
+                write_ubyte(w, window_id)?;
+            }
+            CPacket::PlayClickWindow {
+                window_id,
+                slot,
+                button,
+                action_num,
+                mode,
+                ref clicked_item,
+            } => {
+                write_ubyte(w, window_id)?;
+                write_ishort(w, slot)?;
+                write_byte(w, button)?;
+                write_ishort(w, action_num)?;
+                write_byte(w, mode)?;
+                clicked_item.write(w)?;
+            }
+            CPacket::PlayConfirmTransaction {
+                window_id,
+                action_num,
+                accepted,
+            } => {
+                write_byte(w, window_id)?;
+                write_ishort(w, action_num)?;
+                write_bool(w, accepted)?;
+            }
+            CPacket::PlayCreativeInventoryAction {
+                slot,
+                ref clicked_item,
+            } => {
+                write_ishort(w, slot)?;
+                clicked_item.write(w)?;
+            }
+            CPacket::PlayEnchantItem {
+                window_id,
+                enchantment,
+            } => {
+                write_byte(w, window_id)?;
+                write_byte(w, enchantment)?;
+            }
+            CPacket::PlayUpdateSign {
+                location,
+                ref line1,
+                ref line2,
+                ref line3,
+                ref line4,
+            } => {
+                location.write_proto(w)?;
+                line1.write_proto(w)?;
+                line2.write_proto(w)?;
+                line3.write_proto(w)?;
+                line4.write_proto(w)?;
+            }
+            CPacket::PlayPlayerAbilities {
+                flags,
+                flying_speed,
+                walking_speed,
+            } => {
+                write_byte(w, flags)?;
+                write_float(w, flying_speed)?;
+                write_float(w, walking_speed)?;
+            }
+            CPacket::PlayTabComplete { ref text, pos } => {
+                data::write_string(w, text)?;
+                match pos {
+                    Some(pos) => {
+                        write_bool(w, true)?;
+                        pos.write_proto(w)?;
+                    }
+                    None => write_bool(w, false)?,
+                }
+            }
+            CPacket::PlayClientSettings {
+                ref locale,
+                view_distance,
+                chat_mode,
+                chat_colors,
+                displayed_skin_parts,
+            } => {
+                data::write_string(w, locale)?;
+                write_byte(w, view_distance)?;
+                write_byte(w, chat_mode)?;
+                write_bool(w, chat_colors)?;
+                write_ubyte(w, displayed_skin_parts)?;
+            }
+            CPacket::PlayClientStatus { action_id } => {
+                write_varint(w, action_id)?;
+            }
+            CPacket::PlayPluginMessage { ref channel, ref data } => {
+                data::write_string(w, channel)?;
+                w.write_all(data)?;
+            }
+            CPacket::PlaySpectate { ref target_player } => {
+                data::write_uuid(w, target_player)?;
+            }
+            CPacket::PlayResourcePackStatus { ref hash, result } => {
+                data::write_string(w, hash)?;
+                write_varint(w, result)?;
+            }
+            CPacket::StatusRequest {} => (),
+            CPacket::StatusPing { payload } => {
+                write_long(w, payload)?;
+            }
+            CPacket::LoginLoginStart { ref name } => {
+                data::write_string(w, name)?;
+            }
+            CPacket::LoginEncryptionResponse {
+                ref shared_secret,
+                ref verify_token,
+            } => {
+                data::write_byte_array(w, shared_secret)?;
+                data::write_byte_array(w, verify_token)?;
+            }
+        }
+
+        Ok(())
+    }
 }
 
 #[derive(Debug)]
@@ -347,7 +632,7 @@ pub enum SPacket {
         dimension: i8,
         difficulty: u8,
         max_players: u8,
-        level_type: &'static str,
+        level_type: Cow<'static, str>,
         reduced_debug_info: bool,
     },
     PlayChatMessage {
@@ -375,7 +660,7 @@ pub enum SPacket {
         dimension: i32,
         difficulty: u8,
         gamemode: u8,
-        level_type: &'static str,
+        level_type: Cow<'static, str>,
     },
     PlayPlayerPositionAndLook {
         x: f64,
@@ -575,10 +860,12 @@ pub enum SPacket {
         disable_relative_volume: bool,
     },
     PlaySoundEffect {
-        sound: Sound,
+        name: Cow<'static, str>,
         effect_pos_x: f64,
         effect_pos_y: f64,
         effect_pos_z: f64,
+        volume: f32,
+        pitch: f32,
     },
     PlayParticle {
         particle_id: i32,
@@ -606,7 +893,7 @@ pub enum SPacket {
     },
     PlayOpenWindow {
         window_id: u8,
-        window_type: &'static str,
+        window_type: Cow<'static, str>,
         window_title: Chat,
         slot_num: u8,
         entity_id: i32,
@@ -655,7 +942,7 @@ pub enum SPacket {
         location: BlockPos,
     },
     PlayStatistics {
-        statistics: Vec<(&'static str, i32)>,
+        statistics: Vec<(String, i32)>,
     },
     PlayPlayerListItem {
         players: Vec<Arc<SPlayPlayerListItemData>>,
@@ -686,7 +973,7 @@ pub enum SPacket {
         data: SPlayTeamsData,
     },
     PlayPluginMessage {
-        channel: &'static str,
+        channel: Cow<'static, str>,
         data: Vec<u8>,
     },
     PlayDisconnect {
@@ -731,6 +1018,11 @@ pub enum SPacket {
     LoginDisconnect {
         reason: Chat,
     },
+    LoginEncryptionRequest {
+        server_id: String,
+        public_key: Vec<u8>,
+        verify_token: Vec<u8>,
+    },
     LoginLoginSuccess {
         uuid: String,
         username: String,
@@ -760,8 +1052,8 @@ impl SPacket {
             SPacket::PlaySpawnObject { .. } => 14,
             SPacket::PlaySpawnMob { .. } => 15,
             SPacket::PlaySpawnPainting { .. } => 16,
-            SPacket::PlaySpawnExperienceOrb { .. } => 18,
-            SPacket::PlayEntityVelocity { .. } => 19,
+            SPacket::PlaySpawnExperienceOrb { .. } => 17,
+            SPacket::PlayEntityVelocity { .. } => 18,
             SPacket::PlayDestroyEntities { .. } => 19,
             SPacket::PlayEntity { .. } => 20,
             SPacket::PlayEntityRelativeMove { .. } => 21,
@@ -820,6 +1112,7 @@ impl SPacket {
             SPacket::StatusResponse { .. } => 0,
             SPacket::StatusPong { .. } => 1,
             SPacket::LoginDisconnect { .. } => 0,
+            SPacket::LoginEncryptionRequest { .. } => 1,
             SPacket::LoginLoginSuccess { .. } => 2,
             SPacket::LoginSetCompression { .. } => 3,
         }
@@ -836,7 +1129,7 @@ impl SPacket {
                 dimension,
                 difficulty,
                 max_players,
-                level_type,
+                ref level_type,
                 reduced_debug_info,
             } => {
                 write_int(w, entity_id)?;
@@ -886,7 +1179,7 @@ impl SPacket {
                 dimension,
                 difficulty,
                 gamemode,
-                level_type,
+                ref level_type,
             } => {
                 write_int(w, dimension)?;
                 write_ubyte(w, difficulty)?;
@@ -971,13 +1264,14 @@ impl SPacket {
                 data::write_angle(w, pitch)?;
                 data::write_angle(w, yaw)?;
                 match *data {
-                    Some(ref x) => {
+                    // velocity is only sent with non-zero data.
+                    Some(ref x) if x.data != 0 => {
                         write_int(w, x.data)?;
                         write_ishort(w, x.velocity_x)?;
                         write_ishort(w, x.velocity_y)?;
                         write_ishort(w, x.velocity_z)?;
                     }
-                    None => write_int(w, 0)?,
+                    _ => write_int(w, 0)?,
                 }
             }
             SPacket::PlaySpawnMob {
@@ -1299,17 +1593,19 @@ impl SPacket {
                 write_bool(w, disable_relative_volume)?;
             }
             SPacket::PlaySoundEffect {
-                sound,
+                ref name,
                 effect_pos_x,
                 effect_pos_y,
                 effect_pos_z,
+                volume,
+                pitch,
             } => {
-                data::write_string(w, sound.name)?;
+                data::write_string(w, name)?;
                 write_int(w, (effect_pos_x * 8.0) as i32)?;
                 write_int(w, (effect_pos_y * 8.0) as i32)?;
                 write_int(w, (effect_pos_z * 8.0) as i32)?;
-                write_float(w, sound.volume)?;
-                write_ubyte(w, (sound.pitch * 63.0) as u8)?;
+                write_float(w, volume)?;
+                write_ubyte(w, (pitch * 63.0).round() as u8)?;
             }
             SPacket::PlayParticle {
                 particle_id,
@@ -1335,7 +1631,7 @@ impl SPacket {
                 write_float(w, particle_data)?;
                 write_int(w, particle_count)?;
                 for &d in data.iter() {
-                    write_int(w, d)?;
+                    write_varint(w, d)?;
                 }
             }
             SPacket::PlayChangeGameState { reason, value } => {
@@ -1357,7 +1653,7 @@ impl SPacket {
             }
             SPacket::PlayOpenWindow {
                 window_id,
-                window_type,
+                ref window_type,
                 ref window_title,
                 slot_num,
                 entity_id,
@@ -1431,8 +1727,9 @@ impl SPacket {
             } => {
                 write_varint(w, map_id)?;
                 write_byte(w, scale)?;
+                write_varint(w, icons.len() as i32)?;
                 for i in icons.iter() {
-                    let b = (i.direction << 4) | (i.icon_type & 0x0f);
+                    let b = (i.icon_type << 4) | (i.direction & 0x0f);
                     write_byte(w, b)?;
                     write_byte(w, i.x)?;
                     write_byte(w, i.z)?;
@@ -1559,14 +1856,16 @@ impl SPacket {
                         ref objective_value,
                         ref objective_type,
                     } => {
+                        write_byte(w, 0)?;
                         data::write_string(w, objective_value)?;
                         data::write_string(w, objective_type)?;
                     }
-                    SPlayScoreboardObjectiveData::RemoveScoreboard => (),
+                    SPlayScoreboardObjectiveData::RemoveScoreboard => write_byte(w, 1)?,
                     SPlayScoreboardObjectiveData::UpdateScoreboard {
                         ref objective_value,
                         ref objective_type,
                     } => {
+                        write_byte(w, 2)?;
                         data::write_string(w, objective_value)?;
                         data::write_string(w, objective_type)?;
                     }
@@ -1607,7 +1906,7 @@ impl SPacket {
                         ref prefix,
                         ref suffix,
                         friendly_fire,
-                        name_tag_visibility,
+                        ref name_tag_visibility,
                         color,
                         ref players,
                     } => {
@@ -1617,7 +1916,7 @@ impl SPacket {
                         data::write_string(w, suffix)?;
                         write_byte(w, friendly_fire)?;
                         data::write_string(w, name_tag_visibility)?;
-                        write_byte(w, color as i8)?;
+                        write_byte(w, color.to_color_index())?;
                         write_varint(w, players.len() as i32)?;
                         for p in players {
                             data::write_string(w, p)?;
@@ -1629,7 +1928,7 @@ impl SPacket {
                         ref prefix,
                         ref suffix,
                         friendly_fire,
-                        name_tag_visibility,
+                        ref name_tag_visibility,
                         color,
                     } => {
                         write_byte(w, 2)?;
@@ -1638,23 +1937,25 @@ impl SPacket {
                         data::write_string(w, suffix)?;
                         write_byte(w, friendly_fire)?;
                         data::write_string(w, name_tag_visibility)?;
-                        write_byte(w, color as i8)?;
+                        write_byte(w, color.to_color_index())?;
                     }
                     SPlayTeamsData::AddPlayers { ref players } => {
                         write_byte(w, 3)?;
+                        write_varint(w, players.len() as i32)?;
                         for p in players {
                             data::write_string(w, p)?;
                         }
                     }
                     SPlayTeamsData::RemovePlayers { ref players } => {
                         write_byte(w, 4)?;
+                        write_varint(w, players.len() as i32)?;
                         for p in players {
                             data::write_string(w, p)?;
                         }
                     }
                 }
             }
-            SPacket::PlayPluginMessage { channel, ref data } => {
+            SPacket::PlayPluginMessage { ref channel, ref data } => {
                 data::write_string(w, channel)?;
                 w.write_all(data)?;
             }
@@ -1733,7 +2034,7 @@ impl SPacket {
                     write_varint(w, warning_time)?;
                 }
                 SPlayWorldBorderData::SetWarningBlocks { warning_blocks } => {
-                    write_varint(w, 4)?;
+                    write_varint(w, 5)?;
                     write_varint(w, warning_blocks)?;
                 }
             },
@@ -1786,6 +2087,15 @@ impl SPacket {
             SPacket::LoginDisconnect { ref reason } => {
                 reason.write_proto(w)?;
             }
+            SPacket::LoginEncryptionRequest {
+                ref server_id,
+                ref public_key,
+                ref verify_token,
+            } => {
+                data::write_string(w, server_id)?;
+                data::write_byte_array(w, public_key)?;
+                data::write_byte_array(w, verify_token)?;
+            }
             SPacket::LoginLoginSuccess {
                 ref uuid,
                 ref username,
@@ -1800,31 +2110,705 @@ impl SPacket {
 
         Ok(())
     }
+
+    pub fn read(r: &mut &[u8], state: State, id: i32) -> Result<Self> {
+        match state {
+            State::Handshake => Err(Error::InvalidPacketId(id)),
+            State::Play => {
+                match id {
+                    0 => Ok(SPacket::PlayKeepAlive { id: read_varint(r)? }),
+                    1 => Ok(SPacket::PlayJoinGame {
+                        entity_id: read_int(r)?,
+                        gamemode: read_ubyte(r)?,
+                        dimension: read_byte(r)?,
+                        difficulty: read_ubyte(r)?,
+                        max_players: read_ubyte(r)?,
+                        level_type: data::read_string(r)?.into(),
+                        reduced_debug_info: read_bool(r)?,
+                    }),
+                    2 => Ok(SPacket::PlayChatMessage {
+                        message: Chat::read_proto(r)?,
+                        position: match read_byte(r)? {
+                            1 => text::ChatPos::SystemMessage,
+                            2 => text::ChatPos::AboveHotbar,
+                            _ => text::ChatPos::Normal,
+                        },
+                    }),
+                    3 => Ok(SPacket::PlayTimeUpdate {
+                        world_age: read_long(r)?,
+                        time_of_day: read_long(r)?,
+                    }),
+                    4 => Ok(SPacket::PlayEntityEquipment {
+                        entity_id: read_varint(r)?,
+                        slot: read_ishort(r)?,
+                        item: data::SlotData::read(r)?,
+                    }),
+                    5 => Ok(SPacket::PlaySpawnPosition { location: BlockPos::read_proto(r)? }),
+                    6 => Ok(SPacket::PlayUpdateHealth {
+                        health: read_float(r)?,
+                        food: read_varint(r)?,
+                        food_saturation: read_float(r)?,
+                    }),
+                    7 => Ok(SPacket::PlayRespawn {
+                        dimension: read_int(r)?,
+                        difficulty: read_ubyte(r)?,
+                        gamemode: read_ubyte(r)?,
+                        level_type: data::read_string(r)?.into(),
+                    }),
+                    8 => Ok(SPacket::PlayPlayerPositionAndLook {
+                        x: read_double(r)?,
+                        y: read_double(r)?,
+                        z: read_double(r)?,
+                        yaw: read_float(r)?,
+                        pitch: read_float(r)?,
+                        flags: read_byte(r)?,
+                    }),
+                    9 => Ok(SPacket::PlayHeldItemChange { slot: read_byte(r)? }),
+                    10 => Ok(SPacket::PlayUseBed {
+                        entity_id: read_varint(r)?,
+                        location: BlockPos::read_proto(r)?,
+                    }),
+                    11 => Ok(SPacket::PlayAnimation {
+                        entity_id: read_varint(r)?,
+                        animation: read_ubyte(r)?,
+                    }),
+                    12 => Ok(SPacket::PlaySpawnPlayer {
+                        entity_id: read_varint(r)?,
+                        uuid: data::read_uuid(r)?,
+                        x: fixed_point_to_double(read_int(r)?),
+                        y: fixed_point_to_double(read_int(r)?),
+                        z: fixed_point_to_double(read_int(r)?),
+                        yaw: data::read_angle(r)?,
+                        pitch: data::read_angle(r)?,
+                        current_item: read_ishort(r)?,
+                        metadata: EntityMetadata::read_proto(r)?,
+                    }),
+                    13 => Ok(SPacket::PlayCollectItem {
+                        collected_entity_id: read_varint(r)?,
+                        collector_entity_id: read_varint(r)?,
+                    }),
+                    14 => Ok(SPacket::PlaySpawnObject {
+                        entity_id: read_varint(r)?,
+                        object_type: read_byte(r)?,
+                        x: fixed_point_to_double(read_int(r)?),
+                        y: fixed_point_to_double(read_int(r)?),
+                        z: fixed_point_to_double(read_int(r)?),
+                        pitch: data::read_angle(r)?,
+                        yaw: data::read_angle(r)?,
+                        data: match read_int(r)? {
+                            0 => None,
+                            data => Some(SPlaySpawnObjectData {
+                                data,
+                                velocity_x: read_ishort(r)?,
+                                velocity_y: read_ishort(r)?,
+                                velocity_z: read_ishort(r)?,
+                            }),
+                        },
+                    }),
+                    15 => Ok(SPacket::PlaySpawnMob {
+                        entity_id: read_varint(r)?,
+                        mob_type: read_ubyte(r)?,
+                        x: fixed_point_to_double(read_int(r)?),
+                        y: fixed_point_to_double(read_int(r)?),
+                        z: fixed_point_to_double(read_int(r)?),
+                        yaw: data::read_angle(r)?,
+                        pitch: data::read_angle(r)?,
+                        head_pitch: data::read_angle(r)?,
+                        velocity_x: read_ishort(r)?,
+                        velocity_y: read_ishort(r)?,
+                        velocity_z: read_ishort(r)?,
+                        metadata: EntityMetadata::read_proto(r)?,
+                    }),
+                    16 => Ok(SPacket::PlaySpawnPainting {
+                        entity_id: read_varint(r)?,
+                        title: data::read_string(r)?,
+                        location: BlockPos::read_proto(r)?,
+                        direction: read_ubyte(r)?,
+                    }),
+                    17 => Ok(SPacket::PlaySpawnExperienceOrb {
+                        entity_id: read_varint(r)?,
+                        x: fixed_point_to_double(read_int(r)?),
+                        y: fixed_point_to_double(read_int(r)?),
+                        z: fixed_point_to_double(read_int(r)?),
+                        count: read_ishort(r)?,
+                    }),
+                    18 => Ok(SPacket::PlayEntityVelocity {
+                        entity_id: read_varint(r)?,
+                        velocity_x: read_ishort(r)?,
+                        velocity_y: read_ishort(r)?,
+                        velocity_z: read_ishort(r)?,
+                    }),
+                    19 => {
+                        let count = read_varint(r)?;
+                        let mut entity_ids = Vec::new();
+                        for _ in 0..count {
+                            entity_ids.push(read_varint(r)?);
+                        }
+                        Ok(SPacket::PlayDestroyEntities { entity_ids })
+                    }
+                    20 => Ok(SPacket::PlayEntity { entity_id: read_varint(r)? }),
+                    21 => Ok(SPacket::PlayEntityRelativeMove {
+                        entity_id: read_varint(r)?,
+                        delta_x: read_byte(r)?,
+                        delta_y: read_byte(r)?,
+                        delta_z: read_byte(r)?,
+                        on_ground: read_bool(r)?,
+                    }),
+                    22 => Ok(SPacket::PlayEntityLook {
+                        entity_id: read_varint(r)?,
+                        yaw: data::read_angle(r)?,
+                        pitch: data::read_angle(r)?,
+                        on_ground: read_bool(r)?,
+                    }),
+                    23 => Ok(SPacket::PlayEntityLookAndRelativeMove {
+                        entity_id: read_varint(r)?,
+                        delta_x: read_byte(r)?,
+                        delta_y: read_byte(r)?,
+                        delta_z: read_byte(r)?,
+                        yaw: data::read_angle(r)?,
+                        pitch: data::read_angle(r)?,
+                        on_ground: read_bool(r)?,
+                    }),
+                    24 => Ok(SPacket::PlayEntityTeleport {
+                        entity_id: read_varint(r)?,
+                        x: fixed_point_to_double(read_int(r)?),
+                        y: fixed_point_to_double(read_int(r)?),
+                        z: fixed_point_to_double(read_int(r)?),
+                        yaw: data::read_angle(r)?,
+                        pitch: data::read_angle(r)?,
+                        on_ground: read_bool(r)?,
+                    }),
+                    25 => Ok(SPacket::PlayEntityHeadLook {
+                        entity_id: read_varint(r)?,
+                        head_yaw: data::read_angle(r)?,
+                    }),
+                    26 => Ok(SPacket::PlayEntityStatus {
+                        entity_id: read_int(r)?,
+                        entity_status: read_byte(r)?,
+                    }),
+                    27 => Ok(SPacket::PlayAttachEntity {
+                        entity_id: read_int(r)?,
+                        vehicle_id: read_int(r)?,
+                        leash: read_bool(r)?,
+                    }),
+                    28 => Ok(SPacket::PlayEntityMetadata {
+                        entity_id: read_varint(r)?,
+                        metadata: EntityMetadata::read_proto(r)?,
+                    }),
+                    29 => Ok(SPacket::PlayEntityEffect {
+                        entity_id: read_varint(r)?,
+                        effect_id: read_byte(r)?,
+                        amplifier: read_byte(r)?,
+                        duration: read_varint(r)?,
+                        hide_particles: read_bool(r)?,
+                    }),
+                    30 => Ok(SPacket::PlayRemoveEntityEffect {
+                        entity_id: read_varint(r)?,
+                        effect_id: read_byte(r)?,
+                    }),
+                    31 => Ok(SPacket::PlaySetExperience {
+                        experience_bar: read_float(r)?,
+                        level: read_varint(r)?,
+                        total_experience: read_varint(r)?,
+                    }),
+                    32 => {
+                        let entity_id = read_varint(r)?;
+                        let count = read_int(r)?;
+                        let mut properties = Vec::new();
+                        for _ in 0..count {
+                            let key = data::read_string(r)?;
+                            let value = read_double(r)?;
+                            let modifier_count = read_varint(r)?;
+                            let mut modifiers = Vec::new();
+                            for _ in 0..modifier_count {
+                                modifiers.push(data::ModifierData::read(r)?);
+                            }
+                            properties.push(SPlayEntityPropertiesData { key, value, modifiers });
+                        }
+                        Ok(SPacket::PlayEntityProperties { entity_id, properties })
+                    }
+                    33 => {
+                        let chunk_x = read_int(r)?;
+                        let chunk_z = read_int(r)?;
+                        let ground_up_continuous = read_bool(r)?;
+                        let primary_bit_mask = read_ushort(r)?;
+                        let size = read_varint(r)?;
+                        let sections = primary_bit_mask.count_ones() as i32;
+                        // sky light is only sent in the overworld, so it has to be told apart by the size.
+                        let without_sky_light = sections * 10240 + if ground_up_continuous { 256 } else { 0 };
+                        let sky_light = if size == without_sky_light + sections * 2048 {
+                            true
+                        } else if size == without_sky_light {
+                            false
+                        } else {
+                            return Err(Error::InvalidChunkDataSize { size, primary_bit_mask });
+                        };
+                        let sections = sections as usize;
+                        let data = if ground_up_continuous {
+                            SPlayChunkDataData::GroundUpContinuous(data::GroundUpContinuous::read(r, sections, sky_light)?)
+                        } else {
+                            SPlayChunkDataData::GroundUpNonContinuous(data::GroundUpNonContinuous::read(r, sections, sky_light)?)
+                        };
+                        Ok(SPacket::PlayChunkData { chunk_x, chunk_z, primary_bit_mask, data })
+                    }
+                    34 => {
+                        let chunk_x = read_int(r)?;
+                        let chunk_z = read_int(r)?;
+                        let count = read_varint(r)?;
+                        let mut data = Vec::new();
+                        for _ in 0..count {
+                            let b = read_ubyte(r)?;
+                            data.push(SPlayMultiBlockChangeData {
+                                x: b >> 4,
+                                z: b & 0x0f,
+                                y: read_ubyte(r)?,
+                                block_id: read_varint(r)?,
+                            });
+                        }
+                        Ok(SPacket::PlayMultiBlockChange { chunk_x, chunk_z, data })
+                    }
+                    35 => Ok(SPacket::PlayBlockChange {
+                        location: BlockPos::read_proto(r)?,
+                        block_id: read_varint(r)?,
+                    }),
+                    36 => Ok(SPacket::PlayBlockAction {
+                        location: BlockPos::read_proto(r)?,
+                        byte1: read_ubyte(r)?,
+                        byte2: read_ubyte(r)?,
+                        block_type: read_varint(r)?,
+                    }),
+                    37 => Ok(SPacket::PlayBlockBreakAnimation {
+                        entity_id: read_varint(r)?,
+                        location: BlockPos::read_proto(r)?,
+                        destroy_stage: read_byte(r)?,
+                    }),
+                    38 => {
+                        let sky_light_sent = read_bool(r)?;
+                        let count = read_varint(r)?;
+                        let mut meta = Vec::new();
+                        for _ in 0..count {
+                            meta.push((read_int(r)?, read_int(r)?, read_ushort(r)?));
+                        }
+                        let mut chunks = Vec::new();
+                        for (chunk_x, chunk_z, primary_bit_mask) in meta {
+                            let chunk = data::GroundUpContinuous::read(r, primary_bit_mask.count_ones() as usize, sky_light_sent)?;
+                            chunks.push(SPlayMapChunkBulkData { chunk_x, chunk_z, primary_bit_mask, chunk });
+                        }
+                        Ok(SPacket::PlayMapChunkBulk { sky_light_sent, chunks })
+                    }
+                    39 => {
+                        let x = read_float(r)?;
+                        let y = read_float(r)?;
+                        let z = read_float(r)?;
+                        let radius = read_float(r)?;
+                        let count = read_int(r)?;
+                        let mut records = Vec::new();
+                        for _ in 0..count {
+                            records.push((read_byte(r)?, read_byte(r)?, read_byte(r)?));
+                        }
+                        Ok(SPacket::PlayExplosion {
+                            x,
+                            y,
+                            z,
+                            radius,
+                            records,
+                            player_motion_x: read_float(r)?,
+                            player_motion_y: read_float(r)?,
+                            player_motion_z: read_float(r)?,
+                        })
+                    }
+                    40 => Ok(SPacket::PlayEffect {
+                        effect_id: read_int(r)?,
+                        location: BlockPos::read_proto(r)?,
+                        data: read_int(r)?,
+                        disable_relative_volume: read_bool(r)?,
+                    }),
+                    41 => Ok(SPacket::PlaySoundEffect {
+                        name: data::read_string(r)?.into(),
+                        effect_pos_x: f64::from(read_int(r)?) / 8.0,
+                        effect_pos_y: f64::from(read_int(r)?) / 8.0,
+                        effect_pos_z: f64::from(read_int(r)?) / 8.0,
+                        volume: read_float(r)?,
+                        pitch: f32::from(read_ubyte(r)?) / 63.0,
+                    }),
+                    42 => {
+                        let particle_id = read_int(r)?;
+                        let long_distance = read_bool(r)?;
+                        let x = read_float(r)?;
+                        let y = read_float(r)?;
+                        let z = read_float(r)?;
+                        let offset_x = read_float(r)?;
+                        let offset_y = read_float(r)?;
+                        let offset_z = read_float(r)?;
+                        let particle_data = read_float(r)?;
+                        let particle_count = read_int(r)?;
+                        // iconcrack has an item id and damage, blockcrack and blockdust a block state.
+                        let data_count = match particle_id {
+                            36 => 2,
+                            37 | 38 => 1,
+                            _ => 0,
+                        };
+                        let mut data = Vec::new();
+                        for _ in 0..data_count {
+                            data.push(read_varint(r)?);
+                        }
+                        Ok(SPacket::PlayParticle {
+                            particle_id,
+                            long_distance,
+                            x,
+                            y,
+                            z,
+                            offset_x,
+                            offset_y,
+                            offset_z,
+                            particle_data,
+                            particle_count,
+                            data,
+                        })
+                    }
+                    43 => Ok(SPacket::PlayChangeGameState {
+                        reason: read_ubyte(r)?,
+                        value: read_float(r)?,
+                    }),
+                    44 => Ok(SPacket::PlaySpawnGlobalEntity {
+                        entity_id: read_varint(r)?,
+                        entity_type: read_byte(r)?,
+                        x: fixed_point_to_double(read_int(r)?),
+                        y: fixed_point_to_double(read_int(r)?),
+                        z: fixed_point_to_double(read_int(r)?),
+                    }),
+                    45 => {
+                        let window_id = read_ubyte(r)?;
+                        let window_type = data::read_string(r)?;
+                        let window_title = Chat::read_proto(r)?;
+                        let slot_num = read_ubyte(r)?;
+                        let entity_id = if window_type == "EntityHorse" { read_int(r)? } else { 0 };
+                        Ok(SPacket::PlayOpenWindow {
+                            window_id,
+                            window_type: window_type.into(),
+                            window_title,
+                            slot_num,
+                            entity_id,
+                        })
+                    }
+                    46 => Ok(SPacket::PlayCloseWindow { window_id: read_ubyte(r)? }),
+                    47 => Ok(SPacket::PlaySetSlot {
+                        window_id: read_byte(r)?,
+                        slot: read_ishort(r)?,
+                        slot_data: data::SlotData::read(r)?,
+                    }),
+                    48 => {
+                        let window_id = read_ubyte(r)?;
+                        let count = read_ishort(r)?;
+                        let mut slots = Vec::new();
+                        for _ in 0..count {
+                            slots.push(data::SlotData::read(r)?);
+                        }
+                        Ok(SPacket::PlayWindowItems { window_id, slots })
+                    }
+                    49 => Ok(SPacket::PlayWindowProperty {
+                        window_id: read_ubyte(r)?,
+                        property: read_ishort(r)?,
+                        value: read_ishort(r)?,
+                    }),
+                    50 => Ok(SPacket::PlayConfirmTransaction {
+                        window_id: read_byte(r)?,
+                        action_num: read_ishort(r)?,
+                        accepted: read_bool(r)?,
+                    }),
+                    51 => Ok(SPacket::PlayUpdateSign {
+                        location: BlockPos::read_proto(r)?,
+                        line1: Chat::read_proto(r)?,
+                        line2: Chat::read_proto(r)?,
+                        line3: Chat::read_proto(r)?,
+                        line4: Chat::read_proto(r)?,
+                    }),
+                    52 => {
+                        let map_id = read_varint(r)?;
+                        let scale = read_byte(r)?;
+                        let count = read_varint(r)?;
+                        let mut icons = Vec::new();
+                        for _ in 0..count {
+                            let b = read_byte(r)?;
+                            icons.push(SPlayMapIcon {
+                                direction: b & 0x0f,
+                                icon_type: (b >> 4) & 0x0f,
+                                x: read_byte(r)?,
+                                z: read_byte(r)?,
+                            });
+                        }
+                        let columns = read_byte(r)?;
+                        let data = if columns == 0 {
+                            None
+                        } else {
+                            Some(SPlayMapData {
+                                columns,
+                                rows: read_byte(r)?,
+                                x: read_byte(r)?,
+                                z: read_byte(r)?,
+                                data: data::read_byte_array(r)?,
+                            })
+                        };
+                        Ok(SPacket::PlayMap { map_id, scale, icons, data })
+                    }
+                    53 => Ok(SPacket::PlayUpdateBlockEntity {
+                        location: BlockPos::read_proto(r)?,
+                        action: read_ubyte(r)?,
+                        nbt_data: Nbt::read(r)?,
+                    }),
+                    54 => Ok(SPacket::PlayOpenSignEditor { location: BlockPos::read_proto(r)? }),
+                    55 => {
+                        let count = read_varint(r)?;
+                        let mut statistics = Vec::new();
+                        for _ in 0..count {
+                            statistics.push((data::read_string(r)?, read_varint(r)?));
+                        }
+                        Ok(SPacket::PlayStatistics { statistics })
+                    }
+                    56 => {
+                        let action = read_varint(r)?;
+                        let count = read_varint(r)?;
+                        let mut players = Vec::new();
+                        for _ in 0..count {
+                            let uuid = data::read_uuid(r)?;
+                            let action = match action {
+                                0 => {
+                                    let name = data::read_string(r)?;
+                                    let property_count = read_varint(r)?;
+                                    let mut properties = Vec::new();
+                                    for _ in 0..property_count {
+                                        properties.push(SPlayerListItemProperty {
+                                            name: data::read_string(r)?,
+                                            value: data::read_string(r)?,
+                                            signature: if read_bool(r)? { Some(data::read_string(r)?) } else { None },
+                                        });
+                                    }
+                                    SPlayPlayerListItemDataAction::AddPlayer {
+                                        name,
+                                        properties,
+                                        gamemode: read_varint(r)?,
+                                        ping: read_varint(r)?,
+                                        display_name: if read_bool(r)? { Some(Chat::read_proto(r)?) } else { None },
+                                    }
+                                }
+                                1 => SPlayPlayerListItemDataAction::UpdateGamemode { gamemode: read_varint(r)? },
+                                2 => SPlayPlayerListItemDataAction::UpdateLatency { ping: read_varint(r)? },
+                                3 => SPlayPlayerListItemDataAction::UpdateDisplayName {
+                                    display_name: if read_bool(r)? { Some(Chat::read_proto(r)?) } else { None },
+                                },
+                                4 => SPlayPlayerListItemDataAction::RemovePlayer,
+                                _ => return Err(Error::InvalidPacketId(id)),
+                            };
+                            players.push(Arc::new(SPlayPlayerListItemData { uuid, action }));
+                        }
+                        Ok(SPacket::PlayPlayerListItem { players })
+                    }
+                    57 => Ok(SPacket::PlayPlayerAbilities {
+                        flags: read_byte(r)?,
+                        flying_speed: read_float(r)?,
+                        field_of_view_modifier: read_float(r)?,
+                    }),
+                    58 => {
+                        let count = read_varint(r)?;
+                        let mut matches = Vec::new();
+                        for _ in 0..count {
+                            matches.push(data::read_string(r)?);
+                        }
+                        Ok(SPacket::PlayTabComplete { matches })
+                    }
+                    59 => {
+                        let objective_name = data::read_string(r)?;
+                        let data = match read_byte(r)? {
+                            0 => SPlayScoreboardObjectiveData::CreateScoreboard {
+                                objective_value: data::read_string(r)?,
+                                objective_type: data::read_string(r)?,
+                            },
+                            1 => SPlayScoreboardObjectiveData::RemoveScoreboard,
+                            _ => SPlayScoreboardObjectiveData::UpdateScoreboard {
+                                objective_value: data::read_string(r)?,
+                                objective_type: data::read_string(r)?,
+                            },
+                        };
+                        Ok(SPacket::PlayScoreboardObjective { objective_name, data })
+                    }
+                    60 => {
+                        let score_name = data::read_string(r)?;
+                        let action = read_byte(r)?;
+                        let objective_name = data::read_string(r)?;
+                        let data = match action {
+                            0 => SPlayUpdateScoreData::CreateOrUpdateItem { value: read_varint(r)? },
+                            _ => SPlayUpdateScoreData::RemoveItem,
+                        };
+                        Ok(SPacket::PlayUpdateScore { score_name, objective_name, data })
+                    }
+                    61 => Ok(SPacket::PlayDisplayScoreboard {
+                        position: read_byte(r)?,
+                        score_name: data::read_string(r)?,
+                    }),
+                    62 => {
+                        let team_name = data::read_string(r)?;
+                        let read_players = |r: &mut &[u8]| -> Result<Vec<String>> {
+                            let count = read_varint(r)?;
+                            let mut players = Vec::new();
+                            for _ in 0..count {
+                                players.push(data::read_string(r)?);
+                            }
+                            Ok(players)
+                        };
+                        let data = match read_byte(r)? {
+                            0 => SPlayTeamsData::CreateTeam {
+                                display_name: data::read_string(r)?,
+                                prefix: data::read_string(r)?,
+                                suffix: data::read_string(r)?,
+                                friendly_fire: read_byte(r)?,
+                                name_tag_visibility: data::read_string(r)?.into(),
+                                color: text::Code::from_color_index(read_byte(r)?),
+                                players: read_players(r)?,
+                            },
+                            1 => SPlayTeamsData::RemoveTeam,
+                            2 => SPlayTeamsData::UpdateTeam {
+                                display_name: data::read_string(r)?,
+                                prefix: data::read_string(r)?,
+                                suffix: data::read_string(r)?,
+                                friendly_fire: read_byte(r)?,
+                                name_tag_visibility: data::read_string(r)?.into(),
+                                color: text::Code::from_color_index(read_byte(r)?),
+                            },
+                            3 => SPlayTeamsData::AddPlayers { players: read_players(r)? },
+                            _ => SPlayTeamsData::RemovePlayers { players: read_players(r)? },
+                        };
+                        Ok(SPacket::PlayTeams { team_name, data })
+                    }
+                    63 => Ok(SPacket::PlayPluginMessage {
+                        channel: data::read_string(r)?.into(),
+                        data: {
+                            let mut vec = vec![0u8; r.len()];
+                            r.read_exact(&mut vec)?;
+                            vec
+                        }
+                    }),
+                    64 => Ok(SPacket::PlayDisconnect { reason: Chat::read_proto(r)? }),
+                    65 => Ok(SPacket::PlayServerDifficulty { difficulty: read_ubyte(r)? }),
+                    66 => Ok(SPacket::PlayCombatEvent {
+                        data: match read_varint(r)? {
+                            0 => SPlayCombatEventData::EnterCombat,
+                            1 => SPlayCombatEventData::EndCombat {
+                                duration: read_varint(r)?,
+                                entity_id: read_varint(r)?,
+                            },
+                            _ => SPlayCombatEventData::EntityDead {
+                                player_id: read_varint(r)?,
+                                entity_id: read_int(r)?,
+                                message: data::read_string(r)?,
+                            },
+                        }
+                    }),
+                    67 => Ok(SPacket::PlayCamera { camera_id: read_varint(r)? }),
+                    68 => Ok(SPacket::PlayWorldBorder {
+                        data: match read_varint(r)? {
+                            0 => SPlayWorldBorderData::SetSize { radius: read_double(r)? },
+                            1 => SPlayWorldBorderData::LerpSize {
+                                old_radius: read_double(r)?,
+                                new_radius: read_double(r)?,
+                                speed: read_varlong(r)?,
+                            },
+                            2 => SPlayWorldBorderData::SetCenter {
+                                x: read_double(r)?,
+                                z: read_double(r)?,
+                            },
+                            3 => SPlayWorldBorderData::Initialize {
+                                x: read_double(r)?,
+                                z: read_double(r)?,
+                                old_radius: read_double(r)?,
+                                new_radius: read_double(r)?,
+                                speed: read_varlong(r)?,
+                                portal_teleport_boundary: read_varint(r)?,
+                                warning_time: read_varint(r)?,
+                                warning_blocks: read_varint(r)?,
+                            },
+                            4 => SPlayWorldBorderData::SetWarningTime { warning_time: read_varint(r)? },
+                            _ => SPlayWorldBorderData::SetWarningBlocks { warning_blocks: read_varint(r)? },
+                        }
+                    }),
+                    69 => Ok(SPacket::PlayTitle {
+                        data: match read_varint(r)? {
+                            0 => SPlayTitleData::SetTitle { text: Chat::read_proto(r)? },
+                            1 => SPlayTitleData::SetSubtitle { subtitle: Chat::read_proto(r)? },
+                            2 => SPlayTitleData::SetTimesAndDisplay {
+                                fade_in: read_int(r)?,
+                                stay: read_int(r)?,
+                                fade_out: read_int(r)?,
+                            },
+                            3 => SPlayTitleData::Hide,
+                            _ => SPlayTitleData::Reset,
+                        }
+                    }),
+                    70 => Ok(SPacket::PlaySetCompression { threshold: read_varint(r)? }),
+                    71 => Ok(SPacket::PlayPlayerListHeaderAndFooter {
+                        header: Chat::read_proto(r)?,
+                        footer: Chat::read_proto(r)?,
+                    }),
+                    72 => Ok(SPacket::PlayResourcePackSend {
+                        url: data::read_string(r)?,
+                        hash: data::read_string(r)?,
+                    }),
+                    73 => Ok(SPacket::PlayUpdateEntityNBT {
+                        entity_id: read_varint(r)?,
+                        tag: Nbt::read(r)?,
+                    }),
+                    _ => Err(Error::InvalidPacketId(id))
+                }
+            },
+            State::Status => {
+                match id {
+                    0 => Ok(SPacket::StatusResponse { data: serde_json::from_str(&data::read_string(r)?)? }),
+                    1 => Ok(SPacket::StatusPong { payload: read_long(r)? }),
+                    _ => Err(Error::InvalidPacketId(id))
+                }
+            },
+            State::Login => {
+                match id {
+                    0 => Ok(SPacket::LoginDisconnect { reason: Chat::read_proto(r)? }),
+                    1 => Ok(SPacket::LoginEncryptionRequest {
+                        server_id: data::read_string(r)?,
+                        public_key: data::read_byte_array(r)?,
+                        verify_token: data::read_byte_array(r)?,
+                    }),
+                    2 => Ok(SPacket::LoginLoginSuccess {
+                        uuid: data::read_string(r)?,
+                        username: data::read_string(r)?,
+                    }),
+                    3 => Ok(SPacket::LoginSetCompression { threshold: read_varint(r)? }),
+                    _ => Err(Error::InvalidPacketId(id))
+                }
+            }
+        }
+    }
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct SStatusResponseData {
     pub version: SStatusResponseVersion,
     pub players: SStatusResponsePlayers,
     pub description: Chat,
-    #[serde(skip_serializing_if = "Option::is_none")] pub favicon: Arc<Option<String>>,
+    #[serde(skip_serializing_if = "Option::is_none", default)] pub favicon: Arc<Option<String>>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct SStatusResponseVersion {
-    pub name: &'static str,
+    pub name: Cow<'static, str>,
     pub protocol: i32,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct SStatusResponsePlayers {
     pub max: i32,
     pub online: i32,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub sample: Option<Arc<Mutex<HashSet<SStatusResponsePlayer>>>>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub sample: Option<Vec<SStatusResponsePlayer>>,
 }
 
-#[derive(Debug, Serialize, Hash, Clone, PartialEq, Eq)]
+#[derive(Debug, Serialize, Deserialize, Hash, Clone, PartialEq, Eq)]
 pub struct SStatusResponsePlayer {
     pub name: String,
     pub id: String,
@@ -1900,7 +2884,7 @@ pub enum SPlayTeamsData {
         prefix: String,
         suffix: String,
         friendly_fire: i8,
-        name_tag_visibility: &'static str,
+        name_tag_visibility: Cow<'static, str>,
         color: text::Code,
         players: Vec<String>,
     },
@@ -1910,7 +2894,7 @@ pub enum SPlayTeamsData {
         prefix: String,
         suffix: String,
         friendly_fire: i8,
-        name_tag_visibility: &'static str,
+        name_tag_visibility: Cow<'static, str>,
         color: text::Code,
     },
     AddPlayers {
@@ -2027,3 +3011,316 @@ pub struct SPlaySpawnObjectData {
     pub velocity_y: i16,
     pub velocity_z: i16,
 }
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use crate::entity::metadata::MetadataEntry;
+    use crate::nbt::{Compound, Tag};
+    use crate::world::chunk::CHUNK_SECTION_BLOCK_COUNT;
+
+    use super::*;
+
+    fn chat(s: &str) -> Chat {
+        Chat::from(text::parse_legacy(s))
+    }
+
+    fn item() -> data::SlotData {
+        let mut tag = HashMap::new();
+        tag.insert("Damage".to_string(), Tag::Int(3));
+        data::SlotData::Some { id: 276, item_count: 1, item_damage: 3, tag: Nbt::Some(String::new(), Compound(tag)) }
+    }
+
+    fn metadata() -> EntityMetadata {
+        let mut metadata = EntityMetadata::new();
+        metadata.insert(0, MetadataEntry::Byte(2));
+        metadata.insert(2, MetadataEntry::String("name".into()));
+        metadata.insert(6, MetadataEntry::Float(20.0));
+        metadata.insert(10, MetadataEntry::Slot(item()));
+        metadata.insert(12, MetadataEntry::Pos { x: 1, y: 2, z: 3 });
+        metadata
+    }
+
+    fn sections(count: usize, sky_light: bool) -> data::GroundUpNonContinuous {
+        let sections = (0..count)
+            .map(|i| data::ChunkSection {
+                blocks: [i as u8; CHUNK_SECTION_BLOCK_COUNT * 2],
+                block_light: [0xf0; CHUNK_SECTION_BLOCK_COUNT / 2],
+                sky_light: if sky_light { Some([0x0f; CHUNK_SECTION_BLOCK_COUNT / 2]) } else { None },
+            })
+            .collect();
+        data::GroundUpNonContinuous { sections }
+    }
+
+    fn chunk(count: usize, sky_light: bool) -> data::GroundUpContinuous {
+        data::GroundUpContinuous { sections: sections(count, sky_light), biome_array: Box::new([[1; 16]; 16]) }
+    }
+
+    fn pos() -> BlockPos {
+        BlockPos::new(-100, 64, 3000)
+    }
+
+    /// writes the packet, reads it back and checks that writing that gives the same bytes.
+    fn round_trip_s(state: State, packet: SPacket) -> i32 {
+        let mut buf = Vec::new();
+        packet.write(&mut buf).unwrap();
+        let mut r = &buf[..];
+        let read = SPacket::read(&mut r, state, packet.id()).unwrap_or_else(|e| panic!("{:?}: {}", packet, e));
+        assert!(r.is_empty(), "{} bytes left after {:?}", r.len(), read);
+        assert_eq!(read.id(), packet.id());
+        let mut again = Vec::new();
+        read.write(&mut again).unwrap();
+        assert!(buf == again, "{:?} was read as {:?}", packet, read);
+        packet.id()
+    }
+
+    fn round_trip_c(state: State, packet: CPacket) -> i32 {
+        let mut buf = Vec::new();
+        packet.write(&mut buf).unwrap();
+        let mut r = &buf[..];
+        let read = CPacket::read(&mut r, state, packet.id()).unwrap_or_else(|e| panic!("{:?}: {}", packet, e));
+        assert!(r.is_empty(), "{} bytes left after {:?}", r.len(), read);
+        assert_eq!(read.id(), packet.id());
+        let mut again = Vec::new();
+        read.write(&mut again).unwrap();
+        assert!(buf == again, "{:?} was read as {:?}", packet, read);
+        packet.id()
+    }
+
+    fn assert_all_ids(mut ids: Vec<i32>, count: i32) {
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids, (0..count).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn server_play_round_trip() {
+        let uuid = Uuid::parse_str("069a79f4-44e9-4726-a5be-fca90e38aaf5").unwrap();
+        let packets = vec![
+            SPacket::PlayKeepAlive { id: 42 },
+            SPacket::PlayJoinGame { entity_id: 1, gamemode: 1, dimension: -1, difficulty: 2, max_players: 20, level_type: "flat".into(), reduced_debug_info: false },
+            SPacket::PlayChatMessage { message: chat("hi"), position: text::ChatPos::AboveHotbar },
+            SPacket::PlayTimeUpdate { world_age: 100, time_of_day: -6000 },
+            SPacket::PlayEntityEquipment { entity_id: 5, slot: 0, item: item() },
+            SPacket::PlaySpawnPosition { location: pos() },
+            SPacket::PlayUpdateHealth { health: 19.5, food: 20, food_saturation: 5.0 },
+            SPacket::PlayRespawn { dimension: 1, difficulty: 1, gamemode: 0, level_type: "default".into() },
+            SPacket::PlayPlayerPositionAndLook { x: 0.5, y: 64.0, z: -0.5, yaw: 90.0, pitch: 10.0, flags: 0x1f },
+            SPacket::PlayHeldItemChange { slot: 8 },
+            SPacket::PlayUseBed { entity_id: 3, location: pos() },
+            SPacket::PlayAnimation { entity_id: 3, animation: 1 },
+            SPacket::PlaySpawnPlayer { entity_id: 3, uuid, x: 1.5, y: 65.0, z: -2.25, yaw: 180.0, pitch: -45.0, current_item: 0, metadata: metadata() },
+            SPacket::PlayCollectItem { collected_entity_id: 4, collector_entity_id: 3 },
+            SPacket::PlaySpawnObject { entity_id: 4, object_type: 2, x: 1.0, y: 2.0, z: 3.0, pitch: 0.0, yaw: 90.0, data: Some(SPlaySpawnObjectData { data: 1, velocity_x: 10, velocity_y: -20, velocity_z: 30 }) },
+            SPacket::PlaySpawnMob { entity_id: 5, mob_type: 50, x: 1.0, y: 2.0, z: 3.0, yaw: 0.0, pitch: 0.0, head_pitch: 0.0, velocity_x: 0, velocity_y: 0, velocity_z: 0, metadata: metadata() },
+            SPacket::PlaySpawnPainting { entity_id: 6, title: "Kebab".into(), location: pos(), direction: 2 },
+            SPacket::PlaySpawnExperienceOrb { entity_id: 7, x: 1.0, y: 2.0, z: 3.0, count: 5 },
+            SPacket::PlayEntityVelocity { entity_id: 7, velocity_x: 1, velocity_y: 2, velocity_z: 3 },
+            SPacket::PlayDestroyEntities { entity_ids: vec![4, 5, 6] },
+            SPacket::PlayEntity { entity_id: 3 },
+            SPacket::PlayEntityRelativeMove { entity_id: 3, delta_x: 1, delta_y: -2, delta_z: 3, on_ground: true },
+            SPacket::PlayEntityLook { entity_id: 3, yaw: 45.0, pitch: 10.0, on_ground: true },
+            SPacket::PlayEntityLookAndRelativeMove { entity_id: 3, delta_x: 1, delta_y: 0, delta_z: -1, yaw: 270.0, pitch: 0.0, on_ground: false },
+            SPacket::PlayEntityTeleport { entity_id: 3, x: 100.0, y: 70.0, z: -100.0, yaw: 0.0, pitch: 0.0, on_ground: true },
+            SPacket::PlayEntityHeadLook { entity_id: 3, head_yaw: 359.0 },
+            SPacket::PlayEntityStatus { entity_id: 3, entity_status: 2 },
+            SPacket::PlayAttachEntity { entity_id: 3, vehicle_id: 8, leash: false },
+            SPacket::PlayEntityMetadata { entity_id: 3, metadata: metadata() },
+            SPacket::PlayEntityEffect { entity_id: 3, effect_id: 1, amplifier: 2, duration: 600, hide_particles: true },
+            SPacket::PlayRemoveEntityEffect { entity_id: 3, effect_id: 1 },
+            SPacket::PlaySetExperience { experience_bar: 0.5, level: 3, total_experience: 30 },
+            SPacket::PlayEntityProperties {
+                entity_id: 3,
+                properties: vec![SPlayEntityPropertiesData {
+                    key: "generic.movementSpeed".into(),
+                    value: 0.1,
+                    modifiers: vec![data::ModifierData { uuid, amount: 0.3, operation: 2 }],
+                }],
+            },
+            SPacket::PlayChunkData { chunk_x: 1, chunk_z: -1, primary_bit_mask: 0b101, data: SPlayChunkDataData::GroundUpContinuous(chunk(2, true)) },
+            SPacket::PlayMultiBlockChange { chunk_x: 1, chunk_z: -1, data: vec![SPlayMultiBlockChangeData { x: 15, z: 3, y: 200, block_id: 1 << 4 }] },
+            SPacket::PlayBlockChange { location: pos(), block_id: 2 << 4 },
+            SPacket::PlayBlockAction { location: pos(), byte1: 1, byte2: 2, block_type: 54 },
+            SPacket::PlayBlockBreakAnimation { entity_id: 3, location: pos(), destroy_stage: 5 },
+            SPacket::PlayMapChunkBulk {
+                sky_light_sent: true,
+                chunks: vec![
+                    SPlayMapChunkBulkData { chunk_x: 0, chunk_z: 0, primary_bit_mask: 1, chunk: chunk(1, true) },
+                    SPlayMapChunkBulkData { chunk_x: 0, chunk_z: 1, primary_bit_mask: 0b110, chunk: chunk(2, true) },
+                ],
+            },
+            SPacket::PlayExplosion { x: 1.0, y: 2.0, z: 3.0, radius: 4.0, records: vec![(1, 0, -1)], player_motion_x: 0.1, player_motion_y: 0.2, player_motion_z: 0.3 },
+            SPacket::PlayEffect { effect_id: 2001, location: pos(), data: 1, disable_relative_volume: false },
+            SPacket::PlaySoundEffect { name: "dig.stone".into(), effect_pos_x: 0.5, effect_pos_y: 64.5, effect_pos_z: -0.5, volume: 1.0, pitch: 0.8 },
+            SPacket::PlayParticle { particle_id: 36, long_distance: true, x: 1.0, y: 2.0, z: 3.0, offset_x: 0.0, offset_y: 0.5, offset_z: 0.0, particle_data: 0.1, particle_count: 10, data: vec![276, 0] },
+            SPacket::PlayChangeGameState { reason: 3, value: 1.0 },
+            SPacket::PlaySpawnGlobalEntity { entity_id: 9, entity_type: 1, x: 1.0, y: 2.0, z: 3.0 },
+            SPacket::PlayOpenWindow { window_id: 1, window_type: "EntityHorse".into(), window_title: chat("Horse"), slot_num: 2, entity_id: 10 },
+            SPacket::PlayCloseWindow { window_id: 1 },
+            SPacket::PlaySetSlot { window_id: -1, slot: -1, slot_data: item() },
+            SPacket::PlayWindowItems { window_id: 0, slots: vec![data::SlotData::Empty, item()] },
+            SPacket::PlayWindowProperty { window_id: 1, property: 2, value: 200 },
+            SPacket::PlayConfirmTransaction { window_id: 1, action_num: 12, accepted: false },
+            SPacket::PlayUpdateSign { location: pos(), line1: chat("a"), line2: chat("b"), line3: chat(""), line4: chat("d") },
+            SPacket::PlayMap {
+                map_id: 1,
+                scale: 2,
+                icons: vec![SPlayMapIcon { direction: 15, icon_type: 1, x: -10, z: 20 }],
+                data: Some(SPlayMapData { columns: 2, rows: 1, x: 0, z: 0, data: vec![1, 2] }),
+            },
+            SPacket::PlayUpdateBlockEntity { location: pos(), action: 1, nbt_data: Nbt::Empty },
+            SPacket::PlayOpenSignEditor { location: pos() },
+            SPacket::PlayStatistics { statistics: vec![("stat.jump".into(), 3)] },
+            SPacket::PlayPlayerListItem {
+                players: vec![Arc::new(SPlayPlayerListItemData {
+                    uuid,
+                    action: SPlayPlayerListItemDataAction::AddPlayer {
+                        name: "Notch".into(),
+                        properties: vec![SPlayerListItemProperty { name: "textures".into(), value: "abc".into(), signature: Some("sig".into()) }],
+                        gamemode: 1,
+                        ping: 30,
+                        display_name: Some(chat("Notch")),
+                    },
+                })],
+            },
+            SPacket::PlayPlayerAbilities { flags: 0x0f, flying_speed: 0.05, field_of_view_modifier: 0.1 },
+            SPacket::PlayTabComplete { matches: vec!["/help".into(), "/list".into()] },
+            SPacket::PlayScoreboardObjective { objective_name: "kills".into(), data: SPlayScoreboardObjectiveData::CreateScoreboard { objective_value: "Kills".into(), objective_type: "integer".into() } },
+            SPacket::PlayUpdateScore { score_name: "Notch".into(), objective_name: "kills".into(), data: SPlayUpdateScoreData::CreateOrUpdateItem { value: 3 } },
+            SPacket::PlayDisplayScoreboard { position: 1, score_name: "kills".into() },
+            SPacket::PlayTeams {
+                team_name: "red".into(),
+                data: SPlayTeamsData::CreateTeam {
+                    display_name: "Red".into(),
+                    prefix: "[R]".into(),
+                    suffix: String::new(),
+                    friendly_fire: 1,
+                    name_tag_visibility: "always".into(),
+                    color: text::Code::Red,
+                    players: vec!["Notch".into()],
+                },
+            },
+            SPacket::PlayPluginMessage { channel: "MC|Brand".into(), data: b"\x06quartz".to_vec() },
+            SPacket::PlayDisconnect { reason: chat("bye") },
+            SPacket::PlayServerDifficulty { difficulty: 3 },
+            SPacket::PlayCombatEvent { data: SPlayCombatEventData::EntityDead { player_id: 3, entity_id: 5, message: "died".into() } },
+            SPacket::PlayCamera { camera_id: 3 },
+            SPacket::PlayWorldBorder { data: SPlayWorldBorderData::Initialize { x: 0.0, z: 0.0, old_radius: 100.0, new_radius: 50.0, speed: 1000, portal_teleport_boundary: 29999984, warning_time: 15, warning_blocks: 5 } },
+            SPacket::PlayTitle { data: SPlayTitleData::SetTimesAndDisplay { fade_in: 10, stay: 70, fade_out: 20 } },
+            SPacket::PlaySetCompression { threshold: 256 },
+            SPacket::PlayPlayerListHeaderAndFooter { header: chat("header"), footer: chat("footer") },
+            SPacket::PlayResourcePackSend { url: "http://example.com/pack.zip".into(), hash: "abc".into() },
+            SPacket::PlayUpdateEntityNBT { entity_id: 3, tag: Nbt::Empty },
+        ];
+        let ids = packets.into_iter().map(|p| round_trip_s(State::Play, p)).collect();
+        assert_all_ids(ids, 74);
+    }
+
+    #[test]
+    fn server_play_variants_round_trip() {
+        let packets = vec![
+            SPacket::PlaySpawnObject { entity_id: 4, object_type: 2, x: 1.0, y: 2.0, z: 3.0, pitch: 0.0, yaw: 0.0, data: None },
+            SPacket::PlayChunkData { chunk_x: 0, chunk_z: 0, primary_bit_mask: 0b11, data: SPlayChunkDataData::GroundUpNonContinuous(sections(2, false)) },
+            SPacket::PlayChunkData { chunk_x: 0, chunk_z: 0, primary_bit_mask: 0, data: SPlayChunkDataData::GroundUpContinuous(chunk(0, false)) },
+            SPacket::PlayMapChunkBulk { sky_light_sent: false, chunks: vec![SPlayMapChunkBulkData { chunk_x: 0, chunk_z: 0, primary_bit_mask: 1, chunk: chunk(1, false) }] },
+            SPacket::PlayOpenWindow { window_id: 2, window_type: "minecraft:chest".into(), window_title: chat("Chest"), slot_num: 27, entity_id: 0 },
+            SPacket::PlayMap { map_id: 1, scale: 0, icons: Vec::new(), data: None },
+            SPacket::PlayTeams { team_name: "red".into(), data: SPlayTeamsData::UpdateTeam { display_name: "Red".into(), prefix: String::new(), suffix: String::new(), friendly_fire: 0, name_tag_visibility: "never".into(), color: text::Code::Reset } },
+            SPacket::PlayTeams { team_name: "red".into(), data: SPlayTeamsData::RemovePlayers { players: vec!["a".into(), "b".into()] } },
+            SPacket::PlayScoreboardObjective { objective_name: "kills".into(), data: SPlayScoreboardObjectiveData::RemoveScoreboard },
+            SPacket::PlayUpdateScore { score_name: "Notch".into(), objective_name: "kills".into(), data: SPlayUpdateScoreData::RemoveItem },
+            SPacket::PlayWorldBorder { data: SPlayWorldBorderData::SetWarningBlocks { warning_blocks: 3 } },
+            SPacket::PlayTitle { data: SPlayTitleData::Reset },
+            SPacket::PlayPlayerListItem { players: vec![Arc::new(SPlayPlayerListItemData { uuid: Uuid::nil(), action: SPlayPlayerListItemDataAction::RemovePlayer })] },
+        ];
+        for p in packets {
+            round_trip_s(State::Play, p);
+        }
+    }
+
+    #[test]
+    fn chunk_data_with_wrong_size() {
+        let mut buf = Vec::new();
+        SPacket::PlayChunkData { chunk_x: 0, chunk_z: 0, primary_bit_mask: 1, data: SPlayChunkDataData::GroundUpContinuous(chunk(1, true)) }
+            .write(&mut buf)
+            .unwrap();
+        // claim a second section the data doesn't have.
+        buf[10] = 0b11;
+        assert!(SPacket::read(&mut &buf[..], State::Play, 33).is_err());
+    }
+
+    #[test]
+    fn server_status_and_login_round_trip() {
+        let sample = vec![SStatusResponsePlayer { name: "Notch".into(), id: "069a79f4-44e9-4726-a5be-fca90e38aaf5".into() }];
+        let status = SPacket::StatusResponse {
+            data: SStatusResponseData {
+                version: SStatusResponseVersion { name: "1.8.9".into(), protocol: 47 },
+                players: SStatusResponsePlayers { max: 20, online: 1, sample: Some(sample) },
+                description: chat("A Minecraft Server"),
+                favicon: Arc::new(None),
+            },
+        };
+        let ids = vec![status, SPacket::StatusPong { payload: 1234 }].into_iter().map(|p| round_trip_s(State::Status, p)).collect();
+        assert_all_ids(ids, 2);
+
+        let packets = vec![
+            SPacket::LoginDisconnect { reason: chat("no") },
+            SPacket::LoginEncryptionRequest { server_id: String::new(), public_key: vec![1, 2, 3], verify_token: vec![4, 5, 6, 7] },
+            SPacket::LoginLoginSuccess { uuid: "069a79f4-44e9-4726-a5be-fca90e38aaf5".into(), username: "Notch".into() },
+            SPacket::LoginSetCompression { threshold: 256 },
+        ];
+        let ids = packets.into_iter().map(|p| round_trip_s(State::Login, p)).collect();
+        assert_all_ids(ids, 4);
+    }
+
+    #[test]
+    fn client_round_trip() {
+        let handshake = CPacket::Handshake { protocol_version: 47, server_address: "localhost".into(), server_port: 25565, next_state: 2 };
+        assert_all_ids(vec![round_trip_c(State::Handshake, handshake)], 1);
+
+        let packets = vec![
+            CPacket::PlayKeepAlive { id: 42 },
+            CPacket::PlayChatMessage { message: "/help".into() },
+            CPacket::PlayUseEntity { target: 3, data: CPlayUseEntityData::InteractAt { target_x: 0.5, target_y: 1.0, target_z: 0.25 } },
+            CPacket::PlayPlayer { on_ground: true },
+            CPacket::PlayPlayerPosition { x: 0.5, feet_y: 64.0, z: 0.5, on_ground: true },
+            CPacket::PlayPlayerLook { yaw: 90.0, pitch: -10.0, on_ground: false },
+            CPacket::PlayPlayerPositionAndLook { x: 0.5, feet_y: 64.0, z: 0.5, yaw: 90.0, pitch: -10.0, on_ground: false },
+            CPacket::PlayPlayerDigging { status: 0, location: pos(), face: 1 },
+            CPacket::PlayPlayerBlockPlacement { location: pos(), face: 1, held_item: item(), cursor_pos_x: 8, cursor_pos_y: 16, cursor_pos_z: 0 },
+            CPacket::PlayHeldItemChange { slot: 3 },
+            CPacket::PlayAnimation {},
+            CPacket::PlayEntityAction { entity_id: 3, action_id: 0, action_param: 0 },
+            CPacket::PlaySteerVehicle { sideways: 0.98, forward: -0.98, flags: 1 },
+            CPacket::PlayCloseWindow { window_id: 1 },
+            CPacket::PlayClickWindow { window_id: 0, slot: 36, button: 0, action_num: 1, mode: 0, clicked_item: item() },
+            CPacket::PlayConfirmTransaction { window_id: 0, action_num: 1, accepted: true },
+            CPacket::PlayCreativeInventoryAction { slot: 36, clicked_item: data::SlotData::Empty },
+            CPacket::PlayEnchantItem { window_id: 1, enchantment: 2 },
+            CPacket::PlayUpdateSign { location: pos(), line1: chat("a"), line2: chat("b"), line3: chat("c"), line4: chat("d") },
+            CPacket::PlayPlayerAbilities { flags: 2, flying_speed: 0.05, walking_speed: 0.1 },
+            CPacket::PlayTabComplete { text: "/tp ".into(), pos: Some(pos()) },
+            CPacket::PlayClientSettings { locale: "en_US".into(), view_distance: 8, chat_mode: 0, chat_colors: true, displayed_skin_parts: 0x7f },
+            CPacket::PlayClientStatus { action_id: 0 },
+            CPacket::PlayPluginMessage { channel: "MC|Brand".into(), data: b"\x07vanilla".to_vec() },
+            CPacket::PlaySpectate { target_player: Uuid::nil() },
+            CPacket::PlayResourcePackStatus { hash: "abc".into(), result: 3 },
+        ];
+        let ids = packets.into_iter().map(|p| round_trip_c(State::Play, p)).collect();
+        assert_all_ids(ids, 26);
+
+        let ids = vec![CPacket::StatusRequest {}, CPacket::StatusPing { payload: 1234 }].into_iter().map(|p| round_trip_c(State::Status, p)).collect();
+        assert_all_ids(ids, 2);
+
+        let packets = vec![
+            CPacket::LoginLoginStart { name: "Notch".into() },
+            CPacket::LoginEncryptionResponse { shared_secret: vec![1; 128], verify_token: vec![2; 128] },
+        ];
+        let ids = packets.into_iter().map(|p| round_trip_c(State::Login, p)).collect();
+        assert_all_ids(ids, 2);
+
+        let unknown = CPacket::PlayUseEntity { target: 3, data: CPlayUseEntityData::Unknown };
+        assert!(unknown.write(&mut Vec::new()).is_err());
+    }
+}
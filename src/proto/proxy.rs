@@ -0,0 +1,123 @@
+//! HAProxy PROXY protocol headers, which proxies put before the handshake so the server knows where players connect
+//! from. See https://www.haproxy.org/download/2.0/doc/proxy-protocol.txt
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::str;
+
+use crate::proto::{Error, Result};
+
+const V1_PREFIX: &[u8] = b"PROXY ";
+/// longest v1 header, with the line break.
+const V1_MAX_LEN: usize = 107;
+const V2_SIGNATURE: [u8; 12] = [0x0d, 0x0a, 0x0d, 0x0a, 0x00, 0x0d, 0x0a, 0x51, 0x55, 0x49, 0x54, 0x0a];
+/// signature, version and command, family and length.
+const V2_HEADER_LEN: usize = 16;
+
+/// Parses the header at the start of data. Returns None if not all of it was read yet, or the source address and the
+/// length of the header. The address is None for connections the proxy made itself, like health checks.
+pub fn parse(data: &[u8]) -> Result<Option<(Option<SocketAddr>, usize)>> {
+    if starts_with(data, V1_PREFIX) {
+        parse_v1(data)
+    } else if starts_with(data, &V2_SIGNATURE) {
+        parse_v2(data)
+    } else {
+        Err(invalid("no PROXY header"))
+    }
+}
+
+/// Whether data starts with prefix, or is the start of it.
+fn starts_with(data: &[u8], prefix: &[u8]) -> bool {
+    let len = data.len().min(prefix.len());
+    data[..len] == prefix[..len]
+}
+
+/// `PROXY TCP4 <source ip> <destination ip> <source port> <destination port>\r\n`
+fn parse_v1(data: &[u8]) -> Result<Option<(Option<SocketAddr>, usize)>> {
+    let end = match data.windows(2).take(V1_MAX_LEN - 1).position(|w| w == b"\r\n") {
+        Some(end) => end,
+        None if data.len() >= V1_MAX_LEN => return Err(invalid("v1 header too long")),
+        None => return Ok(None),
+    };
+    let line = str::from_utf8(&data[..end]).map_err(|_| invalid("v1 header is not ascii"))?;
+    let fields: Vec<&str> = line.split(' ').collect();
+    let source = match fields.get(1) {
+        Some(&"TCP4") | Some(&"TCP6") if fields.len() == 6 => {
+            let ip: IpAddr = fields[2].parse().map_err(|_| invalid("invalid v1 source address"))?;
+            let port: u16 = fields[4].parse().map_err(|_| invalid("invalid v1 source port"))?;
+            Some(SocketAddr::new(ip, port))
+        }
+        Some(&"UNKNOWN") => None,
+        _ => return Err(invalid("invalid v1 header")),
+    };
+    Ok(Some((source, end + 2)))
+}
+
+/// Binary header: signature, version and command, address family and protocol, length of the addresses and the
+/// addresses.
+fn parse_v2(data: &[u8]) -> Result<Option<(Option<SocketAddr>, usize)>> {
+    if data.len() < V2_HEADER_LEN {
+        return Ok(None);
+    }
+    if data[12] >> 4 != 2 {
+        return Err(invalid("unsupported version"));
+    }
+    let len = V2_HEADER_LEN + ((data[14] as usize) << 8 | data[15] as usize);
+    if data.len() < len {
+        return Ok(None);
+    }
+    let addrs = &data[V2_HEADER_LEN..len];
+    let source = match (data[12] & 0xf, data[13] >> 4) {
+        // local. the proxy's own connection.
+        (0, _) => None,
+        (1, 1) if addrs.len() >= 12 => {
+            let ip = Ipv4Addr::new(addrs[0], addrs[1], addrs[2], addrs[3]);
+            Some(SocketAddr::new(ip.into(), (addrs[8] as u16) << 8 | addrs[9] as u16))
+        }
+        (1, 2) if addrs.len() >= 36 => {
+            let mut octets = [0; 16];
+            octets.copy_from_slice(&addrs[..16]);
+            Some(SocketAddr::new(Ipv6Addr::from(octets).into(), (addrs[32] as u16) << 8 | addrs[33] as u16))
+        }
+        // unix sockets and unknown families have no ip to use.
+        (1, _) => None,
+        _ => return Err(invalid("unsupported command")),
+    };
+    Ok(Some((source, len)))
+}
+
+fn invalid(reason: &'static str) -> Error {
+    Error::InvalidProxyHeader(reason)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn v1() {
+        let data = b"PROXY TCP4 192.0.2.1 198.51.100.1 51234 25565\r\n\x10";
+        let expected = SocketAddr::new(Ipv4Addr::new(192, 0, 2, 1).into(), 51234);
+        assert_eq!(parse(data).unwrap(), Some((Some(expected), data.len() - 1)));
+        assert_eq!(parse(&data[..20]).unwrap(), None);
+        assert_eq!(parse(b"PROXY UNKNOWN\r\n").unwrap(), Some((None, 15)));
+        assert!(parse(b"PROXY TCP4 nonsense\r\n").is_err());
+        assert!(parse(&[0x10, 0x00]).is_err());
+    }
+
+    #[test]
+    fn v2() {
+        let mut data = V2_SIGNATURE.to_vec();
+        // proxy command, tcp over ipv6.
+        data.extend_from_slice(&[0x21, 0x21, 0, 36]);
+        data.extend_from_slice(&Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1).octets());
+        data.extend_from_slice(&Ipv6Addr::LOCALHOST.octets());
+        data.extend_from_slice(&[0xc8, 0x22, 0x63, 0xdd]);
+        let expected = SocketAddr::new(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1).into(), 51234);
+        assert_eq!(parse(&data).unwrap(), Some((Some(expected), 52)));
+        assert_eq!(parse(&data[..30]).unwrap(), None);
+
+        let mut local = V2_SIGNATURE.to_vec();
+        local.extend_from_slice(&[0x20, 0x00, 0, 0]);
+        assert_eq!(parse(&local).unwrap(), Some((None, 16)));
+    }
+}
@@ -0,0 +1,219 @@
+//! Packets of client versions other than 1.8. The server only deals in 1.8 packets, a Translator turns them into
+//! what the client's version expects and turns what the client sends back into 1.8 packets.
+
+mod v1_7;
+mod v1_9;
+
+use std::collections::HashMap;
+use std::io;
+
+use uuid::Uuid;
+
+use crate::binary;
+use crate::proto::capture;
+use crate::proto::data::SlotData;
+use crate::proto::packets::{CPacket, SPacket, SPlayPlayerListItemDataAction};
+use crate::proto::version::ProtocolVersion;
+use crate::proto::{Error, Result, State};
+
+/// What an entity is, as far as the client knows. Metadata indices depend on it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EntityKind {
+    Player,
+    Mob(u8),
+    Object(i8),
+}
+
+/// Name and skin of a player in the tab list.
+#[derive(Debug, Clone)]
+struct PlayerInfo {
+    name: String,
+    /// name, value and signature.
+    properties: Vec<(String, String, Option<String>)>,
+}
+
+/// Translates the packets of one connection. It keeps track of what other versions need but the 1.8 packets leave out.
+pub struct Translator {
+    version: ProtocolVersion,
+    /// 1.7 spawns players with their name and skin instead of looking them up in the tab list.
+    players: HashMap<Uuid, PlayerInfo>,
+    entities: HashMap<i32, EntityKind>,
+    /// passenger to vehicle. 1.9 sets all passengers of a vehicle at once.
+    vehicles: HashMap<i32, i32>,
+    /// 1.9 doesn't send the item in hand when placing blocks.
+    hotbar: Vec<SlotData>,
+    held_slot: usize,
+    /// 1.9 confirms teleports by these.
+    next_teleport_id: i32,
+}
+
+impl Translator {
+    pub fn new(version: ProtocolVersion) -> Translator {
+        Translator {
+            version,
+            players: HashMap::new(),
+            entities: HashMap::new(),
+            vehicles: HashMap::new(),
+            hotbar: vec![SlotData::Empty; 9],
+            held_slot: 0,
+            next_teleport_id: 0,
+        }
+    }
+
+    pub fn get_version(&self) -> ProtocolVersion {
+        self.version
+    }
+
+    /// Encodes the packet for the client as its id followed by its fields. A packet can turn into any number of packets,
+    /// packets the version has nothing like are left out.
+    pub fn write_clientbound(&mut self, packet: &SPacket, out: &mut Vec<Vec<u8>>) -> io::Result<()> {
+        match *packet {
+            SPacket::StatusResponse { .. }
+            | SPacket::StatusPong { .. }
+            | SPacket::LoginDisconnect { .. }
+            | SPacket::LoginLoginSuccess { .. }
+            | SPacket::LoginSetCompression { .. } => out.push(capture::encode(packet)?),
+            SPacket::LoginEncryptionRequest { .. } if self.version < ProtocolVersion::V1_8 => {
+                v1_7::write_login(packet, out)?
+            }
+            SPacket::LoginEncryptionRequest { .. } => out.push(capture::encode(packet)?),
+            _ if self.version < ProtocolVersion::V1_8 => v1_7::write(self, packet, out)?,
+            _ => v1_9::write(self, packet, out)?,
+        }
+        self.track_clientbound(packet);
+        Ok(())
+    }
+
+    /// Decodes a packet of the client from its id and fields. Returns None for packets 1.8 has nothing like.
+    pub fn read_serverbound(&mut self, data: &[u8], state: State) -> Result<Option<CPacket>> {
+        let mut r = data;
+        let id = binary::read_varint(&mut r)?;
+        let packet = match state {
+            State::Play if self.version < ProtocolVersion::V1_8 => v1_7::read(self, &mut r, id)?,
+            State::Play => v1_9::read(self, &mut r, id)?,
+            State::Login if self.version < ProtocolVersion::V1_8 => Some(v1_7::read_login(&mut r, id)?),
+            _ => Some(CPacket::read(&mut r, state, id)?),
+        };
+        if !r.is_empty() {
+            return Err(Error::PacketTooLarge(r.len()));
+        }
+        if let Some(ref packet) = packet {
+            self.track_serverbound(packet);
+        }
+        Ok(packet)
+    }
+
+    fn held_item(&self) -> SlotData {
+        self.hotbar[self.held_slot].clone()
+    }
+
+    fn track_clientbound(&mut self, packet: &SPacket) {
+        match *packet {
+            SPacket::PlayJoinGame { entity_id, .. } => {
+                self.entities.insert(entity_id, EntityKind::Player);
+            }
+            SPacket::PlaySpawnPlayer { entity_id, .. } => {
+                self.entities.insert(entity_id, EntityKind::Player);
+            }
+            SPacket::PlaySpawnMob { entity_id, mob_type, .. } => {
+                self.entities.insert(entity_id, EntityKind::Mob(mob_type));
+            }
+            SPacket::PlaySpawnObject { entity_id, object_type, .. } => {
+                self.entities.insert(entity_id, EntityKind::Object(object_type));
+            }
+            SPacket::PlayDestroyEntities { ref entity_ids } => {
+                for entity_id in entity_ids {
+                    self.entities.remove(entity_id);
+                    self.vehicles.remove(entity_id);
+                }
+            }
+            SPacket::PlayAttachEntity { entity_id, vehicle_id, leash: false } => {
+                if vehicle_id == -1 {
+                    self.vehicles.remove(&entity_id);
+                } else {
+                    self.vehicles.insert(entity_id, vehicle_id);
+                }
+            }
+            SPacket::PlayPlayerListItem { ref players } => {
+                for player in players {
+                    match player.action {
+                        SPlayPlayerListItemDataAction::AddPlayer { ref name, ref properties, .. } => {
+                            let properties =
+                                properties.iter().map(|p| (p.name.clone(), p.value.clone(), p.signature.clone())).collect();
+                            self.players.insert(player.uuid, PlayerInfo { name: name.clone(), properties });
+                        }
+                        SPlayPlayerListItemDataAction::RemovePlayer => {
+                            self.players.remove(&player.uuid);
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            // window 0 is the player's inventory. the hotbar is slots 36 to 44 of it.
+            SPacket::PlayWindowItems { window_id: 0, ref slots } if slots.len() >= 45 => {
+                self.hotbar = slots[36..45].to_vec();
+            }
+            SPacket::PlaySetSlot { window_id: 0, slot, ref slot_data } if slot >= 36 && slot < 45 => {
+                self.hotbar[slot as usize - 36] = slot_data.clone();
+            }
+            SPacket::PlayHeldItemChange { slot } if slot >= 0 && slot < 9 => self.held_slot = slot as usize,
+            _ => {}
+        }
+    }
+
+    fn track_serverbound(&mut self, packet: &CPacket) {
+        match *packet {
+            CPacket::PlayHeldItemChange { slot } if slot >= 0 && slot < 9 => self.held_slot = slot as usize,
+            CPacket::PlayCreativeInventoryAction { slot, ref clicked_item } if slot >= 36 && slot < 45 => {
+                self.hotbar[slot as usize - 36] = clicked_item.clone();
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Starts a packet of the client's version.
+fn start(id: i32) -> Vec<u8> {
+    let mut w = Vec::new();
+    // writing to a vec can't fail.
+    binary::write_varint(&mut w, id).unwrap();
+    w
+}
+
+/// Skips the rest of a packet which is ignored.
+fn skip(r: &mut &[u8]) {
+    *r = &[];
+}
+
+/// Splits a block state id into block id and meta.
+fn split_state(state: i32) -> (u16, u8) {
+    ((state >> 4) as u16, (state & 0x0f) as u8)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::nbt::Nbt;
+
+    use super::*;
+
+    fn stone() -> SlotData {
+        SlotData::Some { id: 1, item_count: 64, item_damage: 0, tag: Nbt::Empty }
+    }
+
+    #[test]
+    fn tracks_held_item() {
+        let mut translator = Translator::new(ProtocolVersion::V1_12_2);
+        let mut slots = vec![SlotData::Empty; 45];
+        slots[38] = stone();
+        translator.write_clientbound(&SPacket::PlayWindowItems { window_id: 0, slots }, &mut Vec::new()).unwrap();
+        assert!(match translator.held_item() {
+            SlotData::Empty => true,
+            _ => false,
+        });
+        translator.track_serverbound(&CPacket::PlayHeldItemChange { slot: 2 });
+        assert!(match translator.held_item() {
+            SlotData::Some { id: 1, .. } => true,
+            _ => false,
+        });
+    }
+}
@@ -0,0 +1,935 @@
+//! 1.7.10. Mostly the 1.8 packets with ints instead of varints and positions, no uuids in the tab list and none of the
+//! blocks and items added in 1.8.
+
+use std::io::{self, Read, Write};
+
+use flate2::read::GzDecoder;
+use flate2::write::{GzEncoder, ZlibEncoder};
+use flate2::Compression;
+
+use crate::binary::*;
+use crate::block::BlockPos;
+use crate::entity::metadata::{EntityMetadata, MetadataEntry};
+use crate::nbt::Nbt;
+use crate::proto::data::{self, GroundUpNonContinuous, SlotData};
+use crate::proto::packets::*;
+use crate::proto::{Error, Result, State};
+use crate::text::chat::Chat;
+use crate::text::{self, ChatPos};
+use crate::world::chunk::CHUNK_SECTION_BLOCK_COUNT;
+
+use super::{skip, split_state, start, EntityKind, Translator};
+
+/// Play packets up to this id are the same packets as in 1.8, later ones were added in 1.8.
+const LAST_PACKET_ID: i32 = 0x40;
+const LAST_SERVERBOUND_PACKET_ID: i32 = 0x17;
+
+/// Turns a block added in 1.8 into the closest 1.7 block.
+fn remap_block(id: u16, meta: u8) -> (u16, u8) {
+    match id {
+        // granite, diorite and andesite.
+        1 if meta > 0 => (1, 0),
+        // coarse dirt.
+        3 if meta == 1 => (3, 0),
+        // wet sponge.
+        19 if meta == 1 => (19, 0),
+        // slime block as lime wool.
+        165 => (35, 5),
+        166 => (20, 0),
+        167 => (96, meta),
+        168 => (98, 0),
+        169 => (89, 0),
+        // banners.
+        176 | 177 => (0, 0),
+        178 => (151, meta),
+        179 => (24, meta),
+        180 => (128, meta),
+        181 => (43, 1),
+        182 => (44, 1 | (meta & 8)),
+        183..=187 => (107, meta),
+        188..=192 => (85, 0),
+        193..=197 => (64, meta),
+        _ if id > 197 => (0, 0),
+        _ => (id, meta),
+    }
+}
+
+fn remap_item(id: i16, damage: i16) -> (i16, i16) {
+    match id {
+        0..=255 => {
+            let (new_id, new_meta) = remap_block(id as u16, (damage & 0x0f) as u8);
+            if new_id as i16 == id && i16::from(new_meta) == damage & 0x0f {
+                (id, damage)
+            } else {
+                (new_id as i16, i16::from(new_meta))
+            }
+        }
+        // doors as wooden doors.
+        427..=431 => (324, 0),
+        // prismarine, rabbit, armor stands, mutton and banners.
+        409..=416 | 423..=426 => (1, 0),
+        _ => (id, damage),
+    }
+}
+
+/// Slots have their nbt gzipped and prefixed with its length.
+fn write_slot<W: Write>(w: &mut W, slot: &SlotData) -> io::Result<()> {
+    match *slot {
+        SlotData::Empty => write_ishort(w, -1),
+        SlotData::Some { id, item_count, item_damage, ref tag } => {
+            let (id, damage) = remap_item(id, item_damage);
+            write_ishort(w, id)?;
+            write_byte(w, item_count)?;
+            write_ishort(w, damage)?;
+            write_gzip_nbt(w, tag)
+        }
+    }
+}
+
+fn write_gzip_nbt<W: Write>(w: &mut W, nbt: &Nbt) -> io::Result<()> {
+    match *nbt {
+        Nbt::Empty => write_ishort(w, -1),
+        Nbt::Some(..) => {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            nbt.write(&mut encoder)?;
+            let gzipped = encoder.finish()?;
+            write_ishort(w, gzipped.len() as i16)?;
+            w.write_all(&gzipped)
+        }
+    }
+}
+
+fn read_slot(r: &mut &[u8]) -> Result<SlotData> {
+    let id = read_ishort(r)?;
+    if id == -1 {
+        return Ok(SlotData::Empty);
+    }
+    let item_count = read_byte(r)?;
+    let item_damage = read_ishort(r)?;
+    let len = read_ishort(r)?;
+    let tag = if len < 0 {
+        Nbt::Empty
+    } else {
+        let mut gzipped = vec![0; len as usize];
+        r.read_exact(&mut gzipped)?;
+        let mut decoded = Vec::new();
+        GzDecoder::new(&gzipped[..]).read_to_end(&mut decoded)?;
+        Nbt::read(&mut &decoded[..])?
+    };
+    Ok(SlotData::Some { id, item_count, item_damage, tag })
+}
+
+/// Same types as 1.8 but without rotations. Living entities had their name tags at 10 and 11.
+fn write_metadata(kind: Option<EntityKind>, metadata: &EntityMetadata, w: &mut Vec<u8>) -> io::Result<()> {
+    for (index, entry) in metadata.iter() {
+        let index = match (kind, index) {
+            // silent.
+            (_, 4) => continue,
+            (Some(EntityKind::Mob(_)), 2) => 10,
+            (Some(EntityKind::Mob(_)), 3) => 11,
+            (_, 2) | (_, 3) => continue,
+            // skin parts.
+            (Some(EntityKind::Player), 10) => continue,
+            (_, index) => index,
+        };
+        match *entry {
+            // ages were ints.
+            MetadataEntry::Byte(x) if index == 12 && matches!(kind, Some(EntityKind::Mob(_))) => {
+                write_ubyte(w, index | (2 << 5))?;
+                write_int(w, i32::from(x))?;
+            }
+            MetadataEntry::Byte(x) => {
+                write_ubyte(w, index)?;
+                write_byte(w, x)?;
+            }
+            MetadataEntry::Short(x) => {
+                write_ubyte(w, index | (1 << 5))?;
+                write_ishort(w, x)?;
+            }
+            MetadataEntry::Int(x) => {
+                write_ubyte(w, index | (2 << 5))?;
+                write_int(w, x)?;
+            }
+            MetadataEntry::Float(x) => {
+                write_ubyte(w, index | (3 << 5))?;
+                write_float(w, x)?;
+            }
+            MetadataEntry::String(ref x) => {
+                write_ubyte(w, index | (4 << 5))?;
+                data::write_string(w, x)?;
+            }
+            MetadataEntry::Slot(ref x) => {
+                write_ubyte(w, index | (5 << 5))?;
+                write_slot(w, x)?;
+            }
+            MetadataEntry::Pos { x, y, z } => {
+                write_ubyte(w, index | (6 << 5))?;
+                write_int(w, x)?;
+                write_int(w, y)?;
+                write_int(w, z)?;
+            }
+            MetadataEntry::Orientation { .. } => {}
+        }
+    }
+    write_ubyte(w, 127)
+}
+
+/// Block ids, metas, block light, sky light and biomes.
+fn chunk_data(sections: &GroundUpNonContinuous, biomes: Option<&[[u8; 16]; 16]>) -> Vec<u8> {
+    let mut ids = Vec::with_capacity(sections.sections.len() * CHUNK_SECTION_BLOCK_COUNT);
+    let mut metas = Vec::with_capacity(sections.sections.len() * CHUNK_SECTION_BLOCK_COUNT / 2);
+    for section in &sections.sections {
+        let states = section.blocks.chunks(2).map(|b| i32::from(b[0]) | i32::from(b[1]) << 8);
+        let mut meta_byte = 0;
+        for (i, state) in states.enumerate() {
+            let (id, meta) = split_state(state);
+            let (id, meta) = remap_block(id, meta);
+            ids.push(id as u8);
+            if i % 2 == 0 {
+                meta_byte = meta;
+            } else {
+                metas.push(meta_byte | meta << 4);
+            }
+        }
+    }
+
+    let mut data = ids;
+    data.extend_from_slice(&metas);
+    for section in &sections.sections {
+        data.extend_from_slice(&section.block_light);
+    }
+    for section in &sections.sections {
+        if let Some(ref sky_light) = section.sky_light {
+            data.extend_from_slice(sky_light);
+        }
+    }
+    if let Some(biomes) = biomes {
+        for z in biomes {
+            data.extend_from_slice(z);
+        }
+    }
+    data
+}
+
+fn zlib(data: &[u8]) -> io::Result<Vec<u8>> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(data)?;
+    encoder.finish()
+}
+
+fn write_chunk(
+    chunk_x: i32,
+    chunk_z: i32,
+    primary_bit_mask: u16,
+    sections: &GroundUpNonContinuous,
+    biomes: Option<&[[u8; 16]; 16]>,
+) -> io::Result<Vec<u8>> {
+    let data = zlib(&chunk_data(sections, biomes))?;
+    let mut w = start(0x21);
+    write_int(&mut w, chunk_x)?;
+    write_int(&mut w, chunk_z)?;
+    write_bool(&mut w, biomes.is_some())?;
+    write_ushort(&mut w, primary_bit_mask)?;
+    // add bit mask. no block ids above 255.
+    write_ushort(&mut w, 0)?;
+    write_int(&mut w, data.len() as i32)?;
+    w.extend_from_slice(&data);
+    Ok(w)
+}
+
+/// Positions were three ints, with a short or byte for y in some packets.
+fn write_position(w: &mut Vec<u8>, location: BlockPos) -> io::Result<()> {
+    write_int(w, location.x)?;
+    write_int(w, i32::from(location.y))?;
+    write_int(w, location.z)
+}
+
+fn particle_name(particle_id: i32, data: &[i32]) -> Option<String> {
+    const NAMES: &[&str] = &[
+        "explode",
+        "largeexplode",
+        "hugeexplosion",
+        "fireworksSpark",
+        "bubble",
+        "splash",
+        "",
+        "suspended",
+        "depthsuspend",
+        "crit",
+        "magicCrit",
+        "smoke",
+        "largesmoke",
+        "spell",
+        "instantSpell",
+        "mobSpell",
+        "mobSpellAmbient",
+        "witchMagic",
+        "dripWater",
+        "dripLava",
+        "angryVillager",
+        "happyVillager",
+        "townaura",
+        "note",
+        "portal",
+        "enchantmenttable",
+        "flame",
+        "lava",
+        "footstep",
+        "cloud",
+        "reddust",
+        "snowballpoof",
+        "snowshovel",
+        "slime",
+        "heart",
+    ];
+    let get = |i| data.get(i).cloned().unwrap_or(0);
+    match particle_id {
+        36 => Some(format!("iconcrack_{}_{}", get(0), get(1))),
+        37 => Some(format!("blockcrack_{}_{}", get(0) & 0xfff, get(0) >> 12)),
+        38 => Some(format!("blockdust_{}_{}", get(0) & 0xfff, get(0) >> 12)),
+        _ => NAMES.get(particle_id as usize).filter(|name| !name.is_empty()).map(|&name| name.into()),
+    }
+}
+
+fn window_type(name: &str) -> Option<u8> {
+    let id = match name {
+        "minecraft:chest" | "minecraft:container" => 0,
+        "minecraft:crafting_table" => 1,
+        "minecraft:furnace" => 2,
+        "minecraft:dispenser" => 3,
+        "minecraft:enchanting_table" => 4,
+        "minecraft:brewing_stand" => 5,
+        "minecraft:villager" => 6,
+        "minecraft:beacon" => 7,
+        "minecraft:anvil" => 8,
+        "minecraft:hopper" => 9,
+        "minecraft:dropper" => 10,
+        "EntityHorse" => 11,
+        _ => return None,
+    };
+    Some(id)
+}
+
+/// Sign lines were plain text of at most 15 characters.
+fn sign_line(line: &Chat) -> String {
+    line.to_plain().chars().take(15).collect()
+}
+
+pub fn write_login(packet: &SPacket, out: &mut Vec<Vec<u8>>) -> io::Result<()> {
+    if let SPacket::LoginEncryptionRequest { ref server_id, ref public_key, ref verify_token } = *packet {
+        let mut w = start(1);
+        data::write_string(&mut w, server_id)?;
+        write_ishort(&mut w, public_key.len() as i16)?;
+        w.extend_from_slice(public_key);
+        write_ishort(&mut w, verify_token.len() as i16)?;
+        w.extend_from_slice(verify_token);
+        out.push(w);
+    }
+    Ok(())
+}
+
+pub fn read_login(r: &mut &[u8], id: i32) -> Result<CPacket> {
+    if id != 1 {
+        return CPacket::read(r, State::Login, id);
+    }
+    let mut array = || -> Result<Vec<u8>> {
+        let len = read_ishort(r)?;
+        if len < 0 {
+            return Err(Error::NegativePacketLen(i32::from(len)));
+        }
+        let mut buf = vec![0; len as usize];
+        r.read_exact(&mut buf)?;
+        Ok(buf)
+    };
+    Ok(CPacket::LoginEncryptionResponse { shared_secret: array()?, verify_token: array()? })
+}
+
+pub fn write(t: &mut Translator, packet: &SPacket, out: &mut Vec<Vec<u8>>) -> io::Result<()> {
+    let id = packet.id();
+    if id > LAST_PACKET_ID {
+        return Ok(());
+    }
+    let mut w = start(id);
+    match *packet {
+        SPacket::PlayKeepAlive { id } => write_int(&mut w, id)?,
+        SPacket::PlayJoinGame { entity_id, gamemode, dimension, difficulty, max_players, ref level_type, .. } => {
+            write_int(&mut w, entity_id)?;
+            write_ubyte(&mut w, gamemode)?;
+            write_byte(&mut w, dimension)?;
+            write_ubyte(&mut w, difficulty)?;
+            write_ubyte(&mut w, max_players)?;
+            data::write_string(&mut w, level_type)?;
+        }
+        SPacket::PlayChatMessage { position: ChatPos::AboveHotbar, .. } => return Ok(()),
+        SPacket::PlayChatMessage { ref message, .. } => message.write_proto(&mut w)?,
+        SPacket::PlayEntityEquipment { entity_id, slot, ref item } => {
+            write_int(&mut w, entity_id)?;
+            write_ishort(&mut w, slot)?;
+            write_slot(&mut w, item)?;
+        }
+        SPacket::PlaySpawnPosition { location } => write_position(&mut w, location)?,
+        SPacket::PlayUpdateHealth { health, food, food_saturation } => {
+            write_float(&mut w, health)?;
+            write_ishort(&mut w, food as i16)?;
+            write_float(&mut w, food_saturation)?;
+        }
+        // y is at the eyes.
+        SPacket::PlayPlayerPositionAndLook { x, y, z, yaw, pitch, .. } => {
+            write_double(&mut w, x)?;
+            write_double(&mut w, y + 1.62)?;
+            write_double(&mut w, z)?;
+            write_float(&mut w, yaw)?;
+            write_float(&mut w, pitch)?;
+            write_bool(&mut w, false)?;
+        }
+        SPacket::PlayUseBed { entity_id, location } => {
+            write_int(&mut w, entity_id)?;
+            write_int(&mut w, location.x)?;
+            write_ubyte(&mut w, location.y)?;
+            write_int(&mut w, location.z)?;
+        }
+        // players were spawned with their name and skin.
+        SPacket::PlaySpawnPlayer { entity_id, uuid, x, y, z, yaw, pitch, current_item, ref metadata } => {
+            write_varint(&mut w, entity_id)?;
+            data::write_string(&mut w, &uuid.hyphenated().to_string())?;
+            let (name, properties) = match t.players.get(&uuid) {
+                Some(info) => (&info.name[..], &info.properties[..]),
+                None => ("", &[][..]),
+            };
+            data::write_string(&mut w, name)?;
+            write_varint(&mut w, properties.len() as i32)?;
+            for &(ref name, ref value, ref signature) in properties {
+                data::write_string(&mut w, name)?;
+                data::write_string(&mut w, value)?;
+                data::write_string(&mut w, signature.as_ref().map_or("", |s| &s[..]))?;
+            }
+            write_int(&mut w, double_to_fixed_point(x))?;
+            write_int(&mut w, double_to_fixed_point(y))?;
+            write_int(&mut w, double_to_fixed_point(z))?;
+            data::write_angle(&mut w, yaw)?;
+            data::write_angle(&mut w, pitch)?;
+            write_ishort(&mut w, remap_item(current_item, 0).0)?;
+            write_metadata(Some(EntityKind::Player), metadata, &mut w)?;
+        }
+        SPacket::PlayCollectItem { collected_entity_id, collector_entity_id } => {
+            write_int(&mut w, collected_entity_id)?;
+            write_int(&mut w, collector_entity_id)?;
+        }
+        SPacket::PlaySpawnMob {
+            entity_id,
+            mob_type,
+            x,
+            y,
+            z,
+            yaw,
+            pitch,
+            head_pitch,
+            velocity_x,
+            velocity_y,
+            velocity_z,
+            ref metadata,
+        } => {
+            write_varint(&mut w, entity_id)?;
+            write_ubyte(&mut w, mob_type)?;
+            write_int(&mut w, double_to_fixed_point(x))?;
+            write_int(&mut w, double_to_fixed_point(y))?;
+            write_int(&mut w, double_to_fixed_point(z))?;
+            data::write_angle(&mut w, yaw)?;
+            data::write_angle(&mut w, pitch)?;
+            data::write_angle(&mut w, head_pitch)?;
+            write_ishort(&mut w, velocity_x)?;
+            write_ishort(&mut w, velocity_y)?;
+            write_ishort(&mut w, velocity_z)?;
+            write_metadata(Some(EntityKind::Mob(mob_type)), metadata, &mut w)?;
+        }
+        SPacket::PlaySpawnPainting { entity_id, ref title, location, direction } => {
+            write_varint(&mut w, entity_id)?;
+            data::write_string(&mut w, title)?;
+            write_position(&mut w, location)?;
+            write_int(&mut w, i32::from(direction))?;
+        }
+        SPacket::PlayEntityVelocity { entity_id, velocity_x, velocity_y, velocity_z } => {
+            write_int(&mut w, entity_id)?;
+            write_ishort(&mut w, velocity_x)?;
+            write_ishort(&mut w, velocity_y)?;
+            write_ishort(&mut w, velocity_z)?;
+        }
+        // the count is a byte.
+        SPacket::PlayDestroyEntities { ref entity_ids } => {
+            for ids in entity_ids.chunks(127) {
+                let mut w = start(id);
+                write_byte(&mut w, ids.len() as i8)?;
+                for &entity_id in ids {
+                    write_int(&mut w, entity_id)?;
+                }
+                out.push(w);
+            }
+            return Ok(());
+        }
+        SPacket::PlayEntity { entity_id } => write_int(&mut w, entity_id)?,
+        SPacket::PlayEntityRelativeMove { entity_id, delta_x, delta_y, delta_z, .. } => {
+            write_int(&mut w, entity_id)?;
+            write_byte(&mut w, delta_x)?;
+            write_byte(&mut w, delta_y)?;
+            write_byte(&mut w, delta_z)?;
+        }
+        SPacket::PlayEntityLook { entity_id, yaw, pitch, .. } => {
+            write_int(&mut w, entity_id)?;
+            data::write_angle(&mut w, yaw)?;
+            data::write_angle(&mut w, pitch)?;
+        }
+        SPacket::PlayEntityLookAndRelativeMove { entity_id, delta_x, delta_y, delta_z, yaw, pitch, .. } => {
+            write_int(&mut w, entity_id)?;
+            write_byte(&mut w, delta_x)?;
+            write_byte(&mut w, delta_y)?;
+            write_byte(&mut w, delta_z)?;
+            data::write_angle(&mut w, yaw)?;
+            data::write_angle(&mut w, pitch)?;
+        }
+        SPacket::PlayEntityTeleport { entity_id, x, y, z, yaw, pitch, .. } => {
+            write_int(&mut w, entity_id)?;
+            write_int(&mut w, double_to_fixed_point(x))?;
+            write_int(&mut w, double_to_fixed_point(y))?;
+            write_int(&mut w, double_to_fixed_point(z))?;
+            data::write_angle(&mut w, yaw)?;
+            data::write_angle(&mut w, pitch)?;
+        }
+        SPacket::PlayEntityHeadLook { entity_id, head_yaw } => {
+            write_int(&mut w, entity_id)?;
+            data::write_angle(&mut w, head_yaw)?;
+        }
+        SPacket::PlayEntityMetadata { entity_id, ref metadata } => {
+            write_int(&mut w, entity_id)?;
+            write_metadata(t.entities.get(&entity_id).cloned(), metadata, &mut w)?;
+        }
+        SPacket::PlayEntityEffect { entity_id, effect_id, amplifier, duration, .. } => {
+            write_int(&mut w, entity_id)?;
+            write_byte(&mut w, effect_id)?;
+            write_byte(&mut w, amplifier)?;
+            write_ishort(&mut w, duration.min(i32::from(i16::max_value())) as i16)?;
+        }
+        SPacket::PlayRemoveEntityEffect { entity_id, effect_id } => {
+            write_int(&mut w, entity_id)?;
+            write_byte(&mut w, effect_id)?;
+        }
+        SPacket::PlaySetExperience { experience_bar, level, total_experience } => {
+            write_float(&mut w, experience_bar)?;
+            write_ishort(&mut w, level as i16)?;
+            write_ishort(&mut w, total_experience as i16)?;
+        }
+        SPacket::PlayEntityProperties { entity_id, ref properties } => {
+            write_int(&mut w, entity_id)?;
+            write_int(&mut w, properties.len() as i32)?;
+            for p in properties {
+                data::write_string(&mut w, &p.key)?;
+                write_double(&mut w, p.value)?;
+                write_ishort(&mut w, p.modifiers.len() as i16)?;
+                for m in &p.modifiers {
+                    m.write(&mut w)?;
+                }
+            }
+        }
+        SPacket::PlayChunkData { chunk_x, chunk_z, primary_bit_mask, ref data } => {
+            let w = match *data {
+                SPlayChunkDataData::GroundUpContinuous(ref g) => {
+                    write_chunk(chunk_x, chunk_z, primary_bit_mask, &g.sections, Some(&g.biome_array))?
+                }
+                SPlayChunkDataData::GroundUpNonContinuous(ref g) => write_chunk(chunk_x, chunk_z, primary_bit_mask, g, None)?,
+            };
+            out.push(w);
+            return Ok(());
+        }
+        SPacket::PlayMultiBlockChange { chunk_x, chunk_z, ref data } => {
+            write_int(&mut w, chunk_x)?;
+            write_int(&mut w, chunk_z)?;
+            write_ishort(&mut w, data.len() as i16)?;
+            write_int(&mut w, data.len() as i32 * 4)?;
+            for d in data {
+                let (id, meta) = split_state(d.block_id);
+                let (id, meta) = remap_block(id, meta);
+                let record = u32::from(d.x & 0x0f) << 28
+                    | u32::from(d.z & 0x0f) << 24
+                    | u32::from(d.y) << 16
+                    | u32::from(id) << 4
+                    | u32::from(meta);
+                write_int(&mut w, record as i32)?;
+            }
+        }
+        SPacket::PlayBlockChange { location, block_id } => {
+            let (id, meta) = split_state(block_id);
+            let (id, meta) = remap_block(id, meta);
+            write_int(&mut w, location.x)?;
+            write_ubyte(&mut w, location.y)?;
+            write_int(&mut w, location.z)?;
+            write_varint(&mut w, i32::from(id))?;
+            write_ubyte(&mut w, meta)?;
+        }
+        SPacket::PlayBlockAction { location, byte1, byte2, block_type } => {
+            write_int(&mut w, location.x)?;
+            write_ishort(&mut w, i16::from(location.y))?;
+            write_int(&mut w, location.z)?;
+            write_ubyte(&mut w, byte1)?;
+            write_ubyte(&mut w, byte2)?;
+            write_varint(&mut w, i32::from(remap_block(block_type as u16, 0).0))?;
+        }
+        SPacket::PlayBlockBreakAnimation { entity_id, location, destroy_stage } => {
+            write_varint(&mut w, entity_id)?;
+            write_position(&mut w, location)?;
+            write_byte(&mut w, destroy_stage)?;
+        }
+        SPacket::PlayMapChunkBulk { sky_light_sent, ref chunks } => {
+            let mut data = Vec::new();
+            for c in chunks {
+                data.extend(chunk_data(&c.chunk.sections, Some(&c.chunk.biome_array)));
+            }
+            let data = zlib(&data)?;
+            write_ishort(&mut w, chunks.len() as i16)?;
+            write_int(&mut w, data.len() as i32)?;
+            write_bool(&mut w, sky_light_sent)?;
+            w.extend_from_slice(&data);
+            for c in chunks {
+                write_int(&mut w, c.chunk_x)?;
+                write_int(&mut w, c.chunk_z)?;
+                write_ushort(&mut w, c.primary_bit_mask)?;
+                write_ushort(&mut w, 0)?;
+            }
+        }
+        SPacket::PlayEffect { effect_id, location, data, disable_relative_volume } => {
+            write_int(&mut w, effect_id)?;
+            write_int(&mut w, location.x)?;
+            write_byte(&mut w, location.y as i8)?;
+            write_int(&mut w, location.z)?;
+            write_int(&mut w, data)?;
+            write_bool(&mut w, disable_relative_volume)?;
+        }
+        SPacket::PlayParticle {
+            particle_id,
+            x,
+            y,
+            z,
+            offset_x,
+            offset_y,
+            offset_z,
+            particle_data,
+            particle_count,
+            ref data,
+            ..
+        } => {
+            let name = match particle_name(particle_id, data) {
+                Some(name) => name,
+                None => return Ok(()),
+            };
+            data::write_string(&mut w, &name)?;
+            for &f in &[x, y, z, offset_x, offset_y, offset_z, particle_data] {
+                write_float(&mut w, f)?;
+            }
+            write_int(&mut w, particle_count)?;
+        }
+        SPacket::PlayOpenWindow { window_id, ref window_type, ref window_title, slot_num, entity_id } => {
+            let type_id = match self::window_type(window_type) {
+                Some(type_id) => type_id,
+                None => {
+                    debug!("1.7 has no {} windows", window_type);
+                    return Ok(());
+                }
+            };
+            let title = window_title.to_plain();
+            write_ubyte(&mut w, window_id)?;
+            write_ubyte(&mut w, type_id)?;
+            data::write_string(&mut w, &title)?;
+            write_ubyte(&mut w, slot_num)?;
+            write_bool(&mut w, !title.is_empty())?;
+            if type_id == 11 {
+                write_int(&mut w, entity_id)?;
+            }
+        }
+        SPacket::PlaySetSlot { window_id, slot, ref slot_data } => {
+            write_byte(&mut w, window_id)?;
+            write_ishort(&mut w, slot)?;
+            write_slot(&mut w, slot_data)?;
+        }
+        SPacket::PlayWindowItems { window_id, ref slots } => {
+            write_ubyte(&mut w, window_id)?;
+            write_ishort(&mut w, slots.len() as i16)?;
+            for slot in slots {
+                write_slot(&mut w, slot)?;
+            }
+        }
+        SPacket::PlayUpdateSign { location, ref line1, ref line2, ref line3, ref line4 } => {
+            write_int(&mut w, location.x)?;
+            write_ishort(&mut w, i16::from(location.y))?;
+            write_int(&mut w, location.z)?;
+            for line in &[line1, line2, line3, line4] {
+                data::write_string(&mut w, &sign_line(line))?;
+            }
+        }
+        SPacket::PlayMap { .. } => return Ok(()),
+        // banners.
+        SPacket::PlayUpdateBlockEntity { action: 6, .. } => return Ok(()),
+        SPacket::PlayUpdateBlockEntity { location, action, ref nbt_data } => {
+            write_int(&mut w, location.x)?;
+            write_ishort(&mut w, i16::from(location.y))?;
+            write_int(&mut w, location.z)?;
+            write_ubyte(&mut w, action)?;
+            write_gzip_nbt(&mut w, nbt_data)?;
+        }
+        SPacket::PlayOpenSignEditor { location } => write_position(&mut w, location)?,
+        // one packet per player, by name.
+        SPacket::PlayPlayerListItem { ref players } => {
+            for player in players {
+                let known_name = t.players.get(&player.uuid).map(|info| info.name.clone());
+                let (name, online, ping) = match player.action {
+                    SPlayPlayerListItemDataAction::AddPlayer { ref name, ping, .. } => (Some(name.clone()), true, ping),
+                    SPlayPlayerListItemDataAction::UpdateLatency { ping } => (known_name, true, ping),
+                    SPlayPlayerListItemDataAction::RemovePlayer => (known_name, false, 0),
+                    _ => continue,
+                };
+                let name = match name {
+                    Some(name) => name,
+                    None => continue,
+                };
+                let mut w = start(id);
+                data::write_string(&mut w, &name)?;
+                write_bool(&mut w, online)?;
+                write_ishort(&mut w, ping.min(i32::from(i16::max_value())) as i16)?;
+                out.push(w);
+            }
+            return Ok(());
+        }
+        SPacket::PlayScoreboardObjective { ref objective_name, ref data } => {
+            data::write_string(&mut w, objective_name)?;
+            match *data {
+                SPlayScoreboardObjectiveData::CreateScoreboard { ref objective_value, .. } => {
+                    data::write_string(&mut w, objective_value)?;
+                    write_byte(&mut w, 0)?;
+                }
+                SPlayScoreboardObjectiveData::RemoveScoreboard => {
+                    data::write_string(&mut w, "")?;
+                    write_byte(&mut w, 1)?;
+                }
+                SPlayScoreboardObjectiveData::UpdateScoreboard { ref objective_value, .. } => {
+                    data::write_string(&mut w, objective_value)?;
+                    write_byte(&mut w, 2)?;
+                }
+            }
+        }
+        SPacket::PlayUpdateScore { ref score_name, ref objective_name, ref data } => {
+            data::write_string(&mut w, score_name)?;
+            match *data {
+                SPlayUpdateScoreData::CreateOrUpdateItem { value } => {
+                    write_byte(&mut w, 0)?;
+                    data::write_string(&mut w, objective_name)?;
+                    write_int(&mut w, value)?;
+                }
+                SPlayUpdateScoreData::RemoveItem => write_byte(&mut w, 1)?,
+            }
+        }
+        SPacket::PlayTeams { ref team_name, ref data } => {
+            data::write_string(&mut w, team_name)?;
+            let write_players = |w: &mut Vec<u8>, players: &[String]| -> io::Result<()> {
+                write_ishort(w, players.len() as i16)?;
+                for p in players {
+                    data::write_string(w, p)?;
+                }
+                Ok(())
+            };
+            match *data {
+                SPlayTeamsData::CreateTeam { ref display_name, ref prefix, ref suffix, friendly_fire, ref players, .. } => {
+                    write_byte(&mut w, 0)?;
+                    data::write_string(&mut w, display_name)?;
+                    data::write_string(&mut w, prefix)?;
+                    data::write_string(&mut w, suffix)?;
+                    write_byte(&mut w, friendly_fire)?;
+                    write_players(&mut w, players)?;
+                }
+                SPlayTeamsData::RemoveTeam => write_byte(&mut w, 1)?,
+                SPlayTeamsData::UpdateTeam { ref display_name, ref prefix, ref suffix, friendly_fire, .. } => {
+                    write_byte(&mut w, 2)?;
+                    data::write_string(&mut w, display_name)?;
+                    data::write_string(&mut w, prefix)?;
+                    data::write_string(&mut w, suffix)?;
+                    write_byte(&mut w, friendly_fire)?;
+                }
+                SPlayTeamsData::AddPlayers { ref players } => {
+                    write_byte(&mut w, 3)?;
+                    write_players(&mut w, players)?;
+                }
+                SPlayTeamsData::RemovePlayers { ref players } => {
+                    write_byte(&mut w, 4)?;
+                    write_players(&mut w, players)?;
+                }
+            }
+        }
+        SPacket::PlayPluginMessage { ref channel, ref data } => {
+            data::write_string(&mut w, channel)?;
+            write_ishort(&mut w, data.len() as i16)?;
+            w.extend_from_slice(data);
+        }
+        // the rest didn't change.
+        _ => packet.write(&mut w)?,
+    }
+    out.push(w);
+    Ok(())
+}
+
+pub fn read(_t: &mut Translator, r: &mut &[u8], id: i32) -> Result<Option<CPacket>> {
+    let packet = match id {
+        0x00 => CPacket::PlayKeepAlive { id: read_int(r)? },
+        0x02 => {
+            let target = read_int(r)?;
+            let data = match read_byte(r)? {
+                0 => CPlayUseEntityData::Interact,
+                1 => CPlayUseEntityData::Attack,
+                _ => CPlayUseEntityData::Unknown,
+            };
+            CPacket::PlayUseEntity { target, data }
+        }
+        // the stance is the y of the eyes.
+        0x04 => {
+            let x = read_double(r)?;
+            let feet_y = read_double(r)?;
+            read_double(r)?;
+            CPacket::PlayPlayerPosition { x, feet_y, z: read_double(r)?, on_ground: read_bool(r)? }
+        }
+        0x06 => {
+            let x = read_double(r)?;
+            let feet_y = read_double(r)?;
+            read_double(r)?;
+            CPacket::PlayPlayerPositionAndLook {
+                x,
+                feet_y,
+                z: read_double(r)?,
+                yaw: read_float(r)?,
+                pitch: read_float(r)?,
+                on_ground: read_bool(r)?,
+            }
+        }
+        0x07 => CPacket::PlayPlayerDigging {
+            status: read_byte(r)?,
+            location: BlockPos::new(read_int(r)?, read_ubyte(r)?, read_int(r)?),
+            face: read_byte(r)?,
+        },
+        0x08 => CPacket::PlayPlayerBlockPlacement {
+            location: BlockPos::new(read_int(r)?, read_ubyte(r)?, read_int(r)?),
+            face: read_byte(r)?,
+            held_item: read_slot(r)?,
+            cursor_pos_x: read_byte(r)?,
+            cursor_pos_y: read_byte(r)?,
+            cursor_pos_z: read_byte(r)?,
+        },
+        0x0a => {
+            read_int(r)?;
+            // 1 swings the arm.
+            if read_byte(r)? != 1 {
+                return Ok(None);
+            }
+            CPacket::PlayAnimation {}
+        }
+        0x0b => CPacket::PlayEntityAction {
+            entity_id: read_int(r)?,
+            action_id: i32::from(read_byte(r)?) - 1,
+            action_param: read_int(r)?,
+        },
+        0x0c => {
+            let sideways = read_float(r)?;
+            let forward = read_float(r)?;
+            let jump = read_bool(r)?;
+            let unmount = read_bool(r)?;
+            CPacket::PlaySteerVehicle { sideways, forward, flags: jump as u8 | (unmount as u8) << 1 }
+        }
+        0x0e => CPacket::PlayClickWindow {
+            window_id: read_ubyte(r)?,
+            slot: read_ishort(r)?,
+            button: read_byte(r)?,
+            action_num: read_ishort(r)?,
+            mode: read_byte(r)?,
+            clicked_item: read_slot(r)?,
+        },
+        0x10 => CPacket::PlayCreativeInventoryAction { slot: read_ishort(r)?, clicked_item: read_slot(r)? },
+        0x12 => {
+            let location = BlockPos::new(read_int(r)?, read_ishort(r)? as u8, read_int(r)?);
+            let mut line = || -> Result<Chat> { Ok(Chat::from(text::parse_legacy(&data::read_string(r)?))) };
+            CPacket::PlayUpdateSign { location, line1: line()?, line2: line()?, line3: line()?, line4: line()? }
+        }
+        0x14 => CPacket::PlayTabComplete { text: data::read_string(r)?, pos: None },
+        0x15 => {
+            let locale = data::read_string(r)?;
+            let view_distance = read_byte(r)?;
+            let chat_mode = read_byte(r)? & 3;
+            let chat_colors = read_bool(r)?;
+            // difficulty.
+            read_byte(r)?;
+            let show_cape = read_bool(r)?;
+            CPacket::PlayClientSettings {
+                locale,
+                view_distance,
+                chat_mode,
+                chat_colors,
+                displayed_skin_parts: if show_cape { 0x7f } else { 0x7e },
+            }
+        }
+        0x16 => CPacket::PlayClientStatus { action_id: i32::from(read_byte(r)?) },
+        0x17 => {
+            let channel = data::read_string(r)?;
+            let len = read_ishort(r)?;
+            if len < 0 {
+                return Err(Error::NegativePacketLen(i32::from(len)));
+            }
+            let mut data = vec![0; len as usize];
+            r.read_exact(&mut data)?;
+            CPacket::PlayPluginMessage { channel, data }
+        }
+        0x01 | 0x03 | 0x05 | 0x09 | 0x0d | 0x0f | 0x11 | 0x13 => CPacket::read(r, State::Play, id)?,
+        _ if id > LAST_SERVERBOUND_PACKET_ID => return Err(Error::InvalidPacketId(id)),
+        _ => {
+            skip(r);
+            return Ok(None);
+        }
+    };
+    Ok(Some(packet))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::proto::version::ProtocolVersion;
+
+    use super::*;
+
+    #[test]
+    fn reads_stance() {
+        let mut w = start(0x04);
+        for &f in &[1.5, 64.0, 65.62, -3.0] {
+            write_double(&mut w, f).unwrap();
+        }
+        write_bool(&mut w, true).unwrap();
+        let mut translator = Translator::new(ProtocolVersion::V1_7_10);
+        match translator.read_serverbound(&w, State::Play).unwrap() {
+            Some(CPacket::PlayPlayerPosition { x, feet_y, z, on_ground }) => {
+                assert_eq!((x, feet_y, z, on_ground), (1.5, 64.0, -3.0, true));
+            }
+            p => panic!("{:?}", p),
+        }
+    }
+
+    #[test]
+    fn remaps_new_blocks() {
+        assert_eq!(remap_block(1, 3), (1, 0));
+        assert_eq!(remap_block(182, 8), (44, 9));
+        assert_eq!(remap_block(35, 14), (35, 14));
+        assert_eq!(remap_item(428, 0), (324, 0));
+        assert_eq!(remap_item(276, 12), (276, 12));
+    }
+
+    #[test]
+    fn slot_nbt_is_gzipped() {
+        let mut tag = ::std::collections::HashMap::new();
+        tag.insert("Unbreakable".to_string(), crate::nbt::Tag::Byte(1));
+        let slot = SlotData::Some { id: 276, item_count: 1, item_damage: 0, tag: Nbt::Some(String::new(), crate::nbt::Compound(tag)) };
+        let mut w = Vec::new();
+        write_slot(&mut w, &slot).unwrap();
+        match read_slot(&mut &w[..]).unwrap() {
+            SlotData::Some { id: 276, tag: Nbt::Some(_, ref root), .. } => assert!(root.contains_key("Unbreakable")),
+            s => panic!("{:?}", s),
+        }
+    }
+}
@@ -0,0 +1,1526 @@
+//! 1.9 to 1.12.2. Chunks are sent with block palettes, entities have uuids and exact positions, metadata has new indices
+//! and types and the packet ids are shuffled around in every other version.
+
+use std::collections::HashMap;
+use std::io;
+
+use uuid::Uuid;
+
+use crate::binary::*;
+use crate::block::BlockPos;
+use crate::entity::metadata::{EntityMetadata, MetadataEntry};
+use crate::nbt::{Compound, Nbt, Tag};
+use crate::proto::data::{self, ChunkSection, GroundUpNonContinuous};
+use crate::proto::packets::*;
+use crate::proto::version::ProtocolVersion;
+use crate::proto::{Error, Result};
+use crate::text::chat::Chat;
+use crate::text;
+use crate::world::chunk::CHUNK_SECTION_BLOCK_COUNT;
+
+use super::{skip, start, EntityKind, Translator};
+
+/// Packets sent by the server. Their ids are their positions in the tables below.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Clientbound {
+    SpawnObject,
+    SpawnExperienceOrb,
+    SpawnGlobalEntity,
+    SpawnMob,
+    SpawnPainting,
+    SpawnPlayer,
+    Animation,
+    Statistics,
+    BlockBreakAnimation,
+    UpdateBlockEntity,
+    BlockAction,
+    BlockChange,
+    BossBar,
+    ServerDifficulty,
+    TabComplete,
+    ChatMessage,
+    MultiBlockChange,
+    ConfirmTransaction,
+    CloseWindow,
+    OpenWindow,
+    WindowItems,
+    WindowProperty,
+    SetSlot,
+    SetCooldown,
+    PluginMessage,
+    NamedSoundEffect,
+    Disconnect,
+    EntityStatus,
+    Explosion,
+    UnloadChunk,
+    ChangeGameState,
+    KeepAlive,
+    ChunkData,
+    Effect,
+    Particle,
+    JoinGame,
+    Map,
+    Entity,
+    EntityRelativeMove,
+    EntityLookAndRelativeMove,
+    EntityLook,
+    VehicleMove,
+    OpenSignEditor,
+    CraftRecipeResponse,
+    PlayerAbilities,
+    CombatEvent,
+    PlayerListItem,
+    PlayerPositionAndLook,
+    UseBed,
+    UnlockRecipes,
+    DestroyEntities,
+    RemoveEntityEffect,
+    ResourcePackSend,
+    Respawn,
+    EntityHeadLook,
+    SelectAdvancementTab,
+    WorldBorder,
+    Camera,
+    HeldItemChange,
+    DisplayScoreboard,
+    EntityMetadata,
+    AttachEntity,
+    EntityVelocity,
+    EntityEquipment,
+    SetExperience,
+    UpdateHealth,
+    ScoreboardObjective,
+    SetPassengers,
+    Teams,
+    UpdateScore,
+    SpawnPosition,
+    TimeUpdate,
+    Title,
+    UpdateSign,
+    SoundEffect,
+    PlayerListHeaderAndFooter,
+    CollectItem,
+    EntityTeleport,
+    Advancements,
+    EntityProperties,
+    EntityEffect,
+}
+
+/// Packets sent by the client.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Serverbound {
+    TeleportConfirm,
+    PrepareCraftingGrid,
+    TabComplete,
+    ChatMessage,
+    ClientStatus,
+    ClientSettings,
+    ConfirmTransaction,
+    EnchantItem,
+    ClickWindow,
+    CloseWindow,
+    PluginMessage,
+    UseEntity,
+    KeepAlive,
+    Player,
+    PlayerPosition,
+    PlayerPositionAndLook,
+    PlayerLook,
+    VehicleMove,
+    SteerBoat,
+    CraftRecipeRequest,
+    PlayerAbilities,
+    PlayerDigging,
+    EntityAction,
+    SteerVehicle,
+    CraftingBookData,
+    ResourcePackStatus,
+    AdvancementTab,
+    HeldItemChange,
+    CreativeInventoryAction,
+    UpdateSign,
+    Animation,
+    Spectate,
+    PlayerBlockPlacement,
+    UseItem,
+}
+
+/// 1.9 to 1.9.2.
+const CLIENTBOUND_1_9: &[Clientbound] = &[
+    Clientbound::SpawnObject,
+    Clientbound::SpawnExperienceOrb,
+    Clientbound::SpawnGlobalEntity,
+    Clientbound::SpawnMob,
+    Clientbound::SpawnPainting,
+    Clientbound::SpawnPlayer,
+    Clientbound::Animation,
+    Clientbound::Statistics,
+    Clientbound::BlockBreakAnimation,
+    Clientbound::UpdateBlockEntity,
+    Clientbound::BlockAction,
+    Clientbound::BlockChange,
+    Clientbound::BossBar,
+    Clientbound::ServerDifficulty,
+    Clientbound::TabComplete,
+    Clientbound::ChatMessage,
+    Clientbound::MultiBlockChange,
+    Clientbound::ConfirmTransaction,
+    Clientbound::CloseWindow,
+    Clientbound::OpenWindow,
+    Clientbound::WindowItems,
+    Clientbound::WindowProperty,
+    Clientbound::SetSlot,
+    Clientbound::SetCooldown,
+    Clientbound::PluginMessage,
+    Clientbound::NamedSoundEffect,
+    Clientbound::Disconnect,
+    Clientbound::EntityStatus,
+    Clientbound::Explosion,
+    Clientbound::UnloadChunk,
+    Clientbound::ChangeGameState,
+    Clientbound::KeepAlive,
+    Clientbound::ChunkData,
+    Clientbound::Effect,
+    Clientbound::Particle,
+    Clientbound::JoinGame,
+    Clientbound::Map,
+    Clientbound::EntityRelativeMove,
+    Clientbound::EntityLookAndRelativeMove,
+    Clientbound::EntityLook,
+    Clientbound::Entity,
+    Clientbound::VehicleMove,
+    Clientbound::OpenSignEditor,
+    Clientbound::PlayerAbilities,
+    Clientbound::CombatEvent,
+    Clientbound::PlayerListItem,
+    Clientbound::PlayerPositionAndLook,
+    Clientbound::UseBed,
+    Clientbound::DestroyEntities,
+    Clientbound::RemoveEntityEffect,
+    Clientbound::ResourcePackSend,
+    Clientbound::Respawn,
+    Clientbound::EntityHeadLook,
+    Clientbound::WorldBorder,
+    Clientbound::Camera,
+    Clientbound::HeldItemChange,
+    Clientbound::DisplayScoreboard,
+    Clientbound::EntityMetadata,
+    Clientbound::AttachEntity,
+    Clientbound::EntityVelocity,
+    Clientbound::EntityEquipment,
+    Clientbound::SetExperience,
+    Clientbound::UpdateHealth,
+    Clientbound::ScoreboardObjective,
+    Clientbound::SetPassengers,
+    Clientbound::Teams,
+    Clientbound::UpdateScore,
+    Clientbound::SpawnPosition,
+    Clientbound::TimeUpdate,
+    Clientbound::Title,
+    Clientbound::UpdateSign,
+    Clientbound::SoundEffect,
+    Clientbound::PlayerListHeaderAndFooter,
+    Clientbound::CollectItem,
+    Clientbound::EntityTeleport,
+    Clientbound::EntityProperties,
+    Clientbound::EntityEffect,
+];
+
+/// 1.9.4 to 1.11.2. Update sign is gone.
+const CLIENTBOUND_1_9_4: &[Clientbound] = &[
+    Clientbound::SpawnObject,
+    Clientbound::SpawnExperienceOrb,
+    Clientbound::SpawnGlobalEntity,
+    Clientbound::SpawnMob,
+    Clientbound::SpawnPainting,
+    Clientbound::SpawnPlayer,
+    Clientbound::Animation,
+    Clientbound::Statistics,
+    Clientbound::BlockBreakAnimation,
+    Clientbound::UpdateBlockEntity,
+    Clientbound::BlockAction,
+    Clientbound::BlockChange,
+    Clientbound::BossBar,
+    Clientbound::ServerDifficulty,
+    Clientbound::TabComplete,
+    Clientbound::ChatMessage,
+    Clientbound::MultiBlockChange,
+    Clientbound::ConfirmTransaction,
+    Clientbound::CloseWindow,
+    Clientbound::OpenWindow,
+    Clientbound::WindowItems,
+    Clientbound::WindowProperty,
+    Clientbound::SetSlot,
+    Clientbound::SetCooldown,
+    Clientbound::PluginMessage,
+    Clientbound::NamedSoundEffect,
+    Clientbound::Disconnect,
+    Clientbound::EntityStatus,
+    Clientbound::Explosion,
+    Clientbound::UnloadChunk,
+    Clientbound::ChangeGameState,
+    Clientbound::KeepAlive,
+    Clientbound::ChunkData,
+    Clientbound::Effect,
+    Clientbound::Particle,
+    Clientbound::JoinGame,
+    Clientbound::Map,
+    Clientbound::EntityRelativeMove,
+    Clientbound::EntityLookAndRelativeMove,
+    Clientbound::EntityLook,
+    Clientbound::Entity,
+    Clientbound::VehicleMove,
+    Clientbound::OpenSignEditor,
+    Clientbound::PlayerAbilities,
+    Clientbound::CombatEvent,
+    Clientbound::PlayerListItem,
+    Clientbound::PlayerPositionAndLook,
+    Clientbound::UseBed,
+    Clientbound::DestroyEntities,
+    Clientbound::RemoveEntityEffect,
+    Clientbound::ResourcePackSend,
+    Clientbound::Respawn,
+    Clientbound::EntityHeadLook,
+    Clientbound::WorldBorder,
+    Clientbound::Camera,
+    Clientbound::HeldItemChange,
+    Clientbound::DisplayScoreboard,
+    Clientbound::EntityMetadata,
+    Clientbound::AttachEntity,
+    Clientbound::EntityVelocity,
+    Clientbound::EntityEquipment,
+    Clientbound::SetExperience,
+    Clientbound::UpdateHealth,
+    Clientbound::ScoreboardObjective,
+    Clientbound::SetPassengers,
+    Clientbound::Teams,
+    Clientbound::UpdateScore,
+    Clientbound::SpawnPosition,
+    Clientbound::TimeUpdate,
+    Clientbound::Title,
+    Clientbound::SoundEffect,
+    Clientbound::PlayerListHeaderAndFooter,
+    Clientbound::CollectItem,
+    Clientbound::EntityTeleport,
+    Clientbound::EntityProperties,
+    Clientbound::EntityEffect,
+];
+
+/// 1.12. Entity moved in front of the relative moves and recipes and advancements were added.
+const CLIENTBOUND_1_12: &[Clientbound] = &[
+    Clientbound::SpawnObject,
+    Clientbound::SpawnExperienceOrb,
+    Clientbound::SpawnGlobalEntity,
+    Clientbound::SpawnMob,
+    Clientbound::SpawnPainting,
+    Clientbound::SpawnPlayer,
+    Clientbound::Animation,
+    Clientbound::Statistics,
+    Clientbound::BlockBreakAnimation,
+    Clientbound::UpdateBlockEntity,
+    Clientbound::BlockAction,
+    Clientbound::BlockChange,
+    Clientbound::BossBar,
+    Clientbound::ServerDifficulty,
+    Clientbound::TabComplete,
+    Clientbound::ChatMessage,
+    Clientbound::MultiBlockChange,
+    Clientbound::ConfirmTransaction,
+    Clientbound::CloseWindow,
+    Clientbound::OpenWindow,
+    Clientbound::WindowItems,
+    Clientbound::WindowProperty,
+    Clientbound::SetSlot,
+    Clientbound::SetCooldown,
+    Clientbound::PluginMessage,
+    Clientbound::NamedSoundEffect,
+    Clientbound::Disconnect,
+    Clientbound::EntityStatus,
+    Clientbound::Explosion,
+    Clientbound::UnloadChunk,
+    Clientbound::ChangeGameState,
+    Clientbound::KeepAlive,
+    Clientbound::ChunkData,
+    Clientbound::Effect,
+    Clientbound::Particle,
+    Clientbound::JoinGame,
+    Clientbound::Map,
+    Clientbound::Entity,
+    Clientbound::EntityRelativeMove,
+    Clientbound::EntityLookAndRelativeMove,
+    Clientbound::EntityLook,
+    Clientbound::VehicleMove,
+    Clientbound::OpenSignEditor,
+    Clientbound::PlayerAbilities,
+    Clientbound::CombatEvent,
+    Clientbound::PlayerListItem,
+    Clientbound::PlayerPositionAndLook,
+    Clientbound::UseBed,
+    Clientbound::UnlockRecipes,
+    Clientbound::DestroyEntities,
+    Clientbound::RemoveEntityEffect,
+    Clientbound::ResourcePackSend,
+    Clientbound::Respawn,
+    Clientbound::EntityHeadLook,
+    Clientbound::SelectAdvancementTab,
+    Clientbound::WorldBorder,
+    Clientbound::Camera,
+    Clientbound::HeldItemChange,
+    Clientbound::DisplayScoreboard,
+    Clientbound::EntityMetadata,
+    Clientbound::AttachEntity,
+    Clientbound::EntityVelocity,
+    Clientbound::EntityEquipment,
+    Clientbound::SetExperience,
+    Clientbound::UpdateHealth,
+    Clientbound::ScoreboardObjective,
+    Clientbound::SetPassengers,
+    Clientbound::Teams,
+    Clientbound::UpdateScore,
+    Clientbound::SpawnPosition,
+    Clientbound::TimeUpdate,
+    Clientbound::Title,
+    Clientbound::SoundEffect,
+    Clientbound::PlayerListHeaderAndFooter,
+    Clientbound::CollectItem,
+    Clientbound::EntityTeleport,
+    Clientbound::Advancements,
+    Clientbound::EntityProperties,
+    Clientbound::EntityEffect,
+];
+
+/// 1.12.1 and 1.12.2. Craft recipe response was added.
+const CLIENTBOUND_1_12_1: &[Clientbound] = &[
+    Clientbound::SpawnObject,
+    Clientbound::SpawnExperienceOrb,
+    Clientbound::SpawnGlobalEntity,
+    Clientbound::SpawnMob,
+    Clientbound::SpawnPainting,
+    Clientbound::SpawnPlayer,
+    Clientbound::Animation,
+    Clientbound::Statistics,
+    Clientbound::BlockBreakAnimation,
+    Clientbound::UpdateBlockEntity,
+    Clientbound::BlockAction,
+    Clientbound::BlockChange,
+    Clientbound::BossBar,
+    Clientbound::ServerDifficulty,
+    Clientbound::TabComplete,
+    Clientbound::ChatMessage,
+    Clientbound::MultiBlockChange,
+    Clientbound::ConfirmTransaction,
+    Clientbound::CloseWindow,
+    Clientbound::OpenWindow,
+    Clientbound::WindowItems,
+    Clientbound::WindowProperty,
+    Clientbound::SetSlot,
+    Clientbound::SetCooldown,
+    Clientbound::PluginMessage,
+    Clientbound::NamedSoundEffect,
+    Clientbound::Disconnect,
+    Clientbound::EntityStatus,
+    Clientbound::Explosion,
+    Clientbound::UnloadChunk,
+    Clientbound::ChangeGameState,
+    Clientbound::KeepAlive,
+    Clientbound::ChunkData,
+    Clientbound::Effect,
+    Clientbound::Particle,
+    Clientbound::JoinGame,
+    Clientbound::Map,
+    Clientbound::Entity,
+    Clientbound::EntityRelativeMove,
+    Clientbound::EntityLookAndRelativeMove,
+    Clientbound::EntityLook,
+    Clientbound::VehicleMove,
+    Clientbound::OpenSignEditor,
+    Clientbound::CraftRecipeResponse,
+    Clientbound::PlayerAbilities,
+    Clientbound::CombatEvent,
+    Clientbound::PlayerListItem,
+    Clientbound::PlayerPositionAndLook,
+    Clientbound::UseBed,
+    Clientbound::UnlockRecipes,
+    Clientbound::DestroyEntities,
+    Clientbound::RemoveEntityEffect,
+    Clientbound::ResourcePackSend,
+    Clientbound::Respawn,
+    Clientbound::EntityHeadLook,
+    Clientbound::SelectAdvancementTab,
+    Clientbound::WorldBorder,
+    Clientbound::Camera,
+    Clientbound::HeldItemChange,
+    Clientbound::DisplayScoreboard,
+    Clientbound::EntityMetadata,
+    Clientbound::AttachEntity,
+    Clientbound::EntityVelocity,
+    Clientbound::EntityEquipment,
+    Clientbound::SetExperience,
+    Clientbound::UpdateHealth,
+    Clientbound::ScoreboardObjective,
+    Clientbound::SetPassengers,
+    Clientbound::Teams,
+    Clientbound::UpdateScore,
+    Clientbound::SpawnPosition,
+    Clientbound::TimeUpdate,
+    Clientbound::Title,
+    Clientbound::SoundEffect,
+    Clientbound::PlayerListHeaderAndFooter,
+    Clientbound::CollectItem,
+    Clientbound::EntityTeleport,
+    Clientbound::Advancements,
+    Clientbound::EntityProperties,
+    Clientbound::EntityEffect,
+];
+
+/// 1.9 to 1.11.2.
+const SERVERBOUND_1_9: &[Serverbound] = &[
+    Serverbound::TeleportConfirm,
+    Serverbound::TabComplete,
+    Serverbound::ChatMessage,
+    Serverbound::ClientStatus,
+    Serverbound::ClientSettings,
+    Serverbound::ConfirmTransaction,
+    Serverbound::EnchantItem,
+    Serverbound::ClickWindow,
+    Serverbound::CloseWindow,
+    Serverbound::PluginMessage,
+    Serverbound::UseEntity,
+    Serverbound::KeepAlive,
+    Serverbound::PlayerPosition,
+    Serverbound::PlayerPositionAndLook,
+    Serverbound::PlayerLook,
+    Serverbound::Player,
+    Serverbound::VehicleMove,
+    Serverbound::SteerBoat,
+    Serverbound::PlayerAbilities,
+    Serverbound::PlayerDigging,
+    Serverbound::EntityAction,
+    Serverbound::SteerVehicle,
+    Serverbound::ResourcePackStatus,
+    Serverbound::HeldItemChange,
+    Serverbound::CreativeInventoryAction,
+    Serverbound::UpdateSign,
+    Serverbound::Animation,
+    Serverbound::Spectate,
+    Serverbound::PlayerBlockPlacement,
+    Serverbound::UseItem,
+];
+
+const SERVERBOUND_1_12: &[Serverbound] = &[
+    Serverbound::TeleportConfirm,
+    Serverbound::PrepareCraftingGrid,
+    Serverbound::TabComplete,
+    Serverbound::ChatMessage,
+    Serverbound::ClientStatus,
+    Serverbound::ClientSettings,
+    Serverbound::ConfirmTransaction,
+    Serverbound::EnchantItem,
+    Serverbound::ClickWindow,
+    Serverbound::CloseWindow,
+    Serverbound::PluginMessage,
+    Serverbound::UseEntity,
+    Serverbound::KeepAlive,
+    Serverbound::Player,
+    Serverbound::PlayerPosition,
+    Serverbound::PlayerPositionAndLook,
+    Serverbound::PlayerLook,
+    Serverbound::VehicleMove,
+    Serverbound::SteerBoat,
+    Serverbound::PlayerAbilities,
+    Serverbound::PlayerDigging,
+    Serverbound::EntityAction,
+    Serverbound::SteerVehicle,
+    Serverbound::CraftingBookData,
+    Serverbound::ResourcePackStatus,
+    Serverbound::AdvancementTab,
+    Serverbound::HeldItemChange,
+    Serverbound::CreativeInventoryAction,
+    Serverbound::UpdateSign,
+    Serverbound::Animation,
+    Serverbound::Spectate,
+    Serverbound::PlayerBlockPlacement,
+    Serverbound::UseItem,
+];
+
+/// 1.12.1 and 1.12.2. Prepare crafting grid was replaced by craft recipe request.
+const SERVERBOUND_1_12_1: &[Serverbound] = &[
+    Serverbound::TeleportConfirm,
+    Serverbound::TabComplete,
+    Serverbound::ChatMessage,
+    Serverbound::ClientStatus,
+    Serverbound::ClientSettings,
+    Serverbound::ConfirmTransaction,
+    Serverbound::EnchantItem,
+    Serverbound::ClickWindow,
+    Serverbound::CloseWindow,
+    Serverbound::PluginMessage,
+    Serverbound::UseEntity,
+    Serverbound::KeepAlive,
+    Serverbound::Player,
+    Serverbound::PlayerPosition,
+    Serverbound::PlayerPositionAndLook,
+    Serverbound::PlayerLook,
+    Serverbound::VehicleMove,
+    Serverbound::SteerBoat,
+    Serverbound::CraftRecipeRequest,
+    Serverbound::PlayerAbilities,
+    Serverbound::PlayerDigging,
+    Serverbound::EntityAction,
+    Serverbound::SteerVehicle,
+    Serverbound::CraftingBookData,
+    Serverbound::ResourcePackStatus,
+    Serverbound::AdvancementTab,
+    Serverbound::HeldItemChange,
+    Serverbound::CreativeInventoryAction,
+    Serverbound::UpdateSign,
+    Serverbound::Animation,
+    Serverbound::Spectate,
+    Serverbound::PlayerBlockPlacement,
+    Serverbound::UseItem,
+];
+
+fn clientbound_ids(version: ProtocolVersion) -> &'static [Clientbound] {
+    if version >= ProtocolVersion::V1_12_1 {
+        CLIENTBOUND_1_12_1
+    } else if version >= ProtocolVersion::V1_12 {
+        CLIENTBOUND_1_12
+    } else if version >= ProtocolVersion::V1_9_4 {
+        CLIENTBOUND_1_9_4
+    } else {
+        CLIENTBOUND_1_9
+    }
+}
+
+fn serverbound_ids(version: ProtocolVersion) -> &'static [Serverbound] {
+    if version >= ProtocolVersion::V1_12_1 {
+        SERVERBOUND_1_12_1
+    } else if version >= ProtocolVersion::V1_12 {
+        SERVERBOUND_1_12
+    } else {
+        SERVERBOUND_1_9
+    }
+}
+
+fn packet(version: ProtocolVersion, kind: Clientbound) -> Vec<u8> {
+    let id = clientbound_ids(version)
+        .iter()
+        .position(|&k| k == kind)
+        .unwrap_or_else(|| panic!("{:?} is not in the packet ids of {}", kind, version));
+    start(id as i32)
+}
+
+/// Metadata value types.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MetadataType {
+    Byte,
+    VarInt,
+    Float,
+    String,
+    Slot,
+    Boolean,
+}
+
+impl MetadataType {
+    fn to_u8(self) -> u8 {
+        match self {
+            MetadataType::Byte => 0,
+            MetadataType::VarInt => 1,
+            MetadataType::Float => 2,
+            MetadataType::String => 3,
+            MetadataType::Slot => 5,
+            MetadataType::Boolean => 6,
+        }
+    }
+}
+
+/// New index and type of a 1.8 metadata entry. None for entries 1.9 has nothing like.
+fn metadata_index(version: ProtocolVersion, kind: Option<EntityKind>, index: u8) -> Option<(u8, MetadataType)> {
+    // 1.10 added no gravity to every entity, which moved everything after by one.
+    let base_end = if version >= ProtocolVersion::V1_10 { 6 } else { 5 };
+    let living = match kind {
+        Some(EntityKind::Player) | Some(EntityKind::Mob(_)) => true,
+        _ => false,
+    };
+    match index {
+        0 => Some((0, MetadataType::Byte)),
+        1 => Some((1, MetadataType::VarInt)),
+        2 => Some((2, MetadataType::String)),
+        3 => Some((3, MetadataType::Boolean)),
+        4 => Some((4, MetadataType::Boolean)),
+        // the hand state comes first for living entities.
+        6 if living => Some((base_end + 1, MetadataType::Float)),
+        7 if living => Some((base_end + 2, MetadataType::VarInt)),
+        8 if living => Some((base_end + 3, MetadataType::Boolean)),
+        9 if living => Some((base_end + 4, MetadataType::VarInt)),
+        17 if kind == Some(EntityKind::Player) => Some((base_end + 5, MetadataType::Float)),
+        18 if kind == Some(EntityKind::Player) => Some((base_end + 6, MetadataType::VarInt)),
+        10 if kind == Some(EntityKind::Player) => Some((base_end + 7, MetadataType::Byte)),
+        // dropped items.
+        10 if kind == Some(EntityKind::Object(2)) => Some((base_end, MetadataType::Slot)),
+        _ => None,
+    }
+}
+
+fn write_metadata(
+    version: ProtocolVersion,
+    kind: Option<EntityKind>,
+    metadata: &EntityMetadata,
+    w: &mut Vec<u8>,
+) -> io::Result<()> {
+    for (index, entry) in metadata.iter() {
+        let (new_index, typ) = match metadata_index(version, kind, index) {
+            Some(x) => x,
+            None => continue,
+        };
+        let number = match *entry {
+            MetadataEntry::Byte(x) => Some(i32::from(x)),
+            MetadataEntry::Short(x) => Some(i32::from(x)),
+            MetadataEntry::Int(x) => Some(x),
+            _ => None,
+        };
+        match (typ, number, entry) {
+            // using an item moved to the hand state.
+            (MetadataType::Byte, Some(x), _) if index == 0 => write_metadata_header(w, new_index, typ, |w| write_byte(w, x as i8 & !0x10))?,
+            (MetadataType::Byte, Some(x), _) => write_metadata_header(w, new_index, typ, |w| write_byte(w, x as i8))?,
+            (MetadataType::VarInt, Some(x), _) => write_metadata_header(w, new_index, typ, |w| write_varint(w, x))?,
+            (MetadataType::Boolean, Some(x), _) => write_metadata_header(w, new_index, typ, |w| write_bool(w, x != 0))?,
+            (MetadataType::Float, _, &MetadataEntry::Float(x)) => write_metadata_header(w, new_index, typ, |w| write_float(w, x))?,
+            (MetadataType::String, _, &MetadataEntry::String(ref x)) => {
+                write_metadata_header(w, new_index, typ, |w| data::write_string(w, x))?
+            }
+            (MetadataType::Slot, _, &MetadataEntry::Slot(ref x)) => write_metadata_header(w, new_index, typ, |w| x.write(w))?,
+            _ => debug!("metadata {} of type {:?} doesn't fit {:?}", index, entry, typ),
+        }
+    }
+    write_ubyte(w, 0xff)
+}
+
+fn write_metadata_header<F>(w: &mut Vec<u8>, index: u8, typ: MetadataType, f: F) -> io::Result<()>
+where
+    F: FnOnce(&mut Vec<u8>) -> io::Result<()>,
+{
+    write_ubyte(w, index)?;
+    write_ubyte(w, typ.to_u8())?;
+    f(w)
+}
+
+/// 1.9 entities have uuids. The server doesn't give non-player entities any so they are made from the entity id.
+fn entity_uuid(entity_id: i32) -> Uuid {
+    let mut bytes = [0; 16];
+    bytes[12..].copy_from_slice(&(entity_id as u32).to_be_bytes());
+    Uuid::from_bytes(&bytes).unwrap()
+}
+
+/// Sound names were changed to say what makes the sound. Only the sounds the server plays are renamed.
+fn sound_name(name: &str) -> Option<String> {
+    let mut parts = name.split('.');
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some("dig"), Some(material), None) => return Some(format!("block.{}.break", material)),
+        (Some("step"), Some(material), None) => return Some(format!("block.{}.step", material)),
+        _ => {}
+    }
+    let renamed = match name {
+        "random.click" => "ui.button.click",
+        "random.pop" => "entity.item.pickup",
+        "random.orb" => "entity.experience_orb.pickup",
+        "random.break" => "entity.item.break",
+        "random.levelup" => "entity.player.levelup",
+        "random.door_open" => "block.wooden_door.open",
+        "random.door_close" => "block.wooden_door.close",
+        "random.chestopen" => "block.chest.open",
+        "random.chestclosed" => "block.chest.close",
+        _ => return None,
+    };
+    Some(renamed.into())
+}
+
+/// Sound category. Blocks, players and the interface have their own volume sliders.
+fn sound_category(name: &str) -> i32 {
+    if name.starts_with("block.") {
+        4
+    } else if name.starts_with("ui.") {
+        0
+    } else {
+        7
+    }
+}
+
+/// Bits per block of a section with this many different states.
+fn bits_per_block(palette_len: usize) -> u8 {
+    let mut bits = 4;
+    while palette_len > 1 << bits {
+        bits += 1;
+    }
+    // longer palettes aren't allowed. the global palette uses 13 bits.
+    if bits > 8 {
+        13
+    } else {
+        bits
+    }
+}
+
+/// Writes a chunk section with a palette of the states in it.
+fn write_section(section: &ChunkSection, w: &mut Vec<u8>) -> io::Result<()> {
+    let states: Vec<u16> = section.blocks.chunks(2).map(|b| u16::from(b[0]) | u16::from(b[1]) << 8).collect();
+    let mut palette = Vec::new();
+    let mut indices = HashMap::new();
+    for &state in &states {
+        indices.entry(state).or_insert_with(|| {
+            palette.push(state);
+            palette.len() as u64 - 1
+        });
+    }
+    let bits = bits_per_block(palette.len());
+    write_ubyte(w, bits)?;
+    if bits <= 8 {
+        write_varint(w, palette.len() as i32)?;
+        for &state in &palette {
+            write_varint(w, i32::from(state))?;
+        }
+    } else {
+        write_varint(w, 0)?;
+    }
+
+    // values can span two longs.
+    let mut longs = vec![0u64; CHUNK_SECTION_BLOCK_COUNT * bits as usize / 64];
+    for (i, &state) in states.iter().enumerate() {
+        let value = if bits <= 8 { indices[&state] } else { u64::from(state) };
+        let bit = i * bits as usize;
+        let (long, offset) = (bit / 64, bit % 64);
+        longs[long] |= value << offset;
+        if offset + bits as usize > 64 {
+            longs[long + 1] |= value >> (64 - offset);
+        }
+    }
+    write_varint(w, longs.len() as i32)?;
+    for &l in &longs {
+        write_long(w, l as i64)?;
+    }
+
+    w.extend_from_slice(&section.block_light);
+    if let Some(ref sky_light) = section.sky_light {
+        w.extend_from_slice(sky_light);
+    }
+    Ok(())
+}
+
+fn write_chunk(
+    version: ProtocolVersion,
+    chunk_x: i32,
+    chunk_z: i32,
+    primary_bit_mask: u16,
+    sections: &GroundUpNonContinuous,
+    biomes: Option<&[[u8; 16]; 16]>,
+    out: &mut Vec<Vec<u8>>,
+) -> io::Result<()> {
+    // 1.8 unloads chunks with empty full chunks.
+    if biomes.is_some() && primary_bit_mask == 0 {
+        let mut w = packet(version, Clientbound::UnloadChunk);
+        write_int(&mut w, chunk_x)?;
+        write_int(&mut w, chunk_z)?;
+        out.push(w);
+        return Ok(());
+    }
+
+    let mut data = Vec::new();
+    for section in &sections.sections {
+        write_section(section, &mut data)?;
+    }
+    if let Some(biomes) = biomes {
+        for z in biomes {
+            data.extend_from_slice(z);
+        }
+    }
+
+    let mut w = packet(version, Clientbound::ChunkData);
+    write_int(&mut w, chunk_x)?;
+    write_int(&mut w, chunk_z)?;
+    write_bool(&mut w, biomes.is_some())?;
+    write_varint(&mut w, i32::from(primary_bit_mask))?;
+    data::write_byte_array(&mut w, &data)?;
+    if version >= ProtocolVersion::V1_9_4 {
+        // block entities.
+        write_varint(&mut w, 0)?;
+    }
+    out.push(w);
+    Ok(())
+}
+
+pub fn write(t: &mut Translator, packet: &SPacket, out: &mut Vec<Vec<u8>>) -> io::Result<()> {
+    let v = t.version;
+    let p = |kind| self::packet(v, kind);
+    match *packet {
+        SPacket::PlayKeepAlive { id } => {
+            let mut w = p(Clientbound::KeepAlive);
+            if v >= ProtocolVersion::V1_12_2 {
+                write_long(&mut w, i64::from(id))?;
+            } else {
+                write_varint(&mut w, id)?;
+            }
+            out.push(w);
+        }
+        SPacket::PlayJoinGame { entity_id, gamemode, dimension, difficulty, max_players, ref level_type, reduced_debug_info } => {
+            let mut w = p(Clientbound::JoinGame);
+            write_int(&mut w, entity_id)?;
+            write_ubyte(&mut w, gamemode)?;
+            if v >= ProtocolVersion::V1_9_1 {
+                write_int(&mut w, i32::from(dimension))?;
+            } else {
+                write_byte(&mut w, dimension)?;
+            }
+            write_ubyte(&mut w, difficulty)?;
+            write_ubyte(&mut w, max_players)?;
+            data::write_string(&mut w, level_type)?;
+            write_bool(&mut w, reduced_debug_info)?;
+            out.push(w);
+        }
+        SPacket::PlayChatMessage { .. } => out.push(same(v, Clientbound::ChatMessage, packet)?),
+        SPacket::PlayTimeUpdate { .. } => out.push(same(v, Clientbound::TimeUpdate, packet)?),
+        SPacket::PlayEntityEquipment { entity_id, slot, ref item } => {
+            let mut w = p(Clientbound::EntityEquipment);
+            write_varint(&mut w, entity_id)?;
+            // the off hand was put after the main hand.
+            write_varint(&mut w, if slot == 0 { 0 } else { i32::from(slot) + 1 })?;
+            item.write(&mut w)?;
+            out.push(w);
+        }
+        SPacket::PlaySpawnPosition { .. } => out.push(same(v, Clientbound::SpawnPosition, packet)?),
+        SPacket::PlayUpdateHealth { .. } => out.push(same(v, Clientbound::UpdateHealth, packet)?),
+        SPacket::PlayRespawn { .. } => out.push(same(v, Clientbound::Respawn, packet)?),
+        SPacket::PlayPlayerPositionAndLook { .. } => {
+            let mut w = same(v, Clientbound::PlayerPositionAndLook, packet)?;
+            write_varint(&mut w, t.next_teleport_id)?;
+            t.next_teleport_id = t.next_teleport_id.wrapping_add(1);
+            out.push(w);
+        }
+        SPacket::PlayHeldItemChange { .. } => out.push(same(v, Clientbound::HeldItemChange, packet)?),
+        SPacket::PlayUseBed { .. } => out.push(same(v, Clientbound::UseBed, packet)?),
+        // eating was removed, 3 swings the off hand now.
+        SPacket::PlayAnimation { animation: 3, .. } => {}
+        SPacket::PlayAnimation { .. } => out.push(same(v, Clientbound::Animation, packet)?),
+        SPacket::PlaySpawnPlayer { entity_id, uuid, x, y, z, yaw, pitch, ref metadata, .. } => {
+            let mut w = p(Clientbound::SpawnPlayer);
+            write_varint(&mut w, entity_id)?;
+            data::write_uuid(&mut w, &uuid)?;
+            write_double(&mut w, x)?;
+            write_double(&mut w, y)?;
+            write_double(&mut w, z)?;
+            data::write_angle(&mut w, yaw)?;
+            data::write_angle(&mut w, pitch)?;
+            write_metadata(v, Some(EntityKind::Player), metadata, &mut w)?;
+            out.push(w);
+        }
+        SPacket::PlayCollectItem { collected_entity_id, collector_entity_id } => {
+            let mut w = p(Clientbound::CollectItem);
+            write_varint(&mut w, collected_entity_id)?;
+            write_varint(&mut w, collector_entity_id)?;
+            if v >= ProtocolVersion::V1_11 {
+                write_varint(&mut w, 1)?;
+            }
+            out.push(w);
+        }
+        SPacket::PlaySpawnObject { entity_id, object_type, x, y, z, pitch, yaw, ref data } => {
+            let mut w = p(Clientbound::SpawnObject);
+            write_varint(&mut w, entity_id)?;
+            data::write_uuid(&mut w, &entity_uuid(entity_id))?;
+            write_byte(&mut w, object_type)?;
+            write_double(&mut w, x)?;
+            write_double(&mut w, y)?;
+            write_double(&mut w, z)?;
+            data::write_angle(&mut w, pitch)?;
+            data::write_angle(&mut w, yaw)?;
+            // velocity is always sent now.
+            match *data {
+                Some(ref d) => {
+                    write_int(&mut w, d.data)?;
+                    write_ishort(&mut w, d.velocity_x)?;
+                    write_ishort(&mut w, d.velocity_y)?;
+                    write_ishort(&mut w, d.velocity_z)?;
+                }
+                None => {
+                    write_int(&mut w, 0)?;
+                    w.extend_from_slice(&[0; 6]);
+                }
+            }
+            out.push(w);
+        }
+        SPacket::PlaySpawnMob {
+            entity_id,
+            mob_type,
+            x,
+            y,
+            z,
+            yaw,
+            pitch,
+            head_pitch,
+            velocity_x,
+            velocity_y,
+            velocity_z,
+            ref metadata,
+        } => {
+            let mut w = p(Clientbound::SpawnMob);
+            write_varint(&mut w, entity_id)?;
+            data::write_uuid(&mut w, &entity_uuid(entity_id))?;
+            if v >= ProtocolVersion::V1_11 {
+                write_varint(&mut w, i32::from(mob_type))?;
+            } else {
+                write_ubyte(&mut w, mob_type)?;
+            }
+            write_double(&mut w, x)?;
+            write_double(&mut w, y)?;
+            write_double(&mut w, z)?;
+            data::write_angle(&mut w, yaw)?;
+            data::write_angle(&mut w, pitch)?;
+            data::write_angle(&mut w, head_pitch)?;
+            write_ishort(&mut w, velocity_x)?;
+            write_ishort(&mut w, velocity_y)?;
+            write_ishort(&mut w, velocity_z)?;
+            write_metadata(v, Some(EntityKind::Mob(mob_type)), metadata, &mut w)?;
+            out.push(w);
+        }
+        SPacket::PlaySpawnPainting { entity_id, ref title, location, direction } => {
+            let mut w = p(Clientbound::SpawnPainting);
+            write_varint(&mut w, entity_id)?;
+            data::write_uuid(&mut w, &entity_uuid(entity_id))?;
+            data::write_string(&mut w, title)?;
+            location.write_proto(&mut w)?;
+            write_ubyte(&mut w, direction)?;
+            out.push(w);
+        }
+        SPacket::PlaySpawnExperienceOrb { entity_id, x, y, z, count } => {
+            let mut w = p(Clientbound::SpawnExperienceOrb);
+            write_varint(&mut w, entity_id)?;
+            write_double(&mut w, x)?;
+            write_double(&mut w, y)?;
+            write_double(&mut w, z)?;
+            write_ishort(&mut w, count)?;
+            out.push(w);
+        }
+        SPacket::PlayEntityVelocity { .. } => out.push(same(v, Clientbound::EntityVelocity, packet)?),
+        SPacket::PlayDestroyEntities { .. } => out.push(same(v, Clientbound::DestroyEntities, packet)?),
+        SPacket::PlayEntity { .. } => out.push(same(v, Clientbound::Entity, packet)?),
+        // relative moves are in 1/4096 blocks instead of 1/32.
+        SPacket::PlayEntityRelativeMove { entity_id, delta_x, delta_y, delta_z, on_ground } => {
+            let mut w = p(Clientbound::EntityRelativeMove);
+            write_varint(&mut w, entity_id)?;
+            write_ishort(&mut w, i16::from(delta_x) * 128)?;
+            write_ishort(&mut w, i16::from(delta_y) * 128)?;
+            write_ishort(&mut w, i16::from(delta_z) * 128)?;
+            write_bool(&mut w, on_ground)?;
+            out.push(w);
+        }
+        SPacket::PlayEntityLook { .. } => out.push(same(v, Clientbound::EntityLook, packet)?),
+        SPacket::PlayEntityLookAndRelativeMove { entity_id, delta_x, delta_y, delta_z, yaw, pitch, on_ground } => {
+            let mut w = p(Clientbound::EntityLookAndRelativeMove);
+            write_varint(&mut w, entity_id)?;
+            write_ishort(&mut w, i16::from(delta_x) * 128)?;
+            write_ishort(&mut w, i16::from(delta_y) * 128)?;
+            write_ishort(&mut w, i16::from(delta_z) * 128)?;
+            data::write_angle(&mut w, yaw)?;
+            data::write_angle(&mut w, pitch)?;
+            write_bool(&mut w, on_ground)?;
+            out.push(w);
+        }
+        SPacket::PlayEntityTeleport { entity_id, x, y, z, yaw, pitch, on_ground } => {
+            let mut w = p(Clientbound::EntityTeleport);
+            write_varint(&mut w, entity_id)?;
+            write_double(&mut w, x)?;
+            write_double(&mut w, y)?;
+            write_double(&mut w, z)?;
+            data::write_angle(&mut w, yaw)?;
+            data::write_angle(&mut w, pitch)?;
+            write_bool(&mut w, on_ground)?;
+            out.push(w);
+        }
+        SPacket::PlayEntityHeadLook { .. } => out.push(same(v, Clientbound::EntityHeadLook, packet)?),
+        SPacket::PlayEntityStatus { .. } => out.push(same(v, Clientbound::EntityStatus, packet)?),
+        SPacket::PlayAttachEntity { entity_id, vehicle_id, leash: true } => {
+            let mut w = p(Clientbound::AttachEntity);
+            write_int(&mut w, entity_id)?;
+            write_int(&mut w, vehicle_id)?;
+            out.push(w);
+        }
+        // riding is set on the vehicle now. -1 gets off the vehicle from before.
+        SPacket::PlayAttachEntity { entity_id, vehicle_id, leash: false } => {
+            let (vehicle_id, passengers) = if vehicle_id == -1 {
+                match t.vehicles.get(&entity_id) {
+                    Some(&old) => (old, Vec::new()),
+                    None => return Ok(()),
+                }
+            } else {
+                (vehicle_id, vec![entity_id])
+            };
+            let mut w = p(Clientbound::SetPassengers);
+            write_varint(&mut w, vehicle_id)?;
+            write_varint(&mut w, passengers.len() as i32)?;
+            for passenger in passengers {
+                write_varint(&mut w, passenger)?;
+            }
+            out.push(w);
+        }
+        SPacket::PlayEntityMetadata { entity_id, ref metadata } => {
+            let mut w = p(Clientbound::EntityMetadata);
+            write_varint(&mut w, entity_id)?;
+            write_metadata(v, t.entities.get(&entity_id).cloned(), metadata, &mut w)?;
+            out.push(w);
+        }
+        SPacket::PlayEntityEffect { entity_id, effect_id, amplifier, duration, hide_particles } => {
+            let mut w = p(Clientbound::EntityEffect);
+            write_varint(&mut w, entity_id)?;
+            write_byte(&mut w, effect_id)?;
+            write_byte(&mut w, amplifier)?;
+            write_varint(&mut w, duration)?;
+            // flags. 0x02 shows particles.
+            write_byte(&mut w, if hide_particles { 0 } else { 0x02 })?;
+            out.push(w);
+        }
+        SPacket::PlayRemoveEntityEffect { .. } => out.push(same(v, Clientbound::RemoveEntityEffect, packet)?),
+        SPacket::PlaySetExperience { .. } => out.push(same(v, Clientbound::SetExperience, packet)?),
+        SPacket::PlayEntityProperties { .. } => out.push(same(v, Clientbound::EntityProperties, packet)?),
+        SPacket::PlayChunkData { chunk_x, chunk_z, primary_bit_mask, ref data } => match *data {
+            SPlayChunkDataData::GroundUpContinuous(ref g) => {
+                write_chunk(v, chunk_x, chunk_z, primary_bit_mask, &g.sections, Some(&g.biome_array), out)?
+            }
+            SPlayChunkDataData::GroundUpNonContinuous(ref g) => {
+                write_chunk(v, chunk_x, chunk_z, primary_bit_mask, g, None, out)?
+            }
+        },
+        SPacket::PlayMultiBlockChange { .. } => out.push(same(v, Clientbound::MultiBlockChange, packet)?),
+        SPacket::PlayBlockChange { .. } => out.push(same(v, Clientbound::BlockChange, packet)?),
+        SPacket::PlayBlockAction { .. } => out.push(same(v, Clientbound::BlockAction, packet)?),
+        SPacket::PlayBlockBreakAnimation { .. } => out.push(same(v, Clientbound::BlockBreakAnimation, packet)?),
+        SPacket::PlayMapChunkBulk { ref chunks, .. } => {
+            for c in chunks {
+                write_chunk(v, c.chunk_x, c.chunk_z, c.primary_bit_mask, &c.chunk.sections, Some(&c.chunk.biome_array), out)?;
+            }
+        }
+        SPacket::PlayExplosion { .. } => out.push(same(v, Clientbound::Explosion, packet)?),
+        SPacket::PlayEffect { .. } => out.push(same(v, Clientbound::Effect, packet)?),
+        SPacket::PlaySoundEffect { ref name, effect_pos_x, effect_pos_y, effect_pos_z, volume, pitch } => {
+            let name = match sound_name(name) {
+                Some(name) => name,
+                None => {
+                    debug!("no {} sound for {}", v, name);
+                    return Ok(());
+                }
+            };
+            let mut w = p(Clientbound::NamedSoundEffect);
+            data::write_string(&mut w, &name)?;
+            write_varint(&mut w, sound_category(&name))?;
+            write_int(&mut w, (effect_pos_x * 8.0) as i32)?;
+            write_int(&mut w, (effect_pos_y * 8.0) as i32)?;
+            write_int(&mut w, (effect_pos_z * 8.0) as i32)?;
+            write_float(&mut w, volume)?;
+            if v >= ProtocolVersion::V1_10 {
+                write_float(&mut w, pitch)?;
+            } else {
+                write_ubyte(&mut w, (pitch * 63.0).round() as u8)?;
+            }
+            out.push(w);
+        }
+        SPacket::PlayParticle { .. } => out.push(same(v, Clientbound::Particle, packet)?),
+        SPacket::PlayChangeGameState { .. } => out.push(same(v, Clientbound::ChangeGameState, packet)?),
+        SPacket::PlaySpawnGlobalEntity { entity_id, entity_type, x, y, z } => {
+            let mut w = p(Clientbound::SpawnGlobalEntity);
+            write_varint(&mut w, entity_id)?;
+            write_byte(&mut w, entity_type)?;
+            write_double(&mut w, x)?;
+            write_double(&mut w, y)?;
+            write_double(&mut w, z)?;
+            out.push(w);
+        }
+        SPacket::PlayOpenWindow { .. } => out.push(same(v, Clientbound::OpenWindow, packet)?),
+        SPacket::PlayCloseWindow { .. } => out.push(same(v, Clientbound::CloseWindow, packet)?),
+        SPacket::PlaySetSlot { .. } => out.push(same(v, Clientbound::SetSlot, packet)?),
+        SPacket::PlayWindowItems { .. } => out.push(same(v, Clientbound::WindowItems, packet)?),
+        SPacket::PlayWindowProperty { .. } => out.push(same(v, Clientbound::WindowProperty, packet)?),
+        SPacket::PlayConfirmTransaction { .. } => out.push(same(v, Clientbound::ConfirmTransaction, packet)?),
+        SPacket::PlayUpdateSign { .. } if v < ProtocolVersion::V1_9_4 => out.push(same(v, Clientbound::UpdateSign, packet)?),
+        // signs are block entities like any other now.
+        SPacket::PlayUpdateSign { location, ref line1, ref line2, ref line3, ref line4 } => {
+            let mut tag = HashMap::new();
+            tag.insert("id".to_string(), Tag::String("Sign".into()));
+            tag.insert("x".to_string(), Tag::Int(location.x));
+            tag.insert("y".to_string(), Tag::Int(i32::from(location.y)));
+            tag.insert("z".to_string(), Tag::Int(location.z));
+            for (i, line) in [line1, line2, line3, line4].iter().enumerate() {
+                let json = serde_json::to_string(line).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+                tag.insert(format!("Text{}", i + 1), Tag::String(json));
+            }
+            let mut w = p(Clientbound::UpdateBlockEntity);
+            location.write_proto(&mut w)?;
+            write_ubyte(&mut w, 9)?;
+            Nbt::Some(String::new(), Compound(tag)).write(&mut w)?;
+            out.push(w);
+        }
+        SPacket::PlayMap { map_id, scale, ref icons, ref data } => {
+            let mut w = p(Clientbound::Map);
+            write_varint(&mut w, map_id)?;
+            write_byte(&mut w, scale)?;
+            // tracking position.
+            write_bool(&mut w, true)?;
+            write_varint(&mut w, icons.len() as i32)?;
+            for i in icons {
+                write_byte(&mut w, (i.icon_type << 4) | (i.direction & 0x0f))?;
+                write_byte(&mut w, i.x)?;
+                write_byte(&mut w, i.z)?;
+            }
+            match *data {
+                Some(ref d) => {
+                    write_byte(&mut w, d.columns)?;
+                    write_byte(&mut w, d.rows)?;
+                    write_byte(&mut w, d.x)?;
+                    write_byte(&mut w, d.z)?;
+                    data::write_byte_array(&mut w, &d.data)?;
+                }
+                None => write_byte(&mut w, 0)?,
+            }
+            out.push(w);
+        }
+        SPacket::PlayUpdateBlockEntity { .. } => out.push(same(v, Clientbound::UpdateBlockEntity, packet)?),
+        SPacket::PlayOpenSignEditor { .. } => out.push(same(v, Clientbound::OpenSignEditor, packet)?),
+        SPacket::PlayStatistics { .. } => out.push(same(v, Clientbound::Statistics, packet)?),
+        SPacket::PlayPlayerListItem { .. } => out.push(same(v, Clientbound::PlayerListItem, packet)?),
+        SPacket::PlayPlayerAbilities { .. } => out.push(same(v, Clientbound::PlayerAbilities, packet)?),
+        SPacket::PlayTabComplete { .. } => out.push(same(v, Clientbound::TabComplete, packet)?),
+        SPacket::PlayScoreboardObjective { .. } => out.push(same(v, Clientbound::ScoreboardObjective, packet)?),
+        SPacket::PlayUpdateScore { .. } => out.push(same(v, Clientbound::UpdateScore, packet)?),
+        SPacket::PlayDisplayScoreboard { .. } => out.push(same(v, Clientbound::DisplayScoreboard, packet)?),
+        SPacket::PlayTeams { ref team_name, ref data } => {
+            let mut w = p(Clientbound::Teams);
+            data::write_string(&mut w, team_name)?;
+            match *data {
+                SPlayTeamsData::CreateTeam {
+                    ref display_name,
+                    ref prefix,
+                    ref suffix,
+                    friendly_fire,
+                    ref name_tag_visibility,
+                    color,
+                    ref players,
+                } => {
+                    write_byte(&mut w, 0)?;
+                    write_team_info(&mut w, display_name, prefix, suffix, friendly_fire, name_tag_visibility, color)?;
+                    write_varint(&mut w, players.len() as i32)?;
+                    for p in players {
+                        data::write_string(&mut w, p)?;
+                    }
+                }
+                SPlayTeamsData::RemoveTeam => write_byte(&mut w, 1)?,
+                SPlayTeamsData::UpdateTeam {
+                    ref display_name,
+                    ref prefix,
+                    ref suffix,
+                    friendly_fire,
+                    ref name_tag_visibility,
+                    color,
+                } => {
+                    write_byte(&mut w, 2)?;
+                    write_team_info(&mut w, display_name, prefix, suffix, friendly_fire, name_tag_visibility, color)?;
+                }
+                SPlayTeamsData::AddPlayers { ref players } | SPlayTeamsData::RemovePlayers { ref players } => {
+                    let mode = if let SPlayTeamsData::AddPlayers { .. } = *data { 3 } else { 4 };
+                    write_byte(&mut w, mode)?;
+                    write_varint(&mut w, players.len() as i32)?;
+                    for p in players {
+                        data::write_string(&mut w, p)?;
+                    }
+                }
+            }
+            out.push(w);
+        }
+        SPacket::PlayPluginMessage { .. } => out.push(same(v, Clientbound::PluginMessage, packet)?),
+        SPacket::PlayDisconnect { .. } => out.push(same(v, Clientbound::Disconnect, packet)?),
+        SPacket::PlayServerDifficulty { .. } => out.push(same(v, Clientbound::ServerDifficulty, packet)?),
+        SPacket::PlayCombatEvent { .. } => out.push(same(v, Clientbound::CombatEvent, packet)?),
+        SPacket::PlayCamera { .. } => out.push(same(v, Clientbound::Camera, packet)?),
+        SPacket::PlayWorldBorder { .. } => out.push(same(v, Clientbound::WorldBorder, packet)?),
+        // the action bar got its own title action in 1.11.
+        SPacket::PlayTitle { .. } if v >= ProtocolVersion::V1_11 => {
+            let mut fields = Vec::new();
+            packet.write(&mut fields)?;
+            let mut r = &fields[..];
+            let action = read_varint(&mut r).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+            let mut w = p(Clientbound::Title);
+            write_varint(&mut w, if action >= 2 { action + 1 } else { action })?;
+            w.extend_from_slice(r);
+            out.push(w);
+        }
+        SPacket::PlayTitle { .. } => out.push(same(v, Clientbound::Title, packet)?),
+        SPacket::PlayPlayerListHeaderAndFooter { .. } => out.push(same(v, Clientbound::PlayerListHeaderAndFooter, packet)?),
+        SPacket::PlayResourcePackSend { .. } => out.push(same(v, Clientbound::ResourcePackSend, packet)?),
+        _ => debug!("{} has nothing like {:?}", v, packet),
+    }
+    Ok(())
+}
+
+/// Packet whose fields didn't change since 1.8.
+fn same(version: ProtocolVersion, kind: Clientbound, packet: &SPacket) -> io::Result<Vec<u8>> {
+    let mut w = self::packet(version, kind);
+    packet.write(&mut w)?;
+    Ok(w)
+}
+
+fn write_team_info(
+    w: &mut Vec<u8>,
+    display_name: &str,
+    prefix: &str,
+    suffix: &str,
+    friendly_fire: i8,
+    name_tag_visibility: &str,
+    color: text::Code,
+) -> io::Result<()> {
+    data::write_string(w, display_name)?;
+    data::write_string(w, prefix)?;
+    data::write_string(w, suffix)?;
+    write_byte(w, friendly_fire)?;
+    data::write_string(w, name_tag_visibility)?;
+    // collision rule.
+    data::write_string(w, "always")?;
+    write_byte(w, color.to_color_index())
+}
+
+pub fn read(t: &mut Translator, r: &mut &[u8], id: i32) -> Result<Option<CPacket>> {
+    let v = t.version;
+    let kind = match serverbound_ids(v).get(id as usize) {
+        Some(&kind) if id >= 0 => kind,
+        _ => return Err(Error::InvalidPacketId(id)),
+    };
+    let packet = match kind {
+        Serverbound::TeleportConfirm
+        | Serverbound::PrepareCraftingGrid
+        | Serverbound::VehicleMove
+        | Serverbound::SteerBoat
+        | Serverbound::CraftRecipeRequest
+        | Serverbound::CraftingBookData
+        | Serverbound::AdvancementTab => {
+            skip(r);
+            return Ok(None);
+        }
+        Serverbound::TabComplete => {
+            let text = data::read_string(r)?;
+            // assume command.
+            read_bool(r)?;
+            let pos = if read_bool(r)? { Some(BlockPos::read_proto(r)?) } else { None };
+            CPacket::PlayTabComplete { text, pos }
+        }
+        Serverbound::ChatMessage => CPacket::PlayChatMessage { message: data::read_string(r)? },
+        Serverbound::ClientStatus => CPacket::PlayClientStatus { action_id: read_varint(r)? },
+        Serverbound::ClientSettings => {
+            let packet = CPacket::PlayClientSettings {
+                locale: data::read_string(r)?,
+                view_distance: read_byte(r)?,
+                chat_mode: read_varint(r)? as i8,
+                chat_colors: read_bool(r)?,
+                displayed_skin_parts: read_ubyte(r)?,
+            };
+            // main hand.
+            read_varint(r)?;
+            packet
+        }
+        Serverbound::ConfirmTransaction => CPacket::PlayConfirmTransaction {
+            window_id: read_byte(r)?,
+            action_num: read_ishort(r)?,
+            accepted: read_bool(r)?,
+        },
+        Serverbound::EnchantItem => CPacket::PlayEnchantItem { window_id: read_byte(r)?, enchantment: read_byte(r)? },
+        Serverbound::ClickWindow => CPacket::PlayClickWindow {
+            window_id: read_ubyte(r)?,
+            slot: read_ishort(r)?,
+            button: read_byte(r)?,
+            action_num: read_ishort(r)?,
+            mode: read_varint(r)? as i8,
+            clicked_item: data::SlotData::read(r)?,
+        },
+        Serverbound::CloseWindow => CPacket::PlayCloseWindow { window_id: read_ubyte(r)? },
+        Serverbound::PluginMessage => {
+            let channel = data::read_string(r)?;
+            let data = r.to_vec();
+            skip(r);
+            CPacket::PlayPluginMessage { channel, data }
+        }
+        Serverbound::UseEntity => {
+            let target = read_varint(r)?;
+            let data = match read_varint(r)? {
+                0 => CPlayUseEntityData::Interact,
+                1 => CPlayUseEntityData::Attack,
+                2 => CPlayUseEntityData::InteractAt { target_x: read_float(r)?, target_y: read_float(r)?, target_z: read_float(r)? },
+                _ => CPlayUseEntityData::Unknown,
+            };
+            match data {
+                CPlayUseEntityData::Interact | CPlayUseEntityData::InteractAt { .. } => {
+                    // the off hand interacts separately.
+                    if read_varint(r)? != 0 {
+                        return Ok(None);
+                    }
+                }
+                _ => {}
+            }
+            CPacket::PlayUseEntity { target, data }
+        }
+        Serverbound::KeepAlive => {
+            let id = if v >= ProtocolVersion::V1_12_2 { read_long(r)? as i32 } else { read_varint(r)? };
+            CPacket::PlayKeepAlive { id }
+        }
+        Serverbound::Player => CPacket::PlayPlayer { on_ground: read_bool(r)? },
+        Serverbound::PlayerPosition => CPacket::PlayPlayerPosition {
+            x: read_double(r)?,
+            feet_y: read_double(r)?,
+            z: read_double(r)?,
+            on_ground: read_bool(r)?,
+        },
+        Serverbound::PlayerPositionAndLook => CPacket::PlayPlayerPositionAndLook {
+            x: read_double(r)?,
+            feet_y: read_double(r)?,
+            z: read_double(r)?,
+            yaw: read_float(r)?,
+            pitch: read_float(r)?,
+            on_ground: read_bool(r)?,
+        },
+        Serverbound::PlayerLook => CPacket::PlayPlayerLook { yaw: read_float(r)?, pitch: read_float(r)?, on_ground: read_bool(r)? },
+        Serverbound::PlayerAbilities => CPacket::PlayPlayerAbilities {
+            flags: read_byte(r)?,
+            flying_speed: read_float(r)?,
+            walking_speed: read_float(r)?,
+        },
+        Serverbound::PlayerDigging => {
+            let status = read_varint(r)?;
+            let location = BlockPos::read_proto(r)?;
+            let face = read_byte(r)?;
+            // swapping hands.
+            if status == 6 {
+                return Ok(None);
+            }
+            CPacket::PlayPlayerDigging { status: status as i8, location, face }
+        }
+        Serverbound::EntityAction => {
+            let entity_id = read_varint(r)?;
+            let action_id = match read_varint(r)? {
+                // opening the horse inventory moved behind stopping a horse jump, which 1.8 doesn't have.
+                7 => 6,
+                6 | 8 => {
+                    skip(r);
+                    return Ok(None);
+                }
+                action => action,
+            };
+            CPacket::PlayEntityAction { entity_id, action_id, action_param: read_varint(r)? }
+        }
+        Serverbound::SteerVehicle => CPacket::PlaySteerVehicle { sideways: read_float(r)?, forward: read_float(r)?, flags: read_ubyte(r)? },
+        Serverbound::ResourcePackStatus => {
+            let hash = if v >= ProtocolVersion::V1_10 { String::new() } else { data::read_string(r)? };
+            CPacket::PlayResourcePackStatus { hash, result: read_varint(r)? }
+        }
+        Serverbound::HeldItemChange => CPacket::PlayHeldItemChange { slot: read_ishort(r)? },
+        Serverbound::CreativeInventoryAction => {
+            CPacket::PlayCreativeInventoryAction { slot: read_ishort(r)?, clicked_item: data::SlotData::read(r)? }
+        }
+        Serverbound::UpdateSign => {
+            let location = BlockPos::read_proto(r)?;
+            let mut line = || -> Result<Chat> { Ok(Chat::from(text::parse_legacy(&data::read_string(r)?))) };
+            CPacket::PlayUpdateSign { location, line1: line()?, line2: line()?, line3: line()?, line4: line()? }
+        }
+        Serverbound::Animation => {
+            if read_varint(r)? != 0 {
+                return Ok(None);
+            }
+            CPacket::PlayAnimation {}
+        }
+        Serverbound::Spectate => CPacket::PlaySpectate { target_player: data::read_uuid(r)? },
+        Serverbound::PlayerBlockPlacement => {
+            let location = BlockPos::read_proto(r)?;
+            let face = read_varint(r)? as i8;
+            let hand = read_varint(r)?;
+            // cursor positions are floats from 1.11.
+            let (cursor_pos_x, cursor_pos_y, cursor_pos_z) = if v >= ProtocolVersion::V1_11 {
+                let mut cursor = || -> Result<i8> { Ok((read_float(r)? * 16.0) as i8) };
+                (cursor()?, cursor()?, cursor()?)
+            } else {
+                (read_byte(r)?, read_byte(r)?, read_byte(r)?)
+            };
+            if hand != 0 {
+                return Ok(None);
+            }
+            CPacket::PlayPlayerBlockPlacement { location, face, held_item: t.held_item(), cursor_pos_x, cursor_pos_y, cursor_pos_z }
+        }
+        // 1.8 uses a placement at no block for using items.
+        Serverbound::UseItem => {
+            if read_varint(r)? != 0 {
+                return Ok(None);
+            }
+            CPacket::PlayPlayerBlockPlacement {
+                location: BlockPos::new(-1, 255, -1),
+                face: -1,
+                held_item: t.held_item(),
+                cursor_pos_x: 0,
+                cursor_pos_y: 0,
+                cursor_pos_z: 0,
+            }
+        }
+    };
+    Ok(Some(packet))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::world::chunk::CHUNK_SECTION_BLOCK_COUNT;
+
+    use super::*;
+
+    #[test]
+    fn packet_ids() {
+        let id = |v, kind| clientbound_ids(v).iter().position(|&k| k == kind).unwrap();
+        assert_eq!(id(ProtocolVersion::V1_9, Clientbound::EntityEffect), 0x4c);
+        assert_eq!(id(ProtocolVersion::V1_9_4, Clientbound::EntityEffect), 0x4b);
+        assert_eq!(id(ProtocolVersion::V1_12, Clientbound::PlayerPositionAndLook), 0x2e);
+        assert_eq!(id(ProtocolVersion::V1_12, Clientbound::EntityEffect), 0x4e);
+        assert_eq!(id(ProtocolVersion::V1_12_2, Clientbound::PlayerPositionAndLook), 0x2f);
+        assert_eq!(id(ProtocolVersion::V1_12_2, Clientbound::EntityEffect), 0x4f);
+        for &v in &[ProtocolVersion::V1_9, ProtocolVersion::V1_11, ProtocolVersion::V1_12_2] {
+            assert_eq!(id(v, Clientbound::KeepAlive), 0x1f);
+            assert_eq!(id(v, Clientbound::ChunkData), 0x20);
+            assert_eq!(id(v, Clientbound::JoinGame), 0x23);
+        }
+        assert_eq!(serverbound_ids(ProtocolVersion::V1_9).len(), 0x1e);
+        assert_eq!(serverbound_ids(ProtocolVersion::V1_12)[0x0c], Serverbound::KeepAlive);
+        assert_eq!(serverbound_ids(ProtocolVersion::V1_12_2)[0x0d], Serverbound::PlayerPosition);
+    }
+
+    #[test]
+    fn section_palette() {
+        let mut section = ChunkSection {
+            blocks: [0; CHUNK_SECTION_BLOCK_COUNT * 2],
+            block_light: [0; CHUNK_SECTION_BLOCK_COUNT / 2],
+            sky_light: None,
+        };
+        // stone at block 1 and dirt at block 16, the first block of the second long.
+        section.blocks[2] = 1 << 4;
+        section.blocks[32] = 3 << 4;
+        let mut w = Vec::new();
+        write_section(&section, &mut w).unwrap();
+
+        let mut r = &w[..];
+        assert_eq!(read_ubyte(&mut r).unwrap(), 4);
+        assert_eq!(read_varint(&mut r).unwrap(), 3);
+        let palette: Vec<i32> = (0..3).map(|_| read_varint(&mut r).unwrap()).collect();
+        assert_eq!(palette, vec![0, 16, 48]);
+        assert_eq!(read_varint(&mut r).unwrap(), 256);
+        assert_eq!(read_long(&mut r).unwrap(), 0x10);
+        assert_eq!(read_long(&mut r).unwrap(), 0x2);
+        assert_eq!(r.len(), 254 * 8 + CHUNK_SECTION_BLOCK_COUNT / 2);
+
+        assert_eq!(bits_per_block(16), 4);
+        assert_eq!(bits_per_block(17), 5);
+        assert_eq!(bits_per_block(300), 13);
+    }
+
+    #[test]
+    fn moves_are_finer() {
+        let mut translator = Translator::new(ProtocolVersion::V1_12_2);
+        let mut out = Vec::new();
+        let packet = SPacket::PlayEntityRelativeMove { entity_id: 5, delta_x: 32, delta_y: -1, delta_z: 0, on_ground: true };
+        translator.write_clientbound(&packet, &mut out).unwrap();
+        assert_eq!(out, vec![vec![0x26, 5, 0x10, 0x00, 0xff, 0x80, 0, 0, 1]]);
+    }
+}
@@ -0,0 +1,107 @@
+use std::fmt;
+
+/// Client versions the server can talk to. Packets are 1.8 internally and translated for other versions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ProtocolVersion {
+    V1_7_10,
+    V1_8,
+    V1_9,
+    V1_9_1,
+    V1_9_2,
+    V1_9_4,
+    V1_10,
+    V1_11,
+    V1_11_2,
+    V1_12,
+    V1_12_1,
+    V1_12_2,
+}
+
+impl ProtocolVersion {
+    /// What packets look like inside the server.
+    pub const NATIVE: ProtocolVersion = ProtocolVersion::V1_8;
+    pub const OLDEST: ProtocolVersion = ProtocolVersion::V1_7_10;
+    pub const NEWEST: ProtocolVersion = ProtocolVersion::V1_12_2;
+
+    pub fn from_i32(x: i32) -> Option<ProtocolVersion> {
+        match x {
+            5 => Some(ProtocolVersion::V1_7_10),
+            47 => Some(ProtocolVersion::V1_8),
+            107 => Some(ProtocolVersion::V1_9),
+            108 => Some(ProtocolVersion::V1_9_1),
+            109 => Some(ProtocolVersion::V1_9_2),
+            110 => Some(ProtocolVersion::V1_9_4),
+            210 => Some(ProtocolVersion::V1_10),
+            315 => Some(ProtocolVersion::V1_11),
+            316 => Some(ProtocolVersion::V1_11_2),
+            335 => Some(ProtocolVersion::V1_12),
+            338 => Some(ProtocolVersion::V1_12_1),
+            340 => Some(ProtocolVersion::V1_12_2),
+            _ => None,
+        }
+    }
+
+    pub fn to_i32(self) -> i32 {
+        match self {
+            ProtocolVersion::V1_7_10 => 5,
+            ProtocolVersion::V1_8 => 47,
+            ProtocolVersion::V1_9 => 107,
+            ProtocolVersion::V1_9_1 => 108,
+            ProtocolVersion::V1_9_2 => 109,
+            ProtocolVersion::V1_9_4 => 110,
+            ProtocolVersion::V1_10 => 210,
+            ProtocolVersion::V1_11 => 315,
+            ProtocolVersion::V1_11_2 => 316,
+            ProtocolVersion::V1_12 => 335,
+            ProtocolVersion::V1_12_1 => 338,
+            ProtocolVersion::V1_12_2 => 340,
+        }
+    }
+
+    /// Name shown in the server list. Versions sharing a protocol number are named after the newest.
+    pub fn name(self) -> &'static str {
+        match self {
+            ProtocolVersion::V1_7_10 => "1.7.10",
+            ProtocolVersion::V1_8 => "1.8",
+            ProtocolVersion::V1_9 => "1.9",
+            ProtocolVersion::V1_9_1 => "1.9.1",
+            ProtocolVersion::V1_9_2 => "1.9.2",
+            ProtocolVersion::V1_9_4 => "1.9.4",
+            ProtocolVersion::V1_10 => "1.10.2",
+            ProtocolVersion::V1_11 => "1.11",
+            ProtocolVersion::V1_11_2 => "1.11.2",
+            ProtocolVersion::V1_12 => "1.12",
+            ProtocolVersion::V1_12_1 => "1.12.1",
+            ProtocolVersion::V1_12_2 => "1.12.2",
+        }
+    }
+
+    /// 1.7 has no compression.
+    pub fn supports_compression(self) -> bool {
+        self >= ProtocolVersion::V1_8
+    }
+}
+
+impl fmt::Display for ProtocolVersion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} ({})", self.name(), self.to_i32())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn protocol_numbers() {
+        for x in 0..400 {
+            if let Some(v) = ProtocolVersion::from_i32(x) {
+                assert_eq!(v.to_i32(), x);
+            }
+        }
+        assert_eq!(ProtocolVersion::from_i32(340), Some(ProtocolVersion::NEWEST));
+        assert_eq!(ProtocolVersion::from_i32(4), None);
+        assert_eq!(ProtocolVersion::V1_10.to_string(), "1.10.2 (210)");
+        assert!(!ProtocolVersion::V1_7_10.supports_compression());
+    }
+}
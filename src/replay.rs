@@ -0,0 +1,327 @@
+//! `quartz-rs replay <capture>` plays the client side of a capture against a fresh server and shows where the packets
+//! the server sends now differ from the recording.
+
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, BufReader};
+use std::net::{Shutdown, SocketAddr, TcpStream};
+use std::path::Path;
+use std::process;
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use failure::Fail;
+
+use crate::auth::HttpSessionService;
+use crate::proto::capture::{self, CaptureReader, Direction, Record};
+use crate::proto::packets::{CPacket, SPacket, SPlayPlayerListItemDataAction};
+use crate::proto::{self, Reader, State, Writer};
+use crate::server::properties::ServerProperties;
+use crate::server::{self, Server};
+
+#[derive(Fail, Debug)]
+pub enum Error {
+    #[fail(display = "io error: {}", _0)]
+    IOError(#[cause] io::Error),
+    #[fail(display = "protocol error: {}", _0)]
+    ProtoError(#[cause] proto::Error),
+    #[fail(display = "server error: {}", _0)]
+    ServerError(#[cause] server::Error),
+    #[fail(display = "capture is of protocol version {}, the server speaks {}", _0, _1)]
+    ProtocolMismatch(i32, i32),
+    #[fail(display = "could not log in: {}", _0)]
+    LoginFailed(String),
+}
+
+impl_from_for_newtype_enum!(Error::IOError, io::Error);
+impl_from_for_newtype_enum!(Error::ProtoError, proto::Error);
+impl_from_for_newtype_enum!(Error::ServerError, server::Error);
+
+/// How long to wait for the server after the last recorded packet.
+const SETTLE_TIME: Duration = Duration::from_secs(1);
+/// Differences are searched for this many packets ahead before giving up on lining the packets up again.
+const DIFF_WINDOW: usize = 64;
+/// Packets longer than this are cut off when printed.
+const MAX_PRINTED_LEN: usize = 200;
+
+/// Replays the capture and prints the differences. Returns how many packets differ.
+/// The server gets the given properties but runs offline on localhost with a new world in a temporary directory.
+pub fn replay<P: AsRef<Path>>(path: P, mut properties: ServerProperties) -> Result<usize, Error> {
+    let mut reader = CaptureReader::new(BufReader::new(File::open(path)?))?;
+    let header = reader.get_header().clone();
+    if header.protocol_version != proto::VERSION {
+        return Err(Error::ProtocolMismatch(header.protocol_version, proto::VERSION));
+    }
+    let mut recorded = Vec::new();
+    let mut sent = Vec::new();
+    while let Some(record) = reader.next_record()? {
+        match record.direction {
+            Direction::Serverbound => sent.push(record),
+            Direction::Clientbound => recorded.push(record),
+        }
+    }
+    let end = recorded.iter().chain(&sent).map(|r| r.time).max().unwrap_or_default();
+
+    let dir = env::temp_dir().join(format!("quartz-replay-{}", process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir)?;
+    properties.server_ip = Some("127.0.0.1".parse().unwrap());
+    properties.server_port = 0;
+    properties.level_name = dir.join("world").to_string_lossy().into_owned();
+    properties.online_mode = false;
+    properties.capture_dir = None;
+
+    // the server isn't Send so it is made on its thread.
+    let (tx, rx) = mpsc::channel();
+    let server_thread = thread::Builder::new().name("Replay server".into()).spawn(move || {
+        let session_service = Box::new(HttpSessionService::new(HttpSessionService::MOJANG_URL));
+        let mut server = match Server::new(properties, session_service) {
+            Ok(server) => server,
+            Err(e) => return tx.send(Err(e)).unwrap(),
+        };
+        match server.listen() {
+            Ok(addr) => tx.send(Ok((addr, server.get_running()))).unwrap(),
+            Err(e) => return tx.send(Err(e)).unwrap(),
+        }
+        server.run();
+    })?;
+    let (addr, running) = match rx.recv() {
+        Ok(result) => result?,
+        Err(_) => return Err(io::Error::new(io::ErrorKind::Other, "server thread died").into()),
+    };
+
+    let result = play(addr, &header.name, &sent, end + SETTLE_TIME);
+    *running.lock().unwrap() = false;
+    let _ = server_thread.join();
+    let _ = fs::remove_dir_all(&dir);
+    let replayed = result?;
+
+    let recorded = relevant(&recorded)?;
+    let replayed = relevant(&replayed)?;
+    let changes = diff(&recorded, &replayed);
+    let mut differences = 0;
+    for change in &changes {
+        let (sign, &(ref record, ref packet)) = match *change {
+            Change::Same(_) => continue,
+            Change::Missing(x) => ("-", x),
+            Change::Extra(x) => ("+", x),
+        };
+        differences += 1;
+        let mut s = format!("{:?}", packet);
+        if s.len() > MAX_PRINTED_LEN {
+            let mut end = MAX_PRINTED_LEN;
+            while !s.is_char_boundary(end) {
+                end -= 1;
+            }
+            s.truncate(end);
+            s.push_str("...");
+        }
+        println!("{} {:>8.3}s {}", sign, record.time.as_secs() as f64 + f64::from(record.time.subsec_millis()) / 1000.0, s);
+    }
+    println!(
+        "{} packets recorded, {} replayed, {} differ",
+        recorded.len(),
+        replayed.len(),
+        differences
+    );
+    Ok(differences)
+}
+
+/// Logs in as name and sends the recorded packets at their times. Keep alives are answered instead of replayed.
+/// Returns what the server sent until `until`.
+fn play(addr: SocketAddr, name: &str, sent: &[Record], until: Duration) -> Result<Vec<Record>, Error> {
+    let stream = TcpStream::connect(addr)?;
+    stream.set_nodelay(true)?;
+    let mut reader = Reader::new(stream.try_clone()?);
+    let mut writer = Writer::new(stream.try_clone()?);
+
+    writer.write_packet(&CPacket::Handshake {
+        protocol_version: proto::VERSION,
+        server_address: addr.ip().to_string(),
+        server_port: addr.port(),
+        next_state: 2,
+    })?;
+    reader.set_state(State::Login);
+    writer.write_packet(&CPacket::LoginLoginStart { name: name.into() })?;
+    loop {
+        match reader.read_packet()? {
+            SPacket::LoginSetCompression { threshold } => {
+                reader.set_compression(threshold as u32);
+                writer.set_compression(threshold as u32);
+            }
+            SPacket::LoginLoginSuccess { .. } => break,
+            SPacket::LoginDisconnect { reason } => return Err(Error::LoginFailed(reason.to_plain())),
+            p => return Err(Error::LoginFailed(format!("unexpected packet {:?}", p))),
+        }
+    }
+    reader.set_state(State::Play);
+
+    let start = Instant::now();
+    let writer = Arc::new(Mutex::new(writer));
+    let receiver = {
+        let writer = Arc::clone(&writer);
+        thread::Builder::new().name("Replay client".into()).spawn(move || {
+            let mut received = Vec::new();
+            // ends when the connection is closed.
+            while let Ok(packet) = reader.read_packet::<SPacket>() {
+                if let SPacket::PlayKeepAlive { id } = packet {
+                    let _ = writer.lock().unwrap().write_packet(&CPacket::PlayKeepAlive { id });
+                }
+                if let Ok(data) = capture::encode(&packet) {
+                    received.push(Record { time: start.elapsed(), direction: Direction::Clientbound, data });
+                }
+            }
+            received
+        })?
+    };
+
+    for record in sent {
+        match record.decode(State::Play)? {
+            CPacket::PlayKeepAlive { .. } => {}
+            packet => {
+                if let Some(wait) = record.time.checked_sub(start.elapsed()) {
+                    thread::sleep(wait);
+                }
+                writer.lock().unwrap().write_packet(&packet)?;
+            }
+        }
+    }
+    if let Some(wait) = until.checked_sub(start.elapsed()) {
+        thread::sleep(wait);
+    }
+    stream.shutdown(Shutdown::Both)?;
+    Ok(receiver.join().unwrap_or_default())
+}
+
+/// Decodes the records, leaving out packets which differ every time like keep alives, time and pings.
+fn relevant(records: &[Record]) -> Result<Vec<(&Record, SPacket)>, Error> {
+    let mut ret = Vec::new();
+    for record in records {
+        let packet = record.decode(State::Play)?;
+        let noise = match packet {
+            SPacket::PlayKeepAlive { .. } | SPacket::PlayTimeUpdate { .. } => true,
+            SPacket::PlayPlayerListItem { ref players } => players.iter().all(|p| match p.action {
+                SPlayPlayerListItemDataAction::UpdateLatency { .. } => true,
+                _ => false,
+            }),
+            _ => false,
+        };
+        if !noise {
+            ret.push((record, packet));
+        }
+    }
+    Ok(ret)
+}
+
+#[derive(Debug, PartialEq)]
+enum Change<T> {
+    Same(T),
+    /// only in the recording.
+    Missing(T),
+    /// only in the replay.
+    Extra(T),
+}
+
+/// Lines up both sequences of packets by their bytes. Runs of missing or extra packets are found by looking ahead a
+/// bit, which is enough for packets which come in a different order or a few too many or too few.
+fn diff<'a>(expected: &'a [(&Record, SPacket)], actual: &'a [(&Record, SPacket)]) -> Vec<Change<&'a (&'a Record, SPacket)>> {
+    let same = |i: usize, j: usize| expected[i].0.data == actual[j].0.data;
+    let mut ret = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < expected.len() && j < actual.len() {
+        if same(i, j) {
+            ret.push(Change::Same(&expected[i]));
+            i += 1;
+            j += 1;
+            continue;
+        }
+        let skip = (1..DIFF_WINDOW).find_map(|k| {
+            if i + k < expected.len() && same(i + k, j) {
+                Some((k, 0))
+            } else if j + k < actual.len() && same(i, j + k) {
+                Some((0, k))
+            } else {
+                None
+            }
+        });
+        let (missing, extra) = skip.unwrap_or((1, 1));
+        ret.extend(expected[i..i + missing].iter().map(Change::Missing));
+        ret.extend(actual[j..j + extra].iter().map(Change::Extra));
+        i += missing;
+        j += extra;
+    }
+    ret.extend(expected[i..].iter().map(Change::Missing));
+    ret.extend(actual[j..].iter().map(Change::Extra));
+    ret
+}
+
+#[cfg(test)]
+mod test {
+    use crate::block::{Block, BlockPos, BlockStateId};
+    use crate::test_client::{PlaySoundEffect, TestServer};
+
+    use super::*;
+
+    fn records(ids: &[i64]) -> Vec<Record> {
+        ids.iter()
+            .map(|&id| Record {
+                time: Duration::from_secs(0),
+                direction: Direction::Clientbound,
+                data: capture::encode(&SPacket::PlayKeepAlive { id: id as i32 }).unwrap(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn diff_lines_up_packets() {
+        let expected = records(&[1, 2, 3, 4, 5]);
+        let actual = records(&[1, 3, 4, 9, 5, 6]);
+        let expected: Vec<_> = expected.iter().map(|r| (r, r.decode(State::Play).unwrap())).collect();
+        let actual: Vec<_> = actual.iter().map(|r| (r, r.decode(State::Play).unwrap())).collect();
+        let changes: Vec<String> = diff(&expected, &actual)
+            .into_iter()
+            .map(|c| match c {
+                Change::Same(x) => format!(" {:?}", x.1),
+                Change::Missing(x) => format!("-{:?}", x.1),
+                Change::Extra(x) => format!("+{:?}", x.1),
+            })
+            .collect();
+        assert_eq!(
+            changes,
+            vec![
+                " PlayKeepAlive { id: 1 }",
+                "-PlayKeepAlive { id: 2 }",
+                " PlayKeepAlive { id: 3 }",
+                " PlayKeepAlive { id: 4 }",
+                "+PlayKeepAlive { id: 9 }",
+                " PlayKeepAlive { id: 5 }",
+                "+PlayKeepAlive { id: 6 }",
+            ]
+        );
+    }
+
+    #[test]
+    fn replay_recorded_session() {
+        let capture_dir = env::temp_dir().join(format!("quartz-captures-{}", process::id()));
+        let _ = fs::remove_dir_all(&capture_dir);
+        {
+            let dir = capture_dir.to_string_lossy().into_owned();
+            let server = TestServer::with_properties(|p| p.capture_dir = Some(dir));
+            let mut client = server.connect("Alice");
+            let pos = client.get_pos();
+            let location = BlockPos::new(pos.x.floor() as i32 + 1, pos.y.floor() as u8, pos.z.floor() as i32);
+            client.place_block(location, BlockStateId::new(Block::Stone, 0));
+            // the placing player only hears it.
+            client.wait_for::<PlaySoundEffect>();
+        }
+        let path = fs::read_dir(&capture_dir).unwrap().next().unwrap().unwrap().path();
+
+        let mut properties = ServerProperties::default();
+        properties.view_distance = 2;
+        properties.player_view_distance = 2;
+        assert_eq!(replay(&path, properties).unwrap(), 0);
+        fs::remove_dir_all(&capture_dir).unwrap();
+    }
+}
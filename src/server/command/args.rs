@@ -0,0 +1,178 @@
+use crate::block::BlockPos;
+use crate::math::Vec3;
+use crate::server::Gamemode;
+
+use super::CommandSource;
+
+/// Parses one argument of a command from the words of the command line.
+#[derive(Debug, Clone)]
+pub enum ArgParser {
+    /// name of an online player.
+    Player,
+    /// name of any player, online or not. Online players are suggested.
+    PlayerName,
+    /// any single word, like a permission node or a group.
+    Word,
+    Int { min: i32, max: i32 },
+    /// x y z. every part can be `~` or `~<offset>` to be relative to a position.
+    Vec3,
+    /// like Vec3, but whole blocks inside the world.
+    BlockPos,
+    Gamemode,
+    /// one of the given words.
+    Enum(&'static [&'static str]),
+    /// everything until the end of the line.
+    GreedyString,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ArgValue {
+    Int(i32),
+    Coordinates(Coordinates),
+    Gamemode(Gamemode),
+    String(String),
+}
+
+const GAMEMODES: &[&str] = &["survival", "creative", "adventure", "spectator"];
+
+impl ArgParser {
+    /// How many words the argument takes. None for greedy strings.
+    pub fn word_count(&self) -> Option<usize> {
+        match *self {
+            ArgParser::Vec3 | ArgParser::BlockPos => Some(3),
+            ArgParser::GreedyString => None,
+            _ => Some(1),
+        }
+    }
+
+    /// Returns the value and how many words were used.
+    pub fn parse(&self, words: &[&str], src: &CommandSource) -> Result<(ArgValue, usize), String> {
+        let count = self.word_count().unwrap_or_else(|| words.len().max(1));
+        if words.len() < count {
+            return Err("Not enough arguments".into());
+        }
+        let word = words[0];
+        let value = match *self {
+            ArgParser::Player => {
+                let name = src.player_names.iter().find(|n| n.eq_ignore_ascii_case(word));
+                ArgValue::String(name.ok_or_else(|| format!("Player '{}' cannot be found", word))?.clone())
+            }
+            ArgParser::PlayerName | ArgParser::Word => ArgValue::String(word.into()),
+            ArgParser::Int { min, max } => {
+                let x: i32 = word.parse().map_err(|_| format!("'{}' is not a valid number", word))?;
+                if x < min {
+                    return Err(format!("The number you have entered ({}) is too small, it must be at least {}", x, min));
+                }
+                if x > max {
+                    return Err(format!("The number you have entered ({}) is too big, it must be at most {}", x, max));
+                }
+                ArgValue::Int(x)
+            }
+            ArgParser::Vec3 => ArgValue::Coordinates(Coordinates::parse(&words[..3], false)?),
+            ArgParser::BlockPos => ArgValue::Coordinates(Coordinates::parse(&words[..3], true)?),
+            ArgParser::Gamemode => ArgValue::Gamemode(parse_gamemode(word).ok_or_else(|| format!("'{}' is not a game mode", word))?),
+            ArgParser::Enum(options) => {
+                let option = options.iter().find(|o| o.eq_ignore_ascii_case(word));
+                ArgValue::String(option.ok_or_else(|| format!("'{}' is not one of {}", word, options.join(", ")))?.to_string())
+            }
+            ArgParser::GreedyString => ArgValue::String(words.join(" ")),
+        };
+        Ok((value, count))
+    }
+
+    /// Suggestions for the word at `index` of the argument, which is typed up to `partial`.
+    pub fn suggest(&self, index: usize, partial: &str, src: &CommandSource) -> Vec<String> {
+        let starting_with = |options: &mut dyn Iterator<Item = &str>| -> Vec<String> {
+            let partial = partial.to_lowercase();
+            options.filter(|o| o.to_lowercase().starts_with(&partial)).map(String::from).collect()
+        };
+        match *self {
+            ArgParser::Player | ArgParser::PlayerName => starting_with(&mut src.player_names.iter().map(String::as_str)),
+            ArgParser::Gamemode => starting_with(&mut GAMEMODES.iter().cloned()),
+            ArgParser::Enum(options) => starting_with(&mut options.iter().cloned()),
+            ArgParser::BlockPos if src.looked_at.is_some() => {
+                let pos = src.looked_at.unwrap();
+                let part = [pos.x, pos.y as i32, pos.z][index].to_string();
+                starting_with(&mut ::std::iter::once(part.as_str()))
+            }
+            ArgParser::Vec3 | ArgParser::BlockPos if src.pos.is_some() => starting_with(&mut ::std::iter::once("~")),
+            _ => Vec::new(),
+        }
+    }
+}
+
+pub fn parse_gamemode(s: &str) -> Option<Gamemode> {
+    match s.to_lowercase().as_str() {
+        "survival" | "s" | "0" => Some(Gamemode::Survival),
+        "creative" | "c" | "1" => Some(Gamemode::Creative),
+        "adventure" | "a" | "2" => Some(Gamemode::Adventure),
+        "spectator" | "sp" | "3" => Some(Gamemode::Spectator),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Coordinate {
+    pub value: f64,
+    pub relative: bool,
+}
+
+impl Coordinate {
+    fn parse(s: &str, integer: bool) -> Result<Coordinate, String> {
+        let (relative, number) = if s.starts_with('~') { (true, &s[1..]) } else { (false, s) };
+        if relative && number.is_empty() {
+            return Ok(Coordinate { value: 0.0, relative });
+        }
+        let value = if integer && !relative {
+            number.parse::<i32>().map(f64::from).map_err(|_| format!("'{}' is not a valid block coordinate", s))?
+        } else {
+            number.parse::<f64>().map_err(|_| format!("'{}' is not a valid coordinate", s))?
+        };
+        Ok(Coordinate { value, relative })
+    }
+
+    fn resolve(&self, base: f64) -> f64 {
+        if self.relative { base + self.value } else { self.value }
+    }
+}
+
+/// Coordinates as typed. Relative parts are resolved against a position when the command runs.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Coordinates {
+    pub x: Coordinate,
+    pub y: Coordinate,
+    pub z: Coordinate,
+}
+
+impl Coordinates {
+    fn parse(words: &[&str], integer: bool) -> Result<Coordinates, String> {
+        Ok(Coordinates {
+            x: Coordinate::parse(words[0], integer)?,
+            y: Coordinate::parse(words[1], integer)?,
+            z: Coordinate::parse(words[2], integer)?,
+        })
+    }
+
+    fn is_relative(&self) -> bool {
+        self.x.relative || self.y.relative || self.z.relative
+    }
+
+    /// base is None when there is nothing to be relative to, like for the console.
+    pub fn resolve(&self, base: Option<Vec3>) -> Result<Vec3, String> {
+        let base = match base {
+            Some(base) => base,
+            None if self.is_relative() => return Err("Relative coordinates can only be used by players".into()),
+            None => Vec3::ZERO,
+        };
+        Ok(Vec3::new(self.x.resolve(base.x), self.y.resolve(base.y), self.z.resolve(base.z)))
+    }
+
+    pub fn resolve_block(&self, base: Option<Vec3>) -> Result<BlockPos, String> {
+        let pos = self.resolve(base)?;
+        let y = pos.y.floor();
+        if y < 0.0 || y > 255.0 {
+            return Err("Position is outside of the world".into());
+        }
+        Ok(BlockPos::new(pos.x.floor() as i32, y as u8, pos.z.floor() as i32))
+    }
+}
@@ -0,0 +1,570 @@
+use std::net::IpAddr;
+use std::sync::Arc;
+
+use uuid::Uuid;
+
+use crate::auth;
+use crate::block::BlockPos;
+use crate::proto::packets::SPacket;
+use crate::server::permissions;
+use crate::server::userlist::{self, IpBan, OpEntry, PlayerBan, WhitelistEntry};
+use crate::server::{Server, ServerContext};
+use crate::text::{self, Code};
+use crate::text::chat::Chat;
+
+use super::{ArgParser, CommandContext, CommandResult, CommandSender, Dispatcher, Node};
+
+/// Adds the commands every server has.
+pub fn register_builtin(d: &mut Dispatcher) {
+    d.register(Node::literal("help").describe("Shows this list").executes(help));
+    d.register(Node::literal("list").describe("Lists online players").executes(list));
+    d.register(
+        Node::literal("say").requires(1).describe("Broadcasts a message")
+            .then(Node::argument("message", ArgParser::GreedyString).executes(say)),
+    );
+    d.register(
+        Node::literal("kick").requires(3).describe("Disconnects a player")
+            .then(Node::argument("player", ArgParser::Player).executes(kick)
+                .then(Node::argument("reason", ArgParser::GreedyString).executes(kick))),
+    );
+    d.register(
+        Node::literal("tp").requires(2).describe("Teleports a player")
+            .then(Node::argument("location", ArgParser::Vec3).executes(tp))
+            .then(Node::argument("target", ArgParser::Player).executes(tp)
+                .then(Node::argument("location", ArgParser::Vec3).executes(tp))
+                .then(Node::argument("destination", ArgParser::Player).executes(tp))),
+    );
+    d.register(
+        Node::literal("gamemode").requires(2).describe("Changes the game mode")
+            .then(Node::argument("mode", ArgParser::Gamemode).executes(gamemode)
+                .then(Node::argument("player", ArgParser::Player).executes(gamemode))),
+    );
+    d.register(
+        Node::literal("time").requires(2).describe("Changes or shows the time of day")
+            .then(Node::literal("set")
+                .then(Node::literal("day").executes(time_set_day))
+                .then(Node::literal("night").executes(time_set_night))
+                .then(Node::argument("time", ArgParser::Int { min: 0, max: i32::max_value() }).executes(time_set)))
+            .then(Node::literal("add")
+                .then(Node::argument("amount", ArgParser::Int { min: 0, max: i32::max_value() }).executes(time_add)))
+            .then(Node::literal("query")
+                .then(Node::argument("what", ArgParser::Enum(&["daytime", "gametime"])).executes(time_query))),
+    );
+    d.register(
+        Node::literal("setworldspawn").requires(2).describe("Sets where new players spawn")
+            .executes(set_world_spawn)
+            .then(Node::argument("pos", ArgParser::BlockPos).executes(set_world_spawn)),
+    );
+    d.register(
+        Node::literal("ban").requires(3).describe("Bans a player")
+            .then(Node::argument("player", ArgParser::PlayerName).executes(ban)
+                .then(Node::argument("reason", ArgParser::GreedyString).executes(ban))),
+    );
+    d.register(
+        Node::literal("ban-ip").requires(3).describe("Bans an IP address or the address of a player")
+            .then(Node::argument("target", ArgParser::PlayerName).executes(ban_ip)
+                .then(Node::argument("reason", ArgParser::GreedyString).executes(ban_ip))),
+    );
+    d.register(
+        Node::literal("pardon").requires(3).describe("Unbans a player")
+            .then(Node::argument("player", ArgParser::PlayerName).executes(pardon)),
+    );
+    d.register(
+        Node::literal("pardon-ip").requires(3).describe("Unbans an IP address")
+            .then(Node::argument("address", ArgParser::PlayerName).executes(pardon_ip)),
+    );
+    d.register(
+        Node::literal("banlist").requires(3).describe("Lists banned players or IP addresses")
+            .executes(banlist)
+            .then(Node::argument("type", ArgParser::Enum(&["players", "ips"])).executes(banlist)),
+    );
+    d.register(
+        Node::literal("whitelist").requires(3).describe("Manages who may join")
+            .then(Node::literal("on").executes(whitelist_on))
+            .then(Node::literal("off").executes(whitelist_off))
+            .then(Node::literal("list").executes(whitelist_list))
+            .then(Node::literal("add").then(Node::argument("player", ArgParser::PlayerName).executes(whitelist_add)))
+            .then(Node::literal("remove").then(Node::argument("player", ArgParser::PlayerName).executes(whitelist_remove)))
+            .then(Node::literal("reload").executes(whitelist_reload)),
+    );
+    d.register(
+        Node::literal("op").requires(3).describe("Makes a player an operator")
+            .then(Node::argument("player", ArgParser::PlayerName).executes(op)),
+    );
+    d.register(
+        Node::literal("deop").requires(3).describe("Takes operator status from a player")
+            .then(Node::argument("player", ArgParser::PlayerName).executes(deop)),
+    );
+    d.register(
+        Node::literal("reload").requires(3).describe("Reads the ban lists, whitelist and ops from disk again")
+            .executes(reload),
+    );
+    d.register(
+        Node::literal("permission").requires(4).describe("Manages permission nodes and groups")
+            .then(Node::literal("reload").executes(permission_reload))
+            .then(Node::literal("player").then(Node::argument("player", ArgParser::PlayerName)
+                .then(Node::literal("set").then(Node::argument("node", ArgParser::Word)
+                    .then(Node::argument("value", ArgParser::Enum(&["true", "false"])).executes(permission_player_set))))
+                .then(Node::literal("unset").then(Node::argument("node", ArgParser::Word).executes(permission_player_set)))
+                .then(Node::literal("addgroup").then(Node::argument("group", ArgParser::Word).executes(permission_player_add_group)))
+                .then(Node::literal("removegroup").then(Node::argument("group", ArgParser::Word).executes(permission_player_remove_group)))))
+            .then(Node::literal("group").then(Node::argument("group", ArgParser::Word)
+                .then(Node::literal("set").then(Node::argument("node", ArgParser::Word)
+                    .then(Node::argument("value", ArgParser::Enum(&["true", "false"])).executes(permission_group_set))))
+                .then(Node::literal("unset").then(Node::argument("node", ArgParser::Word).executes(permission_group_set)))
+                .then(Node::literal("inherit").then(Node::argument("parent", ArgParser::Word).executes(permission_group_inherit)))
+                .then(Node::literal("uninherit").then(Node::argument("parent", ArgParser::Word).executes(permission_group_uninherit))))),
+    );
+    d.register(Node::literal("save-all").requires(4).describe("Saves everything to disk").executes(save_all));
+    d.register(Node::literal("stop").requires(4).describe("Stops the server").executes(stop));
+}
+
+impl Server {
+    fn get_sender_name(&self, sender: CommandSender) -> String {
+        match sender {
+            CommandSender::Console => "Server".into(),
+            CommandSender::Player(uuid) => self.player_list.get_by_uuid(&uuid).map_or(String::new(), |p| p.get_name().into()),
+        }
+    }
+
+    /// Player named by the argument, or the sender if there is no such argument and the sender is a player.
+    fn get_target_uuid(&self, ctx: &CommandContext, arg: &str) -> Result<Uuid, String> {
+        match ctx.sender {
+            _ if ctx.has(arg) => {
+                let name = ctx.get_string(arg);
+                self.player_list.get_by_name(name).map(|p| p.get_uuid()).ok_or_else(|| format!("Player '{}' cannot be found", name))
+            }
+            CommandSender::Player(uuid) => Ok(uuid),
+            CommandSender::Console => Err("You must specify which player you wish to perform this action on.".into()),
+        }
+    }
+
+    /// Uuid and name of a player who may be offline. Online players come first, then players on the lists. Other
+    /// players can only be found in offline mode, where the uuid comes from the name.
+    fn find_profile(&self, name: &str) -> Result<(Uuid, String), String> {
+        if let Some(p) = self.player_list.get_by_name(name) {
+            return Ok((p.get_uuid(), p.get_name().into()));
+        }
+        {
+            let lists = self.user_lists.lock().unwrap();
+            let known = lists.banned_players.iter().map(|e| (&e.uuid, &e.name))
+                .chain(lists.whitelist.iter().map(|e| (&e.uuid, &e.name)))
+                .chain(lists.ops.iter().map(|e| (&e.uuid, &e.name)))
+                .find(|&(_, n)| n.eq_ignore_ascii_case(name));
+            if let Some((uuid, name)) = known {
+                if let Ok(uuid) = Uuid::parse_str(uuid) {
+                    return Ok((uuid, name.clone()));
+                }
+            }
+        }
+        if self.online_mode {
+            return Err(format!("Player '{}' cannot be found", name));
+        }
+        Ok((auth::offline_uuid(name), name.into()))
+    }
+
+    /// Sets the permission level of the player if they are online.
+    fn update_permission_level(&self, uuid: Uuid) {
+        if let Some(mut p) = self.player_list.get_by_uuid(&uuid) {
+            p.set_permission_level(self.user_lists.lock().unwrap().get_op_level(uuid));
+        }
+    }
+
+    /// Resolves the nodes of every online player again, after groups changed.
+    fn update_permissions(&self) {
+        for p in self.player_list.iter() {
+            let mut p = p.borrow_mut();
+            let permissions = self.permissions.resolve(p.get_uuid());
+            p.set_permissions(permissions);
+        }
+    }
+}
+
+fn save_error(e: userlist::Error) -> String {
+    format!("Could not save the list: {}", e)
+}
+
+fn permissions_save_error(e: permissions::Error) -> String {
+    format!("Could not save the permissions: {}", e)
+}
+
+/// Some(true) or Some(false) for set, None for unset.
+fn get_node_value(ctx: &CommandContext) -> Option<bool> {
+    if ctx.has("value") {
+        Some(ctx.get_string("value") == "true")
+    } else {
+        None
+    }
+}
+
+fn describe_node_change(node: &str, value: Option<bool>, target: &str) -> String {
+    match value {
+        Some(value) => format!("Set {} to {} for {}", node, value, target),
+        None => format!("Unset {} for {}", node, target),
+    }
+}
+
+fn help(server: &mut Server, ctx: &CommandContext) -> CommandResult {
+    let src = server.get_command_source(ctx.sender, None);
+    for (usages, description) in server.commands.get_help(&src) {
+        let description = description.map_or(String::new(), |d| format!(" - {}", d));
+        server.send_message(ctx.sender, &format!("{}{}{}{}", Code::Gold, usages.join(" OR "), Code::Reset, description));
+    }
+    Ok(())
+}
+
+fn list(server: &mut Server, ctx: &CommandContext) -> CommandResult {
+    let names: Vec<String> = server.player_list.iter().map(|p| p.borrow().get_name().to_string()).collect();
+    server.send_message(ctx.sender, &format!("There are {}/{} players online:", names.len(), server.server_info.max_players));
+    if !names.is_empty() {
+        server.send_message(ctx.sender, &names.join(", "));
+    }
+    Ok(())
+}
+
+fn say(server: &mut Server, ctx: &CommandContext) -> CommandResult {
+    let name = server.get_sender_name(ctx.sender);
+    server.broadcast(&format!("{}[{}] {}", Code::Pink, name, ctx.get_string("message")));
+    Ok(())
+}
+
+fn kick(server: &mut Server, ctx: &CommandContext) -> CommandResult {
+    let reason = if ctx.has("reason") { ctx.get_string("reason") } else { "Kicked by an operator." };
+    let uuid = server.get_target_uuid(ctx, "player")?;
+    let name = {
+        let mut p = server.player_list.get_by_uuid(&uuid).unwrap();
+        p.kick(Chat::from(text::parse_legacy(reason)));
+        p.get_name().to_string()
+    };
+    server.send_message(ctx.sender, &format!("Kicked {} from the game: '{}'", name, reason));
+    Ok(())
+}
+
+fn ban(server: &mut Server, ctx: &CommandContext) -> CommandResult {
+    let reason = if ctx.has("reason") { ctx.get_string("reason") } else { "Banned by an operator." };
+    let (uuid, name) = server.find_profile(ctx.get_string("player"))?;
+    let ban = PlayerBan::new(uuid, name.clone(), server.get_sender_name(ctx.sender), reason.into());
+    server.user_lists.lock().unwrap().banned_players.add(ban).map_err(save_error)?;
+    if let Some(mut p) = server.player_list.get_by_uuid(&uuid) {
+        p.kick(Chat::from(text::parse_legacy("You are banned from this server.")));
+    }
+    server.send_message(ctx.sender, &format!("Banned player {}", name));
+    Ok(())
+}
+
+fn ban_ip(server: &mut Server, ctx: &CommandContext) -> CommandResult {
+    let reason = if ctx.has("reason") { ctx.get_string("reason") } else { "Banned by an operator." };
+    let target = ctx.get_string("target");
+    let ip: IpAddr = match target.parse() {
+        Ok(ip) => ip,
+        Err(_) => match server.player_list.get_by_name(target) {
+            Some(p) => p.get_ip().ip(),
+            None => return Err("You have entered an invalid IP address or a player that is not online".into()),
+        },
+    };
+    let ban = IpBan::new(ip, server.get_sender_name(ctx.sender), reason.into());
+    server.user_lists.lock().unwrap().banned_ips.add(ban).map_err(save_error)?;
+    let mut kicked = Vec::new();
+    for p in server.player_list.iter() {
+        let mut p = p.borrow_mut();
+        if p.get_ip().ip() == ip {
+            p.kick(Chat::from(text::parse_legacy("You have been IP banned.")));
+            kicked.push(p.get_name().to_string());
+        }
+    }
+    server.send_message(ctx.sender, &format!("Banned IP address {}", ip));
+    if !kicked.is_empty() {
+        server.send_message(ctx.sender, &format!("Kicked {}", kicked.join(", ")));
+    }
+    Ok(())
+}
+
+fn pardon(server: &mut Server, ctx: &CommandContext) -> CommandResult {
+    let name = ctx.get_string("player");
+    let mut lists = server.user_lists.lock().unwrap();
+    let ban = lists.banned_players.iter().find(|b| b.name.eq_ignore_ascii_case(name)).cloned();
+    let ban = ban.ok_or_else(|| format!("{} is not banned", name))?;
+    lists.banned_players.remove(&ban.uuid).map_err(save_error)?;
+    drop(lists);
+    server.send_message(ctx.sender, &format!("Unbanned player {}", ban.name));
+    Ok(())
+}
+
+fn pardon_ip(server: &mut Server, ctx: &CommandContext) -> CommandResult {
+    let ip: IpAddr = ctx.get_string("address").parse().map_err(|_| "You have entered an invalid IP address")?;
+    if !server.user_lists.lock().unwrap().banned_ips.remove(&ip.to_string()).map_err(save_error)? {
+        return Err(format!("{} is not banned", ip));
+    }
+    server.send_message(ctx.sender, &format!("Unbanned IP address {}", ip));
+    Ok(())
+}
+
+fn banlist(server: &mut Server, ctx: &CommandContext) -> CommandResult {
+    let ips = ctx.has("type") && ctx.get_string("type") == "ips";
+    let names: Vec<String> = {
+        let lists = server.user_lists.lock().unwrap();
+        if ips {
+            lists.banned_ips.iter().map(|b| b.ip.clone()).collect()
+        } else {
+            lists.banned_players.iter().map(|b| b.name.clone()).collect()
+        }
+    };
+    let what = if ips { "IP addresses" } else { "players" };
+    server.send_message(ctx.sender, &format!("There are {} total banned {}:", names.len(), what));
+    if !names.is_empty() {
+        server.send_message(ctx.sender, &names.join(", "));
+    }
+    Ok(())
+}
+
+fn whitelist_on(server: &mut Server, ctx: &CommandContext) -> CommandResult {
+    server.user_lists.lock().unwrap().whitelist_enabled = true;
+    server.send_message(ctx.sender, "Turned on the whitelist");
+    Ok(())
+}
+
+fn whitelist_off(server: &mut Server, ctx: &CommandContext) -> CommandResult {
+    server.user_lists.lock().unwrap().whitelist_enabled = false;
+    server.send_message(ctx.sender, "Turned off the whitelist");
+    Ok(())
+}
+
+fn whitelist_list(server: &mut Server, ctx: &CommandContext) -> CommandResult {
+    let names: Vec<String> = server.user_lists.lock().unwrap().whitelist.iter().map(|e| e.name.clone()).collect();
+    server.send_message(ctx.sender, &format!("There are {} whitelisted players:", names.len()));
+    if !names.is_empty() {
+        server.send_message(ctx.sender, &names.join(", "));
+    }
+    Ok(())
+}
+
+fn whitelist_add(server: &mut Server, ctx: &CommandContext) -> CommandResult {
+    let (uuid, name) = server.find_profile(ctx.get_string("player"))?;
+    let entry = WhitelistEntry { uuid: uuid.hyphenated().to_string(), name: name.clone() };
+    server.user_lists.lock().unwrap().whitelist.add(entry).map_err(save_error)?;
+    server.send_message(ctx.sender, &format!("Added {} to the whitelist", name));
+    Ok(())
+}
+
+fn whitelist_remove(server: &mut Server, ctx: &CommandContext) -> CommandResult {
+    let name = ctx.get_string("player");
+    let mut lists = server.user_lists.lock().unwrap();
+    let entry = lists.whitelist.iter().find(|e| e.name.eq_ignore_ascii_case(name)).cloned();
+    let entry = entry.ok_or_else(|| format!("{} is not whitelisted", name))?;
+    lists.whitelist.remove(&entry.uuid).map_err(save_error)?;
+    drop(lists);
+    server.send_message(ctx.sender, &format!("Removed {} from the whitelist", entry.name));
+    Ok(())
+}
+
+fn whitelist_reload(server: &mut Server, ctx: &CommandContext) -> CommandResult {
+    server.user_lists.lock().unwrap().whitelist.reload().map_err(|e| format!("Could not reload the whitelist: {}", e))?;
+    server.send_message(ctx.sender, "Reloaded the whitelist");
+    Ok(())
+}
+
+fn op(server: &mut Server, ctx: &CommandContext) -> CommandResult {
+    let (uuid, name) = server.find_profile(ctx.get_string("player"))?;
+    let entry = OpEntry {
+        uuid: uuid.hyphenated().to_string(),
+        name: name.clone(),
+        level: server.op_permission_level,
+        bypasses_player_limit: false,
+    };
+    server.user_lists.lock().unwrap().ops.add(entry).map_err(save_error)?;
+    server.update_permission_level(uuid);
+    server.send_message(ctx.sender, &format!("Opped {}", name));
+    Ok(())
+}
+
+fn deop(server: &mut Server, ctx: &CommandContext) -> CommandResult {
+    let name = ctx.get_string("player");
+    let entry = {
+        let mut lists = server.user_lists.lock().unwrap();
+        let entry = lists.ops.iter().find(|e| e.name.eq_ignore_ascii_case(name)).cloned();
+        let entry = entry.ok_or_else(|| format!("{} is not an operator", name))?;
+        lists.ops.remove(&entry.uuid).map_err(save_error)?;
+        entry
+    };
+    if let Ok(uuid) = Uuid::parse_str(&entry.uuid) {
+        server.update_permission_level(uuid);
+    }
+    server.send_message(ctx.sender, &format!("De-opped {}", entry.name));
+    Ok(())
+}
+
+fn reload(server: &mut Server, ctx: &CommandContext) -> CommandResult {
+    server.user_lists.lock().unwrap().reload().map_err(|e| format!("Could not reload the lists: {}", e))?;
+    let uuids: Vec<Uuid> = server.player_list.iter().map(|p| p.borrow().get_uuid()).collect();
+    for uuid in uuids {
+        server.update_permission_level(uuid);
+    }
+    server.send_message(ctx.sender, "Reloaded the ban lists, whitelist and ops");
+    Ok(())
+}
+
+fn permission_reload(server: &mut Server, ctx: &CommandContext) -> CommandResult {
+    server.permissions.reload().map_err(|e| format!("Could not reload the permissions: {}", e))?;
+    server.update_permissions();
+    server.send_message(ctx.sender, "Reloaded the permissions");
+    Ok(())
+}
+
+fn permission_player_set(server: &mut Server, ctx: &CommandContext) -> CommandResult {
+    let (uuid, name) = server.find_profile(ctx.get_string("player"))?;
+    let (node, value) = (ctx.get_string("node"), get_node_value(ctx));
+    server.permissions.set_player_node(uuid, &name, node, value).map_err(permissions_save_error)?;
+    server.update_permissions();
+    server.send_message(ctx.sender, &describe_node_change(node, value, &name));
+    Ok(())
+}
+
+fn permission_player_add_group(server: &mut Server, ctx: &CommandContext) -> CommandResult {
+    let (uuid, name) = server.find_profile(ctx.get_string("player"))?;
+    let group = ctx.get_string("group");
+    if !server.permissions.set_player_group(uuid, &name, group, true).map_err(permissions_save_error)? {
+        return Err(format!("{} is already in {}", name, group));
+    }
+    server.update_permissions();
+    server.send_message(ctx.sender, &format!("Added {} to {}", name, group));
+    Ok(())
+}
+
+fn permission_player_remove_group(server: &mut Server, ctx: &CommandContext) -> CommandResult {
+    let (uuid, name) = server.find_profile(ctx.get_string("player"))?;
+    let group = ctx.get_string("group");
+    if !server.permissions.set_player_group(uuid, &name, group, false).map_err(permissions_save_error)? {
+        return Err(format!("{} is not in {}", name, group));
+    }
+    server.update_permissions();
+    server.send_message(ctx.sender, &format!("Removed {} from {}", name, group));
+    Ok(())
+}
+
+fn permission_group_set(server: &mut Server, ctx: &CommandContext) -> CommandResult {
+    let (group, node, value) = (ctx.get_string("group"), ctx.get_string("node"), get_node_value(ctx));
+    server.permissions.set_group_node(group, node, value).map_err(permissions_save_error)?;
+    server.update_permissions();
+    server.send_message(ctx.sender, &describe_node_change(node, value, &format!("group {}", group)));
+    Ok(())
+}
+
+fn permission_group_inherit(server: &mut Server, ctx: &CommandContext) -> CommandResult {
+    let (group, parent) = (ctx.get_string("group"), ctx.get_string("parent"));
+    if !server.permissions.set_group_parent(group, parent, true).map_err(permissions_save_error)? {
+        return Err(format!("{} already inherits from {}", group, parent));
+    }
+    server.update_permissions();
+    server.send_message(ctx.sender, &format!("{} now inherits from {}", group, parent));
+    Ok(())
+}
+
+fn permission_group_uninherit(server: &mut Server, ctx: &CommandContext) -> CommandResult {
+    let (group, parent) = (ctx.get_string("group"), ctx.get_string("parent"));
+    if !server.permissions.set_group_parent(group, parent, false).map_err(permissions_save_error)? {
+        return Err(format!("{} does not inherit from {}", group, parent));
+    }
+    server.update_permissions();
+    server.send_message(ctx.sender, &format!("{} no longer inherits from {}", group, parent));
+    Ok(())
+}
+
+fn tp(server: &mut Server, ctx: &CommandContext) -> CommandResult {
+    let uuid = server.get_target_uuid(ctx, "target")?;
+    let (dimension, target_pos) = {
+        let p = server.player_list.get_by_uuid(&uuid).unwrap();
+        (p.get_dimension(), p.get_pos())
+    };
+
+    // relative coordinates are relative to the player being teleported.
+    let pos = if ctx.has("location") {
+        ctx.get_coordinates("location").resolve(Some(target_pos))?
+    } else {
+        let name = ctx.get_string("destination");
+        let destination = server.player_list.get_by_name(name).ok_or_else(|| format!("Player '{}' cannot be found", name))?;
+        if destination.get_dimension() != dimension {
+            return Err("Cannot teleport to players in other dimensions".into());
+        }
+        destination.get_pos()
+    };
+
+    let name = {
+        let mut p = server.player_list.get_by_uuid(&uuid).unwrap();
+        let world = server.worlds.get_world(dimension);
+        p.teleport(pos, &mut ServerContext { player_list: &server.player_list, server_info: &server.server_info, world });
+        p.get_name().to_string()
+    };
+    server.send_message(ctx.sender, &format!("Teleported {} to {:.2}, {:.2}, {:.2}", name, pos.x, pos.y, pos.z));
+    Ok(())
+}
+
+fn gamemode(server: &mut Server, ctx: &CommandContext) -> CommandResult {
+    let gamemode = ctx.get_gamemode("mode");
+    let uuid = server.get_target_uuid(ctx, "player")?;
+    let name = {
+        let mut p = server.player_list.get_by_uuid(&uuid).unwrap();
+        let world = server.worlds.get_world(p.get_dimension());
+        p.set_gamemode(gamemode, &mut ServerContext { player_list: &server.player_list, server_info: &server.server_info, world });
+        p.get_name().to_string()
+    };
+    server.send_message(ctx.sender, &format!("Set {}'s game mode to {:?}", name, gamemode));
+    Ok(())
+}
+
+fn set_time(server: &mut Server, ctx: &CommandContext, day_time: i64) -> CommandResult {
+    server.server_info.day_time = day_time;
+    server.player_list.send_packet_to_all_players(Arc::new(server.server_info.time_update_packet()));
+    server.send_message(ctx.sender, &format!("Set the time to {}", day_time));
+    Ok(())
+}
+
+fn time_set_day(server: &mut Server, ctx: &CommandContext) -> CommandResult {
+    set_time(server, ctx, 1000)
+}
+
+fn time_set_night(server: &mut Server, ctx: &CommandContext) -> CommandResult {
+    set_time(server, ctx, 13000)
+}
+
+fn time_set(server: &mut Server, ctx: &CommandContext) -> CommandResult {
+    set_time(server, ctx, ctx.get_int("time") as i64)
+}
+
+fn time_add(server: &mut Server, ctx: &CommandContext) -> CommandResult {
+    let day_time = server.server_info.day_time + ctx.get_int("amount") as i64;
+    set_time(server, ctx, day_time)
+}
+
+fn time_query(server: &mut Server, ctx: &CommandContext) -> CommandResult {
+    let time = match ctx.get_string("what") {
+        "daytime" => server.server_info.day_time % 24000,
+        _ => server.server_info.tick as i64,
+    };
+    server.send_message(ctx.sender, &format!("Time is {}", time));
+    Ok(())
+}
+
+fn set_world_spawn(server: &mut Server, ctx: &CommandContext) -> CommandResult {
+    let pos = match ctx.pos {
+        _ if ctx.has("pos") => ctx.get_coordinates("pos").resolve_block(ctx.pos)?,
+        Some(pos) => BlockPos::new(pos.x.floor() as i32, pos.y.floor() as u8, pos.z.floor() as i32),
+        None => return Err("You must specify a position.".into()),
+    };
+    server.worlds.overworld.set_spawn_pos(pos);
+    server.player_list.send_packet_to_all_players(Arc::new(SPacket::PlaySpawnPosition { location: pos }));
+    server.send_message(ctx.sender, &format!("Set the world spawn point to ({}, {}, {})", pos.x, pos.y, pos.z));
+    Ok(())
+}
+
+fn save_all(server: &mut Server, ctx: &CommandContext) -> CommandResult {
+    server.send_message(ctx.sender, "Saving...");
+    server.save_all();
+    server.send_message(ctx.sender, "Saved the world");
+    Ok(())
+}
+
+fn stop(server: &mut Server, ctx: &CommandContext) -> CommandResult {
+    server.send_message(ctx.sender, "Stopping the server");
+    *server.running.lock().unwrap() = false;
+    Ok(())
+}
@@ -0,0 +1,477 @@
+mod args;
+mod builtin;
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use uuid::Uuid;
+
+use crate::block::BlockPos;
+use crate::math::Vec3;
+use crate::proto::packets::SPacket;
+use crate::server::{Gamemode, Server};
+use crate::server::permissions::{self, PermissionSet};
+use crate::text::{self, ChatPos, Code};
+use crate::text::chat::Chat;
+
+pub use self::args::{ArgParser, ArgValue, Coordinates};
+pub use self::builtin::register_builtin;
+
+/// Who runs a command. Output of the command goes back to them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommandSender {
+    Console,
+    Player(Uuid),
+}
+
+/// What parsing needs to know about the sender.
+#[derive(Debug, Clone)]
+pub struct CommandSource {
+    pub sender: CommandSender,
+    /// 0 for normal players, 4 for the console.
+    pub permission_level: u8,
+    /// nodes win over the permission level. The console has all of them.
+    pub permissions: PermissionSet,
+    /// position of the player. None for the console.
+    pub pos: Option<Vec3>,
+    pub player_names: Vec<String>,
+    /// block the player looks at. Only known during tab completion.
+    pub looked_at: Option<BlockPos>,
+}
+
+/// Err is shown to the sender in red.
+pub type CommandResult = Result<(), String>;
+
+pub type Executor = fn(&mut Server, &CommandContext) -> CommandResult;
+
+/// Parsed arguments of a command, by name.
+#[derive(Debug)]
+pub struct CommandContext {
+    pub sender: CommandSender,
+    pub pos: Option<Vec3>,
+    args: HashMap<&'static str, ArgValue>,
+}
+
+impl CommandContext {
+    pub fn has(&self, name: &str) -> bool {
+        self.args.contains_key(name)
+    }
+
+    fn get(&self, name: &str) -> &ArgValue {
+        self.args.get(name).unwrap_or_else(|| panic!("command has no argument {}", name))
+    }
+
+    pub fn get_int(&self, name: &str) -> i32 {
+        match *self.get(name) {
+            ArgValue::Int(x) => x,
+            ref x => panic!("argument {} is not an int: {:?}", name, x),
+        }
+    }
+
+    /// Player names, words and strings.
+    pub fn get_string(&self, name: &str) -> &str {
+        match *self.get(name) {
+            ArgValue::String(ref s) => s,
+            ref x => panic!("argument {} is not a string: {:?}", name, x),
+        }
+    }
+
+    pub fn get_coordinates(&self, name: &str) -> Coordinates {
+        match *self.get(name) {
+            ArgValue::Coordinates(c) => c,
+            ref x => panic!("argument {} is not coordinates: {:?}", name, x),
+        }
+    }
+
+    pub fn get_gamemode(&self, name: &str) -> Gamemode {
+        match *self.get(name) {
+            ArgValue::Gamemode(g) => g,
+            ref x => panic!("argument {} is not a game mode: {:?}", name, x),
+        }
+    }
+}
+
+#[derive(Debug)]
+enum NodeKind {
+    Literal(&'static str),
+    Argument(&'static str, ArgParser),
+}
+
+/// A node of the command tree. A command line is run by the executor of the last node it matches.
+#[derive(Debug)]
+pub struct Node {
+    kind: NodeKind,
+    permission_level: u8,
+    /// node which allows or denies the command regardless of level. Set on commands when they are registered.
+    permission: Option<String>,
+    description: Option<&'static str>,
+    executor: Option<Executor>,
+    children: Vec<Node>,
+}
+
+impl Node {
+    pub fn literal(name: &'static str) -> Node {
+        Node::new(NodeKind::Literal(name))
+    }
+
+    pub fn argument(name: &'static str, parser: ArgParser) -> Node {
+        Node::new(NodeKind::Argument(name, parser))
+    }
+
+    fn new(kind: NodeKind) -> Node {
+        Node {
+            kind,
+            permission_level: 0,
+            permission: None,
+            description: None,
+            executor: None,
+            children: Vec::new(),
+        }
+    }
+
+    /// Hides the node from senders below the level.
+    pub fn requires(mut self, permission_level: u8) -> Node {
+        self.permission_level = permission_level;
+        self
+    }
+
+    /// Shown by help. Only used on commands.
+    pub fn describe(mut self, description: &'static str) -> Node {
+        self.description = Some(description);
+        self
+    }
+
+    pub fn executes(mut self, executor: Executor) -> Node {
+        self.executor = Some(executor);
+        self
+    }
+
+    pub fn then(mut self, child: Node) -> Node {
+        self.children.push(child);
+        self
+    }
+
+    fn can_use(&self, src: &CommandSource) -> bool {
+        self.permission.as_ref().and_then(|p| src.permissions.check(p))
+            .unwrap_or(src.permission_level >= self.permission_level)
+    }
+
+    fn get_name(&self) -> &'static str {
+        match self.kind {
+            NodeKind::Literal(name) | NodeKind::Argument(name, _) => name,
+        }
+    }
+
+    fn label(&self) -> String {
+        match self.kind {
+            NodeKind::Literal(name) => name.into(),
+            NodeKind::Argument(name, _) => format!("<{}>", name),
+        }
+    }
+
+    fn usable_children<'a>(&'a self, src: &'a CommandSource) -> impl Iterator<Item = &'a Node> + 'a {
+        self.children.iter().filter(move |c| c.can_use(src))
+    }
+
+    /// Err(None) means the words don't fit the tree, Err(Some) is a problem with an argument.
+    fn parse(&self, words: &[&str], src: &CommandSource, args: &mut HashMap<&'static str, ArgValue>) -> Result<Executor, Option<String>> {
+        if words.is_empty() {
+            return self.executor.ok_or(None);
+        }
+        let mut error = None;
+        for child in self.usable_children(src) {
+            let result = match child.kind {
+                NodeKind::Literal(name) => {
+                    if !words[0].eq_ignore_ascii_case(name) {
+                        continue;
+                    }
+                    child.parse(&words[1..], src, args)
+                }
+                NodeKind::Argument(name, ref parser) => {
+                    if parser.word_count().map_or(false, |n| words.len() < n) {
+                        continue;
+                    }
+                    match parser.parse(words, src) {
+                        Ok((value, used)) => {
+                            args.insert(name, value);
+                            let result = child.parse(&words[used..], src, args);
+                            if result.is_err() {
+                                args.remove(name);
+                            }
+                            result
+                        }
+                        Err(e) => Err(Some(e)),
+                    }
+                }
+            };
+            match result {
+                Ok(executor) => return Ok(executor),
+                Err(e) => error = error.or(e),
+            }
+        }
+        Err(error)
+    }
+
+    /// words are what comes after this node. The last one is the word being typed.
+    fn suggest(&self, words: &[&str], src: &CommandSource, out: &mut Vec<String>) {
+        let partial = words[words.len() - 1];
+        for child in self.usable_children(src) {
+            match child.kind {
+                NodeKind::Literal(name) => {
+                    if words.len() == 1 {
+                        if name.starts_with(&partial.to_lowercase()) {
+                            out.push(name.into());
+                        }
+                    } else if words[0].eq_ignore_ascii_case(name) {
+                        child.suggest(&words[1..], src, out);
+                    }
+                }
+                NodeKind::Argument(_, ref parser) => match parser.word_count() {
+                    Some(n) if words.len() <= n => out.extend(parser.suggest(words.len() - 1, partial, src)),
+                    Some(n) => {
+                        if parser.parse(&words[..n], src).is_ok() {
+                            child.suggest(&words[n..], src, out);
+                        }
+                    }
+                    None => {}
+                },
+            }
+        }
+    }
+
+    fn collect_usages(&self, prefix: &str, src: &CommandSource, out: &mut Vec<String>) {
+        if self.executor.is_some() {
+            out.push(prefix.into());
+        }
+        for child in self.usable_children(src) {
+            child.collect_usages(&format!("{} {}", prefix, child.label()), src, out);
+        }
+    }
+}
+
+/// All registered commands.
+#[derive(Debug)]
+pub struct Dispatcher {
+    commands: Vec<Node>,
+}
+
+impl Dispatcher {
+    pub fn new() -> Dispatcher {
+        Dispatcher { commands: Vec::new() }
+    }
+
+    /// command has to be a literal node. It can be allowed or denied with the `quartz.command.<name>` node.
+    pub fn register(&mut self, mut command: Node) {
+        assert!(match command.kind { NodeKind::Literal(_) => true, _ => false }, "commands have to start with a literal");
+        command.permission = Some(permissions::command_node(command.get_name()));
+        self.commands.push(command);
+        self.commands.sort_by_key(|c| c.get_name());
+    }
+
+    fn get_command(&self, name: &str) -> Option<&Node> {
+        self.commands.iter().find(|c| c.get_name().eq_ignore_ascii_case(name))
+    }
+
+    /// Finds the executor for a command line without the slash. Err is the message for the sender.
+    pub fn parse(&self, line: &str, src: &CommandSource) -> Result<(Executor, CommandContext), String> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let command = match words.first().and_then(|name| self.get_command(name)) {
+            Some(command) => command,
+            None => return Err("Unknown command. Type \"/help\" for help.".into()),
+        };
+        if !command.can_use(src) {
+            return Err("You do not have permission to use this command.".into());
+        }
+        let mut args = HashMap::new();
+        match command.parse(&words[1..], src, &mut args) {
+            Ok(executor) => Ok((executor, CommandContext { sender: src.sender, pos: src.pos, args })),
+            Err(Some(e)) => Err(e),
+            Err(None) => Err(format!("Usage: {}", self.get_usages(command, src).join(" OR "))),
+        }
+    }
+
+    /// Completions for the last word of text, which is a command line without the slash.
+    /// Command names get a slash like vanilla does.
+    pub fn complete(&self, text: &str, src: &CommandSource) -> Vec<String> {
+        let words: Vec<&str> = text.split(' ').collect();
+        if words.len() == 1 {
+            let name = words[0].to_lowercase();
+            return self.commands.iter()
+                .filter(|c| c.can_use(src) && c.get_name().starts_with(&name))
+                .map(|c| format!("/{}", c.get_name()))
+                .collect();
+        }
+        let mut ret = Vec::new();
+        if let Some(command) = self.get_command(words[0]).filter(|c| c.can_use(src)) {
+            command.suggest(&words[1..], src, &mut ret);
+        }
+        ret.sort();
+        ret.dedup();
+        ret
+    }
+
+    fn get_usages(&self, command: &Node, src: &CommandSource) -> Vec<String> {
+        let mut ret = Vec::new();
+        command.collect_usages(&format!("/{}", command.label()), src, &mut ret);
+        ret
+    }
+
+    /// Usages and description of every command the sender can use.
+    pub fn get_help(&self, src: &CommandSource) -> Vec<(Vec<String>, Option<&'static str>)> {
+        self.commands.iter()
+            .filter(|c| c.can_use(src))
+            .map(|c| (self.get_usages(c, src), c.description))
+            .collect()
+    }
+}
+
+impl Server {
+    fn get_command_source(&self, sender: CommandSender, looked_at: Option<BlockPos>) -> CommandSource {
+        let player_names = self.player_list.iter().map(|p| p.borrow().get_name().to_string()).collect();
+        let (permission_level, permissions, pos) = match sender {
+            CommandSender::Console => (4, PermissionSet::all(), None),
+            CommandSender::Player(uuid) => match self.player_list.get_by_uuid(&uuid) {
+                Some(p) => (p.get_permission_level(), p.get_permissions().clone(), Some(p.get_pos())),
+                None => (0, PermissionSet::default(), None),
+            },
+        };
+        CommandSource { sender, permission_level, permissions, pos, player_names, looked_at }
+    }
+
+    /// Runs a command line. The leading slash has to be removed already.
+    pub(super) fn execute_command(&mut self, sender: CommandSender, line: &str) {
+        if line.trim().is_empty() {
+            return;
+        }
+        let src = self.get_command_source(sender, None);
+        let result = match self.commands.parse(line, &src) {
+            Ok((executor, ctx)) => executor(self, &ctx),
+            Err(e) => Err(e),
+        };
+        if let Err(e) = result {
+            self.send_message(sender, &format!("{}{}", Code::Red, e));
+        }
+    }
+
+    /// Answers a tab complete request of a player. Chat messages complete player names.
+    pub(super) fn complete_command(&self, sender: CommandSender, text: &str, looked_at: Option<BlockPos>) -> Vec<String> {
+        let src = self.get_command_source(sender, looked_at);
+        if text.starts_with('/') {
+            return self.commands.complete(&text[1..], &src);
+        }
+        let partial = text.rsplit(' ').next().unwrap_or("").to_lowercase();
+        src.player_names.into_iter().filter(|n| n.to_lowercase().starts_with(&partial)).collect()
+    }
+
+    /// message can have legacy formatting codes.
+    fn send_message(&self, sender: CommandSender, message: &str) {
+        let message = Chat::from(text::parse_legacy(message));
+        match sender {
+            CommandSender::Console => info!("{}", message.to_ansi()),
+            CommandSender::Player(uuid) => self.player_list.send_packet_to_player(
+                uuid,
+                Arc::new(SPacket::PlayChatMessage { position: ChatPos::SystemMessage, message }),
+            ),
+        }
+    }
+
+    /// Sends the message to every player and the console.
+    fn broadcast(&self, message: &str) {
+        let message = Chat::from(text::parse_legacy(message));
+        info!("{}", message.to_ansi());
+        self.player_list.send_packet_to_all_players(Arc::new(SPacket::PlayChatMessage { position: ChatPos::Normal, message }));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn dispatcher() -> Dispatcher {
+        let mut d = Dispatcher::new();
+        register_builtin(&mut d);
+        d
+    }
+
+    fn player(permission_level: u8) -> CommandSource {
+        CommandSource {
+            sender: CommandSender::Player(Uuid::nil()),
+            permission_level,
+            permissions: PermissionSet::default(),
+            pos: Some(Vec3::new(10.0, 64.0, 10.0)),
+            player_names: vec!["Notch".into(), "jeb_".into()],
+            looked_at: Some(BlockPos::new(5, 70, -3)),
+        }
+    }
+
+    fn console() -> CommandSource {
+        CommandSource {
+            sender: CommandSender::Console,
+            permission_level: 4,
+            permissions: PermissionSet::all(),
+            pos: None,
+            player_names: vec!["Notch".into()],
+            looked_at: None,
+        }
+    }
+
+    #[test]
+    fn relative_coordinates() {
+        let d = dispatcher();
+        let (_, ctx) = d.parse("tp ~ ~1 ~-2.5", &player(4)).unwrap();
+        let location = ctx.get_coordinates("location");
+        assert_eq!(location.resolve(ctx.pos), Ok(Vec3::new(10.0, 65.0, 7.5)));
+        assert!(location.resolve(None).is_err());
+
+        let (_, ctx) = d.parse("tp notch 1 2 3", &console()).unwrap();
+        assert_eq!(ctx.get_string("target"), "Notch");
+        assert_eq!(ctx.get_coordinates("location").resolve(None), Ok(Vec3::new(1.0, 2.0, 3.0)));
+
+        let (_, ctx) = d.parse("setworldspawn ~ ~-1 5", &player(4)).unwrap();
+        assert_eq!(ctx.get_coordinates("pos").resolve_block(ctx.pos), Ok(BlockPos::new(10, 63, 5)));
+        assert!(d.parse("setworldspawn 1.5 2 3", &player(4)).is_err());
+    }
+
+    #[test]
+    fn errors_and_permissions() {
+        let d = dispatcher();
+        assert!(d.parse("list", &player(0)).is_ok());
+        assert_eq!(d.parse("stop", &player(0)).unwrap_err(), "You do not have permission to use this command.");
+        assert!(d.parse("foo", &player(4)).unwrap_err().starts_with("Unknown command"));
+        assert!(d.parse("tp", &player(4)).unwrap_err().starts_with("Usage: /tp <location> OR /tp <target>"));
+        assert_eq!(d.parse("tp Dinnerbone", &player(4)).unwrap_err(), "Player 'Dinnerbone' cannot be found");
+        assert_eq!(d.parse("gamemode creative", &player(4)).map(|(_, ctx)| ctx.get_gamemode("mode")).unwrap(), Gamemode::Creative);
+        assert!(d.parse("time add -5", &player(4)).unwrap_err().contains("too small"));
+        assert_eq!(d.parse("ban Dinnerbone being upside down", &player(3)).map(|(_, ctx)| ctx.get_string("reason").to_string()).unwrap(), "being upside down");
+        assert!(d.parse("whitelist add Dinnerbone", &player(2)).is_err());
+    }
+
+    #[test]
+    fn permission_nodes() {
+        let d = dispatcher();
+        let mut src = player(0);
+        src.permissions.apply(&["quartz.command.*".into(), "-quartz.command.stop".into()]);
+        assert!(d.parse("kick Notch", &src).is_ok());
+        assert!(d.parse("stop", &src).is_err());
+        assert!(d.complete("st", &src).is_empty());
+        let mut src = player(4);
+        src.permissions.apply(&["-quartz.command.tp".into()]);
+        assert_eq!(d.parse("tp 1 2 3", &src).unwrap_err(), "You do not have permission to use this command.");
+        assert!(d.parse("stop", &src).is_ok());
+        let (_, ctx) = d.parse("permission group Builder set quartz.build.* false", &console()).unwrap();
+        assert_eq!((ctx.get_string("node"), ctx.get_string("value")), ("quartz.build.*", "false"));
+        assert!(d.parse("permission player Notch unset quartz.chat.color", &console()).is_ok());
+    }
+
+    #[test]
+    fn completions() {
+        let d = dispatcher();
+        assert_eq!(d.complete("ti", &player(4)), vec!["/time"]);
+        assert!(d.complete("", &player(0)).iter().all(|c| c != "/stop"));
+        assert_eq!(d.complete("time s", &player(4)), vec!["set"]);
+        assert_eq!(d.complete("tp j", &player(4)), vec!["jeb_"]);
+        assert_eq!(d.complete("tp Notch ", &player(4)), vec!["Notch", "jeb_", "~"]);
+        assert_eq!(d.complete("gamemode c", &player(4)), vec!["creative"]);
+        assert_eq!(d.complete("setworldspawn 5 ", &player(4)), vec!["70"]);
+    }
+}
@@ -0,0 +1,26 @@
+use std::io::{self, BufRead};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+
+/// Reads commands from stdin on another thread. They are handled on the tick thread.
+pub fn start() -> Receiver<String> {
+    let (tx, rx) = mpsc::channel();
+    let builder = thread::Builder::new().name("Server console handler".into());
+    builder.spawn(move || {
+        let stdin = io::stdin();
+        for line in stdin.lock().lines() {
+            match line {
+                Ok(line) => {
+                    if tx.send(line).is_err() {
+                        break;
+                    }
+                }
+                Err(e) => {
+                    error!("error reading console: {}", e);
+                    break;
+                }
+            }
+        }
+    }).expect("could not start console thread");
+    rx
+}
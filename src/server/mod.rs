@@ -1,29 +1,42 @@
+pub mod command;
+pub mod console;
+pub mod permissions;
 pub mod playerlist;
+pub mod properties;
+pub mod userlist;
 
 use std::fs::File;
 use std::io::{self, BufRead, BufReader, Read, Write};
 use std::net::SocketAddr;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 use std::rc::Rc;
 use std::sync::mpsc::{self, Sender, Receiver};
 use std::time::{Instant, Duration};
 use std::thread;
 use std::collections::{HashMap, VecDeque, HashSet};
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 
 use base64;
 use uuid::Uuid;
 
+use crate::auth::{self, Authenticator, SessionService};
 use crate::network::{self, NetworkServer};
 use crate::entity::player::Player;
 use crate::proto::packets::{SPacket, SPlayPlayerListItemData, SPlayPlayerListItemDataAction};
-use crate::world::{Dimension, LevelType, World};
+use crate::world::{self, Dimension, LevelType, World};
+use crate::world::level_data::LevelData;
 use crate::world::chunk::{ChunkPos, Chunk};
+use self::command::{CommandSender, Dispatcher};
+use self::permissions::Permissions;
 use self::playerlist::PlayerList;
+use self::properties::ServerProperties;
+use self::userlist::UserLists;
 use crate::text::{self, Code, ChatPos};
 use crate::text::chat::{Chat, Component};
 use crate::util;
+use crate::math::Vec3;
+use crate::nbt::Nbt;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Gamemode {
@@ -33,6 +46,18 @@ pub enum Gamemode {
     Spectator,
 }
 
+impl Gamemode {
+    pub fn from_u8(x: u8) -> Option<Gamemode> {
+        match x {
+            0 => Some(Gamemode::Survival),
+            1 => Some(Gamemode::Creative),
+            2 => Some(Gamemode::Adventure),
+            3 => Some(Gamemode::Spectator),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Difficulty {
     Peaceful,
@@ -41,12 +66,32 @@ pub enum Difficulty {
     Hard,
 }
 
+impl Difficulty {
+    pub fn from_u8(x: u8) -> Option<Difficulty> {
+        match x {
+            0 => Some(Difficulty::Peaceful),
+            1 => Some(Difficulty::Easy),
+            2 => Some(Difficulty::Normal),
+            3 => Some(Difficulty::Hard),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Fail, Debug)]
 pub enum Error {
     #[fail(display = "io error: {}", _0)]
     IOError(#[cause] io::Error),
     #[fail(display = "network error: {}", _0)]
     NetworkError(#[cause] network::Error),
+    #[fail(display = "world error: {}", _0)]
+    WorldError(#[cause] world::Error),
+    #[fail(display = "auth error: {}", _0)]
+    AuthError(#[cause] auth::Error),
+    #[fail(display = "error loading user lists: {}", _0)]
+    UserListError(#[cause] userlist::Error),
+    #[fail(display = "error loading permissions: {}", _0)]
+    PermissionsError(#[cause] permissions::Error),
 }
 
 impl From<io::Error> for Error {
@@ -57,16 +102,68 @@ impl From<network::Error> for Error {
     fn from(x: network::Error) -> Self { Error::NetworkError(x) }
 }
 
+impl From<world::Error> for Error {
+    fn from(x: world::Error) -> Self { Error::WorldError(x) }
+}
+
+impl From<auth::Error> for Error {
+    fn from(x: auth::Error) -> Self { Error::AuthError(x) }
+}
+
+impl From<userlist::Error> for Error {
+    fn from(x: userlist::Error) -> Self { Error::UserListError(x) }
+}
+
+impl From<permissions::Error> for Error {
+    fn from(x: permissions::Error) -> Self { Error::PermissionsError(x) }
+}
+
 pub const MS_PER_TICK: u64 = 50;
 pub const TICKS_PER_SEC: u64 = 1000 / MS_PER_TICK;
 
 /// information players need to know about server
 pub struct ServerInfo {
+    pub max_players: i32,
     pub view_distance: u8,
     pub player_view_distance: u8,
+    pub keep_alive_timeout: Duration,
     pub difficulty: Difficulty,
     pub level_type: LevelType,
+    /// total ticks the world has run. saved as "Time" in level.dat.
     pub tick: u64,
+    pub day_time: i64,
+    pub seed: i64,
+    pub game_rules: HashMap<String, String>,
+    /// entity ids are unique across worlds.
+    pub next_entity_id: Cell<i32>,
+}
+
+impl ServerInfo {
+    /// Ids for players and items, wherever they are made.
+    pub fn next_entity_id(&self) -> i32 {
+        let id = self.next_entity_id.get();
+        self.next_entity_id.set(id + 1);
+        id
+    }
+
+    /// game rules are stored as strings. Missing or malformed rules are false.
+    pub fn get_game_rule_bool(&self, rule: &str) -> bool {
+        self.game_rules.get(rule).map_or(false, |v| v == "true")
+    }
+
+    pub fn time_update_packet(&self) -> SPacket {
+        SPacket::PlayTimeUpdate {
+            world_age: self.tick as i64,
+            // negative time of day stops the client from advancing it. -0 would not, so vanilla sends -1 then.
+            time_of_day: if self.get_game_rule_bool("doDaylightCycle") {
+                self.day_time
+            } else if self.day_time == 0 {
+                -1
+            } else {
+                -self.day_time
+            },
+        }
+    }
 }
 
 pub struct Worlds {
@@ -76,11 +173,26 @@ pub struct Worlds {
 }
 
 impl Worlds {
-    fn new() -> Worlds {
+    fn new<P: AsRef<Path>>(dir: P) -> Worlds {
+        let dir = dir.as_ref();
         Worlds {
-            overworld: World::new(Dimension::Overworld),
-            nether: World::new(Dimension::Nether),
-            end: World::new(Dimension::End),
+            overworld: World::new(Dimension::Overworld, dir.to_path_buf()),
+            nether: World::new(Dimension::Nether, dir.join("DIM-1")),
+            end: World::new(Dimension::End, dir.join("DIM1")),
+        }
+    }
+
+    fn save_all(&mut self) {
+        for world in [&mut self.overworld, &mut self.nether, &mut self.end].iter_mut() {
+            if let Err(e) = world.save_all() {
+                error!("error saving world: {}", e);
+            }
+        }
+    }
+
+    fn tick_items(&mut self, player_list: &PlayerList, tick: u64) {
+        for world in [&mut self.overworld, &mut self.nether, &mut self.end].iter_mut() {
+            world.tick_items(player_list, tick);
         }
     }
 
@@ -110,8 +222,18 @@ pub struct Server {
 
     current_time: Instant,
     start_time: Instant,
-    next_entity_id: i32,
     server_info: ServerInfo,
+    /// kept so level.dat fields we don't use are written back.
+    level_data: LevelData,
+    world_dir: PathBuf,
+    shutdown_message: String,
+    /// bans, whitelist and ops. the network thread checks logins against them.
+    user_lists: Arc<Mutex<UserLists>>,
+    permissions: Permissions,
+    online_mode: bool,
+    op_permission_level: u8,
+    console_commands: Option<Receiver<String>>,
+    commands: Dispatcher,
 }
 
 impl Server {
@@ -128,15 +250,52 @@ impl Server {
         Ok(Some(format!("data:image/png;base64,{}", base64::display::Base64Display::standard(&v))))
     }
 
-    pub fn new(addr: SocketAddr) -> Result<Server, Error> {
+    /// session_service is only used in online mode.
+    pub fn new(properties: ServerProperties, session_service: Box<dyn SessionService>) -> Result<Server, Error> {
         let running = Arc::new(Mutex::new(false));
         let favicon = Server::load_favicon()?;
+        let world_dir = PathBuf::from(&properties.level_name);
+        // level type only matters for new worlds, difficulty from properties always wins like vanilla.
+        let mut level_data = LevelData::load(world_dir.join("level.dat"))?.unwrap_or_else(|| {
+            let mut level_data = LevelData::default();
+            level_data.level_type = properties.level_type;
+            level_data
+        });
+        level_data.difficulty = properties.difficulty;
+        let mut worlds = Worlds::new(&world_dir);
+        worlds.overworld.set_spawn_pos(level_data.spawn);
         let (tx, rx) = mpsc::channel();
+        let server_info = ServerInfo {
+            max_players: properties.max_players,
+            view_distance: properties.view_distance,
+            player_view_distance: properties.player_view_distance,
+            keep_alive_timeout: Duration::from_secs(properties.keep_alive_timeout),
+            difficulty: level_data.difficulty,
+            level_type: level_data.level_type,
+            tick: level_data.time as u64,
+            day_time: level_data.day_time,
+            seed: level_data.seed,
+            game_rules: level_data.game_rules.clone(),
+            next_entity_id: Cell::new(0),
+        };
+        let shutdown_message = properties.shutdown_message.clone();
+        // next to server.properties, like vanilla.
+        let user_lists = Arc::new(Mutex::new(UserLists::load("", properties.white_list)?));
+        let permissions = Permissions::load("permissions.json")?;
+        let (online_mode, op_permission_level) = (properties.online_mode, properties.op_permission_level);
+        let mut commands = Dispatcher::new();
+        command::register_builtin(&mut commands);
+        let authenticator = if properties.online_mode {
+            Some(Authenticator::new(session_service)?)
+        } else {
+            None
+        };
         let network_server = NetworkServer::new(
-            addr,
+            Arc::new(properties),
+            authenticator,
             favicon,
-            running.clone(),
             tx,
+            Arc::clone(&user_lists),
         );
         Ok(Server {
             running,
@@ -145,26 +304,43 @@ impl Server {
             player_list: PlayerList::new(),
             incoming_players: rx,
 
-            worlds: Worlds::new(),
+            worlds,
 
             current_time: Instant::now(),
             start_time: Instant::now(),
-            next_entity_id: 0,
-            server_info: ServerInfo {
-                view_distance: 10,
-                player_view_distance: 3,
-                difficulty: Difficulty::Peaceful,
-                level_type: LevelType::Flat,
-                tick: 0,
-            },
+            server_info,
+            level_data,
+            world_dir,
+            shutdown_message,
+            user_lists,
+            permissions,
+            online_mode,
+            op_permission_level,
+            console_commands: None,
+            commands,
         })
     }
 
+    /// Setting this to false stops the server after the current tick.
+    pub fn get_running(&self) -> Arc<Mutex<bool>> {
+        Arc::clone(&self.running)
+    }
+
     pub fn start(&mut self) -> Result<(), Error> {
-        *self.running.lock().unwrap() = true;
+        self.listen()?;
+        self.console_commands = Some(console::start());
+        self.run();
+        Ok(())
+    }
 
-        self.network_server.start()?;
+    /// Starts accepting connections. Players get in once `run` is called.
+    pub fn listen(&mut self) -> Result<SocketAddr, Error> {
+        *self.running.lock().unwrap() = true;
+        Ok(self.network_server.start()?)
+    }
 
+    /// Ticks until running is set to false, then shuts down.
+    pub fn run(&mut self) {
         self.current_time = Instant::now();
         let mut accumulator = 0;
 
@@ -174,7 +350,7 @@ impl Server {
             let mut millis = util::duration_total_ms(duration) as u64;
 
             if millis > 2000 {
-                println!("Ticks taking too long. Can't compensate too much. Skipping {} ticks", (millis - 2000) / MS_PER_TICK);
+                warn!("Can't keep up! Is the server overloaded? Running {}ms behind, skipping {} ticks", millis, (millis - 2000) / MS_PER_TICK);
                 millis = 2000;
             }
 
@@ -189,20 +365,18 @@ impl Server {
             thread::sleep(Duration::from_millis(MS_PER_TICK - accumulator));
         }
 
-        self.cleanup();
-        Ok(())
-    }
-
-    fn get_next_entity_id(&mut self) -> i32 {
-        let ret = self.next_entity_id;
-        self.next_entity_id += 1;
-        ret
+        self.shutdown();
     }
 
     fn add_new_player(&mut self, mut p: Player) {
-        let entity_id = self.get_next_entity_id();
+        let entity_id = self.server_info.next_entity_id();
         p.set_entity_id(entity_id);
         p.set_join_tick(self.server_info.tick);
+        let spawn = self.worlds.overworld.get_spawn_pos();
+        p.set_pos(Vec3::new(spawn.x as f64 + 0.5, spawn.y as f64, spawn.z as f64 + 0.5));
+        self.load_player_data(&mut p);
+        p.set_permission_level(self.user_lists.lock().unwrap().get_op_level(p.get_uuid()));
+        p.set_permissions(self.permissions.resolve(p.get_uuid()));
         let world = self.worlds.get_world(p.get_dimension());
         p.join(&mut ServerContext { server_info: &self.server_info, player_list: &self.player_list, world });
         self.player_list.add_player(p);
@@ -232,6 +406,10 @@ impl Server {
         for u in to_remove {
             let mut p = self.player_list.remove_by_uuid(&u).unwrap();
             let world = self.worlds.get_world(p.get_dimension());
+            // otherwise items on the cursor would be lost.
+            p.close_window(&mut ServerContext { player_list: &self.player_list, server_info: &self.server_info, world });
+            self.save_player_data(&p);
+            let world = self.worlds.get_world(p.get_dimension());
             p.leave(&mut ServerContext { player_list: &self.player_list, server_info: &self.server_info, world });
         }
     }
@@ -244,12 +422,122 @@ impl Server {
         }
     }
 
+    fn handle_console_commands(&mut self) {
+        let commands: Vec<String> = match self.console_commands {
+            Some(ref rx) => rx.try_iter().collect(),
+            None => return,
+        };
+        for command in commands {
+            let command = command.trim();
+            // players type the slash, console users might too.
+            let command = if command.starts_with('/') { &command[1..] } else { command };
+            self.execute_command(CommandSender::Console, command);
+        }
+    }
+
+    fn handle_player_commands(&mut self) {
+        let mut commands = Vec::new();
+        for p in self.player_list.iter() {
+            let mut p = p.borrow_mut();
+            let uuid = p.get_uuid();
+            commands.extend(p.take_pending_commands().into_iter().map(|c| (uuid, c)));
+        }
+        for (uuid, command) in commands {
+            self.execute_command(CommandSender::Player(uuid), &command);
+        }
+    }
+
+    fn handle_tab_completes(&mut self) {
+        let mut requests = Vec::new();
+        for p in self.player_list.iter() {
+            let mut p = p.borrow_mut();
+            let uuid = p.get_uuid();
+            requests.extend(p.take_pending_tab_completes().into_iter().map(|r| (uuid, r)));
+        }
+        for (uuid, (text, looked_at)) in requests {
+            let matches = self.complete_command(CommandSender::Player(uuid), &text, looked_at);
+            self.player_list.send_packet_to_player(uuid, Arc::new(SPacket::PlayTabComplete { matches }));
+        }
+    }
+
     fn tick(&mut self) {
+        self.handle_console_commands();
         self.accept_new_players();
         self.tick_players();
+        self.worlds.tick_items(&self.player_list, self.server_info.tick);
+        self.handle_player_commands();
+        self.handle_tab_completes();
         self.remove_disconnected_players();
         self.server_info.tick += 1;
+        if self.server_info.get_game_rule_bool("doDaylightCycle") {
+            self.server_info.day_time += 1;
+        }
     }
 
-    fn cleanup(&mut self) {}
+    fn player_data_path(&self, uuid: Uuid) -> PathBuf {
+        self.world_dir.join("playerdata").join(format!("{}.dat", uuid.hyphenated()))
+    }
+
+    /// Loads saved data of the player if there is any. Players who never joined keep their defaults.
+    fn load_player_data(&self, p: &mut Player) {
+        let path = self.player_data_path(p.get_uuid());
+        if !path.exists() {
+            return;
+        }
+        let result = Nbt::read_gzip_file(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|nbt| p.load_nbt(&nbt).map_err(|e| e.to_string()));
+        if let Err(e) = result {
+            error!("error loading player data of {}: {}", p.get_name(), e);
+        }
+    }
+
+    fn save_player_data(&self, p: &Player) {
+        if let Err(e) = p.to_nbt().write_gzip_file(self.player_data_path(p.get_uuid())) {
+            error!("error saving player data of {}: {}", p.get_name(), e);
+        }
+    }
+
+    /// Saves players, worlds and level.dat.
+    fn save_all(&mut self) {
+        for p in self.player_list.iter() {
+            self.save_player_data(&p.borrow());
+        }
+        self.worlds.save_all();
+        self.save_level_data();
+    }
+
+    fn save_level_data(&mut self) {
+        self.level_data.spawn = self.worlds.overworld.get_spawn_pos();
+        self.level_data.time = self.server_info.tick as i64;
+        self.level_data.day_time = self.server_info.day_time;
+        self.level_data.seed = self.server_info.seed;
+        self.level_data.difficulty = self.server_info.difficulty;
+        self.level_data.level_type = self.server_info.level_type;
+        self.level_data.game_rules = self.server_info.game_rules.clone();
+        if let Err(e) = self.level_data.save(self.world_dir.join("level.dat")) {
+            error!("error saving level.dat: {}", e);
+        }
+    }
+
+    /// Kicks everyone, waits for the network thread to send the disconnect messages and saves everything.
+    fn shutdown(&mut self) {
+        info!("Stopping server");
+        let reason = Chat::from(text::parse_legacy(&self.shutdown_message));
+        let uuids: Vec<Uuid> = self.player_list.iter().map(|p| p.borrow().get_uuid()).collect();
+        for u in uuids {
+            let mut p = self.player_list.remove_by_uuid(&u).unwrap();
+            p.kick(reason.clone());
+            let world = self.worlds.get_world(p.get_dimension());
+            p.close_window(&mut ServerContext { player_list: &self.player_list, server_info: &self.server_info, world });
+            self.save_player_data(&p);
+            let world = self.worlds.get_world(p.get_dimension());
+            p.leave(&mut ServerContext { player_list: &self.player_list, server_info: &self.server_info, world });
+        }
+
+        self.network_server.stop();
+        info!("Saving worlds");
+        self.worlds.save_all();
+        self.save_level_data();
+    }
 }
\ No newline at end of file
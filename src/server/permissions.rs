@@ -0,0 +1,288 @@
+//! Permission nodes like `quartz.command.kick`, given to groups and players in permissions.json.
+//!
+//! A node ending in `*` matches every node below it, `*` alone matches everything. Nodes starting with `-` take the
+//! permission away. Groups inherit the nodes of their parents and every player is in the `default` group. Players'
+//! own nodes come last, so they override their groups.
+
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter};
+use std::path::PathBuf;
+
+use serde_json;
+use uuid::Uuid;
+
+#[derive(Fail, Debug)]
+pub enum Error {
+    #[fail(display = "io error: {}", _0)]
+    IOError(#[cause] io::Error),
+    #[fail(display = "invalid json: {}", _0)]
+    JsonError(#[cause] serde_json::Error),
+}
+
+impl_from_for_newtype_enum!(Error::IOError, io::Error);
+impl_from_for_newtype_enum!(Error::JsonError, serde_json::Error);
+
+pub const PLACE_BLOCKS: &str = "quartz.build.place";
+pub const BREAK_BLOCKS: &str = "quartz.build.break";
+/// use `&` formatting codes in chat.
+pub const CHAT_COLOR: &str = "quartz.chat.color";
+/// group every player is in.
+pub const DEFAULT_GROUP: &str = "default";
+
+/// Node which allows running the command. Commands fall back to their permission level without one.
+pub fn command_node(command: &str) -> String {
+    format!("quartz.command.{}", command)
+}
+
+/// Permission level needed for a node nothing in the file decides.
+pub fn default_level(node: &str) -> u8 {
+    match node {
+        PLACE_BLOCKS | BREAK_BLOCKS | CHAT_COLOR => 0,
+        _ => 4,
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Group {
+    #[serde(default)]
+    pub inherits: Vec<String>,
+    #[serde(default)]
+    pub permissions: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PlayerPermissions {
+    /// only there so people editing the file know who it is.
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub groups: Vec<String>,
+    #[serde(default)]
+    pub permissions: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct PermissionsFile {
+    #[serde(default)]
+    groups: HashMap<String, Group>,
+    /// by hyphenated uuid.
+    #[serde(default)]
+    players: HashMap<String, PlayerPermissions>,
+}
+
+/// Nodes of a player with groups and overrides applied.
+#[derive(Debug, Clone, Default)]
+pub struct PermissionSet {
+    nodes: HashMap<String, bool>,
+}
+
+impl PermissionSet {
+    /// Everything, for the console.
+    pub fn all() -> PermissionSet {
+        let mut set = PermissionSet::default();
+        set.nodes.insert("*".into(), true);
+        set
+    }
+
+    /// Later nodes win over earlier ones.
+    pub fn apply(&mut self, nodes: &[String]) {
+        for node in nodes {
+            let (node, value) = if node.starts_with('-') { (&node[1..], false) } else { (&node[..], true) };
+            self.nodes.insert(node.to_lowercase(), value);
+        }
+    }
+
+    /// Whether the node is given or taken away, by itself or the closest wildcard above it. None if nothing decides.
+    pub fn check(&self, node: &str) -> Option<bool> {
+        let node = node.to_lowercase();
+        if let Some(&value) = self.nodes.get(&node) {
+            return Some(value);
+        }
+        let mut prefix = &node[..];
+        while let Some(dot) = prefix.rfind('.') {
+            prefix = &prefix[..dot];
+            if let Some(&value) = self.nodes.get(&format!("{}.*", prefix)) {
+                return Some(value);
+            }
+        }
+        self.nodes.get("*").cloned()
+    }
+}
+
+/// Groups and players of permissions.json. The file is only created once something changes.
+#[derive(Debug)]
+pub struct Permissions {
+    path: PathBuf,
+    file: PermissionsFile,
+}
+
+impl Permissions {
+    /// A missing file has no groups or players.
+    pub fn load<P: Into<PathBuf>>(path: P) -> Result<Permissions, Error> {
+        let mut permissions = Permissions { path: path.into(), file: PermissionsFile::default() };
+        permissions.reload()?;
+        Ok(permissions)
+    }
+
+    /// Reads the file again. Nothing changes if the file is invalid.
+    pub fn reload(&mut self) -> Result<(), Error> {
+        self.file = if self.path.exists() {
+            serde_json::from_reader(BufReader::new(File::open(&self.path)?))?
+        } else {
+            PermissionsFile::default()
+        };
+        Ok(())
+    }
+
+    fn save(&self) -> Result<(), Error> {
+        if let Some(dir) = self.path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        serde_json::to_writer_pretty(BufWriter::new(File::create(&self.path)?), &self.file)?;
+        Ok(())
+    }
+
+    pub fn resolve(&self, uuid: Uuid) -> PermissionSet {
+        let mut set = PermissionSet::default();
+        let mut applied = HashSet::new();
+        self.apply_group(DEFAULT_GROUP, &mut set, &mut applied);
+        if let Some(player) = self.file.players.get(&uuid.hyphenated().to_string()) {
+            for group in &player.groups {
+                self.apply_group(group, &mut set, &mut applied);
+            }
+            set.apply(&player.permissions);
+        }
+        set
+    }
+
+    /// Parents first so the group's own nodes win. Groups are applied once, which also stops inheritance loops.
+    fn apply_group(&self, name: &str, set: &mut PermissionSet, applied: &mut HashSet<String>) {
+        if !applied.insert(name.to_lowercase()) {
+            return;
+        }
+        if let Some(group) = self.get_group(name) {
+            for parent in &group.inherits {
+                self.apply_group(parent, set, applied);
+            }
+            set.apply(&group.permissions);
+        }
+    }
+
+    fn get_group(&self, name: &str) -> Option<&Group> {
+        self.file.groups.iter().find(|&(n, _)| n.eq_ignore_ascii_case(name)).map(|(_, g)| g)
+    }
+
+    fn group_mut(&mut self, name: &str) -> &mut Group {
+        let name = self.file.groups.keys().find(|n| n.eq_ignore_ascii_case(name)).cloned().unwrap_or_else(|| name.into());
+        self.file.groups.entry(name).or_insert_with(Group::default)
+    }
+
+    fn player_mut(&mut self, uuid: Uuid, name: &str) -> &mut PlayerPermissions {
+        let player = self.file.players.entry(uuid.hyphenated().to_string()).or_insert_with(PlayerPermissions::default);
+        player.name = name.into();
+        player
+    }
+
+    /// Gives (true) or takes away (false) the node from the player, or removes it from them (None).
+    pub fn set_player_node(&mut self, uuid: Uuid, name: &str, node: &str, value: Option<bool>) -> Result<(), Error> {
+        set_node(&mut self.player_mut(uuid, name).permissions, node, value);
+        self.save()
+    }
+
+    /// Returns false if the player was in the group already when adding, or wasn't when removing.
+    pub fn set_player_group(&mut self, uuid: Uuid, name: &str, group: &str, member: bool) -> Result<bool, Error> {
+        let changed = set_member(&mut self.player_mut(uuid, name).groups, group, member);
+        if changed {
+            self.save()?;
+        }
+        Ok(changed)
+    }
+
+    pub fn set_group_node(&mut self, group: &str, node: &str, value: Option<bool>) -> Result<(), Error> {
+        set_node(&mut self.group_mut(group).permissions, node, value);
+        self.save()
+    }
+
+    /// Returns false if the group inherited from the parent already when adding, or didn't when removing.
+    pub fn set_group_parent(&mut self, group: &str, parent: &str, inherits: bool) -> Result<bool, Error> {
+        let changed = set_member(&mut self.group_mut(group).inherits, parent, inherits);
+        if changed {
+            self.save()?;
+        }
+        Ok(changed)
+    }
+}
+
+fn set_node(nodes: &mut Vec<String>, node: &str, value: Option<bool>) {
+    let node = node.trim_start_matches('-');
+    nodes.retain(|n| !n.trim_start_matches('-').eq_ignore_ascii_case(node));
+    match value {
+        Some(true) => nodes.push(node.into()),
+        Some(false) => nodes.push(format!("-{}", node)),
+        None => {}
+    }
+}
+
+fn set_member(names: &mut Vec<String>, name: &str, member: bool) -> bool {
+    let len = names.len();
+    names.retain(|n| !n.eq_ignore_ascii_case(name));
+    if member {
+        names.push(name.into());
+    }
+    names.len() != len
+}
+
+#[cfg(test)]
+mod test {
+    use std::env;
+    use std::process;
+
+    use super::*;
+
+    #[test]
+    fn wildcards_and_negation() {
+        let mut set = PermissionSet::default();
+        set.apply(&["quartz.command.*".into(), "-quartz.command.stop".into()]);
+        assert_eq!(set.check("quartz.command.kick"), Some(true));
+        assert_eq!(set.check("Quartz.Command.Stop"), Some(false));
+        assert_eq!(set.check("quartz.build.place"), None);
+        set.apply(&["*".into(), "-quartz.command.*".into()]);
+        assert_eq!(set.check("quartz.build.place"), Some(true));
+        assert_eq!(set.check("quartz.command.kick"), Some(false));
+        assert_eq!(PermissionSet::all().check("anything"), Some(true));
+    }
+
+    #[test]
+    fn groups_and_overrides() {
+        let path = env::temp_dir().join(format!("quartz-permissions-{}.json", process::id()));
+        fs::write(&path, r#"{
+            "groups": {
+                "default": {"permissions": ["-quartz.build.*"]},
+                "builder": {"inherits": ["default", "builder"], "permissions": ["quartz.build.*"]},
+                "moderator": {"inherits": ["builder"], "permissions": ["quartz.command.kick", "quartz.command.ban"]}
+            },
+            "players": {
+                "069a79f4-44e9-4726-a5be-fca90e38aaf5": {"name": "Notch", "groups": ["moderator"], "permissions": ["-quartz.command.ban"]}
+            }
+        }"#).unwrap();
+        let mut permissions = Permissions::load(&path).unwrap();
+        let notch = Uuid::parse_str("069a79f4-44e9-4726-a5be-fca90e38aaf5").unwrap();
+        let set = permissions.resolve(notch);
+        assert_eq!(set.check(PLACE_BLOCKS), Some(true));
+        assert_eq!(set.check("quartz.command.kick"), Some(true));
+        assert_eq!(set.check("quartz.command.ban"), Some(false));
+        assert_eq!(permissions.resolve(Uuid::nil()).check(BREAK_BLOCKS), Some(false));
+
+        permissions.set_player_node(notch, "Notch", "quartz.command.ban", None).unwrap();
+        assert!(permissions.set_player_group(notch, "Notch", "moderator", false).unwrap());
+        assert!(!permissions.set_player_group(notch, "Notch", "moderator", false).unwrap());
+        permissions.set_group_node("default", "quartz.chat.color", Some(false)).unwrap();
+        let permissions = Permissions::load(&path).unwrap();
+        let set = permissions.resolve(notch);
+        assert_eq!(set.check("quartz.command.ban"), None);
+        assert_eq!(set.check(PLACE_BLOCKS), Some(false));
+        assert_eq!(set.check(CHAT_COLOR), Some(false));
+        fs::remove_file(&path).unwrap();
+    }
+}
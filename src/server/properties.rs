@@ -0,0 +1,486 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::Path;
+use std::str::FromStr;
+
+use log::LevelFilter;
+
+use crate::logger::LogFilter;
+use crate::server::{Difficulty, Gamemode};
+use crate::world::LevelType;
+
+#[derive(Fail, Debug)]
+pub enum Error {
+    #[fail(display = "io error: {}", _0)]
+    IOError(#[cause] io::Error),
+    #[fail(display = "invalid value \"{}\" for {}, expected {}", value, key, expected)]
+    InvalidValue {
+        key: String,
+        value: String,
+        expected: &'static str,
+    },
+}
+
+impl_from_for_newtype_enum!(Error::IOError, io::Error);
+
+/// Settings read from server.properties. Key names are the same as vanilla.
+#[derive(Debug, Clone)]
+pub struct ServerProperties {
+    pub server_ip: Option<IpAddr>,
+    pub server_port: u16,
+    pub level_name: String,
+    pub level_type: LevelType,
+    pub difficulty: Difficulty,
+    /// game mode of players joining for the first time.
+    pub gamemode: Gamemode,
+    pub max_players: i32,
+    pub motd: String,
+    pub view_distance: u8,
+    /// view distance of other players, in chunks. not a vanilla key.
+    pub player_view_distance: u8,
+    /// packets smaller than this are not compressed. negative disables compression.
+    pub network_compression_threshold: i32,
+    /// verify players with the session server and encrypt the connection.
+    pub online_mode: bool,
+    /// only players on whitelist.json and ops may join.
+    pub white_list: bool,
+    /// permission level players made op get.
+    pub op_permission_level: u8,
+    /// what players are kicked with when the server stops. not a vanilla key.
+    pub shutdown_message: String,
+    /// levels to log, like `info,network=debug`. not a vanilla key.
+    pub log_level: LogFilter,
+    /// directory to record the packets of every player to. empty disables recording. not a vanilla key.
+    pub capture_dir: Option<String>,
+    /// leave the names of online players out of the server list.
+    pub hide_online_players: bool,
+    /// version shown to clients the server can't talk to. empty shows the supported versions. not a vanilla key.
+    pub outdated_version_name: Option<String>,
+    /// connections start with a PROXY protocol header from a proxy like HAProxy. not a vanilla key.
+    pub proxy_protocol: bool,
+    /// take player addresses, uuids and skins from BungeeCord's handshake and don't authenticate players.
+    /// not a vanilla key.
+    pub bungeecord: bool,
+    /// logins allowed from one ip per connection-throttle-interval. 0 disables throttling. not a vanilla key.
+    pub connection_throttle_limit: u32,
+    /// in seconds. not a vanilla key.
+    pub connection_throttle_interval: u64,
+    /// connections allowed from one ip at once. 0 allows any number. not a vanilla key.
+    pub max_connections_per_ip: u32,
+    /// seconds clients have to log in before being disconnected. not a vanilla key.
+    pub login_timeout: u64,
+    /// seconds players have to answer a keep-alive before being disconnected. not a vanilla key.
+    pub keep_alive_timeout: u64,
+    /// every key of the file, including ones we don't know so they are written back.
+    raw: Vec<(String, String)>,
+}
+
+impl Default for ServerProperties {
+    fn default() -> Self {
+        ServerProperties {
+            server_ip: None,
+            server_port: 25565,
+            level_name: "world".into(),
+            level_type: LevelType::Flat,
+            difficulty: Difficulty::Peaceful,
+            // unlike vanilla, as players used to always be in creative.
+            gamemode: Gamemode::Creative,
+            max_players: 20,
+            motd: "Quartz minecraft server".into(),
+            view_distance: 10,
+            player_view_distance: 3,
+            network_compression_threshold: 256,
+            online_mode: true,
+            white_list: false,
+            op_permission_level: 4,
+            shutdown_message: "Server closed".into(),
+            log_level: LogFilter::new(LevelFilter::Info),
+            capture_dir: None,
+            hide_online_players: false,
+            outdated_version_name: None,
+            proxy_protocol: false,
+            bungeecord: false,
+            connection_throttle_limit: 3,
+            connection_throttle_interval: 10,
+            max_connections_per_ip: 3,
+            login_timeout: 30,
+            keep_alive_timeout: 30,
+            raw: Vec::new(),
+        }
+    }
+}
+
+impl ServerProperties {
+    /// Reads the file, then writes it back so missing keys show up with their defaults.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<ServerProperties, Error> {
+        let props = ServerProperties::read(&path)?;
+        props.save(path)?;
+        Ok(props)
+    }
+
+    /// Reads the file without writing it. A missing file gives the defaults.
+    pub fn read<P: AsRef<Path>>(path: P) -> Result<ServerProperties, Error> {
+        let path = path.as_ref();
+        let raw = if path.exists() {
+            parse(BufReader::new(File::open(path)?))?
+        } else {
+            Vec::new()
+        };
+        ServerProperties::from_raw(raw)
+    }
+
+    pub fn from_raw(raw: Vec<(String, String)>) -> Result<ServerProperties, Error> {
+        let mut props = ServerProperties::default();
+        {
+            let map: HashMap<&str, &str> = raw.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect();
+            let get = |key: &str| map.get(key).cloned();
+
+            if let Some(ip) = get("server-ip") {
+                props.server_ip = if ip.is_empty() {
+                    None
+                } else {
+                    Some(parse_value("server-ip", ip, "an ip address")?)
+                };
+            }
+            if let Some(port) = get("server-port") {
+                props.server_port = parse_value("server-port", port, "a port number")?;
+            }
+            if let Some(name) = get("level-name") {
+                props.level_name = name.into();
+            }
+            if let Some(level_type) = get("level-type") {
+                props.level_type = LevelType::from_str(level_type).ok_or_else(|| {
+                    invalid("level-type", level_type, "DEFAULT, FLAT, LARGEBIOMES, AMPLIFIED or DEFAULT_1_1")
+                })?;
+            }
+            if let Some(difficulty) = get("difficulty") {
+                props.difficulty = parse_difficulty(difficulty)
+                    .ok_or_else(|| invalid("difficulty", difficulty, "0-3 or a difficulty name"))?;
+            }
+            if let Some(gamemode) = get("gamemode") {
+                props.gamemode =
+                    parse_gamemode(gamemode).ok_or_else(|| invalid("gamemode", gamemode, "0-3 or a game mode name"))?;
+            }
+            if let Some(max) = get("max-players") {
+                props.max_players = parse_value("max-players", max, "a number")?;
+                if props.max_players < 0 {
+                    return Err(invalid("max-players", max, "a positive number"));
+                }
+            }
+            if let Some(motd) = get("motd") {
+                props.motd = motd.into();
+            }
+            if let Some(distance) = get("view-distance") {
+                let distance = parse_value("view-distance", distance, "a number")?;
+                props.view_distance = clamp("view-distance", distance, 3, 15) as u8;
+            }
+            if let Some(distance) = get("player-view-distance") {
+                let distance = parse_value("player-view-distance", distance, "a number")?;
+                let max = props.view_distance.into();
+                props.player_view_distance = clamp("player-view-distance", distance, 0, max) as u8;
+            }
+            if let Some(threshold) = get("network-compression-threshold") {
+                props.network_compression_threshold =
+                    parse_value("network-compression-threshold", threshold, "a number")?;
+            }
+            if let Some(online_mode) = get("online-mode") {
+                props.online_mode = parse_value("online-mode", online_mode, "true or false")?;
+            }
+            if let Some(white_list) = get("white-list") {
+                props.white_list = parse_value("white-list", white_list, "true or false")?;
+            }
+            if let Some(level) = get("op-permission-level") {
+                let level = parse_value("op-permission-level", level, "a number")?;
+                props.op_permission_level = clamp("op-permission-level", level, 1, 4) as u8;
+            }
+            if let Some(message) = get("shutdown-message") {
+                props.shutdown_message = message.into();
+            }
+            if let Some(level) = get("log-level") {
+                props.log_level = parse_value("log-level", level, "levels like info,network=debug")?;
+            }
+            if let Some(dir) = get("capture-dir") {
+                props.capture_dir = if dir.is_empty() { None } else { Some(dir.into()) };
+            }
+            if let Some(hide) = get("hide-online-players") {
+                props.hide_online_players = parse_value("hide-online-players", hide, "true or false")?;
+            }
+            if let Some(name) = get("outdated-version-name") {
+                props.outdated_version_name = if name.is_empty() { None } else { Some(name.into()) };
+            }
+            if let Some(proxy_protocol) = get("proxy-protocol") {
+                props.proxy_protocol = parse_value("proxy-protocol", proxy_protocol, "true or false")?;
+            }
+            if let Some(bungeecord) = get("bungeecord") {
+                props.bungeecord = parse_value("bungeecord", bungeecord, "true or false")?;
+            }
+            if let Some(limit) = get("connection-throttle-limit") {
+                props.connection_throttle_limit = parse_value("connection-throttle-limit", limit, "a number")?;
+            }
+            if let Some(interval) = get("connection-throttle-interval") {
+                props.connection_throttle_interval =
+                    parse_value("connection-throttle-interval", interval, "a number of seconds")?;
+            }
+            if let Some(max) = get("max-connections-per-ip") {
+                props.max_connections_per_ip = parse_value("max-connections-per-ip", max, "a number")?;
+            }
+            if let Some(timeout) = get("login-timeout") {
+                props.login_timeout = parse_value("login-timeout", timeout, "a number of seconds")?;
+            }
+            if let Some(timeout) = get("keep-alive-timeout") {
+                props.keep_alive_timeout = parse_value("keep-alive-timeout", timeout, "a number of seconds")?;
+            }
+        }
+        props.raw = raw;
+        Ok(props)
+    }
+
+    /// Address to bind to. An empty server-ip binds to every interface.
+    pub fn get_bind_addr(&self) -> SocketAddr {
+        SocketAddr::new(
+            self.server_ip.unwrap_or_else(|| Ipv4Addr::new(0, 0, 0, 0).into()),
+            self.server_port,
+        )
+    }
+
+    /// Compression threshold, or None if compression is disabled.
+    pub fn get_compression_threshold(&self) -> Option<u32> {
+        if self.network_compression_threshold < 0 {
+            None
+        } else {
+            Some(self.network_compression_threshold as u32)
+        }
+    }
+
+    fn to_raw(&self) -> Vec<(String, String)> {
+        let known = vec![
+            ("server-ip", self.server_ip.map_or(String::new(), |ip| ip.to_string())),
+            ("server-port", self.server_port.to_string()),
+            ("level-name", self.level_name.clone()),
+            ("level-type", self.level_type.as_str().to_uppercase()),
+            ("difficulty", (self.difficulty as u8).to_string()),
+            ("gamemode", (self.gamemode as u8).to_string()),
+            ("max-players", self.max_players.to_string()),
+            ("motd", self.motd.clone()),
+            ("view-distance", self.view_distance.to_string()),
+            ("player-view-distance", self.player_view_distance.to_string()),
+            ("network-compression-threshold", self.network_compression_threshold.to_string()),
+            ("online-mode", self.online_mode.to_string()),
+            ("white-list", self.white_list.to_string()),
+            ("op-permission-level", self.op_permission_level.to_string()),
+            ("shutdown-message", self.shutdown_message.clone()),
+            ("log-level", self.log_level.to_string()),
+            ("capture-dir", self.capture_dir.clone().unwrap_or_default()),
+            ("hide-online-players", self.hide_online_players.to_string()),
+            ("outdated-version-name", self.outdated_version_name.clone().unwrap_or_default()),
+            ("proxy-protocol", self.proxy_protocol.to_string()),
+            ("bungeecord", self.bungeecord.to_string()),
+            ("connection-throttle-limit", self.connection_throttle_limit.to_string()),
+            ("connection-throttle-interval", self.connection_throttle_interval.to_string()),
+            ("max-connections-per-ip", self.max_connections_per_ip.to_string()),
+            ("login-timeout", self.login_timeout.to_string()),
+            ("keep-alive-timeout", self.keep_alive_timeout.to_string()),
+        ];
+
+        let mut raw = self.raw.clone();
+        for (k, v) in known {
+            // keep how the user wrote values which are already there.
+            if !raw.iter().any(|&(ref key, _)| key == k) {
+                raw.push((k.into(), v));
+            }
+        }
+        raw.sort_by(|a, b| a.0.cmp(&b.0));
+        raw
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        let mut f = File::create(path)?;
+        writeln!(f, "#Minecraft server properties")?;
+        for (k, v) in self.to_raw() {
+            writeln!(f, "{}={}", escape(&k), escape(&v))?;
+        }
+        Ok(())
+    }
+}
+
+fn invalid(key: &str, value: &str, expected: &'static str) -> Error {
+    Error::InvalidValue {
+        key: key.into(),
+        value: value.into(),
+        expected,
+    }
+}
+
+fn parse_value<T: FromStr>(key: &str, value: &str, expected: &'static str) -> Result<T, Error> {
+    value.trim().parse().map_err(|_| invalid(key, value, expected))
+}
+
+/// Like vanilla, numbers out of range are moved into it instead of stopping the server.
+fn clamp(key: &str, value: i64, min: i64, max: i64) -> i64 {
+    let clamped = value.max(min).min(max);
+    if clamped != value {
+        warn!("{} {} is not between {} and {}, using {}", key, value, min, max, clamped);
+    }
+    clamped
+}
+
+fn parse_difficulty(s: &str) -> Option<Difficulty> {
+    match s.trim().to_lowercase().as_str() {
+        "peaceful" => Some(Difficulty::Peaceful),
+        "easy" => Some(Difficulty::Easy),
+        "normal" => Some(Difficulty::Normal),
+        "hard" => Some(Difficulty::Hard),
+        x => x.parse().ok().and_then(Difficulty::from_u8),
+    }
+}
+
+fn parse_gamemode(s: &str) -> Option<Gamemode> {
+    match s.trim().to_lowercase().as_str() {
+        "survival" => Some(Gamemode::Survival),
+        "creative" => Some(Gamemode::Creative),
+        "adventure" => Some(Gamemode::Adventure),
+        "spectator" => Some(Gamemode::Spectator),
+        x => x.parse().ok().and_then(Gamemode::from_u8),
+    }
+}
+
+/// Parses a java properties file. Only what vanilla writes is supported: `key=value` lines, `#` or `!`
+/// comments and backslash escapes.
+fn parse<R: BufRead>(r: R) -> Result<Vec<(String, String)>, Error> {
+    let mut ret = Vec::new();
+    for line in r.lines() {
+        let line = line?;
+        let line = line.trim_start();
+        if line.is_empty() || line.starts_with('#') || line.starts_with('!') {
+            continue;
+        }
+
+        let mut key = String::new();
+        let mut value = None;
+        let mut chars = line.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => key.push(unescape(&mut chars)),
+                '=' | ':' => {
+                    value = Some(unescape_all(chars.as_str().trim_start()));
+                    break;
+                }
+                c => key.push(c),
+            }
+        }
+        ret.push((key.trim_end().to_string(), value.unwrap_or_default()));
+    }
+    Ok(ret)
+}
+
+fn unescape(chars: &mut ::std::str::Chars) -> char {
+    match chars.next() {
+        Some('t') => '\t',
+        Some('n') => '\n',
+        Some('r') => '\r',
+        Some('u') => {
+            let hex: String = chars.by_ref().take(4).collect();
+            u32::from_str_radix(&hex, 16)
+                .ok()
+                .and_then(::std::char::from_u32)
+                .unwrap_or('\u{fffd}')
+        }
+        Some(c) => c,
+        None => '\\',
+    }
+}
+
+fn unescape_all(s: &str) -> String {
+    let mut ret = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            ret.push(unescape(&mut chars));
+        } else {
+            ret.push(c);
+        }
+    }
+    ret
+}
+
+fn escape(s: &str) -> String {
+    let mut ret = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '=' | ':' | '\\' | '#' | '!' => {
+                ret.push('\\');
+                ret.push(c);
+            }
+            '\t' => ret.push_str("\\t"),
+            '\n' => ret.push_str("\\n"),
+            '\r' => ret.push_str("\\r"),
+            c if c.is_ascii() => ret.push(c),
+            c => ret.push_str(&format!("\\u{:04X}", c as u32)),
+        }
+    }
+    ret
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn props(s: &str) -> Result<ServerProperties, Error> {
+        ServerProperties::from_raw(parse(s.as_bytes()).unwrap())
+    }
+
+    #[test]
+    fn parse_vanilla_file() {
+        let p = props(
+            "#Minecraft server properties\n\
+             server-port=25570\n\
+             difficulty=2\n\
+             level-type=AMPLIFIED\n\
+             max-players=5\n\
+             motd=A \\u00A7cred\\:server\n\
+             network-compression-threshold=-1\n\
+             server-ip=\n\
+             online-mode=false\n\
+             hide-online-players=true\n\
+             spawn-monsters=true\n",
+        )
+        .unwrap();
+        assert_eq!(p.server_port, 25570);
+        assert_eq!(p.difficulty, Difficulty::Normal);
+        assert_eq!(p.level_type, LevelType::Amplified);
+        assert_eq!(p.max_players, 5);
+        assert_eq!(p.motd, "A \u{a7}cred:server");
+        assert_eq!(p.get_compression_threshold(), None);
+        assert_eq!(p.get_bind_addr(), "0.0.0.0:25570".parse().unwrap());
+        assert!(!p.online_mode);
+        assert!(p.hide_online_players);
+        assert_eq!(p.outdated_version_name, None);
+        assert!(p.to_raw().contains(&("spawn-monsters".into(), "true".into())));
+    }
+
+    #[test]
+    fn invalid_values() {
+        assert!(props("server-port=abc").is_err());
+        assert!(props("difficulty=impossible").is_err());
+        assert!(props("level-type=cubes").is_err());
+        assert!(props("online-mode=yes").is_err());
+        assert!(props("log-level=chatty").is_err());
+    }
+
+    #[test]
+    fn clamps_ranges() {
+        let p = props("view-distance=40\nplayer-view-distance=20\nop-permission-level=0\n").unwrap();
+        assert_eq!(p.view_distance, 15);
+        assert_eq!(p.player_view_distance, 15);
+        assert_eq!(p.op_permission_level, 1);
+        assert_eq!(props("view-distance=-2").unwrap().view_distance, 3);
+    }
+
+    #[test]
+    fn escape_round_trip() {
+        let s = "a=b:c\\d \u{a7}e";
+        assert_eq!(unescape_all(&escape(s)), s);
+    }
+}
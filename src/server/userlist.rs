@@ -0,0 +1,294 @@
+//! Ban lists, whitelist and ops, in the json files vanilla uses.
+
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter};
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, FixedOffset, Local};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json;
+use uuid::Uuid;
+
+#[derive(Fail, Debug)]
+pub enum Error {
+    #[fail(display = "io error: {}", _0)]
+    IOError(#[cause] io::Error),
+    #[fail(display = "invalid json: {}", _0)]
+    JsonError(#[cause] serde_json::Error),
+}
+
+impl_from_for_newtype_enum!(Error::IOError, io::Error);
+impl_from_for_newtype_enum!(Error::JsonError, serde_json::Error);
+
+/// How vanilla writes the created and expires dates.
+const DATE_FORMAT: &str = "%Y-%m-%d %H:%M:%S %z";
+/// expires of bans which don't.
+const FOREVER: &str = "forever";
+
+/// An entry of a list, found by its key.
+pub trait Entry: Serialize + DeserializeOwned {
+    /// the hyphenated uuid of the player, or the address of ip bans.
+    fn key(&self) -> &str;
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlayerBan {
+    pub uuid: String,
+    pub name: String,
+    pub created: String,
+    pub source: String,
+    pub expires: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IpBan {
+    pub ip: String,
+    pub created: String,
+    pub source: String,
+    pub expires: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WhitelistEntry {
+    pub uuid: String,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpEntry {
+    pub uuid: String,
+    pub name: String,
+    pub level: u8,
+    #[serde(rename = "bypassesPlayerLimit", default)]
+    pub bypasses_player_limit: bool,
+}
+
+impl Entry for PlayerBan {
+    fn key(&self) -> &str {
+        &self.uuid
+    }
+}
+
+impl Entry for IpBan {
+    fn key(&self) -> &str {
+        &self.ip
+    }
+}
+
+impl Entry for WhitelistEntry {
+    fn key(&self) -> &str {
+        &self.uuid
+    }
+}
+
+impl Entry for OpEntry {
+    fn key(&self) -> &str {
+        &self.uuid
+    }
+}
+
+impl PlayerBan {
+    pub fn new(uuid: Uuid, name: String, source: String, reason: String) -> PlayerBan {
+        PlayerBan { uuid: uuid.hyphenated().to_string(), name, created: now(), source, expires: FOREVER.into(), reason }
+    }
+}
+
+impl IpBan {
+    pub fn new(ip: IpAddr, source: String, reason: String) -> IpBan {
+        IpBan { ip: ip.to_string(), created: now(), source, expires: FOREVER.into(), reason }
+    }
+}
+
+fn now() -> String {
+    Local::now().format(DATE_FORMAT).to_string()
+}
+
+/// None for bans which don't expire or have a date we can't read.
+fn parse_expires(expires: &str) -> Option<DateTime<FixedOffset>> {
+    DateTime::parse_from_str(expires, DATE_FORMAT).ok()
+}
+
+fn is_expired(expires: &str) -> bool {
+    parse_expires(expires).map_or(false, |date| date < Local::now())
+}
+
+/// What banned players are told.
+fn ban_message(start: &str, reason: &str, expires: &str) -> String {
+    let mut message = format!("{}\nReason: {}", start, reason);
+    if let Some(date) = parse_expires(expires) {
+        message.push_str(&format!("\nYour ban will be removed on {}", date.format(DATE_FORMAT)));
+    }
+    message
+}
+
+/// Entries of one file. The file is only created once something is added.
+#[derive(Debug)]
+pub struct UserList<E> {
+    path: PathBuf,
+    entries: Vec<E>,
+}
+
+impl<E: Entry> UserList<E> {
+    /// A missing file is an empty list.
+    pub fn load(path: PathBuf) -> Result<UserList<E>, Error> {
+        let mut list = UserList { path, entries: Vec::new() };
+        list.reload()?;
+        Ok(list)
+    }
+
+    /// Reads the file again. The list is left as it was if the file is invalid.
+    pub fn reload(&mut self) -> Result<(), Error> {
+        self.entries = if self.path.exists() {
+            serde_json::from_reader(BufReader::new(File::open(&self.path)?))?
+        } else {
+            Vec::new()
+        };
+        Ok(())
+    }
+
+    fn save(&self) -> Result<(), Error> {
+        if let Some(dir) = self.path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        serde_json::to_writer_pretty(BufWriter::new(File::create(&self.path)?), &self.entries)?;
+        Ok(())
+    }
+
+    pub fn get(&self, key: &str) -> Option<&E> {
+        self.entries.iter().find(|e| e.key().eq_ignore_ascii_case(key))
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Replaces the entry with the same key and saves the file.
+    pub fn add(&mut self, entry: E) -> Result<(), Error> {
+        self.entries.retain(|e| !e.key().eq_ignore_ascii_case(entry.key()));
+        self.entries.push(entry);
+        self.save()
+    }
+
+    /// Saves the file if there was such an entry. Returns whether there was.
+    pub fn remove(&mut self, key: &str) -> Result<bool, Error> {
+        let len = self.entries.len();
+        self.entries.retain(|e| !e.key().eq_ignore_ascii_case(key));
+        if self.entries.len() == len {
+            return Ok(false);
+        }
+        self.save()?;
+        Ok(true)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &E> {
+        self.entries.iter()
+    }
+}
+
+/// Who may join and who is op. Shared by the network thread, which checks logins, and the server.
+#[derive(Debug)]
+pub struct UserLists {
+    pub banned_players: UserList<PlayerBan>,
+    pub banned_ips: UserList<IpBan>,
+    pub whitelist: UserList<WhitelistEntry>,
+    pub ops: UserList<OpEntry>,
+    /// only whitelisted players and ops may join.
+    pub whitelist_enabled: bool,
+}
+
+impl UserLists {
+    /// Loads the files in dir.
+    pub fn load<P: AsRef<Path>>(dir: P, whitelist_enabled: bool) -> Result<UserLists, Error> {
+        let dir = dir.as_ref();
+        Ok(UserLists {
+            banned_players: UserList::load(dir.join("banned-players.json"))?,
+            banned_ips: UserList::load(dir.join("banned-ips.json"))?,
+            whitelist: UserList::load(dir.join("whitelist.json"))?,
+            ops: UserList::load(dir.join("ops.json"))?,
+            whitelist_enabled,
+        })
+    }
+
+    pub fn reload(&mut self) -> Result<(), Error> {
+        self.banned_players.reload()?;
+        self.banned_ips.reload()?;
+        self.whitelist.reload()?;
+        self.ops.reload()
+    }
+
+    /// Why the player may not join, or None if they may.
+    pub fn check_login(&self, uuid: Uuid, ip: IpAddr) -> Option<String> {
+        let uuid = uuid.hyphenated().to_string();
+        if let Some(ban) = self.banned_players.get(&uuid).filter(|b| !is_expired(&b.expires)) {
+            return Some(ban_message("You are banned from this server!", &ban.reason, &ban.expires));
+        }
+        if let Some(ban) = self.banned_ips.get(&ip.to_string()).filter(|b| !is_expired(&b.expires)) {
+            return Some(ban_message("Your IP address is banned from this server!", &ban.reason, &ban.expires));
+        }
+        if self.whitelist_enabled && !self.whitelist.contains(&uuid) && !self.ops.contains(&uuid) {
+            return Some("You are not white-listed on this server!".into());
+        }
+        None
+    }
+
+    /// Permission level of the player. 0 if they aren't op.
+    pub fn get_op_level(&self, uuid: Uuid) -> u8 {
+        self.ops.get(&uuid.hyphenated().to_string()).map_or(0, |op| op.level)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::env;
+    use std::process;
+
+    use crate::test_client::{TestClient, TestServer};
+
+    use super::*;
+
+    #[test]
+    fn vanilla_files() {
+        let dir = env::temp_dir().join(format!("quartz-userlist-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let uuid = Uuid::parse_str("069a79f4-44e9-4726-a5be-fca90e38aaf5").unwrap();
+        fs::write(dir.join("banned-players.json"), r#"[{"uuid":"069a79f4-44e9-4726-a5be-fca90e38aaf5","name":"Notch",
+            "created":"2018-01-01 12:00:00 +0000","source":"Server","expires":"2999-01-01 00:00:00 +0000","reason":"Griefing"}]"#).unwrap();
+        fs::write(dir.join("ops.json"), r#"[{"uuid":"853c80ef-3c37-49fd-aa49-938b674adae6","name":"jeb_","level":4,
+            "bypassesPlayerLimit":false}]"#).unwrap();
+
+        let mut lists = UserLists::load(&dir, true).unwrap();
+        let ip: IpAddr = "192.0.2.1".parse().unwrap();
+        let message = lists.check_login(uuid, ip).unwrap();
+        assert!(message.starts_with("You are banned from this server!\nReason: Griefing\nYour ban will be removed on 2999"));
+        let jeb = Uuid::parse_str("853c80ef-3c37-49fd-aa49-938b674adae6").unwrap();
+        assert_eq!(lists.get_op_level(jeb), 4);
+        // ops may join when the whitelist is on.
+        assert_eq!(lists.check_login(jeb, ip), None);
+        assert_eq!(lists.check_login(Uuid::nil(), ip).unwrap(), "You are not white-listed on this server!");
+
+        assert!(lists.banned_players.remove(&uuid.hyphenated().to_string()).unwrap());
+        lists.banned_ips.add(IpBan::new(ip, "Server".into(), "Banned by an operator.".into())).unwrap();
+        lists.whitelist_enabled = false;
+        assert!(lists.check_login(uuid, ip).unwrap().starts_with("Your IP address is banned"));
+
+        // changes are saved, and expired bans don't count.
+        lists.banned_players.add(PlayerBan {
+            expires: "2000-01-01 00:00:00 +0000".into(),
+            ..PlayerBan::new(uuid, "Notch".into(), "Server".into(), "Expired".into())
+        }).unwrap();
+        let lists = UserLists::load(&dir, false).unwrap();
+        assert_eq!(lists.banned_players.iter().count(), 1);
+        assert!(lists.check_login(uuid, "192.0.2.2".parse().unwrap()).is_none());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+    #[test]
+    fn enforces_whitelist() {
+        let server = TestServer::with_properties(|p| p.white_list = true);
+        let reason = TestClient::try_connect(server.get_addr(), "Alice").err().unwrap();
+        assert_eq!(reason.to_plain(), "You are not white-listed on this server!");
+    }
+}
@@ -0,0 +1,359 @@
+//! Server on its own thread and a headless client for tests which go through the network like a real client.
+
+use std::any;
+use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::net::{SocketAddr, TcpStream};
+use std::path::PathBuf;
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use uuid::Uuid;
+
+use crate::auth::HttpSessionService;
+use crate::block::{BlockPos, BlockStateId, Facing};
+use crate::entity::metadata::EntityMetadata;
+use crate::math::Vec3;
+use crate::nbt::Nbt;
+use crate::proto::data::SlotData;
+use crate::proto::packets::{CPacket, SPacket, SPlayChunkDataData, SPlayMapChunkBulkData, SPlayPlayerListItemData};
+use crate::proto::{self, Reader, State, Writer};
+use crate::server::properties::ServerProperties;
+use crate::server::Server;
+use crate::text::chat::Chat;
+
+/// Packet from the server which tests can wait for by its type: `client.wait_for::<PlayChunkData>()`.
+pub trait Expect: Sized {
+    /// The fields of the packet, or None if it is another one.
+    fn take(packet: SPacket) -> Option<Self>;
+}
+
+/// Declares structs with the fields of the SPacket variants of the same name. Every field is listed, so this breaks
+/// when a packet changes instead of silently ignoring the new field.
+macro_rules! expect {
+    ($($name:ident { $($field:ident: $ty:ty),* })*) => {
+        $(
+            #[allow(dead_code)]
+            #[derive(Debug)]
+            pub struct $name {
+                $(pub $field: $ty,)*
+            }
+
+            impl Expect for $name {
+                fn take(packet: SPacket) -> Option<$name> {
+                    match packet {
+                        SPacket::$name { $($field),* } => Some($name { $($field),* }),
+                        _ => None,
+                    }
+                }
+            }
+        )*
+    };
+}
+
+expect! {
+    PlayChunkData { chunk_x: i32, chunk_z: i32, primary_bit_mask: u16, data: SPlayChunkDataData }
+    PlayMapChunkBulk { sky_light_sent: bool, chunks: Vec<SPlayMapChunkBulkData> }
+    PlaySpawnPosition { location: BlockPos }
+    PlayPlayerListItem { players: Vec<Arc<SPlayPlayerListItemData>> }
+    PlaySpawnPlayer {
+        entity_id: i32,
+        uuid: Uuid,
+        x: f64,
+        y: f64,
+        z: f64,
+        yaw: f64,
+        pitch: f64,
+        current_item: i16,
+        metadata: EntityMetadata
+    }
+    PlayEntityRelativeMove { entity_id: i32, delta_x: i8, delta_y: i8, delta_z: i8, on_ground: bool }
+    PlayBlockChange { location: BlockPos, block_id: i32 }
+    PlaySoundEffect {
+        name: Cow<'static, str>,
+        effect_pos_x: f64,
+        effect_pos_y: f64,
+        effect_pos_z: f64,
+        volume: f32,
+        pitch: f32
+    }
+    PlayOpenWindow { window_id: u8, window_type: Cow<'static, str>, window_title: Chat, slot_num: u8, entity_id: i32 }
+    PlaySetSlot { window_id: i8, slot: i16, slot_data: SlotData }
+    PlayWindowItems { window_id: u8, slots: Vec<SlotData> }
+    PlayConfirmTransaction { window_id: i8, action_num: i16, accepted: bool }
+    PlayDisconnect { reason: Chat }
+}
+
+/// How long to wait for a packet before failing the test.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+static NEXT_DIR: AtomicUsize = AtomicUsize::new(0);
+
+/// Server with a new world in a temporary directory. It is stopped and the directory removed when dropped.
+pub struct TestServer {
+    addr: SocketAddr,
+    running: Arc<Mutex<bool>>,
+    thread: Option<JoinHandle<()>>,
+    dir: PathBuf,
+}
+
+impl TestServer {
+    pub fn start() -> TestServer {
+        TestServer::with_properties(|_| ())
+    }
+
+    /// Properties start out offline, on an ephemeral port of localhost and with a small view distance.
+    pub fn with_properties<F: FnOnce(&mut ServerProperties)>(f: F) -> TestServer {
+        let dir = env::temp_dir().join(format!("quartz-test-{}-{}", process::id(), NEXT_DIR.fetch_add(1, Ordering::SeqCst)));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let mut properties = ServerProperties::default();
+        properties.server_ip = Some("127.0.0.1".parse().unwrap());
+        properties.server_port = 0;
+        properties.level_name = dir.join("world").to_string_lossy().into_owned();
+        properties.view_distance = 2;
+        properties.player_view_distance = 2;
+        properties.online_mode = false;
+        // tests connect many times from the same address.
+        properties.connection_throttle_limit = 0;
+        properties.max_connections_per_ip = 0;
+        f(&mut properties);
+
+        // the server isn't Send so it is made on its thread.
+        let (tx, rx) = mpsc::channel();
+        let thread = thread::Builder::new()
+            .name("Test server".into())
+            .spawn(move || {
+                let session_service = Box::new(HttpSessionService::new(HttpSessionService::MOJANG_URL));
+                let mut server = Server::new(properties, session_service).expect("error creating server");
+                let addr = server.listen().expect("error starting server");
+                tx.send((addr, server.get_running())).unwrap();
+                server.run();
+            })
+            .unwrap();
+        let (addr, running) = rx.recv().expect("server thread died");
+        TestServer { addr, running, thread: Some(thread), dir }
+    }
+
+    pub fn get_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    pub fn connect(&self, name: &str) -> TestClient {
+        TestClient::connect(self.addr, name)
+    }
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        *self.running.lock().unwrap() = false;
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Client without a game behind it. Keep alives are answered while waiting for packets.
+pub struct TestClient {
+    reader: Reader<TcpStream>,
+    writer: Writer<TcpStream>,
+    name: String,
+    uuid: Uuid,
+    entity_id: i32,
+    pos: Vec3,
+    /// packets read while logging in, given out by recv first.
+    pending: VecDeque<SPacket>,
+}
+
+impl TestClient {
+    /// Logs in and waits until the server has put the player in the world.
+    pub fn connect(addr: SocketAddr, name: &str) -> TestClient {
+        TestClient::connect_through_proxy(addr, name, &[], addr.ip().to_string())
+    }
+
+    /// Like `connect` but returns the reason if the server refuses the login.
+    pub fn try_connect(addr: SocketAddr, name: &str) -> Result<TestClient, Chat> {
+        TestClient::login(addr, name, &[], addr.ip().to_string())
+    }
+
+    /// Like `connect` but starts with a PROXY protocol header and puts server_address in the handshake, like
+    /// proxies do.
+    pub fn connect_through_proxy(addr: SocketAddr, name: &str, proxy_header: &[u8], server_address: String) -> TestClient {
+        TestClient::login(addr, name, proxy_header, server_address)
+            .unwrap_or_else(|reason| panic!("{} could not log in: {}", name, reason.to_plain()))
+    }
+
+    fn login(addr: SocketAddr, name: &str, proxy_header: &[u8], server_address: String) -> Result<TestClient, Chat> {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream.write_all(proxy_header).unwrap();
+        stream.set_read_timeout(Some(TIMEOUT)).unwrap();
+        stream.set_nodelay(true).unwrap();
+        let mut client = TestClient {
+            reader: Reader::new(stream.try_clone().unwrap()),
+            writer: Writer::new(stream),
+            name: name.into(),
+            uuid: Uuid::nil(),
+            entity_id: 0,
+            pos: Vec3::ZERO,
+            pending: VecDeque::new(),
+        };
+
+        client.send(CPacket::Handshake {
+            protocol_version: proto::VERSION,
+            server_address,
+            server_port: addr.port(),
+            next_state: 2,
+        });
+        client.reader.set_state(State::Login);
+        client.send(CPacket::LoginLoginStart { name: name.into() });
+        loop {
+            match client.read() {
+                SPacket::LoginSetCompression { threshold } => {
+                    client.reader.set_compression(threshold as u32);
+                    client.writer.set_compression(threshold as u32);
+                }
+                SPacket::LoginLoginSuccess { ref uuid, .. } => {
+                    client.uuid = Uuid::parse_str(uuid).unwrap();
+                    break;
+                }
+                SPacket::LoginDisconnect { reason } => return Err(reason),
+                p => panic!("unexpected packet while logging in: {:?}", p),
+            }
+        }
+        client.reader.set_state(State::Play);
+
+        // the player is in the world once the server says where.
+        loop {
+            let packet = client.read();
+            let done = match packet {
+                SPacket::PlayJoinGame { entity_id, .. } => {
+                    client.entity_id = entity_id;
+                    false
+                }
+                SPacket::PlayPlayerPositionAndLook { .. } => true,
+                _ => false,
+            };
+            client.pending.push_back(packet);
+            if done {
+                return Ok(client);
+            }
+        }
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_uuid(&self) -> Uuid {
+        self.uuid
+    }
+
+    pub fn get_entity_id(&self) -> i32 {
+        self.entity_id
+    }
+
+    /// Last position sent by either side.
+    pub fn get_pos(&self) -> Vec3 {
+        self.pos
+    }
+
+    pub fn send(&mut self, packet: CPacket) {
+        if let Err(e) = self.writer.write_packet(&packet) {
+            panic!("{} could not send {:?}: {}", self.name, packet, e);
+        }
+    }
+
+    /// Next packet from the server. Fails the test if nothing comes in time.
+    pub fn recv(&mut self) -> SPacket {
+        match self.pending.pop_front() {
+            Some(packet) => packet,
+            None => self.read(),
+        }
+    }
+
+    fn read(&mut self) -> SPacket {
+        let packet = match self.reader.read_packet() {
+            Ok(packet) => packet,
+            Err(e) => panic!("{} did not get a packet: {}", self.name, e),
+        };
+        match packet {
+            SPacket::PlayKeepAlive { id } => self.send(CPacket::PlayKeepAlive { id }),
+            SPacket::PlayPlayerPositionAndLook { x, y, z, .. } => self.pos = Vec3::new(x, y, z),
+            _ => {}
+        }
+        packet
+    }
+
+    /// Reads packets until one of type P comes. The packets before are dropped.
+    pub fn wait_for<P: Expect>(&mut self) -> P {
+        self.wait_until(|_: &P| true)
+    }
+
+    /// Like `wait_for` but also drops the packets of type P which f doesn't accept.
+    pub fn wait_until<P: Expect, F: FnMut(&P) -> bool>(&mut self, mut f: F) -> P {
+        let deadline = Instant::now() + TIMEOUT;
+        while Instant::now() < deadline {
+            if let Some(packet) = P::take(self.recv()) {
+                if f(&packet) {
+                    return packet;
+                }
+            }
+        }
+        panic!("{} timed out waiting for {}", self.name, any::type_name::<P>());
+    }
+
+    pub fn chat(&mut self, message: &str) {
+        self.send(CPacket::PlayChatMessage { message: message.into() });
+    }
+
+    pub fn send_position(&mut self, pos: Vec3) {
+        self.pos = pos;
+        self.send(CPacket::PlayPlayerPosition { x: pos.x, feet_y: pos.y, z: pos.z, on_ground: true });
+    }
+
+    /// Puts the item into the first hotbar slot, which is held, from the creative menu.
+    pub fn set_held_item(&mut self, item: SlotData) {
+        self.send(CPacket::PlayCreativeInventoryAction { slot: 36, clicked_item: item });
+    }
+
+    /// Right clicks the top of the block, which opens containers.
+    pub fn use_block(&mut self, location: BlockPos) {
+        self.send(CPacket::PlayPlayerBlockPlacement {
+            location,
+            face: Facing::Up.to_i8(),
+            held_item: SlotData::Empty,
+            cursor_pos_x: 8,
+            cursor_pos_y: 16,
+            cursor_pos_z: 8,
+        });
+    }
+
+    /// Places the block at pos by clicking the top of the block below with it in hand.
+    pub fn place_block(&mut self, pos: BlockPos, block: BlockStateId) {
+        let against = pos.offset(Facing::Down).expect("blocks can't be placed below the world");
+        let item = SlotData::Some {
+            id: i16::from(block.get_type().to_u8()),
+            item_count: 1,
+            item_damage: i16::from(block.get_meta()),
+            tag: Nbt::Empty,
+        };
+        self.set_held_item(item.clone());
+        self.send(CPacket::PlayPlayerBlockPlacement {
+            location: against,
+            face: Facing::Up.to_i8(),
+            held_item: item,
+            cursor_pos_x: 8,
+            cursor_pos_y: 16,
+            cursor_pos_z: 8,
+        });
+    }
+}
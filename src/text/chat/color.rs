@@ -34,4 +34,29 @@ pub enum Color {
     White,
     #[serde(rename = "reset")]
     Reset,
+}
+
+impl Color {
+    /// ANSI escape code which sets this color in a terminal.
+    pub fn to_ansi(self) -> &'static str {
+        match self {
+            Color::Black => "\x1b[30m",
+            Color::DarkBlue => "\x1b[34m",
+            Color::DarkGreen => "\x1b[32m",
+            Color::DarkCyan => "\x1b[36m",
+            Color::DarkRed => "\x1b[31m",
+            Color::Purple => "\x1b[35m",
+            Color::Gold => "\x1b[33m",
+            Color::Gray => "\x1b[37m",
+            Color::DarkGray => "\x1b[90m",
+            Color::Blue => "\x1b[94m",
+            Color::BrightGreen => "\x1b[92m",
+            Color::Cyan => "\x1b[96m",
+            Color::Red => "\x1b[91m",
+            Color::Pink => "\x1b[95m",
+            Color::Yellow => "\x1b[93m",
+            Color::White => "\x1b[97m",
+            Color::Reset => "\x1b[39m",
+        }
+    }
 }
\ No newline at end of file
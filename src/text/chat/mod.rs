@@ -97,6 +97,25 @@ impl_from_for_newtype_enum!(Component::Score, ScoreComponent);
 impl_from_for_newtype_enum!(Component::Selector, SelectorComponent);
 
 impl Component {
+    fn get_base_ref(&self) -> &Base {
+        match *self {
+            Component::String(ref x) => &x.base,
+            Component::Translation(ref x) => &x.base,
+            Component::Score(ref x) => &x.base,
+            Component::Selector(ref x) => &x.base,
+        }
+    }
+
+    /// Text of this component without its extra.
+    fn get_text(&self) -> &str {
+        match *self {
+            Component::String(ref x) => &x.text,
+            Component::Translation(ref x) => &x.translate,
+            Component::Score(ref x) => x.score.value.as_ref().map_or("", |v| v.as_str()),
+            Component::Selector(ref x) => &x.selector,
+        }
+    }
+
     fn get_base(&mut self) -> &mut Base {
         match *self {
             Component::String(ref mut x) => &mut x.base,
@@ -209,7 +228,75 @@ pub enum Error {
 
 impl_from_for_newtype_enum!(Error::JSONError, serde_json::Error);
 
+/// Formatting a component inherits from its parents.
+#[derive(Debug, Clone, Copy, Default)]
+struct Style {
+    color: Option<Color>,
+    bold: bool,
+    italic: bool,
+    underlined: bool,
+    strikethrough: bool,
+}
+
+impl Style {
+    fn inherit(mut self, base: &Base) -> Style {
+        self.color = base.color.or(self.color);
+        self.bold = base.bold.unwrap_or(self.bold);
+        self.italic = base.italic.unwrap_or(self.italic);
+        self.underlined = base.underlined.unwrap_or(self.underlined);
+        self.strikethrough = base.strikethrough.unwrap_or(self.strikethrough);
+        self
+    }
+}
+
+/// Calls f with the text and style of w and then of its extra, in order.
+fn visit<F: FnMut(&str, Style)>(w: &Wrapper, parent: Style, f: &mut F) {
+    let style = parent.inherit(w.0.get_base_ref());
+    f(w.0.get_text(), style);
+    if let Some(ref extra) = w.0.get_base_ref().extra {
+        for e in extra {
+            visit(e, style, f);
+        }
+    }
+}
+
 impl Chat {
+    /// Text with formatting as ANSI escape codes, for printing to a terminal.
+    pub fn to_ansi(&self) -> String {
+        let mut s = String::new();
+        visit(&self.0, Style::default(), &mut |text, style| {
+            if text.is_empty() {
+                return;
+            }
+            s.push_str("\x1b[0m");
+            if let Some(color) = style.color {
+                s.push_str(color.to_ansi());
+            }
+            if style.bold {
+                s.push_str("\x1b[1m");
+            }
+            if style.italic {
+                s.push_str("\x1b[3m");
+            }
+            if style.underlined {
+                s.push_str("\x1b[4m");
+            }
+            if style.strikethrough {
+                s.push_str("\x1b[9m");
+            }
+            s.push_str(text);
+        });
+        s.push_str("\x1b[0m");
+        s
+    }
+
+    /// Text without any formatting.
+    pub fn to_plain(&self) -> String {
+        let mut s = String::new();
+        visit(&self.0, Style::default(), &mut |text, _| s.push_str(text));
+        s
+    }
+
     pub fn write_proto<W: io::Write>(&self, w: &mut W) -> io::Result<()> {
         let s = serde_json::to_string(self).unwrap();
         proto::data::write_string(w, &s)
@@ -260,6 +347,13 @@ mod tests {
         assert_eq!(st, "{\"bold\":true,\"text\":\"Hello\"}");
     }
 
+    #[test]
+    fn test_to_ansi() {
+        let chat = Chat::from(crate::text::parse_legacy_ex("&chi &lthere&r!", '&'));
+        assert_eq!(chat.to_plain(), "hi there!");
+        assert_eq!(chat.to_ansi(), "\x1b[0m\x1b[91mhi \x1b[0m\x1b[91m\x1b[1mthere\x1b[0m\x1b[39m!\x1b[0m");
+    }
+
     #[test]
     fn test_de() {
         let com: Wrapper = serde_json::from_str("{\"bold\":true,\"text\":\"Hello\",\"extra\":[\"hi\"]}").unwrap();
@@ -134,6 +134,22 @@ impl Code {
     fn is_valid(c: char) -> bool {
         Self::from_char(c).is_some()
     }
+
+    /// Color as sent in team packets. -1 for reset and formatting codes.
+    pub fn to_color_index(&self) -> i8 {
+        match *self {
+            Code::Reset => -1,
+            c if c.is_color() => c as i8,
+            _ => -1,
+        }
+    }
+
+    pub fn from_color_index(i: i8) -> Code {
+        ::std::char::from_digit(i as u32, 16)
+            .filter(|_| i >= 0)
+            .and_then(Code::from_char)
+            .unwrap_or(Code::Reset)
+    }
 }
 
 pub const LEGACY_CHAR: char = '§';
@@ -172,7 +188,7 @@ impl Tokenizer {
     }
 
     fn has_next(&self) -> bool {
-        self.index < (self.len - 1) as isize
+        self.index + 1 < self.len as isize
     }
 
     fn next(&mut self) -> char {
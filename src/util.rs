@@ -3,11 +3,21 @@ use std::collections::VecDeque;
 use std::iter::Iterator;
 use std::time::Duration;
 
+use openssl::rand::rand_bytes;
+
 pub fn duration_total_ms(dur: Duration) -> f64 {
     let total_secs = dur.as_secs() as f64 + dur.subsec_nanos() as f64 / 1_000_000_000.0;
     total_secs * 1000.0
 }
 
+/// From 0 to 1, not including 1. Only for gameplay, like how many items a block drops.
+pub fn random_f32() -> f32 {
+    let mut bytes = [0; 4];
+    // only fails without entropy. 0 is as good as anything then.
+    let _ = rand_bytes(&mut bytes);
+    (u32::from_le_bytes(bytes) >> 8) as f32 / (1 << 24) as f32
+}
+
 pub fn iter_foreach_every<I, F, P>(i: I, p: P, mut f: F)
 where
     I: Iterator,
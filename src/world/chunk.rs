@@ -6,6 +6,7 @@ use crate::block::block_entity::BlockEntity;
 use crate::block::{Block, BlockPos, BlockStateId};
 use crate::collections::{NibbleArray, VarbitArray};
 use crate::math::Vec3;
+use crate::nbt::{ByteArray, Compound, DeserializeError, IntArray, List, Nbt, Tag};
 use crate::proto::data::{self, GroundUpContinuous, GroundUpNonContinuous};
 use crate::proto::packets::{SPacket, SPlayChunkDataData, SPlayMapChunkBulkData};
 
@@ -101,6 +102,77 @@ impl ChunkSection {
         }
     }
 
+    /// Anvil section compound. Block ids are only 8 bits wide so "Add" is never written.
+    pub fn to_nbt(&self, y: u8) -> Compound {
+        let mut blocks = vec![0; CHUNK_SECTION_BLOCK_COUNT];
+        let mut data = NibbleArray::new(CHUNK_SECTION_BLOCK_COUNT);
+        for y in 0..16 {
+            for z in 0..16 {
+                for x in 0..16 {
+                    let i = Self::get_linear_index(x, y, z);
+                    let b = self.get_block(x, y, z);
+                    blocks[i] = b.get_type().to_u8();
+                    data.set(i, b.get_meta());
+                }
+            }
+        }
+        let sky_light = match self.sky_light {
+            Some(ref s) => s.as_bytes().to_vec(),
+            None => vec![0; CHUNK_SECTION_BLOCK_COUNT / 2],
+        };
+
+        let mut compound = Compound(HashMap::new());
+        compound.0.insert("Y".into(), (y as i8).into());
+        compound.0.insert("Blocks".into(), ByteArray(blocks).into());
+        compound.0.insert("Data".into(), ByteArray(data.as_bytes().to_vec()).into());
+        compound.0.insert("BlockLight".into(), ByteArray(self.block_light.as_bytes().to_vec()).into());
+        compound.0.insert("SkyLight".into(), ByteArray(sky_light).into());
+        compound
+    }
+
+    /// Reads an anvil section compound. Unknown block ids become air.
+    pub fn from_nbt(compound: &Compound, has_sky_light: bool) -> Result<ChunkSection, DeserializeError> {
+        fn nibbles(compound: &Compound, field: &'static str) -> Result<NibbleArray, DeserializeError> {
+            let bytes = &compound.get(field)?.as_byte_array()?.0;
+            if bytes.len() != CHUNK_SECTION_BLOCK_COUNT / 2 {
+                return Err(DeserializeError::InvalidLength(field, bytes.len()));
+            }
+            Ok(NibbleArray::from_bytes(bytes.clone()))
+        }
+
+        let blocks = &compound.get("Blocks")?.as_byte_array()?.0;
+        if blocks.len() != CHUNK_SECTION_BLOCK_COUNT {
+            return Err(DeserializeError::InvalidLength("Blocks", blocks.len()));
+        }
+        let add = if compound.contains_key("Add") {
+            Some(nibbles(compound, "Add")?)
+        } else {
+            None
+        };
+        let data = nibbles(compound, "Data")?;
+
+        let mut section = ChunkSection::new(has_sky_light);
+        section.block_light = nibbles(compound, "BlockLight")?;
+        if has_sky_light {
+            section.sky_light = Some(nibbles(compound, "SkyLight")?);
+        }
+
+        for y in 0..16 {
+            for z in 0..16 {
+                for x in 0..16 {
+                    let i = Self::get_linear_index(x, y, z);
+                    if add.as_ref().map_or(false, |a| a.get(i) != 0) {
+                        continue;
+                    }
+                    if let Some(block) = Block::from_u8(blocks[i]) {
+                        section.set_block(x, y, z, BlockStateId::new(block, data.get(i)));
+                    }
+                }
+            }
+        }
+        Ok(section)
+    }
+
     pub fn to_proto_chunk_section(&self) -> data::ChunkSection {
         let mut blocks = [0; CHUNK_SECTION_BLOCK_COUNT * 2];
 
@@ -178,6 +250,10 @@ pub struct Chunk {
     players_in_vicinity: HashSet<Uuid>,
     players: HashSet<Uuid>,
     block_entities: HashMap<(u8, u8, u8), Box<dyn BlockEntity>>,
+    /// "Entities" as loaded. The server doesn't handle mobs and the like, but keeps them for vanilla.
+    entities: List,
+    /// true if the chunk changed since it was loaded or last saved.
+    modified: bool,
 }
 
 impl Chunk {
@@ -190,6 +266,8 @@ impl Chunk {
             players_in_vicinity: HashSet::new(),
             players: HashSet::new(),
             block_entities: HashMap::new(),
+            entities: List(Vec::new()),
+            modified: true,
         }
     }
 
@@ -197,6 +275,14 @@ impl Chunk {
         self.pos
     }
 
+    pub fn is_modified(&self) -> bool {
+        self.modified
+    }
+
+    pub fn set_modified(&mut self, modified: bool) {
+        self.modified = modified;
+    }
+
     /// returns true when there is no player who see this chunk.
     pub fn is_abandoned(&self) -> bool {
         self.players_in_vicinity.len() == 0
@@ -250,17 +336,13 @@ impl Chunk {
 
     pub fn set_block(&mut self, x: u8, y: u8, z: u8, b: BlockStateId) {
         let has_sky_light = self.has_sky_light;
+        self.modified = true;
 
         let sec = Self::get_section_mut(&mut self.sections, y);
         match *sec {
             Some(ref mut s) => {
                 s.set_block(x, y % 16, z, b);
-                let block_entity = b.get_type().create_new_block_entity();
-                if let Some(block_entity) = block_entity {
-                    self.block_entities.insert((x, y, z), block_entity);
-                } else {
-                    self.block_entities.remove(&(x, y, z));
-                }
+                Self::update_block_entity(&mut self.block_entities, (x, y, z), b);
             }
             None => {
                 if b.get_type() == Block::Air {
@@ -269,18 +351,41 @@ impl Chunk {
 
                 let mut section = ChunkSection::new(has_sky_light);
                 section.set_block(x, y % 16, z, b);
-                let block_entity = b.get_type().create_new_block_entity();
-                if let Some(block_entity) = block_entity {
-                    self.block_entities.insert((x, y, z), block_entity);
-                } else {
-                    self.block_entities.remove(&(x, y, z));
-                }
+                Self::update_block_entity(&mut self.block_entities, (x, y, z), b);
                 *sec = Some(Box::new(section));
                 return;
             }
         }
     }
 
+    /// Replaces the block entity for a new block.
+    /// The old one is kept if the block still has the same kind, e.g. when a chest turns or a furnace lights up.
+    fn update_block_entity(
+        block_entities: &mut HashMap<(u8, u8, u8), Box<dyn BlockEntity>>,
+        pos: (u8, u8, u8),
+        b: BlockStateId,
+    ) {
+        match b.get_type().create_new_block_entity() {
+            Some(block_entity) => {
+                if block_entities.get(&pos).map(|e| e.id()) != Some(block_entity.id()) {
+                    block_entities.insert(pos, block_entity);
+                }
+            }
+            None => {
+                block_entities.remove(&pos);
+            }
+        }
+    }
+
+    /// Gets the block entity to change it. The chunk is marked as modified.
+    pub fn get_block_entity_mut(&mut self, x: u8, y: u8, z: u8) -> Option<&mut Box<dyn BlockEntity>> {
+        let block_entity = self.block_entities.get_mut(&(x, y, z));
+        if block_entity.is_some() {
+            self.modified = true;
+        }
+        block_entity
+    }
+
     pub fn get_block_light(&self, x: u8, y: u8, z: u8) -> u8 {
         let sec = Self::get_section(&self.sections, y);
         match *sec {
@@ -311,6 +416,132 @@ impl Chunk {
         }
     }
 
+    /// returns the chunk as an anvil chunk tag.
+    pub fn to_nbt(&self) -> Nbt {
+        let mut height_map = vec![0; 256];
+        for z in 0..16u8 {
+            for x in 0..16u8 {
+                height_map[z as usize * 16 + x as usize] = (0..=255u8)
+                    .rev()
+                    .find(|&y| self.get_block(x, y, z).get_type() != Block::Air)
+                    .map_or(0, |y| y as i32 + 1);
+            }
+        }
+
+        let mut sections = List(Vec::new());
+        for (y, sec) in self.sections.iter().enumerate() {
+            if let Some(ref s) = *sec {
+                if !s.is_empty() {
+                    sections.0.push(s.to_nbt(y as u8).into());
+                }
+            }
+        }
+
+        let mut biomes = Vec::with_capacity(256);
+        for row in self.biomes.iter() {
+            biomes.extend_from_slice(row);
+        }
+
+        let mut tile_entities = List(Vec::new());
+        for (&(x, y, z), block_entity) in self.block_entities.iter() {
+            let mut compound = block_entity.to_nbt();
+            compound.0.insert("id".into(), block_entity.id().to_string().into());
+            compound.0.insert("x".into(), (self.pos.x * 16 + x as i32).into());
+            compound.0.insert("y".into(), (y as i32).into());
+            compound.0.insert("z".into(), (self.pos.z * 16 + z as i32).into());
+            tile_entities.0.push(compound.into());
+        }
+
+        let mut level = Compound(HashMap::new());
+        level.0.insert("xPos".into(), self.pos.x.into());
+        level.0.insert("zPos".into(), self.pos.z.into());
+        level.0.insert("LastUpdate".into(), 0i64.into());
+        level.0.insert("TerrainPopulated".into(), true.into());
+        level.0.insert("LightPopulated".into(), true.into());
+        level.0.insert("V".into(), 1i8.into());
+        level.0.insert("InhabitedTime".into(), 0i64.into());
+        level.0.insert("HeightMap".into(), IntArray(height_map).into());
+        level.0.insert("Sections".into(), sections.into());
+        level.0.insert("Biomes".into(), ByteArray(biomes).into());
+        level.0.insert("Entities".into(), self.entities.clone().into());
+        level.0.insert("TileEntities".into(), tile_entities.into());
+
+        let mut root = Compound(HashMap::new());
+        root.0.insert("Level".into(), level.into());
+        Nbt::Some("".into(), root)
+    }
+
+    /// reads an anvil chunk tag. Block entities missing from the tag are created with their defaults.
+    pub fn from_nbt(nbt: &Nbt, has_sky_light: bool) -> Result<Chunk, DeserializeError> {
+        let root = match *nbt {
+            Nbt::Some(_, ref root) => root,
+            Nbt::Empty => return Err(DeserializeError::FieldNotFound("Level".into())),
+        };
+        let level = root.get("Level")?.as_compound()?;
+        let pos = ChunkPos::new(level.get("xPos")?.as_int()?, level.get("zPos")?.as_int()?);
+        let mut chunk = Chunk::new(pos, has_sky_light);
+
+        for tag in level.get("Sections")?.as_list()?.0.iter() {
+            let compound = tag.as_compound()?;
+            let y = compound.get("Y")?.as_byte()?;
+            if y < 0 || y >= 16 {
+                return Err(DeserializeError::OutOfBounds(16, y as usize));
+            }
+            chunk.sections[y as usize] = Some(Box::new(ChunkSection::from_nbt(compound, has_sky_light)?));
+        }
+
+        if level.contains_key("Biomes") {
+            let biomes = &level.get("Biomes")?.as_byte_array()?.0;
+            if biomes.len() != 256 {
+                return Err(DeserializeError::InvalidLength("Biomes", biomes.len()));
+            }
+            for (i, &b) in biomes.iter().enumerate() {
+                chunk.biomes[i / 16][i % 16] = b;
+            }
+        }
+
+        for (sy, sec) in chunk.sections.iter().enumerate() {
+            let sec = match *sec {
+                Some(ref s) => s,
+                None => continue,
+            };
+            if !sec.palette.iter().any(|b| b.get_type().create_new_block_entity().is_some()) {
+                continue;
+            }
+            for y in 0..16 {
+                for z in 0..16 {
+                    for x in 0..16 {
+                        if let Some(b) = sec.get_block(x, y, z).get_type().create_new_block_entity() {
+                            chunk.block_entities.insert((x, sy as u8 * 16 + y, z), b);
+                        }
+                    }
+                }
+            }
+        }
+
+        if level.contains_key("Entities") {
+            chunk.entities = level.get("Entities")?.as_list()?.clone();
+        }
+
+        if level.contains_key("TileEntities") {
+            for tag in level.get("TileEntities")?.as_list()?.0.iter() {
+                let compound = tag.as_compound()?;
+                let x = (compound.get("x")?.as_int()? - pos.x * 16) as u8 & 0x0f;
+                let y = compound.get("y")?.as_int()? as u8;
+                let z = (compound.get("z")?.as_int()? - pos.z * 16) as u8 & 0x0f;
+                // block entities of blocks which are not there anymore are dropped.
+                if let Some(block_entity) = chunk.block_entities.get_mut(&(x, y, z)) {
+                    if block_entity.id() == compound.get("id")?.as_string()? {
+                        block_entity.load_nbt(compound)?;
+                    }
+                }
+            }
+        }
+
+        chunk.modified = false;
+        Ok(chunk)
+    }
+
     pub const FULL_BIT_MASK: u16 = !0;
 
     /// returns a ChunkData packet.
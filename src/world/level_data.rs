@@ -0,0 +1,199 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::block::BlockPos;
+use crate::nbt::{Compound, Nbt, Tag};
+use crate::server::Difficulty;
+use crate::world::{Error, LevelType};
+
+/// level.dat version of anvil worlds.
+const ANVIL_VERSION: i32 = 19133;
+
+const DEFAULT_GAME_RULES: &[(&str, &str)] = &[
+    ("commandBlockOutput", "true"),
+    ("doDaylightCycle", "true"),
+    ("doEntityDrops", "true"),
+    ("doFireTick", "true"),
+    ("doMobLoot", "true"),
+    ("doMobSpawning", "true"),
+    ("doTileDrops", "true"),
+    ("keepInventory", "false"),
+    ("logAdminCommands", "true"),
+    ("mobGriefing", "true"),
+    ("naturalRegeneration", "true"),
+    ("randomTickSpeed", "3"),
+    ("reducedDebugInfo", "false"),
+    ("sendCommandFeedback", "true"),
+    ("showDeathMessages", "true"),
+];
+
+/// Contents of the `Data` compound of level.dat.
+#[derive(Debug, Clone)]
+pub struct LevelData {
+    pub level_name: String,
+    pub spawn: BlockPos,
+    /// total ticks the world has run.
+    pub time: i64,
+    pub day_time: i64,
+    pub game_rules: HashMap<String, String>,
+    pub seed: i64,
+    pub level_type: LevelType,
+    pub difficulty: Difficulty,
+    /// the whole compound as it was read, so fields we don't understand survive a save.
+    data: Compound,
+}
+
+impl Default for LevelData {
+    fn default() -> Self {
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64 ^ (d.subsec_nanos() as i64) << 32)
+            .unwrap_or(0);
+        LevelData {
+            level_name: "world".into(),
+            spawn: BlockPos::new(0, 80, 0),
+            time: 0,
+            day_time: 0,
+            game_rules: DEFAULT_GAME_RULES
+                .iter()
+                .map(|&(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            seed,
+            level_type: LevelType::Flat,
+            difficulty: Difficulty::Peaceful,
+            data: Compound(HashMap::new()),
+        }
+    }
+}
+
+impl LevelData {
+    /// Reads level.dat. Returns None if it doesn't exist, meaning this is a new world.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Option<LevelData>, Error> {
+        if !path.as_ref().exists() {
+            return Ok(None);
+        }
+        Ok(Some(LevelData::from_nbt(&Nbt::read_gzip_file(path)?)?))
+    }
+
+    /// Writes level.dat. The previous file is kept as level.dat_old.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        let path = path.as_ref();
+        if path.exists() {
+            fs::copy(path, path.with_extension("dat_old"))?;
+        }
+        self.to_nbt().write_gzip_file(path)?;
+        Ok(())
+    }
+
+    pub fn from_nbt(nbt: &Nbt) -> Result<LevelData, Error> {
+        let data = match *nbt {
+            Nbt::Some(_, ref root) => root.get("Data")?.as_compound()?,
+            Nbt::Empty => return Ok(LevelData::default()),
+        };
+        let mut ret = LevelData::default();
+
+        let spawn_y = data.get("SpawnY")?.as_int()?;
+        ret.spawn = BlockPos::new(
+            data.get("SpawnX")?.as_int()?,
+            spawn_y.max(0).min(255) as u8,
+            data.get("SpawnZ")?.as_int()?,
+        );
+        ret.time = data.get("Time")?.as_long()?;
+        ret.day_time = if data.contains_key("DayTime") {
+            data.get("DayTime")?.as_long()?
+        } else {
+            ret.time
+        };
+        ret.seed = data.get("RandomSeed")?.as_long()?;
+        if data.contains_key("LevelName") {
+            ret.level_name = data.get("LevelName")?.as_string()?.to_string();
+        }
+        if data.contains_key("generatorName") {
+            let name = data.get("generatorName")?.as_string()?;
+            ret.level_type = LevelType::from_str(name).unwrap_or(LevelType::Default);
+        }
+        if data.contains_key("Difficulty") {
+            let difficulty = data.get("Difficulty")?.as_byte()? as u8;
+            ret.difficulty = Difficulty::from_u8(difficulty).unwrap_or(Difficulty::Normal);
+        }
+        if data.contains_key("GameRules") {
+            for (k, v) in data.get("GameRules")?.as_compound()?.0.iter() {
+                ret.game_rules.insert(k.clone(), v.as_string()?.to_string());
+            }
+        }
+        ret.data = data.clone();
+        Ok(ret)
+    }
+
+    pub fn to_nbt(&self) -> Nbt {
+        let mut data = self.data.clone();
+        let mut game_rules = Compound(HashMap::new());
+        for (k, v) in self.game_rules.iter() {
+            game_rules.0.insert(k.clone(), v.clone().into());
+        }
+
+        data.0.insert("version".into(), ANVIL_VERSION.into());
+        data.0.insert("initialized".into(), true.into());
+        data.0.insert("LevelName".into(), self.level_name.clone().into());
+        data.0.insert("SpawnX".into(), self.spawn.x.into());
+        data.0.insert("SpawnY".into(), (self.spawn.y as i32).into());
+        data.0.insert("SpawnZ".into(), self.spawn.z.into());
+        data.0.insert("Time".into(), self.time.into());
+        data.0.insert("DayTime".into(), self.day_time.into());
+        data.0.insert("RandomSeed".into(), self.seed.into());
+        data.0.insert("generatorName".into(), self.level_type.as_str().to_string().into());
+        data.0.insert("Difficulty".into(), (self.difficulty as u8 as i8).into());
+        data.0.insert("GameRules".into(), game_rules.into());
+        // fields vanilla expects but we don't use. keep existing values.
+        let defaults = [
+            ("generatorVersion", Tag::Int(0)),
+            ("generatorOptions", Tag::String("".into())),
+            ("GameType", Tag::Int(0)),
+            ("MapFeatures", Tag::Byte(1)),
+            ("allowCommands", Tag::Byte(0)),
+            ("hardcore", Tag::Byte(0)),
+        ];
+        for (k, v) in defaults.iter() {
+            data.0.entry(k.to_string()).or_insert_with(|| v.clone());
+        }
+        let last_played = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64 * 1000)
+            .unwrap_or(0);
+        data.0.insert("LastPlayed".into(), last_played.into());
+
+        let mut root = Compound(HashMap::new());
+        root.0.insert("Data".into(), data.into());
+        Nbt::Some("".into(), root)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn nbt_round_trip_keeps_unknown_fields() {
+        let mut level = LevelData::default();
+        level.spawn = BlockPos::new(-120, 64, 3000);
+        level.time = 123456;
+        level.day_time = 6000;
+        level.seed = -42;
+        level.level_type = LevelType::Amplified;
+        level.difficulty = Difficulty::Hard;
+        level.game_rules.insert("keepInventory".into(), "true".into());
+        level.data.0.insert("raining".into(), true.into());
+
+        let read = LevelData::from_nbt(&level.to_nbt()).unwrap();
+        assert_eq!(read.spawn, level.spawn);
+        assert_eq!(read.time, 123456);
+        assert_eq!(read.day_time, 6000);
+        assert_eq!(read.seed, -42);
+        assert_eq!(read.level_type, LevelType::Amplified);
+        assert_eq!(read.difficulty, Difficulty::Hard);
+        assert_eq!(read.game_rules["keepInventory"], "true");
+        assert_eq!(read.data.get("raining").unwrap().as_byte().unwrap(), 1);
+    }
+}
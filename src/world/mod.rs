@@ -1,19 +1,52 @@
 pub mod chunk;
+pub mod level_data;
+pub mod region;
 pub mod world_properties;
 
 use std::collections::HashMap;
+use std::fs;
 use std::io::{self, Read, Write};
 use std::iter::Filter;
+use std::path::PathBuf;
 
 use uuid::Uuid;
 
 use self::chunk::{Chunk, ChunkPos};
+use self::region::{RegionFile, RegionPos};
 use self::world_properties::WorldProperties;
 use crate::binary;
+use crate::block::block_entity::BlockEntity;
 use crate::block::BlockPos;
 use crate::block::BlockStateId;
+use crate::entity::item::ItemEntity;
+use crate::item::item_stack::ItemStack;
 use crate::math::Vec3;
+use crate::nbt;
 use crate::proto;
+use crate::server::playerlist::PlayerList;
+use crate::util;
+
+#[derive(Fail, Debug)]
+pub enum Error {
+    #[fail(display = "io error: {}", _0)]
+    IOError(#[cause] io::Error),
+    #[fail(display = "nbt error: {}", _0)]
+    NBTError(#[cause] nbt::Error),
+    #[fail(display = "nbt deserialize error: {}", _0)]
+    DeserializeError(#[cause] nbt::DeserializeError),
+    #[fail(display = "chunk {:?} has an invalid location in region file", _0)]
+    InvalidChunkLocation(ChunkPos),
+    #[fail(display = "chunk {:?} has an invalid length: {}", _0, _1)]
+    InvalidChunkLength(ChunkPos, i32),
+    #[fail(display = "unknown chunk compression type: {}", _0)]
+    UnknownCompression(u8),
+    #[fail(display = "chunk {:?} is too large to be saved: {} bytes", _0, _1)]
+    ChunkTooLarge(ChunkPos, usize),
+}
+
+impl_from_for_newtype_enum!(Error::IOError, io::Error);
+impl_from_for_newtype_enum!(Error::NBTError, nbt::Error);
+impl_from_for_newtype_enum!(Error::DeserializeError, nbt::DeserializeError);
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum LevelType {
@@ -34,6 +67,20 @@ impl LevelType {
             LevelType::Default11 => "default_1_1",
         }
     }
+
+    /// parses the generator name. case insensitive like vanilla.
+    pub fn from_str(s: &str) -> Option<LevelType> {
+        [
+            LevelType::Default,
+            LevelType::Flat,
+            LevelType::LargeBiomes,
+            LevelType::Amplified,
+            LevelType::Default11,
+        ]
+        .iter()
+        .cloned()
+        .find(|t| t.as_str().eq_ignore_ascii_case(s))
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -43,18 +90,38 @@ pub enum Dimension {
     End,
 }
 
+impl Dimension {
+    pub fn from_i32(x: i32) -> Option<Dimension> {
+        match x {
+            -1 => Some(Dimension::Nether),
+            0 => Some(Dimension::Overworld),
+            1 => Some(Dimension::End),
+            _ => None,
+        }
+    }
+}
+
 pub struct World {
     chunks: HashMap<ChunkPos, Chunk>,
     spawn_pos: BlockPos,
     properties: WorldProperties,
+    /// directory containing the region files of this dimension.
+    region_dir: PathBuf,
+    regions: HashMap<RegionPos, RegionFile>,
+    /// dropped items. they are not saved.
+    items: Vec<ItemEntity>,
 }
 
 impl World {
-    pub fn new(dimension: Dimension) -> World {
+    /// `dir` is the directory of this dimension. ("world", "world/DIM-1", ...)
+    pub fn new(dimension: Dimension, dir: PathBuf) -> World {
         World {
             chunks: HashMap::new(),
             properties: WorldProperties::new(dimension),
             spawn_pos: BlockPos::ZERO,
+            region_dir: dir.join("region"),
+            regions: HashMap::new(),
+            items: Vec::new(),
         }
     }
 
@@ -62,6 +129,10 @@ impl World {
         self.spawn_pos
     }
 
+    pub fn set_spawn_pos(&mut self, spawn_pos: BlockPos) {
+        self.spawn_pos = spawn_pos;
+    }
+
     pub fn get_block(&self, pos: BlockPos) -> Option<BlockStateId> {
         self.chunks.get(&ChunkPos::from(pos)).and_then(|c| {
             let (x, y, z) = pos.to_relative_chunk_pos();
@@ -69,6 +140,14 @@ impl World {
         })
     }
 
+    /// Gets the block entity at given position, if its chunk is loaded.
+    pub fn get_block_entity_mut(&mut self, pos: BlockPos) -> Option<&mut Box<dyn BlockEntity>> {
+        self.chunks.get_mut(&ChunkPos::from(pos)).and_then(|c| {
+            let (x, y, z) = pos.to_relative_chunk_pos();
+            c.get_block_entity_mut(x, y, z)
+        })
+    }
+
     /// Sets the block at given position.
     /// # Panics
     /// If the chunk is not loaded, it will panic.
@@ -111,16 +190,114 @@ impl World {
         };
 
         if unload {
-            self.chunks.remove(&pos);
+            let chunk = self.chunks.remove(&pos).unwrap();
+            if let Err(e) = self.save_chunk(&chunk) {
+                error!("error saving chunk {:?}: {}", pos, e);
+            }
         }
     }
 
+    /// Loads the chunk from disk, generating it if it was never saved.
     fn load_chunk(&mut self, pos: ChunkPos) {
-        println!("chunk load");
-        let chk = self.properties.load_chunk(pos);
+        debug!("loading chunk {:?}", pos);
+        let chk = match self.read_chunk(pos) {
+            Ok(Some(chk)) => chk,
+            Ok(None) => self.properties.load_chunk(pos),
+            Err(e) => {
+                warn!("error loading chunk {:?}, regenerating: {}", pos, e);
+                self.properties.load_chunk(pos)
+            }
+        };
         self.chunks.insert(pos, chk);
     }
 
+    fn get_region(&mut self, pos: RegionPos, create: bool) -> Result<Option<&mut RegionFile>, Error> {
+        if !self.regions.contains_key(&pos) {
+            let path = self.region_dir.join(pos.file_name());
+            if !create && !path.exists() {
+                return Ok(None);
+            }
+            fs::create_dir_all(&self.region_dir)?;
+            self.regions.insert(pos, RegionFile::open(path)?);
+        }
+        Ok(self.regions.get_mut(&pos))
+    }
+
+    fn read_chunk(&mut self, pos: ChunkPos) -> Result<Option<Chunk>, Error> {
+        let has_sky_light = self.properties.has_sky_light();
+        let nbt = match self.get_region(pos.into(), false)? {
+            Some(region) => region.read_chunk(pos)?,
+            None => None,
+        };
+        match nbt {
+            Some(nbt) => Ok(Some(Chunk::from_nbt(&nbt, has_sky_light)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Writes the chunk to its region file if it was modified.
+    fn save_chunk(&mut self, chunk: &Chunk) -> Result<(), Error> {
+        if !chunk.is_modified() {
+            return Ok(());
+        }
+        let pos = chunk.get_pos();
+        let region = self.get_region(pos.into(), true)?.unwrap();
+        region.write_chunk(pos, &chunk.to_nbt())
+    }
+
+    /// Saves all loaded chunks which were modified.
+    pub fn save_all(&mut self) -> Result<(), Error> {
+        let mut chunks = ::std::mem::replace(&mut self.chunks, HashMap::new());
+        let mut result = Ok(());
+        for chunk in chunks.values_mut() {
+            match self.save_chunk(chunk) {
+                Ok(()) => chunk.set_modified(false),
+                Err(e) => {
+                    error!("error saving chunk {:?}: {}", chunk.get_pos(), e);
+                    result = Err(e);
+                }
+            }
+        }
+        self.chunks = chunks;
+        for region in self.regions.values_mut() {
+            region.flush()?;
+        }
+        result
+    }
+
+    /// Players who have the chunk loaded. None if it isn't loaded.
+    pub fn get_players_in_vicinity<'a>(&'a self, pos: ChunkPos) -> impl Iterator<Item = Uuid> + 'a {
+        self.chunks.get(&pos).into_iter().flat_map(|c| c.players_in_vicinity_iter().cloned())
+    }
+
+    /// The item is shown to players on the next tick.
+    pub fn add_item(&mut self, item: ItemEntity) {
+        self.items.push(item);
+    }
+
+    /// Drops the stack somewhere in the block and throws it up a little, like the drops of broken blocks.
+    pub fn drop_item(&mut self, entity_id: i32, location: BlockPos, stack: ItemStack, tick: u64) {
+        let offset = || util::random_f32() as f64 * 0.7 + 0.15;
+        let pos = Vec3::new(location.x as f64 + offset(), location.y as f64 + offset(), location.z as f64 + offset());
+        let throw = || (util::random_f32() as f64 - 0.5) * 0.2;
+        let velocity = Vec3::new(throw(), 0.2, throw());
+        self.add_item(ItemEntity::new(entity_id, stack, pos, velocity, tick));
+    }
+
+    /// Shows items to players who can see them and removes old ones.
+    pub fn tick_items(&mut self, player_list: &PlayerList, tick: u64) {
+        let mut items = ::std::mem::replace(&mut self.items, Vec::new());
+        items.retain_mut(|item| {
+            if item.is_expired(tick) {
+                item.despawn(player_list);
+                return false;
+            }
+            item.update_viewers(self, player_list);
+            true
+        });
+        self.items = items;
+    }
+
     pub fn get_properties(&self) -> &WorldProperties {
         &self.properties
     }
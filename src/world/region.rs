@@ -0,0 +1,320 @@
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use flate2::read::{GzDecoder, ZlibDecoder};
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+
+use crate::binary;
+use crate::nbt::Nbt;
+use crate::world::chunk::ChunkPos;
+use crate::world::Error;
+
+const SECTOR_SIZE: usize = 4096;
+const HEADER_SECTORS: usize = 2;
+const CHUNKS_PER_REGION: usize = 32 * 32;
+/// a chunk can take up at most 255 sectors as the sector count is stored in a byte.
+const MAX_CHUNK_SECTORS: usize = 255;
+
+const COMPRESSION_GZIP: u8 = 1;
+const COMPRESSION_ZLIB: u8 = 2;
+
+/// Position of a region file. Each region holds 32x32 chunks.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct RegionPos {
+    pub x: i32,
+    pub z: i32,
+}
+
+impl From<ChunkPos> for RegionPos {
+    fn from(pos: ChunkPos) -> Self {
+        RegionPos {
+            x: pos.x >> 5,
+            z: pos.z >> 5,
+        }
+    }
+}
+
+impl RegionPos {
+    pub fn file_name(&self) -> String {
+        format!("r.{}.{}.mca", self.x, self.z)
+    }
+}
+
+/// An anvil (.mca) region file.
+/// The file starts with two 4KiB tables, one with the location of each chunk (3 byte sector offset and 1 byte sector count)
+/// and one with the last modification timestamps. Chunk payloads follow, each padded to a multiple of sectors.
+pub struct RegionFile {
+    file: File,
+    locations: [u32; CHUNKS_PER_REGION],
+    timestamps: [u32; CHUNKS_PER_REGION],
+    /// true if the sector is in use.
+    sectors: Vec<bool>,
+}
+
+impl RegionFile {
+    /// Opens the region file at given path, creating it if it doesn't exist.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<RegionFile, Error> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .open(path)?;
+
+        let mut len = file.metadata()?.len() as usize;
+        if len < HEADER_SECTORS * SECTOR_SIZE {
+            // new or truncated file. write an empty header.
+            file.seek(SeekFrom::Start(0))?;
+            file.write_all(&[0; HEADER_SECTORS * SECTOR_SIZE])?;
+            len = HEADER_SECTORS * SECTOR_SIZE;
+        }
+        if len % SECTOR_SIZE != 0 {
+            // pad to a sector boundary so sector math stays simple.
+            let padding = SECTOR_SIZE - len % SECTOR_SIZE;
+            file.seek(SeekFrom::End(0))?;
+            file.write_all(&vec![0; padding])?;
+            len += padding;
+        }
+
+        let mut sectors = vec![false; len / SECTOR_SIZE];
+        for s in sectors.iter_mut().take(HEADER_SECTORS) {
+            *s = true;
+        }
+
+        file.seek(SeekFrom::Start(0))?;
+        let mut locations = [0; CHUNKS_PER_REGION];
+        for l in locations.iter_mut() {
+            *l = binary::read_int(&mut file)? as u32;
+        }
+        let mut timestamps = [0; CHUNKS_PER_REGION];
+        for t in timestamps.iter_mut() {
+            *t = binary::read_int(&mut file)? as u32;
+        }
+
+        for &location in locations.iter() {
+            let (offset, count) = Self::split_location(location);
+            if offset >= HEADER_SECTORS && offset + count <= sectors.len() {
+                for s in &mut sectors[offset..offset + count] {
+                    *s = true;
+                }
+            }
+        }
+
+        Ok(RegionFile {
+            file,
+            locations,
+            timestamps,
+            sectors,
+        })
+    }
+
+    fn index(pos: ChunkPos) -> usize {
+        ((pos.x & 31) + (pos.z & 31) * 32) as usize
+    }
+
+    fn split_location(location: u32) -> (usize, usize) {
+        ((location >> 8) as usize, (location & 0xff) as usize)
+    }
+
+    pub fn has_chunk(&self, pos: ChunkPos) -> bool {
+        self.locations[Self::index(pos)] != 0
+    }
+
+    /// Reads the chunk at given position. Position is a world chunk position, only the lower 5 bits are used.
+    pub fn read_chunk(&mut self, pos: ChunkPos) -> Result<Option<Nbt>, Error> {
+        let (offset, count) = Self::split_location(self.locations[Self::index(pos)]);
+        if offset == 0 {
+            return Ok(None);
+        }
+        if offset < HEADER_SECTORS || offset + count > self.sectors.len() {
+            return Err(Error::InvalidChunkLocation(pos));
+        }
+
+        self.file.seek(SeekFrom::Start((offset * SECTOR_SIZE) as u64))?;
+        let len = binary::read_int(&mut self.file)?;
+        if len <= 1 || len as usize > count * SECTOR_SIZE - 4 {
+            return Err(Error::InvalidChunkLength(pos, len));
+        }
+        let compression = binary::read_ubyte(&mut self.file)?;
+        let mut data = vec![0; len as usize - 1];
+        self.file.read_exact(&mut data)?;
+
+        let nbt = match compression {
+            COMPRESSION_GZIP => Nbt::read(&mut GzDecoder::new(&data[..]))?,
+            COMPRESSION_ZLIB => Nbt::read(&mut ZlibDecoder::new(&data[..]))?,
+            _ => return Err(Error::UnknownCompression(compression)),
+        };
+
+        Ok(Some(nbt))
+    }
+
+    /// Writes the chunk at given position, replacing what was there before.
+    pub fn write_chunk(&mut self, pos: ChunkPos, nbt: &Nbt) -> Result<(), Error> {
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        nbt.write(&mut encoder)?;
+        let data = encoder.finish()?;
+
+        // 4 bytes of length and 1 byte of compression type.
+        let needed = (data.len() + 5 + SECTOR_SIZE - 1) / SECTOR_SIZE;
+        if needed > MAX_CHUNK_SECTORS {
+            return Err(Error::ChunkTooLarge(pos, data.len()));
+        }
+
+        let index = Self::index(pos);
+        let (old_offset, old_count) = Self::split_location(self.locations[index]);
+        // a corrupt header may point outside the file.
+        let old_valid = old_offset >= HEADER_SECTORS && old_offset + old_count <= self.sectors.len();
+
+        let offset = if old_valid && needed <= old_count {
+            // fits where it was. release what is not needed anymore.
+            for s in &mut self.sectors[old_offset + needed..old_offset + old_count] {
+                *s = false;
+            }
+            old_offset
+        } else {
+            if old_valid {
+                for s in &mut self.sectors[old_offset..old_offset + old_count] {
+                    *s = false;
+                }
+            }
+            self.allocate(needed)
+        };
+
+        self.file.seek(SeekFrom::Start((offset * SECTOR_SIZE) as u64))?;
+        let mut buf = Vec::with_capacity(needed * SECTOR_SIZE);
+        binary::write_int(&mut buf, data.len() as i32 + 1)?;
+        binary::write_ubyte(&mut buf, COMPRESSION_ZLIB)?;
+        buf.extend_from_slice(&data);
+        buf.resize(needed * SECTOR_SIZE, 0);
+        self.file.write_all(&buf)?;
+
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs() as u32)
+            .unwrap_or(0);
+        self.set_header(index, ((offset as u32) << 8) | needed as u32, timestamp)?;
+        Ok(())
+    }
+
+    /// Finds a run of free sectors, growing the file if there is none.
+    fn allocate(&mut self, count: usize) -> usize {
+        let mut run_start = 0;
+        let mut run_len = 0;
+        for (i, &used) in self.sectors.iter().enumerate() {
+            if used {
+                run_len = 0;
+                continue;
+            }
+            if run_len == 0 {
+                run_start = i;
+            }
+            run_len += 1;
+            if run_len == count {
+                for s in &mut self.sectors[run_start..run_start + count] {
+                    *s = true;
+                }
+                return run_start;
+            }
+        }
+
+        // no gap is large enough, append. a free run at the end can be reused.
+        let start = if run_len > 0 && run_start + run_len == self.sectors.len() {
+            run_start
+        } else {
+            self.sectors.len()
+        };
+        self.sectors.resize(start + count, false);
+        for s in &mut self.sectors[start..start + count] {
+            *s = true;
+        }
+        start
+    }
+
+    fn set_header(&mut self, index: usize, location: u32, timestamp: u32) -> io::Result<()> {
+        self.locations[index] = location;
+        self.timestamps[index] = timestamp;
+
+        self.file.seek(SeekFrom::Start((index * 4) as u64))?;
+        binary::write_int(&mut self.file, location as i32)?;
+        self.file
+            .seek(SeekFrom::Start((SECTOR_SIZE + index * 4) as u64))?;
+        binary::write_int(&mut self.file, timestamp as i32)
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.file.sync_data()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+    use std::env;
+    use std::fs;
+    use std::process;
+
+    use super::*;
+    use crate::nbt::{Compound, List, Tag};
+    use crate::block::{Block, BlockStateId};
+    use crate::world::chunk::Chunk;
+
+    #[test]
+    fn chunk_round_trip() {
+        let path = env::temp_dir().join(format!("quartz-region-test-{}.mca", process::id()));
+        let _ = fs::remove_file(&path);
+
+        let pos = ChunkPos::new(-3, 40);
+        let mut chunk = Chunk::new(pos, true);
+        chunk.set_block(1, 2, 3, BlockStateId::new(Block::Wool, 5));
+        chunk.set_block(15, 200, 0, BlockStateId::new(Block::Chest, 2));
+
+        {
+            let mut region = RegionFile::open(&path).unwrap();
+            assert!(!region.has_chunk(pos));
+            region.write_chunk(pos, &chunk.to_nbt()).unwrap();
+        }
+
+        let mut region = RegionFile::open(&path).unwrap();
+        let nbt = region.read_chunk(pos).unwrap().unwrap();
+        let loaded = Chunk::from_nbt(&nbt, true).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.get_pos(), pos);
+        assert_eq!(loaded.get_block(1, 2, 3), BlockStateId::new(Block::Wool, 5));
+        assert_eq!(loaded.get_block(15, 200, 0), BlockStateId::new(Block::Chest, 2));
+        assert_eq!(loaded.get_block(0, 0, 0), BlockStateId::AIR);
+        assert!(region.read_chunk(ChunkPos::new(0, 0)).unwrap().is_none());
+    }
+
+    #[test]
+    fn keeps_entities() {
+        let pos = ChunkPos::new(0, 0);
+        let mut nbt = Chunk::new(pos, true).to_nbt();
+        let mut pig = Compound(HashMap::new());
+        pig.0.insert("id".into(), "Pig".to_string().into());
+        if let Nbt::Some(_, ref mut root) = nbt {
+            let level = root.0.get_mut("Level").unwrap();
+            if let Tag::Compound(ref mut level) = *level {
+                level.0.insert("Entities".into(), List(vec![pig.into()]).into());
+            }
+        }
+        let saved = Chunk::from_nbt(&nbt, true).unwrap().to_nbt();
+        assert_eq!(saved, nbt);
+    }
+
+    #[test]
+    fn ignores_locations_outside_the_file() {
+        let path = env::temp_dir().join(format!("quartz-region-corrupt-{}.mca", process::id()));
+        let _ = fs::remove_file(&path);
+
+        let pos = ChunkPos::new(1, 1);
+        let mut region = RegionFile::open(&path).unwrap();
+        region.locations[RegionFile::index(pos)] = (100 << 8) | 5;
+        region.write_chunk(pos, &Chunk::new(pos, true).to_nbt()).unwrap();
+        let nbt = region.read_chunk(pos).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert!(nbt.is_some());
+    }
+}